@@ -48,6 +48,10 @@ where
         pp_elusivity,
         pp_stack_key
     );
+    // these are used to index fixed-size arrays in CharacterAttributes
+    verify_array_size("pp_damage", CharacterAttributes::DAMAGE_TYPE_SIZE, attrib_names.pp_damage.len())?;
+    verify_array_size("pp_defense", CharacterAttributes::DEFENSE_TYPE_SIZE, attrib_names.pp_defense.len())?;
+    verify_array_size("pp_elusivity", CharacterAttributes::ELUSIVITY_SIZE, attrib_names.pp_elusivity.len())?;
 
     // Not technically part of this file but we'll read them from the message store here.
     // If you're wondering where these came from, these are the same messages used by the
@@ -203,3 +207,49 @@ where
     attrib_name.pch_icon_name = read_pool_string(reader, strings, messages)?;
     verify_struct_length(attrib_name, expected_bytes, begin_pos, reader)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Writes an array of `count` empty `AttribName` structs.
+    fn push_names(data: &mut Vec<u8>, count: usize) {
+        data.extend_from_slice(&(count as u32).to_le_bytes());
+        for _ in 0..count {
+            // struct length followed by 3 null string offsets
+            data.extend_from_slice(&12u32.to_le_bytes());
+            data.extend(vec![0u8; 12]);
+        }
+    }
+
+    #[test]
+    fn damage_names_size_mismatch_test() {
+        let mut data = Vec::new();
+        push_names(&mut data, 19); // damage
+        push_names(&mut data, CharacterAttributes::DEFENSE_TYPE_SIZE);
+        for _ in 0..3 {
+            push_names(&mut data, 0); // boost, group, mode
+        }
+        push_names(&mut data, CharacterAttributes::ELUSIVITY_SIZE);
+        push_names(&mut data, 0); // stack key
+        let mut bytes = (data.len() as u32).to_le_bytes().to_vec();
+        bytes.extend(data);
+
+        let strings = StringPool::new(vec![0], StringEncoding::Lossy);
+        let messages = MessageStore::new();
+        let result = serialized_read_attribs(&mut Cursor::new(bytes), &strings, &messages);
+        match result.map(|_| ()).unwrap_err().kind() {
+            ParseErrorKind::ArraySizeMismatch {
+                field,
+                expected,
+                found,
+            } => {
+                assert_eq!(field, "pp_damage");
+                assert_eq!(expected, 20);
+                assert_eq!(found, 19);
+            }
+            _ => panic!("Unexpected error kind"),
+        }
+    }
+}
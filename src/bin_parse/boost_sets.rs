@@ -173,3 +173,85 @@ where
     )?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Builds a string pool from `strings`, returning it along with each string's offset.
+    fn make_pool(strings: &[&str]) -> (StringPool, Vec<u32>) {
+        let mut pool = vec![0u8];
+        let mut offsets = Vec::new();
+        for s in strings {
+            offsets.push(pool.len() as u32);
+            pool.extend_from_slice(s.as_bytes());
+            pool.push(0);
+        }
+        (StringPool::new(pool, StringEncoding::Lossy), offsets)
+    }
+
+    /// Prefixes `data` with its length, as structs are stored in .bin files.
+    fn make_struct(data: Vec<u8>) -> Vec<u8> {
+        let mut bytes = (data.len() as u32).to_le_bytes().to_vec();
+        bytes.extend(data);
+        bytes
+    }
+
+    /// Writes a padded Pascal string.
+    fn push_pascal(data: &mut Vec<u8>, s: &str) {
+        data.extend_from_slice(&(s.len() as u16).to_le_bytes());
+        data.extend_from_slice(s.as_bytes());
+        let padding = (4 - (s.len() + 2) % 4) % 4;
+        data.extend(vec![0u8; padding]);
+    }
+
+    fn push_u32(data: &mut Vec<u8>, val: u32) {
+        data.extend_from_slice(&val.to_le_bytes());
+    }
+
+    fn make_bonus(display_name: u32, min_boosts: u32, bonus_power: &str) -> Vec<u8> {
+        let mut data = Vec::new();
+        push_u32(&mut data, display_name);
+        push_u32(&mut data, min_boosts);
+        push_u32(&mut data, 0); // max boosts
+        push_u32(&mut data, 0); // requires
+        push_u32(&mut data, 0); // auto powers
+        push_pascal(&mut data, bonus_power);
+        make_struct(data)
+    }
+
+    #[test]
+    fn read_boost_set_bonuses_test() {
+        let (strings, offsets) =
+            make_pool(&["Crafted_Test_Set", "Test Set", "Bonus Two", "Bonus Three"]);
+        let messages = MessageStore::new();
+
+        let mut data = Vec::new();
+        push_u32(&mut data, offsets[0]); // name
+        push_u32(&mut data, offsets[1]); // display name
+        push_u32(&mut data, 0); // group name
+        push_u32(&mut data, 0); // conversion groups
+        push_u32(&mut data, 0); // powers
+        push_u32(&mut data, 0); // boost lists
+        push_u32(&mut data, 2); // bonuses
+        data.extend(make_bonus(offsets[2], 2, "Set_Bonus.Set_Bonus.Two"));
+        data.extend(make_bonus(offsets[3], 3, "Set_Bonus.Set_Bonus.Three"));
+        push_u32(&mut data, 9); // min level
+        push_u32(&mut data, 49); // max level
+        push_u32(&mut data, 0); // store product
+
+        let mut reader = Cursor::new(make_struct(data));
+        let boost_set = read_boost_set(&mut reader, &strings, &messages).unwrap();
+        assert_eq!(boost_set.pch_display_name.as_deref(), Some("Test Set"));
+        assert_eq!(boost_set.pp_bonuses.len(), 2);
+        let two = &boost_set.pp_bonuses[0];
+        assert_eq!(two.i_min_boosts, 2);
+        assert_eq!(two.pch_display_name.as_deref(), Some("Bonus Two"));
+        assert!(matches!(&two.pch_bonus_power, Some(p) if p == "Set_Bonus.Set_Bonus.Two"));
+        let three = &boost_set.pp_bonuses[1];
+        assert_eq!(three.i_min_boosts, 3);
+        assert!(matches!(&three.pch_bonus_power, Some(p) if p == "Set_Bonus.Set_Bonus.Three"));
+        assert_eq!(boost_set.i_max_level, 49);
+    }
+}
@@ -1,5 +1,5 @@
 use super::*;
-use crate::structs::{MessageStore, TextMessage};
+use crate::structs::{decode_string, MessageStore, TextMessage};
 use std::cmp;
 
 /// Signature used for message store .bin files.
@@ -35,12 +35,13 @@ pub fn open_message_store(path: &Path) -> ParseResult<BufReader<File>> {
 /// Arguments:
 ///
 /// * `reader` - An open `Read` + `Seek`.
+/// * `encoding` - How the strings are converted to UTF-8.
 ///
 /// Returns:
 ///
 /// If successful, a `Vec<String>` containing all of the strings.
 /// Otherwise, a `ParseError` containing the error information.
-pub fn read_string_table<T>(reader: &mut T) -> ParseResult<Vec<String>>
+pub fn read_string_table<T>(reader: &mut T, encoding: StringEncoding) -> ParseResult<Vec<String>>
 where
     T: Read + Seek,
 {
@@ -56,30 +57,31 @@ where
     let mut bytes_read = 0usize;
     while bytes_read < expected_bytes {
         // Read in READ_BUF_SIZE-sized chunks, making sure we don't read more than the data length told us.
-        bytes_read += reader
+        let chunk_len = reader
             .take(cmp::min(expected_bytes - bytes_read, READ_BUF_SIZE) as u64)
             .read(&mut buf)
             .map_err(to_pe)?;
-        let mut i = buf.iter();
+        bytes_read += chunk_len;
+        // only look at what was just read, the rest of `buf` is left over from the previous chunk
+        let mut i = buf[..chunk_len].iter();
         while let Some(b) = i.next() {
             match *b {
                 0 => {
                     // As we read, we look for delimiting NULs -- this is where the string boundaries are.
                     // Anything we read up to this point is combined into a string, then we start anew.
                     strings.push(
-                        str::from_utf8(sbuf.as_slice())
+                        decode_string(sbuf.as_slice(), encoding)
                             .map_err(|_| ParseError::new(ParseErrorKind::StringConversion))?
-                            .to_owned()
-                            .replace("&nbsp;", " ") // HACK
+                            .replace("&nbsp;", " "), // HACK
                     );
                     sbuf.clear();
                 }
-                0xa0 => {
+                0xa0 if encoding != StringEncoding::Utf8Strict => {
                     // ASCII nbsp gets used sometimes which is confusing to utf-8
                     sbuf.push(0x20);
                 }
-                0x80..=0xff => (), // dump all high- and low-order ASCII (used for some random Latin chars I don't care about)
-                0x01..=0x1f => (), // (they tend to be used in mission/NPC text rather than powers descriptions)
+                // high-order ASCII (used for some random Latin chars) is left to `encoding`
+                0x01..=0x1f => (), // dump low-order ASCII (tends to be used in mission/NPC text rather than powers descriptions)
                 _ => sbuf.push(*b),
             }
         }
@@ -98,13 +100,18 @@ where
 ///
 /// * `reader` - An open `Read` + `Seek`.
 /// * `store` - A `MessageStore` previously populated by `read_string_table`.
+/// * `encoding` - How the message IDs are converted to UTF-8.
 ///
 /// Returns:
 ///
 /// On success, the `store.message_ids` map will be updated with information on the
 /// message IDs.
 /// Otherwise, a `ParseError` containing the error information.
-pub fn read_message_ids<T>(reader: &mut T, store: &mut MessageStore) -> ParseResult<()>
+pub fn read_message_ids<T>(
+    reader: &mut T,
+    store: &mut MessageStore,
+    encoding: StringEncoding,
+) -> ParseResult<()>
 where
     T: Read + Seek,
 {
@@ -113,7 +120,7 @@ where
         let len: usize = bin_read(reader)?;
         let mut buf = vec![0u8; len];
         reader.read_exact(&mut buf).map_err(to_pe)?;
-        let string = str::from_utf8(buf.as_slice())
+        let string = decode_string(buf.as_slice(), encoding)
             .map_err(|_| ParseError::new(ParseErrorKind::StringConversion))?;
 
         let message_index = bin_read(reader)?;
@@ -131,10 +138,43 @@ where
         };
 
         store.message_ids.insert(
-            string.to_owned(),
+            string.into_owned(),
             TextMessage::new(message_index, help_index, vars),
         );
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Builds a string table holding `strings`, each NUL-terminated.
+    fn make_table(strings: &[&[u8]]) -> Cursor<Vec<u8>> {
+        let data: Vec<u8> = strings
+            .iter()
+            .flat_map(|s| s.iter().copied().chain(Some(0)))
+            .collect();
+        let mut bytes = (strings.len() as u32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend(data);
+        Cursor::new(bytes)
+    }
+
+    #[test]
+    fn read_string_table_encoding_test() {
+        let strings: &[&[u8]] = &[b"Caf\xe9", b"a\xa0b"];
+        let read = |encoding| read_string_table(&mut make_table(strings), encoding);
+        assert_eq!(read(StringEncoding::Lossy).unwrap(), vec!["Caf", "a b"]);
+        assert_eq!(
+            read(StringEncoding::Latin1).unwrap(),
+            vec!["Caf\u{e9}", "a b"]
+        );
+        assert!(matches!(
+            read(StringEncoding::Utf8Strict).unwrap_err().kind(),
+            ParseErrorKind::StringConversion
+        ));
+    }
+}
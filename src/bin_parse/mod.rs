@@ -7,13 +7,16 @@ mod powers;
 mod powersets;
 mod villains;
 
+use crate::structs::config::StringEncoding;
 use crate::structs::{IntoMessage, MessageStore, NameKey, StringPool, Vec3, RGBA};
 pub use archetypes::*;
 pub use attribs::*;
 pub use boost_sets::*;
+use log::warn;
 pub use powercats::*;
 pub use powers::*;
 pub use powersets::*;
+use std::borrow::Cow;
 use std::convert::TryFrom;
 use std::fmt;
 use std::fs::File;
@@ -22,6 +25,7 @@ use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::mem::size_of;
 use std::path::Path;
 use std::str;
+use thiserror::Error;
 pub use villains::*;
 
 const CRYPTIC_SIG: &'static [u8] = "CrypticS".as_bytes();
@@ -46,22 +50,27 @@ pub enum ParseErrorKind {
     },
     /// The currently read object does not have a name key, which shouldn't be possible.
     MissingNameKey,
+    /// The array `field` was expected to have `expected` entries, but `found` were read.
+    ArraySizeMismatch {
+        field: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    /// More than one object has the same name key.
+    DuplicateNameKey,
 }
 
 /// Represents an error the occurred while parsing a .bin file.
+#[derive(Error)]
+#[error("Unable to parse .bin file.")]
 pub struct ParseError {
     /// The kind of error that occured.
     kind: ParseErrorKind,
     /// If `kind` is `ParseErrorKind::ReadError`, this will contain the `io::Error` that caused it.
+    #[source]
     io_error: Option<io::Error>,
 }
 
-impl fmt::Display for ParseError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Unable to parse .bin file.")
-    }
-}
-
 impl fmt::Debug for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{{ file: {}, line: {} }}", file!(), line!())
@@ -70,7 +79,7 @@ impl fmt::Debug for ParseError {
 
 impl ParseError {
     /// Creates a new `ParseError` set to the specified `kind`.
-    fn new(kind: ParseErrorKind) -> Self {
+    pub(crate) fn new(kind: ParseErrorKind) -> Self {
         ParseError {
             kind,
             io_error: None,
@@ -94,6 +103,12 @@ impl ParseError {
         self.io_error.as_ref()
     }
 
+    /// Tests if this error was caused by the file not existing, as opposed to being unreadable
+    /// or corrupt.
+    pub fn is_file_not_found(&self) -> bool {
+        matches!(&self.io_error, Some(e) if e.kind() == io::ErrorKind::NotFound)
+    }
+
     /// Gets the kind of error that occurred.
     ///
     /// # Returns
@@ -104,8 +119,6 @@ impl ParseError {
     }
 }
 
-impl std::error::Error for ParseError {}
-
 /// Convenience wrapper for `Result<T, ParseError>`.
 pub type ParseResult<T> = Result<T, ParseError>;
 
@@ -145,12 +158,16 @@ pub fn open_serialized(path: &Path) -> ParseResult<BufReader<File>> {
 /// # Arguments:
 ///
 /// * `reader` - An open `Read` + `Seek`
+/// * `encoding` - How strings in the pool are converted to UTF-8.
 ///
 /// # Returns:
 ///
 /// If successful, a `StringPool` containing all of the strings.
 /// Otherwise, a `ParseError` containing the error information.
-pub fn serialized_read_string_pool<T>(reader: &mut T) -> ParseResult<StringPool>
+pub fn serialized_read_string_pool<T>(
+    reader: &mut T,
+    encoding: StringEncoding,
+) -> ParseResult<StringPool>
 where
     T: Read + Seek,
 {
@@ -168,7 +185,7 @@ where
         .map_err(to_pe)?;
 
     verify_struct_length(
-        StringPool::new(pool),
+        StringPool::new(pool, encoding),
         expected_bytes + padding,
         begin_pos,
         reader,
@@ -287,7 +304,7 @@ where
     if let Ok(val) = T::try_from(ival) {
         Ok(val)
     } else {
-        eprintln!("Unknown enum {} value {}", std::any::type_name::<T>(), ival);
+        warn!("Unknown enum {} value {}", std::any::type_name::<T>(), ival);
         Ok(T::default())
     }
 }
@@ -431,6 +448,14 @@ where
     Ok(return_str)
 }
 
+/// Looks up the string at `offset` in `strings`, failing with `ParseErrorKind::StringConversion`
+/// if it can't be converted to UTF-8.
+fn get_pool_string(strings: &StringPool, offset: usize) -> ParseResult<Option<Cow<'_, str>>> {
+    strings
+        .get_string(offset)
+        .map_err(|_| ParseError::new(ParseErrorKind::StringConversion))
+}
+
 /// Reads an offset into the `StringPool` from the stream and then performs a lookup to convert
 /// it into the desired string. This is specifically used with `NameKey` so it does not refer to
 /// the message store, as they are not UI strings.
@@ -440,7 +465,7 @@ where
 {
     let offset: usize = bin_read(reader)?;
     if offset > 0 {
-        if let Some(s) = strings.get_string(offset) {
+        if let Some(s) = get_pool_string(strings, offset)? {
             return Ok(Some(NameKey::new(s)));
         }
     }
@@ -480,7 +505,9 @@ where
 {
     let offset: usize = bin_read(reader)?;
     if offset > 0 {
-        Ok(strings.get_string(offset).into_message(messages))
+        Ok(get_pool_string(strings, offset)?
+            .as_deref()
+            .into_message(messages))
     } else {
         Ok(None)
     }
@@ -519,6 +546,30 @@ where
     Ok((expected_bytes, cur_pos))
 }
 
+/// Verifies that an array read from a .bin file has the number of entries the rest of
+/// the parser expects, so that fixed-size tables indexed by it don't become misaligned.
+///
+/// # Arguments
+///
+/// * `field` - The name of the array, used for error reporting.
+/// * `expected` - The number of entries expected.
+/// * `found` - The number of entries actually read.
+///
+/// # Returns
+///
+/// Nothing if the sizes match. Otherwise a `ParseError` of kind `ParseErrorKind::ArraySizeMismatch`.
+fn verify_array_size(field: &'static str, expected: usize, found: usize) -> ParseResult<()> {
+    if expected != found {
+        Err(ParseError::new(ParseErrorKind::ArraySizeMismatch {
+            field,
+            expected,
+            found,
+        }))
+    } else {
+        Ok(())
+    }
+}
+
 /// Verifies that we read the correct number of bytes while parsing the current
 /// struct.
 ///
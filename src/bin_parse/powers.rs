@@ -16,13 +16,42 @@ const ATTRIBMOD_FLAGS_SIZE: usize = 2;
 ///
 /// # Returns:
 ///
-/// If successful, a map containing zero or more `BasePower` structs.
+/// If successful, a list containing zero or more `BasePower` structs in the order they were read.
 /// Otherwise, a `ParseError` with the error information.
+///
+/// # Notes:
+///
+/// Powers aren't keyed by name here so that the caller can check for duplicate names.
 pub fn serialized_read_powers<T>(
     reader: &mut T,
     strings: &StringPool,
     messages: &MessageStore,
-) -> ParseResult<Keyed<BasePower>>
+    frame_rate: u32,
+) -> ParseResult<Vec<BasePower>>
+where
+    T: Read + Seek,
+{
+    serialized_iter_powers(reader, strings, messages, frame_rate)?.collect()
+}
+
+/// Reads the powers in the current .bin file one at a time.
+///
+/// # Arguments:
+///
+/// * `reader` - An open `Read` + `Seek`
+/// * `strings` - The `StringPool` for power sets
+/// * `messages` - The global `MessageStore` containing client messages
+///
+/// # Returns:
+///
+/// If successful, a `PowerIter` that reads the next `BasePower` each time it's advanced.
+/// Otherwise, a `ParseError` with the error information.
+pub fn serialized_iter_powers<'a, T>(
+    reader: &'a mut T,
+    strings: &'a StringPool,
+    messages: &'a MessageStore,
+    frame_rate: u32,
+) -> ParseResult<PowerIter<'a, T>>
 where
     T: Read + Seek,
 {
@@ -30,15 +59,57 @@ where
     let (expected_bytes, begin_pos) = read_struct_length(reader)?;
 
     // first read the length of the TOK_EARRAY ParseBasePower[]
-    let pbp_size: usize = bin_read(reader)?;
-    let mut powers = Keyed::<_>::new();
-    for _ in 0..pbp_size {
-        let power = read_base_power(reader, strings, messages)?;
-        if let Some(power_name) = power.pch_full_name.clone() {
-            powers.insert(power_name, power);
+    let remaining: usize = bin_read(reader)?;
+    Ok(PowerIter {
+        reader,
+        strings,
+        messages,
+        frame_rate,
+        remaining,
+        expected_bytes,
+        begin_pos,
+        done: false,
+    })
+}
+
+/// Iterator over the powers in a .bin file, returned by `serialized_iter_powers`.
+///
+/// # Notes:
+///
+/// After the last power, the length of the powers table is verified and a `ParseError` is
+/// returned if it doesn't match. Iteration stops after the first error.
+pub struct PowerIter<'a, T> {
+    reader: &'a mut T,
+    strings: &'a StringPool,
+    messages: &'a MessageStore,
+    frame_rate: u32,
+    remaining: usize,
+    expected_bytes: u64,
+    begin_pos: u64,
+    done: bool,
+}
+
+impl<'a, T> Iterator for PowerIter<'a, T>
+where
+    T: Read + Seek,
+{
+    type Item = ParseResult<BasePower>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if self.remaining == 0 {
+            self.done = true;
+            return verify_struct_length((), self.expected_bytes, self.begin_pos, self.reader)
+                .err()
+                .map(Err);
         }
+        self.remaining -= 1;
+        let result = read_base_power(self.reader, self.strings, self.messages, self.frame_rate);
+        self.done = result.is_err();
+        Some(result)
     }
-    verify_struct_length(powers, expected_bytes, begin_pos, reader)
 }
 
 /// Reads a BasePower struct from a .bin file.
@@ -47,6 +118,7 @@ fn read_base_power<T>(
     reader: &mut T,
     strings: &StringPool,
     messages: &MessageStore,
+    frame_rate: u32,
 ) -> ParseResult<BasePower>
 where
     T: Read + Seek,
@@ -223,9 +295,14 @@ where
         |re| read_power_redirect(re, strings, messages),
         reader,
     )?;
+    let power_type = &power.e_type;
     bin_read_arr_fn(
         &mut power.pp_effects,
-        |re| Ok(Rc::new(RefCell::new(read_effect_group(re, strings, messages)?))),
+        |re| {
+            Ok(Rc::new(RefCell::new(read_effect_group(
+                re, power_type, strings, messages,
+            )?)))
+        },
         reader,
     )?;
 
@@ -313,11 +390,12 @@ where
         reader,
         strings,
         messages,
+        frame_rate,
     )?);
 
     bin_read_arr_fn(
         &mut power.pp_custom_fx,
-        |re| read_custom_power_fx(re, strings, messages),
+        |re| read_custom_power_fx(re, strings, messages, frame_rate),
         reader,
     )?;
     // power redirector TOK_IGNORE
@@ -352,6 +430,7 @@ where
 /// Refer to Common/entity/attribmod.h TokenizerParseInfo structs.
 fn read_effect_group<T>(
     reader: &mut T,
+    power_type: &PowerType,
     strings: &StringPool,
     messages: &MessageStore,
 ) -> ParseResult<EffectGroup>
@@ -373,12 +452,12 @@ where
     egroup.i_eval_flags = bin_read(reader)?;
     bin_read_arr_fn(
         &mut egroup.pp_templates,
-        |re| read_attrib_mod_template(re, strings, messages),
+        |re| read_attrib_mod_template(re, power_type, strings, messages),
         reader,
     )?;
     bin_read_arr_fn(
         &mut egroup.pp_effects,
-        |re| read_effect_group(re, strings, messages),
+        |re| read_effect_group(re, power_type, strings, messages),
         reader,
     )?;
     Ok(verify_struct_length(
@@ -393,6 +472,7 @@ where
 /// Refer to Common/entity/attribmod.h TokenizerParseInfo structs.
 fn read_attrib_mod_template<T>(
     reader: &mut T,
+    power_type: &PowerType,
     strings: &StringPool,
     messages: &MessageStore,
 ) -> ParseResult<AttribModTemplate>
@@ -423,7 +503,7 @@ where
     }
     template.pch_table = read_pool_string(reader, strings, messages)?;
     template.f_scale = bin_read(reader)?;
-    template.f_duration = ModDuration::from_f32(bin_read(reader)?);
+    template.f_duration = ModDuration::from_f32(bin_read(reader)?, power_type);
     template.f_magnitude = bin_read(reader)?; // TODO: ParsePowerDefines
     tpl_string_arr!(ppch_duration);
     tpl_string_arr!(ppch_magnitude);
@@ -697,6 +777,7 @@ fn read_power_fx<T>(
     reader: &mut T,
     strings: &StringPool,
     messages: &MessageStore,
+    frame_rate: u32,
 ) -> ParseResult<PowerFX>
 where
     T: Read + Seek,
@@ -723,6 +804,7 @@ where
 
     // We don't read the struct len here because this struct packed in-line with the BasePower struct
     fx.pch_source_file = source_file;
+    fx.frame_rate = frame_rate;
     fx_arr!(
         pi_attack_bits,
         pi_block_bits,
@@ -809,6 +891,7 @@ fn read_custom_power_fx<T>(
     reader: &mut T,
     strings: &StringPool,
     messages: &MessageStore,
+    frame_rate: u32,
 ) -> ParseResult<CustomPowerFX>
 where
     T: Read + Seek,
@@ -828,6 +911,7 @@ where
         reader,
         strings,
         messages,
+        frame_rate,
     )?);
     cfx.pch_palette_name = read_pool_string(reader, strings, messages)?;
     verify_struct_length(cfx, expected_bytes, begin_pos, reader)
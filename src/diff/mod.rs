@@ -0,0 +1,173 @@
+//! Compares two powers dictionaries, e.g. the bins from two different game issues.
+
+use crate::structs::*;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// The differences between two `PowersDictionary` values.
+#[derive(Debug, Default, Serialize)]
+pub struct DictionaryDiff {
+    /// Powers only found in the new dictionary.
+    pub added: Vec<NameKey>,
+    /// Powers only found in the old dictionary.
+    pub removed: Vec<NameKey>,
+    /// Powers found in both dictionaries whose values changed.
+    pub changed: Vec<PowerDiff>,
+}
+
+/// The changed values of a power found in both dictionaries.
+#[derive(Debug, Serialize)]
+pub struct PowerDiff {
+    pub name: NameKey,
+    pub fields: Vec<FieldChange>,
+}
+
+/// A single changed value.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: f32,
+    pub new: f32,
+}
+
+/// The scalar `BasePower` fields that are compared.
+const COMPARED_FIELDS: &'static [(&'static str, fn(&BasePower) -> f32)] = &[
+    ("accuracy", |p| p.f_accuracy),
+    ("recharge_time", |p| p.f_recharge_time),
+    ("endurance_cost", |p| p.f_endurance_cost),
+    ("range", |p| p.f_range),
+];
+
+/// Compares two powers dictionaries.
+///
+/// # Arguments:
+///
+/// * `old` - The dictionary to compare from.
+/// * `new` - The dictionary to compare to.
+///
+/// # Returns:
+///
+/// A `DictionaryDiff` listing the added, removed, and changed powers, each sorted by name.
+///
+/// # Notes:
+///
+/// Powers are paired by `pch_full_name`, ignoring case.
+#[allow(dead_code)]
+pub fn diff_dictionaries(old: &PowersDictionary, new: &PowersDictionary) -> DictionaryDiff {
+    let mut diff = DictionaryDiff::default();
+    for old_power in all_powers(old) {
+        let old_power = old_power.borrow();
+        let name = match &old_power.pch_full_name {
+            Some(name) => name,
+            None => continue,
+        };
+        match new.find_power(name) {
+            Some(new_power) => {
+                let new_power = new_power.borrow();
+                let fields: Vec<_> = COMPARED_FIELDS
+                    .iter()
+                    .filter_map(|(field, get)| {
+                        let (old_value, new_value) = (get(&*old_power), get(&*new_power));
+                        if old_value != new_value {
+                            Some(FieldChange {
+                                field,
+                                old: old_value,
+                                new: new_value,
+                            })
+                        } else {
+                            None
+                        }
+                    })
+                    .collect();
+                if !fields.is_empty() {
+                    diff.changed.push(PowerDiff {
+                        name: name.clone(),
+                        fields,
+                    });
+                }
+            }
+            None => diff.removed.push(name.clone()),
+        }
+    }
+    for new_power in all_powers(new) {
+        if let Some(name) = &new_power.borrow().pch_full_name {
+            if old.find_power(name).is_none() {
+                diff.added.push(name.clone());
+            }
+        }
+    }
+    diff.added
+        .sort_by_cached_key(|name| name.get().to_ascii_lowercase());
+    diff.removed
+        .sort_by_cached_key(|name| name.get().to_ascii_lowercase());
+    diff.changed
+        .sort_by_cached_key(|power| power.name.get().to_ascii_lowercase());
+    diff
+}
+
+/// Collects every power in the hierarchy, skipping any that appear more than once.
+fn all_powers(powers_dict: &PowersDictionary) -> Vec<ObjRef<BasePower>> {
+    let mut seen = HashSet::new();
+    powers_dict
+        .iter_powers(false)
+        .filter(|power| match &power.borrow().pch_full_name {
+            Some(name) => seen.insert(name.clone()),
+            None => false,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn make_dict(powers: &[(&str, f32, f32)]) -> PowersDictionary {
+        let mut pset = BasePowerSet::new();
+        for (name, accuracy, recharge_time) in powers {
+            let mut power = BasePower::new();
+            power.pch_full_name = Some(NameKey::new(*name));
+            power.f_accuracy = *accuracy;
+            power.f_recharge_time = *recharge_time;
+            pset.pp_powers.push(Rc::new(RefCell::new(power)));
+        }
+        let mut pcat = PowerCategory::new();
+        pcat.pp_power_sets.push(Rc::new(RefCell::new(pset)));
+        PowersDictionary::from_power_categories(vec![Rc::new(RefCell::new(pcat))])
+    }
+
+    #[test]
+    fn diff_dictionaries_test() {
+        let old = make_dict(&[
+            ("Pool.Flight.Hover", 1.0, 4.0),
+            ("Pool.Flight.Fly", 1.0, 2.0),
+            ("Pool.Flight.Air_Superiority", 1.0, 4.0),
+        ]);
+        let new = make_dict(&[
+            ("pool.flight.hover", 1.0, 4.0),
+            ("Pool.Flight.Fly", 1.0, 4.0),
+            ("Pool.Flight.Afterburner", 1.0, 10.0),
+        ]);
+
+        let diff = diff_dictionaries(&old, &new);
+        assert_eq!(diff.added, vec![NameKey::new("Pool.Flight.Afterburner")]);
+        assert_eq!(
+            diff.removed,
+            vec![NameKey::new("Pool.Flight.Air_Superiority")]
+        );
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].name, NameKey::new("Pool.Flight.Fly"));
+        assert_eq!(
+            diff.changed[0].fields,
+            vec![FieldChange {
+                field: "recharge_time",
+                old: 2.0,
+                new: 4.0
+            }]
+        );
+        assert!(serde_json::to_string(&diff)
+            .unwrap()
+            .contains(r#""fields":[{"field":"recharge_time","old":2.0,"new":4.0}]"#));
+    }
+}
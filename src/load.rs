@@ -1,10 +1,16 @@
 use crate::bin_parse;
 use crate::structs::config::PowersConfig;
 use crate::structs::*;
+use log::{debug, error, info, warn};
 use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
 use std::process;
 use std::rc::Rc;
 use std::time::Instant;
+use thiserror::Error;
 
 /// Default names for the bin files.
 const ATTRIB_NAMES_BIN: &'static str = "attrib_names.bin";
@@ -17,11 +23,42 @@ const POWERS_BIN: &'static str = "powers.bin";
 const VILLAIN_CLASSES_BIN: &'static str = "villain_classes.bin";
 const VILLAIN_DEF_BIN: &'static str = "villaindef.bin";
 
+/// A `ParseError` along with a message describing what was being loaded when it occurred.
+#[derive(Debug, Error)]
+#[error("{message}")]
 pub struct ErrContext {
     pub message: Cow<'static, str>,
+    #[source]
     pub error: bin_parse::ParseError,
 }
 
+/// Progress reported by `load_powers_dictionary_with_progress`.
+#[derive(Debug, PartialEq)]
+pub enum LoadProgress {
+    /// Started reading the file at this path.
+    ReadingFile(String),
+    /// Finished reading `n` objects of `kind` (e.g. "powers").
+    ReadCount { kind: &'static str, n: usize },
+    /// Matching archetypes to power categories.
+    Matching,
+    /// Finished a pass of resolving entity defs, power grants, and redirects, which found `resolved`
+    /// more objects to include. Passes repeat until nothing new is resolved.
+    Resolving { pass: usize, resolved: usize },
+    /// Finished loading after `seconds`.
+    Done { seconds: u64 },
+}
+
+/// An error from `stream_power_sets`.
+#[derive(Debug, Error)]
+pub enum StreamPowerSetsError {
+    /// One of the .bin files couldn't be read.
+    #[error(transparent)]
+    Load(#[from] ErrContext),
+    /// The callback returned an error, which stops the iteration.
+    #[error("Unable to process power set: {0}")]
+    Callback(#[from] io::Error),
+}
+
 macro_rules! ecxt {
     ($msg:literal,$err:ident) => {
         ErrContext {
@@ -56,7 +93,7 @@ fn match_archetypes_to_power_categories(
         if let Some(mut pcat) =
             find_power_category(power_categories, a.pch_primary_category.as_ref())
         {
-            println!(
+            debug!(
                 "Matched {} to primary {}",
                 a.pch_name.as_ref().unwrap(),
                 pcat.pch_name.as_ref().unwrap()
@@ -68,7 +105,7 @@ fn match_archetypes_to_power_categories(
         if let Some(mut pcat) =
             find_power_category(power_categories, a.pch_secondary_category.as_ref())
         {
-            println!(
+            debug!(
                 "Matched {} to secondary {}",
                 a.pch_name.as_ref().unwrap(),
                 pcat.pch_name.as_ref().unwrap()
@@ -79,7 +116,7 @@ fn match_archetypes_to_power_categories(
         if let Some(mut pcat) =
             find_power_category(power_categories, a.pch_epic_pool_category.as_ref())
         {
-            println!(
+            debug!(
                 "Matched {} to epic {}",
                 a.pch_name.as_ref().unwrap(),
                 pcat.pch_name.as_ref().unwrap()
@@ -89,7 +126,7 @@ fn match_archetypes_to_power_categories(
         if let Some(mut pcat) =
             find_power_category(power_categories, a.pch_power_pool_category.as_ref())
         {
-            println!(
+            debug!(
                 "Matched {} to pool {}",
                 a.pch_name.as_ref().unwrap(),
                 pcat.pch_name.as_ref().unwrap()
@@ -98,7 +135,7 @@ fn match_archetypes_to_power_categories(
         }
         for pcat in &config.global_categories {
             if let Some(mut pcat) = find_power_category(power_categories, Some(pcat)) {
-                println!(
+                debug!(
                     "Matched {} to {}",
                     a.pch_name.as_ref().unwrap(),
                     pcat.pch_name.as_ref().unwrap()
@@ -243,6 +280,54 @@ fn resolve_entity_defs_and_power_grants(
     count_resolved
 }
 
+/// Resolves entity defs, power grants, and redirects in passes until a pass doesn't find anything
+/// new to include in the output.
+///
+/// # Arguments:
+///
+/// * `resolve_summons` - If `false`, entity defs and power grants are skipped and their
+///   `AttribModParam`s are left unresolved. Only redirects are followed.
+/// * `on_progress` - Called after each pass.
+///
+/// # Returns:
+///
+/// The number of passes made.
+fn resolve_references(
+    resolve_summons: bool,
+    villains: &Keyed<VillainDef>,
+    villain_archetypes: &Keyed<Archetype>,
+    power_cats: &Keyed<PowerCategory>,
+    power_sets: &Keyed<BasePowerSet>,
+    powers: &Keyed<BasePower>,
+    on_progress: &mut dyn FnMut(LoadProgress),
+) -> usize {
+    let mut pass = 0;
+    loop {
+        pass += 1;
+        // copy pet entity defs into powers
+        let mut count = if resolve_summons {
+            resolve_entity_defs_and_power_grants(
+                villains,
+                villain_archetypes,
+                power_cats,
+                power_sets,
+                powers,
+            )
+        } else {
+            0
+        };
+        // look for redirects and make sure the referenced powers are included in the output data
+        count += resolve_power_redirects(powers, power_cats, power_sets);
+        on_progress(LoadProgress::Resolving {
+            pass,
+            resolved: count,
+        });
+        if count == 0 {
+            return pass;
+        }
+    }
+}
+
 /// Mark the three parts represented by `power_ref` (category, set, power) to be included
 /// in the output set.
 fn mark_power_for_inclusion(
@@ -316,6 +401,241 @@ fn resolve_power_redirects(
     count_resolved
 }
 
+/// Moves each power into the power sets that list it in `pp_power_names`.
+///
+/// # Returns:
+///
+/// The names of any powers referenced by a power set that don't exist in `powers`.
+fn move_powers_into_sets(
+    power_sets: &mut Keyed<BasePowerSet>,
+    powers: &Keyed<BasePower>,
+) -> Vec<NameKey> {
+    let mut missing_powers = Vec::new();
+    for mut pset in power_sets.values_mut().map(|p| p.borrow_mut()) {
+        let power_names = pset.pp_power_names.clone();
+        for power_name in power_names {
+            if let Some(power) = powers.get(&power_name) {
+                pset.pp_powers.push(Rc::clone(power));
+            } else {
+                missing_powers.push(power_name);
+            }
+        }
+    }
+    missing_powers
+}
+
+/// Counts how many power sets list each power in `pp_power_names`.
+fn count_power_references(power_sets: &Keyed<BasePowerSet>) -> HashMap<NameKey, usize> {
+    let mut references = HashMap::new();
+    for pset in power_sets.values() {
+        for power_name in &pset.borrow().pp_power_names {
+            *references.entry(power_name.clone()).or_insert(0) += 1;
+        }
+    }
+    references
+}
+
+/// Moves the powers listed in `pset` into it. A power is removed from `powers` once the last
+/// power set referencing it (according to `references`) has taken it, so it's dropped along with
+/// that set.
+///
+/// # Returns:
+///
+/// The names of any powers referenced by `pset` that don't exist in `powers`.
+fn take_powers_into_set(
+    pset: &mut BasePowerSet,
+    powers: &mut HashMap<NameKey, ObjRef<BasePower>>,
+    references: &mut HashMap<NameKey, usize>,
+) -> Vec<NameKey> {
+    let mut missing_powers = Vec::new();
+    for power_name in &pset.pp_power_names {
+        let remaining = references.get_mut(power_name).map(|count| {
+            *count -= 1;
+            *count
+        });
+        let power = if remaining == Some(0) {
+            powers.remove(power_name)
+        } else {
+            powers.get(power_name).cloned()
+        };
+        match power {
+            Some(power) => pset.pp_powers.push(power),
+            None => missing_powers.push(power_name.clone()),
+        }
+    }
+    missing_powers
+}
+
+/// Tests if the power set named `pset_name` is removed by `filter_powersets` in the config.
+fn is_power_set_filtered(config: &PowersConfig, pset_name: &NameKey) -> bool {
+    config.filter_powersets.iter().any(|f| {
+        if f.get().contains('*') {
+            pset_name.matches_glob(f.get())
+        } else {
+            pset_name.partial_match(f.get())
+        }
+    })
+}
+
+/// Checks that each power set has exactly one available level per power.
+///
+/// # Returns:
+///
+/// The name, number of available levels, and number of powers for each power set that
+/// doesn't match.
+fn find_available_level_mismatches(
+    power_sets: &Keyed<BasePowerSet>,
+) -> Vec<(NameKey, usize, usize)> {
+    let mut mismatches = Vec::new();
+    for (pset_name, pset) in &power_sets.0 {
+        let pset = pset.borrow();
+        if pset.pi_available.len() != pset.pp_power_names.len() {
+            mismatches.push((
+                pset_name.clone(),
+                pset.pi_available.len(),
+                pset.pp_power_names.len(),
+            ));
+        }
+    }
+    mismatches
+}
+
+/// Counts the powers sharing each full name.
+///
+/// # Returns:
+///
+/// The names used by more than one power, with the number of powers using them, sorted by name.
+fn find_duplicate_power_names(powers: &Vec<BasePower>) -> Vec<(NameKey, usize)> {
+    let mut counts: HashMap<NameKey, usize> = HashMap::new();
+    for power_name in powers.iter().filter_map(|p| p.pch_full_name.as_ref()) {
+        *counts.entry(power_name.clone()).or_insert(0) += 1;
+    }
+    let mut duplicates: Vec<_> = counts.into_iter().filter(|(_, count)| *count > 1).collect();
+    duplicates.sort_by_cached_key(|(name, _)| name.get().to_ascii_lowercase());
+    duplicates
+}
+
+/// Reports powers sharing a full name, since only the last one read can be looked up by name.
+///
+/// # Returns:
+///
+/// Nothing if there are no duplicates or `strict` is `false` (the duplicates are reported as a
+/// warning instead). Otherwise, an `ErrContext` listing the duplicates.
+fn check_duplicate_power_names(powers: &Vec<BasePower>, strict: bool) -> Result<(), ErrContext> {
+    let duplicates = find_duplicate_power_names(powers);
+    if duplicates.is_empty() {
+        return Ok(());
+    }
+    let mut message = format!(
+        "{} power names are used by more than one power:",
+        duplicates.len()
+    );
+    for (power_name, count) in &duplicates {
+        message.push_str(&format!("\n\t{} ({} powers)", power_name, count));
+    }
+    if strict {
+        Err(ErrContext {
+            message: Cow::Owned(message),
+            error: bin_parse::ParseError::new(bin_parse::ParseErrorKind::DuplicateNameKey),
+        })
+    } else {
+        warn!("{}", message);
+        Ok(())
+    }
+}
+
+/// Removes any archetypes not listed in `config.filter_archetypes`, then excludes the power
+/// categories none of the remaining archetypes use, other than `config.global_categories`.
+/// Does nothing if the filter is empty.
+fn filter_archetypes(
+    config: &PowersConfig,
+    archetypes: &mut Keyed<Archetype>,
+    power_categories: &Keyed<PowerCategory>,
+) {
+    if config.filter_archetypes.is_empty() {
+        return;
+    }
+    archetypes.0.retain(|_, at| match &at.borrow().pch_name {
+        Some(name) => config
+            .filter_archetypes
+            .iter()
+            .any(|f| f.eq_ignore_ascii_case(name)),
+        None => false,
+    });
+    info!("Filtered to {} archetypes", archetypes.len());
+    let mut used_categories: Vec<&NameKey> = config.global_categories.iter().collect();
+    let archetypes: Vec<_> = archetypes.values().map(|at| at.borrow()).collect();
+    for at in &archetypes {
+        used_categories.extend(
+            [
+                &at.pch_primary_category,
+                &at.pch_secondary_category,
+                &at.pch_power_pool_category,
+                &at.pch_epic_pool_category,
+            ]
+            .iter()
+            .filter_map(|c| c.as_ref()),
+        );
+    }
+    for mut pcat in power_categories.values().map(|p| p.borrow_mut()) {
+        let is_used = match &pcat.pch_name {
+            Some(name) => used_categories.contains(&name),
+            None => false,
+        };
+        if !is_used {
+            pcat.top_level = false;
+            pcat.include_in_output = false;
+        }
+    }
+}
+
+/// Forces any powers listed in `exclude` out of the output data, regardless of how they
+/// were included.
+fn exclude_powers(exclude: &Vec<NameKey>, powers: &Keyed<BasePower>) {
+    for power_name in exclude {
+        if let Some(power) = powers.get(power_name) {
+            power.borrow_mut().include_in_output = false;
+        } else {
+            warn!("Excluded power {} not found.", power_name);
+        }
+    }
+}
+
+/// Excludes any powers whose display names don't contain one of the `filters` substrings, ignoring
+/// case, along with any power sets and power categories left without included powers.
+fn filter_powers_by_name(filters: &Vec<String>, power_categories: &Vec<ObjRef<PowerCategory>>) {
+    if filters.is_empty() {
+        return;
+    }
+    let filters: Vec<_> = filters.iter().map(|f| f.to_lowercase()).collect();
+    for mut pcat in power_categories.iter().map(|p| p.borrow_mut()) {
+        for mut pset in pcat.pp_power_sets.iter().map(|p| p.borrow_mut()) {
+            for mut power in pset.pp_powers.iter().map(|p| p.borrow_mut()) {
+                let matches = match &power.pch_display_name {
+                    Some(name) => {
+                        let name = name.to_lowercase();
+                        filters.iter().any(|f| name.contains(f.as_str()))
+                    }
+                    None => false,
+                };
+                if !matches {
+                    power.include_in_output = false;
+                }
+            }
+            pset.include_in_output = pset.include_in_output
+                && pset
+                    .pp_powers
+                    .iter()
+                    .any(|pwr| pwr.borrow().include_in_output);
+        }
+        pcat.include_in_output = pcat.include_in_output
+            && pcat
+                .pp_power_sets
+                .iter()
+                .any(|pset| pset.borrow().include_in_output);
+    }
+}
+
 /// Iterates through all of the enhancement set categories and tags the powers that can be enhanced
 /// by them.
 fn match_enh_categories_to_powers(boost_sets: &Keyed<BoostSet>, powers: &mut Keyed<BasePower>) {
@@ -381,45 +701,76 @@ fn fix_data_in_power_hierarchy(power_categories: &mut Vec<ObjRef<PowerCategory>>
 
 /// Read all .bin files and merge them into a single powers dictionary.
 pub fn load_powers_dictionary(config: &PowersConfig) -> Result<PowersDictionary, ErrContext> {
+    load_powers_dictionary_with_progress(config, &mut |_| ())
+}
+
+/// Read all .bin files and merge them into a single powers dictionary, calling `on_progress`
+/// as each step is reached.
+pub fn load_powers_dictionary_with_progress(
+    config: &PowersConfig,
+    on_progress: &mut dyn FnMut(LoadProgress),
+) -> Result<PowersDictionary, ErrContext> {
     let begin_time = Instant::now();
 
     // load everything
-    let messages = read_client_messages(config)?;
-    let attrib_names = read_attributes(config, &messages)?;
-    let archetypes = read_classes_bin(config, &messages)?;
-    let boost_sets = read_boostsets_bin(config, &messages)?;
-    let villain_archetypes = read_villain_classes_bin(config, &messages)?;
-    let villains = read_villaindef_bin(config, &messages)?;
-    let mut power_categories = read_powercats_bin(config, &messages)?;
+    let messages = read_client_messages(config, on_progress)?;
+    let attrib_names = read_attributes(config, &messages, on_progress)?;
+    let mut archetypes = read_classes_bin(config, &messages, on_progress)?;
+    let boost_sets = read_boostsets_bin(config, &messages, on_progress)?;
+    let (villain_archetypes, villains) = if config.resolve_summons {
+        read_villain_bins(config, &messages, on_progress)?
+    } else {
+        info!("Skipping villain classes and entity defs ...");
+        (Keyed::new(), Keyed::new())
+    };
+    let mut power_categories = read_powercats_bin(config, &messages, on_progress)?;
+    filter_archetypes(config, &mut archetypes, &power_categories);
 
     // match archetypes to power categories
-    println!("Matching archetypes to power categories ...");
+    info!("Matching archetypes to power categories ...");
+    on_progress(LoadProgress::Matching);
     match_archetypes_to_power_categories(&archetypes, &config, &mut power_categories);
 
     // read in power sets and powers
-    let mut power_sets = read_powersets_bin(config, &messages)?;
-    let mut powers = read_powers_bin(config, &messages)?;
+    let mut power_sets = read_powersets_bin(config, &messages, on_progress)?;
+    let power_list = read_powers_bin(config, &messages, on_progress)?;
+    check_duplicate_power_names(&power_list, config.strict)?;
+    let mut powers = Keyed::new();
+    for power in power_list {
+        if let Some(power_name) = power.pch_full_name.clone() {
+            powers.insert(power_name, power);
+        }
+    }
 
     // assign enhancement category names to individual powers
     match_enh_categories_to_powers(&boost_sets, &mut powers);
 
     // filter out power sets
-    power_sets.0.retain(|pset_name, _| {
-        !config
-            .filter_powersets
-            .iter()
-            .any(|f| pset_name.partial_match(f.get()))
-    });
+    power_sets
+        .0
+        .retain(|pset_name, _| !is_power_set_filtered(config, pset_name));
+
+    if config.validate_available_levels {
+        for (pset_name, available, power_count) in find_available_level_mismatches(&power_sets) {
+            warn!(
+                "Power set {} has {} available levels for {} powers.",
+                pset_name, available, power_count
+            );
+        }
+    }
 
-    println!("Merging dictionaries ...");
+    info!("Merging dictionaries ...");
     // move powers into their power sets
-    for mut pset in power_sets.values_mut().map(|p| p.borrow_mut()) {
-        let power_names = pset.pp_power_names.clone();
-        for power_name in &power_names {
-            if let Some(power) = powers.get(power_name) {
-                pset.pp_powers.push(Rc::clone(power));
-            }
+    let missing_powers = move_powers_into_sets(&mut power_sets, &powers);
+    if !missing_powers.is_empty() {
+        let mut warning = format!(
+            "{} powers referenced by power sets were not found:",
+            missing_powers.len()
+        );
+        for power_name in &missing_powers {
+            warning.push_str(&format!("\n\t{}", power_name));
         }
+        warn!("{}", warning);
     }
 
     // move power sets into their power categories
@@ -468,51 +819,216 @@ pub fn load_powers_dictionary(config: &PowersConfig) -> Result<PowersDictionary,
             }
         });
 
-    println!("Resolving entity defs, power grants, and redirects ...");
-    loop {
-        // copy pet entity defs into powers
-        let mut count = resolve_entity_defs_and_power_grants(
-            &villains,
-            &villain_archetypes,
-            &mut power_categories,
-            &mut power_sets,
-            &mut powers,
-        );
-        // look for redirects and make sure the referenced powers are included in the output data
-        count += resolve_power_redirects(&mut powers, &mut power_categories, &mut power_sets);
-        if count == 0 {
-            break;
-        }
-    }
+    info!("Resolving entity defs, power grants, and redirects ...");
+    resolve_references(
+        config.resolve_summons,
+        &villains,
+        &villain_archetypes,
+        &power_categories,
+        &power_sets,
+        &powers,
+        on_progress,
+    );
+
+    // remove any individually excluded powers
+    exclude_powers(&config.exclude_powers, &powers);
+
+    // keep only powers matching the display name filters
+    filter_powers_by_name(&config.filter_powers_by_name, &power_categories_returned);
 
-    println!("Final clean up ...");
+    info!("Final clean up ...");
     fix_data_in_power_hierarchy(&mut power_categories_returned);
 
     let elapsed = Instant::now().duration_since(begin_time);
-    println!("Done.");
-    println!("Powers dictionary parsed in {} seconds.", elapsed.as_secs());
-    Ok(PowersDictionary {
-        power_categories: power_categories_returned,
+    info!("Done.");
+    info!("Powers dictionary parsed in {} seconds.", elapsed.as_secs());
+    on_progress(LoadProgress::Done {
+        seconds: elapsed.as_secs(),
+    });
+    Ok(PowersDictionary::new(
+        power_categories_returned,
         archetypes,
-        attrib_names: Rc::new(attrib_names),
-    })
+        boost_sets,
+        Rc::new(attrib_names),
+        if config.retain_orphans {
+            Some(powers)
+        } else {
+            None
+        },
+    ))
+}
+
+/// Calls `on_power_set` with each power set as soon as all of its powers have been read, without
+/// building a whole powers dictionary.
+///
+/// # Arguments:
+///
+/// * `config` - The config, for the input path and `filter_powersets`.
+/// * `on_power_set` - Called once per power set. An error stops the iteration and is returned.
+///
+/// # Returns:
+///
+/// Nothing if every power set was visited. Otherwise, a `StreamPowerSetsError`.
+///
+/// # Notes:
+///
+/// Power sets only list their powers by name, so all of the power sets are read up front. They're
+/// small compared to the powers, which are read from powers.bin one at a time. A power is kept
+/// only until every set listing it has been visited, and each set is dropped after `on_power_set`
+/// returns. The powers in a set are usually stored together in the .bin, so only a few are held
+/// at once. Sets with powers that don't exist are visited after the whole file has been read.
+///
+/// Nothing is resolved or indexed: entity defs, power grants, and redirects are left unresolved.
+/// Attribute names and archetypes are loaded first since powers reference them. Each power's
+/// `archetypes` are set from the categories listing the set being visited, so a power shared by
+/// several sets may see different archetypes in each. The attribute names are entered with
+/// `AttribNamesScope` while `on_power_set` runs so powers can be serialized directly.
+// entry point for tooling built on this crate, the CLI always loads the whole dictionary
+#[allow(dead_code)]
+pub fn stream_power_sets(
+    config: &PowersConfig,
+    on_power_set: &mut dyn FnMut(&BasePowerSet) -> io::Result<()>,
+) -> Result<(), StreamPowerSetsError> {
+    let on_progress = &mut |_| ();
+    let messages = read_client_messages(config, on_progress)?;
+    let attrib_names = Rc::new(read_attributes(config, &messages, on_progress)?);
+    let archetypes = read_classes_bin(config, &messages, on_progress)?;
+    let mut power_categories = read_powercats_bin(config, &messages, on_progress)?;
+    match_archetypes_to_power_categories(&archetypes, config, &mut power_categories);
+    let mut set_archetypes: HashMap<NameKey, Vec<ObjRef<Archetype>>> = HashMap::new();
+    for pcat in power_categories.values().map(|p| p.borrow()) {
+        for pset_name in &pcat.ppch_power_set_names {
+            set_archetypes
+                .entry(pset_name.clone())
+                .or_default()
+                .extend(pcat.archetypes.iter().cloned());
+        }
+    }
+    drop(power_categories);
+
+    let mut power_sets = read_powersets_bin(config, &messages, on_progress)?;
+    power_sets
+        .0
+        .retain(|pset_name, _| !is_power_set_filtered(config, pset_name));
+    let mut references = count_power_references(&power_sets);
+    // the sets waiting on each power, and how many powers each set is still waiting on
+    let mut power_to_sets: HashMap<NameKey, Vec<NameKey>> = HashMap::new();
+    let mut pending: HashMap<NameKey, usize> = HashMap::new();
+    for (pset_name, pset) in power_sets.0.iter() {
+        let pset = pset.borrow();
+        for power_name in &pset.pp_power_names {
+            power_to_sets
+                .entry(power_name.clone())
+                .or_default()
+                .push(pset_name.clone());
+        }
+        pending.insert(pset_name.clone(), pset.pp_power_names.len());
+    }
+
+    let _scope = AttribNamesScope::enter(attrib_names);
+    let mut visit = |pset_name: &NameKey,
+                     powers: &mut HashMap<NameKey, ObjRef<BasePower>>,
+                     references: &mut HashMap<NameKey, usize>|
+     -> io::Result<()> {
+        if let Some(pset) = power_sets.0.remove(pset_name) {
+            let mut pset = pset.borrow_mut();
+            let missing_powers = take_powers_into_set(&mut pset, powers, references);
+            for power_name in &missing_powers {
+                warn!(
+                    "Power {} referenced by {} was not found.",
+                    power_name, pset_name
+                );
+            }
+            set_power_archetypes(&pset, set_archetypes.get(pset_name));
+            on_power_set(&pset)?;
+        }
+        Ok(())
+    };
+
+    // sets without any powers don't have to wait for powers.bin
+    let empty_sets: Vec<_> = pending
+        .iter()
+        .filter(|(_, count)| **count == 0)
+        .map(|(pset_name, _)| pset_name.clone())
+        .collect();
+    let mut powers: HashMap<NameKey, ObjRef<BasePower>> = HashMap::new();
+    for pset_name in &empty_sets {
+        visit(pset_name, &mut powers, &mut references)?;
+    }
+
+    let (mut reader, strings) = open_powers_bin(config, on_progress)?;
+    let power_iter =
+        bin_parse::serialized_iter_powers(&mut reader, &strings, &messages, config.frame_rate)
+            .map_err(|e| ecxt!("Unable to parse powers table.", e))?;
+    for power in power_iter {
+        let power = power.map_err(|e| ecxt!("Unable to parse powers table.", e))?;
+        let power_name = match power.pch_full_name.clone() {
+            Some(power_name) => power_name,
+            None => continue,
+        };
+        // powers no set uses are dropped right away, as are duplicates of a power already read
+        let pset_names = match power_to_sets.remove(&power_name) {
+            Some(pset_names) => pset_names,
+            None => continue,
+        };
+        powers.insert(power_name, Rc::new(RefCell::new(power)));
+        for pset_name in &pset_names {
+            if let Some(count) = pending.get_mut(pset_name) {
+                *count -= 1;
+                if *count == 0 {
+                    visit(pset_name, &mut powers, &mut references)?;
+                }
+            }
+        }
+    }
+
+    // whatever is left is missing at least one power
+    let mut incomplete: Vec<_> = pending
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(pset_name, _)| pset_name)
+        .collect();
+    incomplete.sort_by(|a, b| a.get().cmp(b.get()));
+    for pset_name in &incomplete {
+        visit(pset_name, &mut powers, &mut references)?;
+    }
+    Ok(())
+}
+
+/// Replaces the `archetypes` of every power in `pset` with `archetypes`, or clears them if no
+/// category lists the set. Shared powers are visited once per set, so anything left over from
+/// a previous set has to be overwritten.
+fn set_power_archetypes(pset: &BasePowerSet, archetypes: Option<&Vec<ObjRef<Archetype>>>) {
+    for power in &pset.pp_powers {
+        power.borrow_mut().archetypes = archetypes.cloned().unwrap_or_default();
+    }
 }
 
 /// Read in the clientmessages-en.bin data.
-fn read_client_messages(config: &PowersConfig) -> Result<MessageStore, ErrContext> {
+fn read_client_messages(
+    config: &PowersConfig,
+    on_progress: &mut dyn FnMut(LoadProgress),
+) -> Result<MessageStore, ErrContext> {
     let ms_path = config.join_to_input_path(MESSAGESTORE_BIN);
-    println!("Reading {} ...", ms_path.display());
+    info!("Reading {} ...", ms_path.display());
+    on_progress(LoadProgress::ReadingFile(ms_path.display().to_string()));
     let mut reader = bin_parse::messagestore::open_message_store(&ms_path)
         .map_err(|e| ecxt!("Unable to open client messages!", e))?;
 
     let mut messages = MessageStore::new();
-    messages.messages = bin_parse::messagestore::read_string_table(&mut reader)
-        .map_err(|e| ecxt!("Unable to read message string table!", e))?;
-    messages.variables = bin_parse::messagestore::read_string_table(&mut reader)
-        .map_err(|e| ecxt!("Unable to read variable string table!", e))?;
-    bin_parse::messagestore::read_message_ids(&mut reader, &mut messages)
+    messages.messages =
+        bin_parse::messagestore::read_string_table(&mut reader, config.string_encoding)
+            .map_err(|e| ecxt!("Unable to read message string table!", e))?;
+    messages.variables =
+        bin_parse::messagestore::read_string_table(&mut reader, config.string_encoding)
+            .map_err(|e| ecxt!("Unable to read variable string table!", e))?;
+    bin_parse::messagestore::read_message_ids(&mut reader, &mut messages, config.string_encoding)
         .map_err(|e| ecxt!("Unable to read message IDs!", e))?;
-    println!("Message store contains {} entries.", messages.len_ids());
+    info!("Message store contains {} entries.", messages.len_ids());
+    on_progress(LoadProgress::ReadCount {
+        kind: "messages",
+        n: messages.len_ids(),
+    });
     Ok(messages)
 }
 
@@ -520,15 +1036,29 @@ fn read_client_messages(config: &PowersConfig) -> Result<MessageStore, ErrContex
 fn read_attributes(
     config: &PowersConfig,
     messages: &MessageStore,
+    on_progress: &mut dyn FnMut(LoadProgress),
 ) -> Result<AttribNames, ErrContext> {
     let attr_path = config.join_to_input_path(ATTRIB_NAMES_BIN);
-    println!("Reading {} ...", attr_path.display());
+    info!("Reading {} ...", attr_path.display());
+    on_progress(LoadProgress::ReadingFile(attr_path.display().to_string()));
     let mut reader = bin_parse::open_serialized(&attr_path)
         .map_err(|e| ecxt!("Unable to open attributes!", e))?;
-    let strings = bin_parse::serialized_read_string_pool(&mut reader)
+    let strings = bin_parse::serialized_read_string_pool(&mut reader, config.string_encoding)
         .map_err(|e| ecxt!("Unable to parse string pool!", e))?;
     let attribs = bin_parse::serialized_read_attribs(&mut reader, &strings, messages)
         .map_err(|e| ecxt!("Unable to read attribute names!", e))?;
+    let count = attribs.pp_defense.len()
+        + attribs.pp_damage.len()
+        + attribs.pp_boost.len()
+        + attribs.pp_group.len()
+        + attribs.pp_mode.len()
+        + attribs.pp_elusivity.len()
+        + attribs.pp_stack_key.len();
+    info!("Read {} attribute names.", count);
+    on_progress(LoadProgress::ReadCount {
+        kind: "attribute names",
+        n: count,
+    });
     Ok(attribs)
 }
 
@@ -536,16 +1066,24 @@ fn read_attributes(
 fn read_classes_bin(
     config: &PowersConfig,
     messages: &MessageStore,
+    on_progress: &mut dyn FnMut(LoadProgress),
 ) -> Result<Keyed<Archetype>, ErrContext> {
     let classes_path = config.join_to_input_path(CLASSES_BIN);
-    println!("Reading {} ...", classes_path.display());
+    info!("Reading {} ...", classes_path.display());
+    on_progress(LoadProgress::ReadingFile(
+        classes_path.display().to_string(),
+    ));
     let mut reader = bin_parse::open_serialized(&classes_path)
         .map_err(|e| ecxt!("Unable to open classes!", e))?;
-    let strings = bin_parse::serialized_read_string_pool(&mut reader)
+    let strings = bin_parse::serialized_read_string_pool(&mut reader, config.string_encoding)
         .map_err(|e| ecxt!("Unable to parse string pool!", e))?;
     let archetypes = bin_parse::serialized_read_archetypes(&mut reader, &strings, messages, false)
         .map_err(|e| ecxt!("Unable to parse classes table.", e))?;
-    println!("Read {} archetypes.", archetypes.len());
+    info!("Read {} archetypes.", archetypes.len());
+    on_progress(LoadProgress::ReadCount {
+        kind: "archetypes",
+        n: archetypes.len(),
+    });
     Ok(archetypes)
 }
 
@@ -553,16 +1091,22 @@ fn read_classes_bin(
 fn read_powercats_bin(
     config: &PowersConfig,
     messages: &MessageStore,
+    on_progress: &mut dyn FnMut(LoadProgress),
 ) -> Result<Keyed<PowerCategory>, ErrContext> {
     let pc_path = config.join_to_input_path(POWER_CATEGORIES_BIN);
-    println!("Reading {} ...", pc_path.display());
+    info!("Reading {} ...", pc_path.display());
+    on_progress(LoadProgress::ReadingFile(pc_path.display().to_string()));
     let mut reader = bin_parse::open_serialized(&pc_path)
         .map_err(|e| ecxt!("Unable to open power categories!", e))?;
-    let strings = bin_parse::serialized_read_string_pool(&mut reader)
+    let strings = bin_parse::serialized_read_string_pool(&mut reader, config.string_encoding)
         .map_err(|e| ecxt!("Unable to parse string pool!", e))?;
     let powercats = bin_parse::serialized_read_power_categories(&mut reader, &strings, messages)
         .map_err(|e| ecxt!("Unable to parse power categories table.", e))?;
-    println!("Read {} power categories.", powercats.len());
+    info!("Read {} power categories.", powercats.len());
+    on_progress(LoadProgress::ReadCount {
+        kind: "power categories",
+        n: powercats.len(),
+    });
     if config.power_categories.len() > 0 {
         powercats
             .values()
@@ -581,10 +1125,10 @@ fn read_powercats_bin(
             .filter(|pcat| pcat.borrow().top_level)
             .count();
         if top_level_count == 0 {
-            println!("No power categories to work on. Did you filter them all?");
+            error!("No power categories to work on. Did you filter them all?");
             process::exit(1);
         }
-        println!("Filtered to {} top level categories", top_level_count);
+        info!("Filtered to {} top level categories", top_level_count);
     } else {
         powercats.values().for_each(|pcat| {
             pcat.borrow_mut().top_level = true;
@@ -597,16 +1141,22 @@ fn read_powercats_bin(
 fn read_powersets_bin(
     config: &PowersConfig,
     messages: &MessageStore,
+    on_progress: &mut dyn FnMut(LoadProgress),
 ) -> Result<Keyed<BasePowerSet>, ErrContext> {
     let ps_path = config.join_to_input_path(POWER_SETS_BIN);
-    println!("Reading {} ...", ps_path.display());
+    info!("Reading {} ...", ps_path.display());
+    on_progress(LoadProgress::ReadingFile(ps_path.display().to_string()));
     let mut reader =
         bin_parse::open_serialized(&ps_path).map_err(|e| ecxt!("Unable to open power sets!", e))?;
-    let strings = bin_parse::serialized_read_string_pool(&mut reader)
+    let strings = bin_parse::serialized_read_string_pool(&mut reader, config.string_encoding)
         .map_err(|e| ecxt!("Unable to parse string pool!", e))?;
     let powersets = bin_parse::serialized_read_powersets(&mut reader, &strings, messages)
         .map_err(|e| ecxt!("Unable to parse power sets table.", e))?;
-    println!("Read {} power sets.", powersets.len());
+    info!("Read {} power sets.", powersets.len());
+    on_progress(LoadProgress::ReadCount {
+        kind: "power sets",
+        n: powersets.len(),
+    });
     Ok(powersets)
 }
 
@@ -614,33 +1164,82 @@ fn read_powersets_bin(
 fn read_powers_bin(
     config: &PowersConfig,
     messages: &MessageStore,
-) -> Result<Keyed<BasePower>, ErrContext> {
+    on_progress: &mut dyn FnMut(LoadProgress),
+) -> Result<Vec<BasePower>, ErrContext> {
+    let (mut reader, strings) = open_powers_bin(config, on_progress)?;
+    let powers =
+        bin_parse::serialized_read_powers(&mut reader, &strings, messages, config.frame_rate)
+            .map_err(|e| ecxt!("Unable to parse powers table.", e))?;
+    info!("Read {} powers.", powers.len());
+    on_progress(LoadProgress::ReadCount {
+        kind: "powers",
+        n: powers.len(),
+    });
+    Ok(powers)
+}
+
+/// Opens the powers.bin data and reads its string pool, leaving the reader at the powers table.
+fn open_powers_bin(
+    config: &PowersConfig,
+    on_progress: &mut dyn FnMut(LoadProgress),
+) -> Result<(io::BufReader<fs::File>, StringPool), ErrContext> {
     let pwr_path = config.join_to_input_path(POWERS_BIN);
-    println!("Reading {} ...", pwr_path.display());
+    info!("Reading {} ...", pwr_path.display());
+    on_progress(LoadProgress::ReadingFile(pwr_path.display().to_string()));
     let mut reader =
         bin_parse::open_serialized(&pwr_path).map_err(|e| ecxt!("Unable to open powers!", e))?;
-    let strings = bin_parse::serialized_read_string_pool(&mut reader)
+    let strings = bin_parse::serialized_read_string_pool(&mut reader, config.string_encoding)
         .map_err(|e| ecxt!("Unable to parse string pool!", e))?;
-    let powers = bin_parse::serialized_read_powers(&mut reader, &strings, messages)
-        .map_err(|e| ecxt!("Unable to parse powers table.", e))?;
-    println!("Read {} powers.", powers.len());
-    Ok(powers)
+    Ok((reader, strings))
+}
+
+/// Reads the villain_classes.bin and villaindef.bin data. If either file doesn't exist and
+/// `config.allow_missing_villains` is set, logs a warning and returns empty dictionaries so
+/// summoned entities are left unresolved. Files that exist but can't be parsed are still errors.
+fn read_villain_bins(
+    config: &PowersConfig,
+    messages: &MessageStore,
+    on_progress: &mut dyn FnMut(LoadProgress),
+) -> Result<(Keyed<Archetype>, Keyed<VillainDef>), ErrContext> {
+    let result = match read_villain_classes_bin(config, messages, on_progress) {
+        Ok(villain_archetypes) => read_villaindef_bin(config, messages, on_progress)
+            .map(|villains| (villain_archetypes, villains)),
+        Err(context) => Err(context),
+    };
+    match result {
+        Err(context) if config.allow_missing_villains && context.error.is_file_not_found() => {
+            warn!(
+                "{} File not found, summoned entities won't be resolved.",
+                context.message
+            );
+            Ok((Keyed::new(), Keyed::new()))
+        }
+        result => result,
+    }
 }
 
 /// Read in the villain_classes.bin data.
 fn read_villain_classes_bin(
     config: &PowersConfig,
     messages: &MessageStore,
+    on_progress: &mut dyn FnMut(LoadProgress),
 ) -> Result<Keyed<Archetype>, ErrContext> {
     let classes_path = config.join_to_input_path(VILLAIN_CLASSES_BIN);
-    println!("Reading {} ...", classes_path.display());
+    info!("Reading {} ...", classes_path.display());
+    on_progress(LoadProgress::ReadingFile(
+        classes_path.display().to_string(),
+    ));
     let mut reader = bin_parse::open_serialized(&classes_path)
         .map_err(|e| ecxt!("Unable to open classes!", e))?;
-    let strings = bin_parse::serialized_read_string_pool(&mut reader)
+    let strings = bin_parse::serialized_read_string_pool(&mut reader, config.string_encoding)
         .map_err(|e| ecxt!("Unable to parse string pool!", e))?;
     let archetypes = bin_parse::serialized_read_archetypes(&mut reader, &strings, messages, true)
         .map_err(|e| ecxt!("Unable to parse classes table.", e))?;
-    println!("Read {} villain archetypes.", archetypes.len());
+    info!("Read {} villain archetypes.", archetypes.len());
+    on_progress(LoadProgress::ReadCount {
+        kind: "villain archetypes",
+        n: archetypes.len(),
+    });
     Ok(archetypes)
 }
 
@@ -648,16 +1247,24 @@ fn read_villain_classes_bin(
 fn read_villaindef_bin(
     config: &PowersConfig,
     messages: &MessageStore,
+    on_progress: &mut dyn FnMut(LoadProgress),
 ) -> Result<Keyed<VillainDef>, ErrContext> {
     let villain_path = config.join_to_input_path(VILLAIN_DEF_BIN);
-    println!("Reading {} ...", villain_path.display());
+    info!("Reading {} ...", villain_path.display());
+    on_progress(LoadProgress::ReadingFile(
+        villain_path.display().to_string(),
+    ));
     let mut reader = bin_parse::open_serialized(&villain_path)
         .map_err(|e| ecxt!("Unable to open villains!", e))?;
-    let strings = bin_parse::serialized_read_string_pool(&mut reader)
+    let strings = bin_parse::serialized_read_string_pool(&mut reader, config.string_encoding)
         .map_err(|e| ecxt!("Unable to parse string pool!", e))?;
     let villains = bin_parse::serialized_read_villains(&mut reader, &strings, messages)
         .map_err(|e| ecxt!("Unable to parse villains table.", e))?;
-    println!("Read {} villain definitions.", villains.len());
+    info!("Read {} villain definitions.", villains.len());
+    on_progress(LoadProgress::ReadCount {
+        kind: "villain definitions",
+        n: villains.len(),
+    });
     Ok(villains)
 }
 
@@ -665,15 +1272,618 @@ fn read_villaindef_bin(
 fn read_boostsets_bin(
     config: &PowersConfig,
     messages: &MessageStore,
+    on_progress: &mut dyn FnMut(LoadProgress),
 ) -> Result<Keyed<BoostSet>, ErrContext> {
     let boostsets_path = config.join_to_input_path(BOOST_SETS_BIN);
-    println!("Reading {} ...", boostsets_path.display());
+    info!("Reading {} ...", boostsets_path.display());
+    on_progress(LoadProgress::ReadingFile(
+        boostsets_path.display().to_string(),
+    ));
     let mut reader = bin_parse::open_serialized(&boostsets_path)
         .map_err(|e| ecxt!("Unable to open boost sets!", e))?;
-    let strings = bin_parse::serialized_read_string_pool(&mut reader)
+    let strings = bin_parse::serialized_read_string_pool(&mut reader, config.string_encoding)
         .map_err(|e| ecxt!("Unable to parse string pool!", e))?;
     let boost_sets = bin_parse::serialized_read_boost_sets(&mut reader, &strings, messages)
         .map_err(|e| ecxt!("Unable to parse boost sets table.", e))?;
-    println!("Read {} boost sets.", boost_sets.len());
+    info!("Read {} boost sets.", boost_sets.len());
+    on_progress(LoadProgress::ReadCount {
+        kind: "boost sets",
+        n: boost_sets.len(),
+    });
     Ok(boost_sets)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_references_without_summons_test() {
+        // a player summon power and a pet power that's only reachable through the entity def
+        let make_powers = || {
+            let mut entcreate = AttribModParam_EntCreate::new();
+            entcreate.pch_entity_def = Some(NameKey::new("Pets_Thugs_Thug"));
+            let mut template = AttribModTemplate::new();
+            template.p_params = Some(AttribModParam::EntCreate(entcreate));
+            let mut effect_group = EffectGroup::new();
+            effect_group.pp_templates.push(template);
+            let mut summon = BasePower::new();
+            summon.pch_full_name = Some(NameKey::new("Mastermind_Summon.Thugs.Call_Thugs"));
+            summon.include_in_output = true;
+            summon.pp_effects.push(Rc::new(RefCell::new(effect_group)));
+            let mut pet_power = BasePower::new();
+            pet_power.pch_full_name = Some(NameKey::new("Villain_Pets.Thugs.Pistols"));
+            let mut powers = Keyed::<BasePower>::new();
+            for power in vec![summon, pet_power] {
+                powers.insert(power.pch_full_name.clone().unwrap(), power);
+            }
+            powers
+        };
+        let mut villain = VillainDef::new();
+        villain.powers.push(PowerNameRef {
+            power_category: Some(NameKey::new("Villain_Pets")),
+            power_set: Some(NameKey::new("Thugs")),
+            power: Some(NameKey::new("Pistols")),
+            ..Default::default()
+        });
+        let mut villains = Keyed::<VillainDef>::new();
+        villains.insert(NameKey::new("Pets_Thugs_Thug"), villain);
+        let entcreate_of = |powers: &Keyed<BasePower>| {
+            let summon = powers
+                .get(&NameKey::new("Mastermind_Summon.Thugs.Call_Thugs"))
+                .unwrap()
+                .borrow();
+            let effect_group = summon.pp_effects[0].borrow();
+            match &effect_group.pp_templates[0].p_params {
+                Some(AttribModParam::EntCreate(e)) => (e.resolved, e.villain_def.is_some()),
+                _ => unreachable!(),
+            }
+        };
+        let is_included = |powers: &Keyed<BasePower>, name: &str| {
+            powers.get(&NameKey::new(name)).unwrap().borrow().include_in_output
+        };
+
+        // as loaded with resolve_summons = false, where the villain bins are never read
+        let powers = make_powers();
+        resolve_references(
+            false,
+            &Keyed::new(),
+            &Keyed::new(),
+            &Keyed::new(),
+            &Keyed::new(),
+            &powers,
+            &mut |_| (),
+        );
+        assert!(is_included(&powers, "Mastermind_Summon.Thugs.Call_Thugs"));
+        assert!(!is_included(&powers, "Villain_Pets.Thugs.Pistols"));
+        assert_eq!(entcreate_of(&powers), (false, false));
+
+        let powers = make_powers();
+        resolve_references(
+            true,
+            &villains,
+            &Keyed::new(),
+            &Keyed::new(),
+            &Keyed::new(),
+            &powers,
+            &mut |_| (),
+        );
+        assert!(is_included(&powers, "Mastermind_Summon.Thugs.Call_Thugs"));
+        assert!(is_included(&powers, "Villain_Pets.Thugs.Pistols"));
+        assert_eq!(entcreate_of(&powers), (true, true));
+    }
+
+    #[test]
+    fn exclude_powers_test() {
+        let mut powers = Keyed::<BasePower>::new();
+        for name in &["Pool.Test.Good_Power", "Pool.Test.Broken_Power"] {
+            let mut power = BasePower::new();
+            power.pch_full_name = Some(NameKey::new(*name));
+            power.include_in_output = true;
+            powers.insert(NameKey::new(*name), power);
+        }
+
+        exclude_powers(&vec![NameKey::new("pool.test.broken_power")], &powers);
+        let is_included =
+            |name: &str| powers.get(&NameKey::new(name)).unwrap().borrow().include_in_output;
+        assert!(!is_included("Pool.Test.Broken_Power"));
+        assert!(is_included("Pool.Test.Good_Power"));
+    }
+
+    #[test]
+    fn filter_archetypes_test() {
+        let mut config: PowersConfig =
+            toml::from_str(crate::structs::config::MINIMAL_CONFIG).unwrap();
+        config.filter_archetypes = vec![String::from("class_tanker")];
+        config.global_categories = vec![NameKey::new("Inherent")];
+        let mut archetypes = Keyed::<Archetype>::new();
+        for (name, primary, secondary) in &[
+            ("Class_Tanker", "Tanker_Defense", "Tanker_Melee"),
+            ("Class_Blaster", "Blaster_Ranged", "Blaster_Support"),
+        ] {
+            let mut at = Archetype::new();
+            at.pch_name = Some(String::from(*name));
+            at.pch_primary_category = Some(NameKey::new(*primary));
+            at.pch_secondary_category = Some(NameKey::new(*secondary));
+            at.pch_power_pool_category = Some(NameKey::new("Pool"));
+            archetypes.insert(NameKey::new(*name), at);
+        }
+        let mut power_categories = Keyed::<PowerCategory>::new();
+        for name in &[
+            "Tanker_Defense",
+            "Tanker_Melee",
+            "Blaster_Ranged",
+            "Blaster_Support",
+            "Pool",
+            "Inherent",
+            "Teamwork",
+        ] {
+            let mut pcat = PowerCategory::new();
+            pcat.pch_name = Some(NameKey::new(*name));
+            pcat.top_level = true;
+            pcat.include_in_output = true;
+            power_categories.insert(NameKey::new(*name), pcat);
+        }
+
+        filter_archetypes(&config, &mut archetypes, &power_categories);
+        assert_eq!(archetypes.len(), 1);
+        assert!(archetypes.get(&NameKey::new("Class_Tanker")).is_some());
+        let is_included = |name: &str| {
+            let pcat = power_categories.get(&NameKey::new(name)).unwrap().borrow();
+            pcat.top_level && pcat.include_in_output
+        };
+        assert!(is_included("Tanker_Defense"));
+        assert!(is_included("Tanker_Melee"));
+        assert!(is_included("Pool"));
+        assert!(is_included("Inherent"));
+        assert!(!is_included("Blaster_Ranged"));
+        assert!(!is_included("Blaster_Support"));
+        assert!(!is_included("Teamwork"));
+    }
+
+    #[test]
+    fn filter_powers_by_name_test() {
+        let make_set = |names: &[&str]| {
+            let mut pset = BasePowerSet::new();
+            pset.include_in_output = true;
+            for name in names {
+                let mut power = BasePower::new();
+                power.pch_display_name = Some(String::from(*name));
+                power.include_in_output = true;
+                pset.pp_powers.push(Rc::new(RefCell::new(power)));
+            }
+            Rc::new(RefCell::new(pset))
+        };
+        let mut brawling = PowerCategory::new();
+        brawling.include_in_output = true;
+        brawling.pp_power_sets = vec![make_set(&["Brawl", "Sprint"]), make_set(&["Rest"])];
+        let mut flight = PowerCategory::new();
+        flight.include_in_output = true;
+        flight.pp_power_sets = vec![make_set(&["Hover", "Fly"])];
+        let power_categories = vec![
+            Rc::new(RefCell::new(brawling)),
+            Rc::new(RefCell::new(flight)),
+        ];
+
+        filter_powers_by_name(&vec![String::from("bRAWL")], &power_categories);
+        let brawling = power_categories[0].borrow();
+        assert!(brawling.include_in_output);
+        let included: Vec<_> = brawling.pp_power_sets[0]
+            .borrow()
+            .pp_powers
+            .iter()
+            .map(|p| p.borrow().include_in_output)
+            .collect();
+        assert_eq!(included, vec![true, false]);
+        assert!(brawling.pp_power_sets[0].borrow().include_in_output);
+        assert!(!brawling.pp_power_sets[1].borrow().include_in_output);
+        assert!(!power_categories[1].borrow().include_in_output);
+    }
+
+    #[test]
+    fn duplicate_power_names_test() {
+        let powers: Vec<_> = [
+            "Pool.Test.Unique",
+            "Pool.Test.Clobbered",
+            "Pool.Test.Redirect",
+            "pool.test.clobbered",
+            "Pool.Test.Redirect",
+            "Pool.Test.Redirect",
+        ]
+        .iter()
+        .map(|name| {
+            let mut power = BasePower::new();
+            power.pch_full_name = Some(NameKey::new(*name));
+            power
+        })
+        .collect();
+
+        let duplicates = find_duplicate_power_names(&powers);
+        assert_eq!(
+            duplicates,
+            vec![
+                (NameKey::new("Pool.Test.Clobbered"), 2),
+                (NameKey::new("Pool.Test.Redirect"), 3)
+            ]
+        );
+
+        assert!(check_duplicate_power_names(&powers, false).is_ok());
+        let context = check_duplicate_power_names(&powers, true).unwrap_err();
+        assert!(matches!(
+            context.error.kind(),
+            bin_parse::ParseErrorKind::DuplicateNameKey
+        ));
+        assert!(context.message.contains("Pool.Test.Redirect (3 powers)"));
+        let unique: Vec<_> = powers.into_iter().take(2).collect();
+        assert!(check_duplicate_power_names(&unique, true).is_ok());
+    }
+
+    #[test]
+    fn set_power_archetypes_test() {
+        let mut shared = BasePower::new();
+        shared.pch_full_name = Some(NameKey::new("Pool.Shared"));
+        let shared = Rc::new(RefCell::new(shared));
+        let mut first = BasePowerSet::new();
+        first.pp_powers.push(Rc::clone(&shared));
+        let mut second = BasePowerSet::new();
+        second.pp_powers.push(Rc::clone(&shared));
+
+        let archetypes = vec![Rc::new(RefCell::new(Archetype::new()))];
+        set_power_archetypes(&first, Some(&archetypes));
+        assert_eq!(shared.borrow().archetypes.len(), 1);
+        // the second set isn't in any category, so it mustn't see the first set's archetypes
+        set_power_archetypes(&second, None);
+        assert!(shared.borrow().archetypes.is_empty());
+    }
+
+    #[test]
+    fn take_powers_into_set_test() {
+        let mut power_sets = Keyed::<BasePowerSet>::new();
+        for (pset_name, power_names) in &[
+            ("Pool.First", vec!["Pool.Shared", "Pool.Only_First"]),
+            ("Pool.Second", vec!["Pool.Shared", "Pool.Missing"]),
+        ] {
+            let mut pset = BasePowerSet::new();
+            pset.pp_power_names = power_names.iter().map(|n| NameKey::new(*n)).collect();
+            power_sets.insert(NameKey::new(*pset_name), pset);
+        }
+        let mut references = count_power_references(&power_sets);
+        assert_eq!(references[&NameKey::new("Pool.Shared")], 2);
+        let mut powers = HashMap::new();
+        for name in &["Pool.Shared", "Pool.Only_First"] {
+            let mut power = BasePower::new();
+            power.pch_full_name = Some(NameKey::new(*name));
+            powers.insert(NameKey::new(*name), Rc::new(RefCell::new(power)));
+        }
+
+        let first = power_sets.get(&NameKey::new("Pool.First")).unwrap();
+        let missing = take_powers_into_set(&mut first.borrow_mut(), &mut powers, &mut references);
+        assert!(missing.is_empty());
+        assert_eq!(first.borrow().pp_powers.len(), 2);
+        // the shared power is still needed by the second set
+        assert!(powers.contains_key(&NameKey::new("Pool.Shared")));
+        assert!(!powers.contains_key(&NameKey::new("Pool.Only_First")));
+
+        let second = power_sets.get(&NameKey::new("Pool.Second")).unwrap();
+        let missing = take_powers_into_set(&mut second.borrow_mut(), &mut powers, &mut references);
+        assert_eq!(missing, vec![NameKey::new("Pool.Missing")]);
+        assert_eq!(second.borrow().pp_powers.len(), 1);
+        assert!(powers.is_empty());
+    }
+
+    #[test]
+    fn missing_set_powers_test() {
+        let mut powers = Keyed::<BasePower>::new();
+        powers.insert(NameKey::new("Pool.Test.Real_Power"), BasePower::new());
+        let mut power_sets = Keyed::<BasePowerSet>::new();
+        let mut pset = BasePowerSet::new();
+        pset.pp_power_names.push(NameKey::new("Pool.Test.Real_Power"));
+        pset.pp_power_names.push(NameKey::new("Pool.Test.Missing_Power"));
+        power_sets.insert(NameKey::new("Pool.Test"), pset);
+
+        let missing = move_powers_into_sets(&mut power_sets, &powers);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].get(), "Pool.Test.Missing_Power");
+        let pset = power_sets.get(&NameKey::new("Pool.Test")).unwrap();
+        assert_eq!(pset.borrow().pp_powers.len(), 1);
+    }
+
+    #[test]
+    fn available_level_mismatch_test() {
+        let mut power_sets = Keyed::<BasePowerSet>::new();
+        let mut good = BasePowerSet::new();
+        good.pp_power_names.push(NameKey::new("Pool.Good.Power"));
+        good.pi_available.push(0);
+        power_sets.insert(NameKey::new("Pool.Good"), good);
+        let mut bad = BasePowerSet::new();
+        bad.pp_power_names.push(NameKey::new("Pool.Bad.Power_1"));
+        bad.pp_power_names.push(NameKey::new("Pool.Bad.Power_2"));
+        bad.pi_available.push(0);
+        power_sets.insert(NameKey::new("Pool.Bad"), bad);
+
+        let mismatches = find_available_level_mismatches(&power_sets);
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].0.get(), "Pool.Bad");
+        assert_eq!((mismatches[0].1, mismatches[0].2), (1, 2));
+    }
+
+    #[test]
+    fn err_context_source_test() {
+        use std::error::Error;
+        use std::path::Path;
+        let error = bin_parse::open_serialized(Path::new("does/not/exist.bin")).unwrap_err();
+        let context = ecxt!("Unable to read test bin.", error);
+        assert_eq!(context.to_string(), "Unable to read test bin.");
+        let source = context.source().unwrap();
+        assert!(source.downcast_ref::<bin_parse::ParseError>().is_some());
+        // the I/O error that caused the parse error is next in the chain
+        assert!(source.source().unwrap().downcast_ref::<std::io::Error>().is_some());
+    }
+
+    #[test]
+    fn missing_villain_bins_test() {
+        let mut config: PowersConfig =
+            toml::from_str(crate::structs::config::MINIMAL_CONFIG).unwrap();
+        let input_path = std::env::temp_dir().join(format!(
+            "powers_missing_villains_test_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&input_path).unwrap();
+        config.input_path = input_path.to_string_lossy().into_owned();
+        let messages = MessageStore::new();
+
+        let context = read_villain_bins(&config, &messages, &mut |_| ()).unwrap_err();
+        assert!(context.error.is_file_not_found());
+        config.allow_missing_villains = true;
+        let (villain_archetypes, villains) =
+            read_villain_bins(&config, &messages, &mut |_| ()).unwrap();
+        assert_eq!(villain_archetypes.len(), 0);
+        assert_eq!(villains.len(), 0);
+
+        // a corrupt file still stops the load
+        std::fs::write(input_path.join(VILLAIN_CLASSES_BIN), b"not a bin file").unwrap();
+        let context = read_villain_bins(&config, &messages, &mut |_| ()).unwrap_err();
+        assert!(!context.error.is_file_not_found());
+        std::fs::remove_dir_all(&input_path).unwrap();
+    }
+
+    #[test]
+    fn load_progress_test() {
+        let mut config: PowersConfig =
+            toml::from_str(crate::structs::config::MINIMAL_CONFIG).unwrap();
+        config.input_path = std::env::temp_dir()
+            .join(format!("powers_load_progress_test_{}", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        let mut events = Vec::new();
+        let result = load_powers_dictionary_with_progress(&config, &mut |event| events.push(event));
+        assert!(result.is_err());
+        let expected_path = config.join_to_input_path(MESSAGESTORE_BIN);
+        assert_eq!(
+            events,
+            vec![LoadProgress::ReadingFile(
+                expected_path.display().to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn load_progress_fixture_test() {
+        let mut config: PowersConfig =
+            toml::from_str(crate::structs::config::MINIMAL_CONFIG).unwrap();
+        let input_path =
+            std::env::temp_dir().join(format!("powers_load_fixture_test_{}", std::process::id()));
+        write_fixture_bins(
+            &input_path,
+            &[("Pool.First", &["Pool.First.Alpha"])],
+            &["Pool.First.Alpha"],
+        );
+        config.input_path = input_path.to_string_lossy().into_owned();
+        // the fixture has no villain files
+        config.resolve_summons = false;
+
+        let mut events = Vec::new();
+        load_powers_dictionary_with_progress(&config, &mut |event| events.push(event)).unwrap();
+        std::fs::remove_dir_all(&input_path).unwrap();
+        let reading = |file_name| {
+            LoadProgress::ReadingFile(config.join_to_input_path(file_name).display().to_string())
+        };
+        let count = |kind, n| LoadProgress::ReadCount { kind, n };
+        let attrib_count = CharacterAttributes::DAMAGE_TYPE_SIZE
+            + CharacterAttributes::DEFENSE_TYPE_SIZE
+            + CharacterAttributes::ELUSIVITY_SIZE;
+        let seconds = match events.last() {
+            Some(LoadProgress::Done { seconds }) => *seconds,
+            other => panic!("Expected Done last, got {:?}", other),
+        };
+        assert_eq!(
+            events,
+            vec![
+                reading(MESSAGESTORE_BIN),
+                count("messages", 0),
+                reading(ATTRIB_NAMES_BIN),
+                count("attribute names", attrib_count),
+                reading(CLASSES_BIN),
+                count("archetypes", 0),
+                reading(BOOST_SETS_BIN),
+                count("boost sets", 0),
+                reading(POWER_CATEGORIES_BIN),
+                count("power categories", 0),
+                LoadProgress::Matching,
+                reading(POWER_SETS_BIN),
+                count("power sets", 1),
+                reading(POWERS_BIN),
+                count("powers", 1),
+                LoadProgress::Resolving {
+                    pass: 1,
+                    resolved: 0
+                },
+                LoadProgress::Done { seconds },
+            ]
+        );
+    }
+
+    /// Appends `value` to `data` as a little-endian u32.
+    fn push_u32(data: &mut Vec<u8>, value: usize) {
+        data.extend_from_slice(&(value as u32).to_le_bytes());
+    }
+
+    /// Writes a serialized .bin file at `path` with the string pool `pool` and the table `table`.
+    fn write_bin(path: &std::path::Path, pool: &[u8], table: &[u8]) {
+        let mut data = b"CrypticS".to_vec();
+        push_u32(&mut data, 0); // build
+        data.extend_from_slice(&6u16.to_le_bytes());
+        data.extend_from_slice(b"Parse7");
+        push_u32(&mut data, pool.len());
+        data.extend_from_slice(pool);
+        data.extend(vec![0u8; (4 - pool.len() % 4) % 4]);
+        push_u32(&mut data, table.len());
+        data.extend_from_slice(table);
+        std::fs::write(path, data).unwrap();
+    }
+
+    /// Writes the smallest set of .bin files that `load_powers_dictionary` and
+    /// `stream_power_sets` can read to `input_path`: no messages, archetypes, boost sets, or
+    /// categories, the power sets in `power_sets` listing the named powers, and a power for each
+    /// name in `powers` in that order. Every other field is zero.
+    fn write_fixture_bins(
+        input_path: &std::path::Path,
+        power_sets: &[(&str, &[&str])],
+        powers: &[&str],
+    ) {
+        std::fs::create_dir_all(input_path).unwrap();
+
+        // message store with empty string tables and no IDs
+        let mut data = Vec::new();
+        push_u32(&mut data, 20090521);
+        for _ in 0..2 {
+            push_u32(&mut data, 0);
+            push_u32(&mut data, 0);
+        }
+        push_u32(&mut data, 0);
+        std::fs::write(input_path.join(MESSAGESTORE_BIN), data).unwrap();
+
+        // attribute names, only the fixed-size arrays have entries
+        let mut table = Vec::new();
+        for count in &[
+            CharacterAttributes::DAMAGE_TYPE_SIZE,
+            CharacterAttributes::DEFENSE_TYPE_SIZE,
+            0, // boost
+            0, // group
+            0, // mode
+            CharacterAttributes::ELUSIVITY_SIZE,
+            0, // stack key
+        ] {
+            push_u32(&mut table, *count);
+            for _ in 0..*count {
+                push_u32(&mut table, 12);
+                table.extend(vec![0u8; 12]);
+            }
+        }
+        write_bin(&input_path.join(ATTRIB_NAMES_BIN), &[0], &table);
+        for file_name in &[CLASSES_BIN, BOOST_SETS_BIN, POWER_CATEGORIES_BIN] {
+            write_bin(&input_path.join(file_name), &[0], &0u32.to_le_bytes());
+        }
+
+        // both power files share a string pool holding every name
+        let mut pool = vec![0u8];
+        let mut offsets = HashMap::new();
+        let names = power_sets
+            .iter()
+            .flat_map(|(pset_name, power_names)| Some(pset_name).into_iter().chain(*power_names))
+            .chain(powers.iter());
+        for name in names {
+            offsets.entry(*name).or_insert_with(|| {
+                let offset = pool.len();
+                pool.extend_from_slice(name.as_bytes());
+                pool.push(0);
+                offset
+            });
+        }
+
+        let mut table = Vec::new();
+        push_u32(&mut table, power_sets.len());
+        for (pset_name, power_names) in power_sets {
+            let mut pset = Vec::new();
+            push_u32(&mut pset, 0); // source file
+            push_u32(&mut pset, offsets[pset_name]);
+            pset.extend(vec![0u8; 76]); // name through specialize requires
+            push_u32(&mut pset, power_names.len());
+            for power_name in power_names.iter() {
+                push_u32(&mut pset, offsets[power_name]);
+            }
+            pset.extend(vec![0u8; 28]); // available levels through force level bought
+            push_u32(&mut table, pset.len());
+            table.extend(pset);
+        }
+        write_bin(&input_path.join(POWER_SETS_BIN), &pool, &table);
+
+        let mut table = Vec::new();
+        push_u32(&mut table, powers.len());
+        for power_name in powers {
+            push_u32(&mut table, 812);
+            push_u32(&mut table, offsets[power_name]);
+            table.extend(vec![0u8; 808]);
+        }
+        write_bin(&input_path.join(POWERS_BIN), &pool, &table);
+    }
+
+    #[test]
+    fn stream_power_sets_test() {
+        let mut config: PowersConfig =
+            toml::from_str(crate::structs::config::MINIMAL_CONFIG).unwrap();
+        let input_path =
+            std::env::temp_dir().join(format!("powers_stream_test_{}", std::process::id()));
+        write_fixture_bins(
+            &input_path,
+            &[
+                ("Pool.First", &["Pool.First.Alpha", "Pool.Shared.Beta"]),
+                ("Pool.Second", &["Pool.Shared.Beta", "Pool.Second.Missing"]),
+                ("Pool.Empty", &[]),
+            ],
+            &["Pool.First.Alpha", "Pool.Shared.Beta", "Pool.Unused.Gamma"],
+        );
+        config.input_path = input_path.to_string_lossy().into_owned();
+
+        let mut visited = Vec::new();
+        stream_power_sets(&config, &mut |pset| {
+            let power_names: Vec<_> = pset
+                .pp_powers
+                .iter()
+                .map(|p| p.borrow().pch_full_name.as_ref().unwrap().get().to_owned())
+                .collect();
+            visited.push((pset.pch_full_name.as_ref().unwrap().get().to_owned(), power_names));
+            Ok(())
+        })
+        .unwrap();
+        // the empty set goes first, the first set as soon as its powers are read, and the set
+        // with a missing power after the whole file
+        assert_eq!(
+            visited,
+            vec![
+                (String::from("Pool.Empty"), vec![]),
+                (
+                    String::from("Pool.First"),
+                    vec![
+                        String::from("Pool.First.Alpha"),
+                        String::from("Pool.Shared.Beta")
+                    ]
+                ),
+                (
+                    String::from("Pool.Second"),
+                    vec![String::from("Pool.Shared.Beta")]
+                ),
+            ]
+        );
+
+        // an error from the callback stops the stream
+        let mut calls = 0;
+        let result = stream_power_sets(&config, &mut |_| {
+            calls += 1;
+            Err(io::Error::new(io::ErrorKind::Other, "stop"))
+        });
+        assert!(matches!(result, Err(StreamPowerSetsError::Callback(_))));
+        assert_eq!(calls, 1);
+        std::fs::remove_dir_all(&input_path).unwrap();
+    }
+}
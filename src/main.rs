@@ -10,11 +10,22 @@ extern crate serde_json;
 extern crate toml;
 
 mod bin_parse;
+mod diff;
 mod load;
 mod output;
+mod output_csv;
+mod output_dot;
+mod output_md;
+mod output_msgpack;
+mod output_raw;
+mod output_sqlite;
+mod progress;
 mod structs;
 
 use bin_parse::{ParseError, ParseErrorKind};
+use log::{info, LevelFilter};
+use output::OutputError;
+use progress::ProgressLogger;
 use std::borrow::Cow;
 use std::env;
 use std::ffi::OsString;
@@ -22,53 +33,121 @@ use std::io::{Error, ErrorKind};
 use std::path::PathBuf;
 use std::process;
 use std::time::Instant;
-use structs::config::PowersConfig;
+use structs::config::{OutputFormatConfig, PowersConfig};
+use structs::NameKey;
 
 /// Default name for the config file.
 const CONFIG_FILE: &'static str = "PowersConfig.toml";
 
 /// Program entry point.
 fn main() {
+    // `powers --dump-set <name> [--stdout] [config]` only writes the raw output for a single
+    // power set
+    let mut args = env::args_os().skip(1).peekable();
+    let dump_set_name = if args.peek().map_or(false, |arg| arg == "--dump-set") {
+        args.next();
+        match args.next().and_then(|name| name.into_string().ok()) {
+            Some(name) => Some(NameKey::new(name)),
+            None => {
+                eprintln!("Missing power set name after --dump-set.");
+                process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+    // with --stdout, the data goes to stdout so progress messages must not
+    let streaming = dump_set_name.is_some() && args.peek().map_or(false, |arg| arg == "--stdout");
+    if streaming {
+        args.next();
+    }
+
+    // progress messages are logged, set RUST_LOG to change how many are shown (e.g. "debug" or "warn")
+    let log_level = env::var("RUST_LOG")
+        .ok()
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(LevelFilter::Info);
+    ProgressLogger::init_stdio(log_level, streaming).expect("Unable to set up logging.");
+
+    // `powers --schema [dir]` only writes the JSON Schema files for the API output
+    #[cfg(feature = "schema")]
+    {
+        let mut args = env::args_os().skip(1);
+        if args.next().map_or(false, |arg| arg == "--schema") {
+            let schema_dir = args.next().map_or_else(|| PathBuf::from("schema"), PathBuf::from);
+            if let Err(e) = output::write_json_schema(&schema_dir) {
+                eprintln!("Unable to write schema files! {}", get_output_error(&e));
+                process::exit(1);
+            }
+            return;
+        }
+    }
+
     // get path to configuration
-    let config_path = get_config_path();
+    let config_path = get_config_path(args);
 
     // load configuration
     let config = PowersConfig::load(&config_path).unwrap_or_else(|e| {
-        println!(
+        eprintln!(
             "Unable to load {}. {}",
             config_path.display(),
             get_io_error(&e)
         );
         process::exit(1);
     });
-    println!("Configuration loaded.");
+    info!("Configuration loaded.");
 
     // parse the powers dictionary
     let powers_dict = load::load_powers_dictionary(&config).unwrap_or_else(|context| {
-        println!("{} {}.", context.message, get_error(&context.error));
+        eprintln!("{} {}.", context.message, get_error(&context.error));
         process::exit(1);
     });
-    println!("Powers dictionary loaded.");
+    info!("Powers dictionary loaded.");
+
+    if let Some(set_name) = dump_set_name {
+        if let Err(e) =
+            output_raw::dump_single_power_set(&powers_dict, &set_name, &config, streaming)
+        {
+            eprintln!(
+                "Unable to dump power set {}! {}",
+                set_name,
+                get_output_error(&e)
+            );
+            process::exit(1);
+        }
+        return;
+    }
 
     // write output files
     let begin_time = Instant::now();
-    if let Err(e) = output::write_powers_dictionary(powers_dict, &config) {
-        println!("Unable to write ouput files! {}", get_io_error(&e));
+    let result = match config.output_format {
+        OutputFormatConfig::Api => output::write_powers_dictionary(powers_dict, &config),
+        OutputFormatConfig::Raw => output_raw::write_powers_dictionary(powers_dict, &config),
+        OutputFormatConfig::Csv => output_csv::write_powers_csv(&powers_dict, &config),
+        OutputFormatConfig::Dot => output_dot::write_powers_dot(&powers_dict, &config),
+        OutputFormatConfig::Msgpack => {
+            output_msgpack::write_powers_dictionary_msgpack(&powers_dict, &config)
+        }
+        OutputFormatConfig::Sqlite => output_sqlite::write_powers_sqlite(&powers_dict, &config),
+        OutputFormatConfig::Markdown => output_md::write_markdown(&powers_dict, &config),
+    };
+    if let Err(e) = result {
+        eprintln!("Unable to write ouput files! {}", get_output_error(&e));
         process::exit(1);
     }
     let elapsed = Instant::now().duration_since(begin_time);
-    println!("Files written in {} seconds.", elapsed.as_secs());
+    info!("Files written in {} seconds.", elapsed.as_secs());
 }
 
-/// Optionally read path to config file from command line. Otherwise use
+/// Optionally read path to config file from the remaining command line `args`. Otherwise use
 /// `CONFIG_FILE` in the current directory.
-fn get_config_path() -> PathBuf {
+fn get_config_path(args: impl Iterator<Item = OsString>) -> PathBuf {
     let mut config_path: Option<OsString> = None;
-    for arg in env::args_os().skip(1) {
+    for arg in args {
         if config_path.is_none() {
             config_path = Some(arg);
         } else {
-            println!("Too many command line arguments.");
+            eprintln!("Too many command line arguments.");
             process::exit(1);
         }
     }
@@ -109,6 +188,42 @@ fn get_error(error: &ParseError) -> Cow<'static, str> {
         ParseErrorKind::MissingNameKey => {
             Cow::Borrowed("Current object has no name key (corrupted bin?")
         }
+        ParseErrorKind::ArraySizeMismatch {
+            field,
+            expected,
+            found,
+        } => Cow::Owned(format!(
+            "Expected {} entries in {}, but read {} entries",
+            expected, field, found
+        )),
+        ParseErrorKind::DuplicateNameKey => {
+            Cow::Borrowed("More than one object has the same name key")
+        }
+    }
+}
+
+/// Converts an `OutputError` into a human-readable string.
+fn get_output_error(error: &OutputError) -> Cow<'static, str> {
+    match error {
+        OutputError::Io { path, source } => {
+            Cow::Owned(format!("{}: {}", path.display(), get_io_error(source)))
+        }
+        OutputError::Serialize { path, source } => Cow::Owned(format!(
+            "Could not serialize {} ({})",
+            path.display(),
+            source
+        )),
+        OutputError::PathError { path, source } => Cow::Owned(format!(
+            "Output path {}: {}",
+            path.display(),
+            get_io_error(source)
+        )),
+        OutputError::Database { path, source } => {
+            Cow::Owned(format!("Database {}: {}", path.display(), source))
+        }
+        OutputError::MissingSourceFile { .. } | OutputError::PowerSetNotFound { .. } => {
+            Cow::Owned(error.to_string())
+        }
     }
 }
 
@@ -1,19 +1,101 @@
+#[cfg(feature = "schema")]
+mod schema;
 mod structs;
+mod writer;
 
-use crate::structs::config::{OutputStyleConfig, PowersConfig};
+use crate::structs::config::{OutputStyleConfig, OverwritePolicy, PowersConfig};
 use crate::structs::{
-    Archetype, AttribNames, BasePowerSet, Keyed, ObjRef, PowerCategory, PowersDictionary,
+    Archetype, AttribNames, BasePowerSet, BoostSet, Keyed, ObjRef, PowerCategory,
+    PowersDictionary, RgbaFormatScope,
 };
+use log::{debug, error, info, warn};
+use serde::Serialize;
 use std::fs;
 use std::io;
 use std::io::prelude::*;
 use std::io::{Error, ErrorKind};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+#[cfg(feature = "schema")]
+pub use schema::write_json_schema;
+pub(crate) use structs::{requires_to_string, round_to, PowerSetOutput};
+pub(crate) use structs::{AttribModOutput, EffectGroupOutput, PowerOutput};
 use structs::*;
+pub(crate) use writer::{create_output_file, NormalizedWriter};
 
 /// Default name for the .json files.
 const JSON_FILE: &'static str = "index.json";
 
+/// Represents an error that occurred while writing the output files.
+#[derive(Debug, Error)]
+pub enum OutputError {
+    /// Creating a directory or writing the file at `path` failed.
+    #[error("Unable to write {}.", path.display())]
+    Io { path: PathBuf, source: io::Error },
+    /// The data for the file at `path` couldn't be serialized.
+    #[error("Unable to serialize {}.", path.display())]
+    Serialize {
+        path: PathBuf,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// The output directory at `path` couldn't be created, or isn't safe to write to.
+    #[error("Unable to use the output path {}.", path.display())]
+    PathError { path: PathBuf, source: io::Error },
+    /// Writing to the SQLite database at `path` failed.
+    #[error("Unable to write the database {}.", path.display())]
+    Database {
+        path: PathBuf,
+        source: rusqlite::Error,
+    },
+    /// The power called `name` has no source file, so there's nowhere to write it.
+    #[error("Power {name} has no source file.")]
+    MissingSourceFile { name: String },
+    /// There's no power set called `name` to write.
+    #[error("No power set named {name} was found. Use the full name (e.g. Pool.Flight).")]
+    PowerSetNotFound { name: String },
+}
+
+impl OutputError {
+    /// Creates a function that wraps an `io::Error` from writing `path` into `OutputError::Io`,
+    /// for use with `map_err`.
+    pub(crate) fn io(path: &Path) -> impl FnOnce(io::Error) -> Self + '_ {
+        move |source| OutputError::Io {
+            path: path.to_owned(),
+            source,
+        }
+    }
+
+    /// Creates a function that wraps an `io::Error` from preparing the output directory at `path`
+    /// into `OutputError::PathError`, for use with `map_err`.
+    pub(crate) fn path(path: &Path) -> impl FnOnce(io::Error) -> Self + '_ {
+        move |source| OutputError::PathError {
+            path: path.to_owned(),
+            source,
+        }
+    }
+
+    /// Creates a function that wraps a serializer error for `path` into `OutputError::Serialize`,
+    /// for use with `map_err`.
+    pub(crate) fn serialize<E>(path: &Path) -> impl FnOnce(E) -> Self + '_
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        move |source| OutputError::Serialize {
+            path: path.to_owned(),
+            source: Box::new(source),
+        }
+    }
+
+    /// Creates a function that wraps a `rusqlite::Error` from writing the database at `path` into
+    /// `OutputError::Database`, for use with `map_err`.
+    pub(crate) fn database(path: &Path) -> impl FnOnce(rusqlite::Error) -> Self + '_ {
+        move |source| OutputError::Database {
+            path: path.to_owned(),
+            source,
+        }
+    }
+}
+
 /// Begins the process of writing the entire powers dictionary to disk as .json files.
 ///
 /// # Arguments:
@@ -23,7 +105,7 @@ const JSON_FILE: &'static str = "index.json";
 ///
 /// # Returns:
 ///
-/// Nothing if the operation was successful. Otherwise, an `io::Error` containing the error information.
+/// Nothing if the operation was successful. Otherwise, an `OutputError` containing the error information.
 ///
 /// # Notes:
 ///
@@ -32,42 +114,48 @@ const JSON_FILE: &'static str = "index.json";
 /// using a URL that looks like this:
 ///
 /// `http://myserver/powers/tanker-melee/super-strength/`
+///
+/// The URLs in the output refer to .json files, so the `yaml` output style is written as pretty
+/// JSON instead.
 pub fn write_powers_dictionary(
     powers_dict: PowersDictionary,
     config: &PowersConfig,
-) -> io::Result<()> {
+) -> Result<(), OutputError> {
     // setup the output directory
-    let output_path = Path::new(&config.output_path);
-    fs::create_dir_all(&output_path)?;
-    if output_path.read_dir()?.count() > 0 {
-        print!(
-            "WARNING! The output path {} is not empty. Overwrite? (y/n)",
-            output_path.display()
-        );
-        io::stdout().flush()?;
-        //TODO: better input handling
-        for c in io::stdin().lock().bytes() {
-            match c? {
-                b'y' | b'Y' => break,
-                b'n' | b'N' => return Err(Error::from(ErrorKind::Interrupted)),
-                _ => (),
-            }
-        }
-        println!();
+    prepare_output_path(config).map_err(OutputError::path(Path::new(&config.output_path)))?;
+    if matches!(config.output_style, OutputStyleConfig::Yaml) {
+        warn!("The api output format doesn't support YAML, writing pretty JSON instead.");
     }
 
+    // colors are written in the configured format while serializing
+    let _rgba_format_scope = RgbaFormatScope::enter(config.rgba_format);
+
+    // files whose contents haven't changed since the last run are left alone
+    let mut stats = WriteStats::default();
+
     // write the root file
-    write_root(&powers_dict.power_categories, config)?;
+    write_root(&powers_dict.power_categories, config, &mut stats)?;
 
     // write archetypes
-    write_archetypes(&powers_dict.archetypes, config)?;
+    write_archetypes(
+        &powers_dict.archetypes,
+        &powers_dict.attrib_names,
+        config,
+        &mut stats,
+    )?;
+
+    // write boost sets
+    write_boost_sets(&powers_dict.boost_sets, config, &mut stats)?;
+
+    // write inherent powers
+    write_inherents(&powers_dict.power_categories, config, &mut stats)?;
 
     // write all of the categories
     for category in powers_dict.power_categories.iter().map(|c| c.borrow()) {
         if !category.include_in_output {
             continue;
         }
-        write_power_category(&*category, config)?;
+        write_power_category(&*category, config, &mut stats)?;
 
         if let Some(pcat_name) = category.pch_name.as_ref() {
             // write the category's power sets
@@ -76,62 +164,206 @@ pub fn write_powers_dictionary(
                     write_power_set(
                         Some(pcat_name.get_string()),
                         &*set,
-                        &powers_dict.attrib_names,
+                        &powers_dict,
                         config,
+                        &mut stats,
                     )?;
                 }
             }
         }
     }
 
+    info!(
+        "Wrote {} files, skipped {} unchanged files.",
+        stats.written, stats.skipped
+    );
+    Ok(())
+}
+
+/// Counts of the files handled by `write_json_file`.
+#[derive(Debug, Default)]
+struct WriteStats {
+    /// Files that were created or had different contents.
+    written: usize,
+    /// Files that already had identical contents.
+    skipped: usize,
+}
+
+/// Serializes `value` as JSON and writes it to `output_file`, unless the file already exists
+/// with the same contents.
+///
+/// # Arguments:
+///
+/// * `output_file` - The path of the file to write.
+/// * `value` - The value to serialize.
+/// * `config` - Configuration information, for the output style and line endings.
+/// * `stats` - Updated with whether the file was written or skipped.
+///
+/// # Returns:
+///
+/// Nothing if the operation was successful. Otherwise, an `OutputError` containing the error information.
+///
+/// # Notes:
+///
+/// The output is serialized to memory first and its MD5 hash compared with the existing file's,
+/// so repeated runs over the same bins don't touch files that haven't changed.
+fn write_json_file<T>(
+    output_file: &Path,
+    value: &T,
+    config: &PowersConfig,
+    stats: &mut WriteStats,
+) -> Result<(), OutputError>
+where
+    T: Serialize + ?Sized,
+{
+    let mut w = NormalizedWriter::new(Vec::new(), config.line_endings);
+    match config.output_style {
+        // YAML isn't supported here, see `write_powers_dictionary`
+        OutputStyleConfig::Pretty | OutputStyleConfig::Yaml => {
+            serde_json::to_writer_pretty(&mut w, value)
+        }
+        OutputStyleConfig::Compact => serde_json::to_writer(&mut w, value),
+    }
+    .map_err(OutputError::serialize(output_file))?;
+    w.flush().map_err(OutputError::io(output_file))?;
+    let data = w.into_inner();
+    if let Ok(existing) = fs::read(output_file) {
+        if md5::compute(&existing) == md5::compute(&data) {
+            debug!("\tUnchanged: {}", output_file.display());
+            stats.skipped += 1;
+            return Ok(());
+        }
+    }
+    fs::write(output_file, &data).map_err(OutputError::io(output_file))?;
+    stats.written += 1;
     Ok(())
 }
 
+/// Creates the output path and checks whether it's safe to write to, based on `config.overwrite`.
+///
+/// # Returns:
+///
+/// Nothing if the output path is empty or may be overwritten. If it's not empty and shouldn't be
+/// overwritten, an `io::Error` of kind `AlreadyExists` (or `Interrupted` if the user declined).
+pub(crate) fn prepare_output_path(config: &PowersConfig) -> io::Result<()> {
+    let output_path = Path::new(&config.output_path);
+    fs::create_dir_all(&output_path)?;
+    if output_path.read_dir()?.count() == 0 {
+        return Ok(());
+    }
+    match config.overwrite {
+        OverwritePolicy::Always => Ok(()),
+        OverwritePolicy::Never => {
+            error!("The output path {} is not empty.", output_path.display());
+            Err(Error::from(ErrorKind::AlreadyExists))
+        }
+        OverwritePolicy::Prompt => {
+            print!(
+                "WARNING! The output path {} is not empty. Overwrite? (y/n)",
+                output_path.display()
+            );
+            io::stdout().flush()?;
+            let answer = prompt_overwrite(io::stdin().lock());
+            println!();
+            match answer? {
+                Some(true) => Ok(()),
+                Some(false) => Err(Error::from(ErrorKind::Interrupted)),
+                // no answer is possible (e.g. stdin is closed), so treat it like `Never`
+                None => Err(Error::from(ErrorKind::AlreadyExists)),
+            }
+        }
+    }
+}
+
+/// Reads a y/n answer from `input`, ignoring anything else.
+///
+/// # Returns:
+///
+/// `Some(true)` for yes, `Some(false)` for no, or `None` if `input` ended without an answer.
+fn prompt_overwrite<R: Read>(input: R) -> io::Result<Option<bool>> {
+    for c in input.bytes() {
+        match c? {
+            b'y' | b'Y' => return Ok(Some(true)),
+            b'n' | b'N' => return Ok(Some(false)),
+            _ => (),
+        }
+    }
+    Ok(None)
+}
+
 /// Writes the root .json file.
 fn write_root(
     power_categories: &Vec<ObjRef<PowerCategory>>,
     config: &PowersConfig,
-) -> io::Result<()> {
+    stats: &mut WriteStats,
+) -> Result<(), OutputError> {
     let output_file = config.join_to_output_path(JSON_FILE);
-    println!("Writing: {} ...", output_file.display());
-    let mut f = fs::File::create(output_file)?;
+    info!("Writing: {} ...", output_file.display());
     let root = RootOutput::from_power_categories(power_categories, config);
-    match config.output_style {
-        OutputStyleConfig::Pretty => serde_json::to_writer_pretty(&mut f, &root)?,
-        OutputStyleConfig::Compact => serde_json::to_writer(&mut f, &root)?,
-    }
+    write_json_file(&output_file, &root, config, stats)?;
     Ok(())
 }
 
 /// Writes the archetypes .json file.
-fn write_archetypes(archetypes: &Keyed<Archetype>, config: &PowersConfig) -> io::Result<()> {
+fn write_archetypes(
+    archetypes: &Keyed<Archetype>,
+    attrib_names: &AttribNames,
+    config: &PowersConfig,
+    stats: &mut WriteStats,
+) -> Result<(), OutputError> {
     let output_path = config.join_to_output_path("archetypes");
-    fs::create_dir_all(&output_path)?;
+    fs::create_dir_all(&output_path).map_err(OutputError::io(&output_path))?;
     let output_file = output_path.join(JSON_FILE);
-    println!("Writing: {} ...", output_file.display());
-    let mut f = fs::File::create(output_file)?;
-    let ats = ArchetypesOutput::from_archetypes(archetypes, config);
-    match config.output_style {
-        OutputStyleConfig::Pretty => serde_json::to_writer_pretty(&mut f, &ats)?,
-        OutputStyleConfig::Compact => serde_json::to_writer(&mut f, &ats)?,
-    }
+    info!("Writing: {} ...", output_file.display());
+    let ats = ArchetypesOutput::from_archetypes(archetypes, attrib_names, config);
+    write_json_file(&output_file, &ats, config, stats)?;
+    Ok(())
+}
+
+/// Writes the boost sets .json file.
+fn write_boost_sets(
+    boost_sets: &Keyed<BoostSet>,
+    config: &PowersConfig,
+    stats: &mut WriteStats,
+) -> Result<(), OutputError> {
+    let output_path = config.join_to_output_path("boost-sets");
+    fs::create_dir_all(&output_path).map_err(OutputError::io(&output_path))?;
+    let output_file = output_path.join(JSON_FILE);
+    info!("Writing: {} ...", output_file.display());
+    let sets = BoostSetsOutput::from_boost_sets(boost_sets, config);
+    write_json_file(&output_file, &sets, config, stats)?;
+    Ok(())
+}
+
+/// Writes the inherent powers .json file.
+fn write_inherents(
+    power_categories: &Vec<ObjRef<PowerCategory>>,
+    config: &PowersConfig,
+    stats: &mut WriteStats,
+) -> Result<(), OutputError> {
+    let output_path = config.join_to_output_path("inherents");
+    fs::create_dir_all(&output_path).map_err(OutputError::io(&output_path))?;
+    let output_file = output_path.join(JSON_FILE);
+    info!("Writing: {} ...", output_file.display());
+    let inherents = InherentsOutput::from_power_categories(power_categories, config);
+    write_json_file(&output_file, &inherents, config, stats)?;
     Ok(())
 }
 
 /// Writes all of the power category .json files to individual directories.
-fn write_power_category(power_category: &PowerCategory, config: &PowersConfig) -> io::Result<()> {
+fn write_power_category(
+    power_category: &PowerCategory,
+    config: &PowersConfig,
+    stats: &mut WriteStats,
+) -> Result<(), OutputError> {
     if let Some(category_name) = &power_category.pch_name {
         let output_path = config.join_to_output_path(&make_file_name(category_name.get()));
-        fs::create_dir_all(&output_path)?;
+        fs::create_dir_all(&output_path).map_err(OutputError::io(&output_path))?;
         let output_file = output_path.join(JSON_FILE);
-        println!("Writing: {} ...", output_file.display());
-        let mut f = fs::File::create(output_file)?;
+        info!("Writing: {} ...", output_file.display());
 
         let pcat = PowerCategoryOutput::from_power_category(power_category, config);
-        match config.output_style {
-            OutputStyleConfig::Pretty => serde_json::to_writer_pretty(&mut f, &pcat)?,
-            OutputStyleConfig::Compact => serde_json::to_writer(&mut f, &pcat)?,
-        }
+        write_json_file(&output_file, &pcat, config, stats)?;
     }
     Ok(())
 }
@@ -140,29 +372,31 @@ fn write_power_category(power_category: &PowerCategory, config: &PowersConfig) -
 fn write_power_set(
     category_name: Option<&String>,
     power_set: &BasePowerSet,
-    attrib_names: &AttribNames,
+    powers_dict: &PowersDictionary,
     config: &PowersConfig,
-) -> io::Result<()> {
+    stats: &mut WriteStats,
+) -> Result<(), OutputError> {
     let output_path = config
         .join_to_output_path(&make_file_name_opt(category_name))
         .join(&make_file_name_opt(power_set.pch_name.as_ref()));
-    fs::create_dir_all(&output_path)?;
+    fs::create_dir_all(&output_path).map_err(OutputError::io(&output_path))?;
     let output_file = output_path.join(JSON_FILE);
-    println!("\tWriting: {} ...", output_file.display());
-    let mut f = fs::File::create(output_file)?;
+    info!("\tWriting: {} ...", output_file.display());
 
-    let pset = PowerSetOutput::from_base_power_set(power_set, attrib_names, config);
-    match config.output_style {
-        OutputStyleConfig::Pretty => serde_json::to_writer_pretty(&mut f, &pset)?,
-        OutputStyleConfig::Compact => serde_json::to_writer(&mut f, &pset)?,
-    }
+    let pset = PowerSetOutput::from_base_power_set(
+        power_set,
+        &powers_dict.attrib_names,
+        config,
+        Some(powers_dict),
+    );
+    write_json_file(&output_file, &pset, config, stats)?;
 
     Ok(())
 }
 
 /// Takes a string of arbitrary data and attempts to create a representation suitable for use
 /// as a file name.
-fn make_file_name_opt(string: Option<&String>) -> String {
+pub(crate) fn make_file_name_opt(string: Option<&String>) -> String {
     if let Some(s) = string {
         make_file_name(&s[..])
     } else {
@@ -172,7 +406,7 @@ fn make_file_name_opt(string: Option<&String>) -> String {
 
 /// Takes a string of arbitrary data and attempts to create a representation suitable for use
 /// as a file name.
-fn make_file_name(string: &str) -> String {
+pub(crate) fn make_file_name(string: &str) -> String {
     let mut s = String::new();
     for c in string.chars() {
         if c.is_alphanumeric() {
@@ -185,3 +419,90 @@ fn make_file_name(string: &str) -> String {
     }
     s
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::config::MINIMAL_CONFIG;
+
+    #[test]
+    fn prompt_overwrite_test() {
+        assert_eq!(prompt_overwrite(&b"x\nY\n"[..]).unwrap(), Some(true));
+        assert_eq!(prompt_overwrite(&b"n"[..]).unwrap(), Some(false));
+        // closed stdin shouldn't hang or default to overwriting
+        assert_eq!(prompt_overwrite(&b""[..]).unwrap(), None);
+    }
+
+    #[test]
+    fn overwrite_never_test() {
+        let mut config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        let output_path =
+            std::env::temp_dir().join(format!("powers_overwrite_test_{}", std::process::id()));
+        config.output_path = output_path.to_string_lossy().into_owned();
+        config.overwrite = OverwritePolicy::Never;
+        prepare_output_path(&config).unwrap();
+        fs::write(output_path.join("index.json"), b"{}").unwrap();
+        let err = prepare_output_path(&config).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+        config.overwrite = OverwritePolicy::Always;
+        assert!(prepare_output_path(&config).is_ok());
+        fs::remove_dir_all(&output_path).unwrap();
+    }
+
+    #[test]
+    fn write_json_file_unchanged_test() {
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        let output_path =
+            std::env::temp_dir().join(format!("powers_unchanged_test_{}", std::process::id()));
+        fs::create_dir_all(&output_path).unwrap();
+        let output_file = output_path.join(JSON_FILE);
+        let mut stats = WriteStats::default();
+        write_json_file(&output_file, &vec!["Fly", "Hover"], &config, &mut stats).unwrap();
+        write_json_file(&output_file, &vec!["Fly", "Hover"], &config, &mut stats).unwrap();
+        assert_eq!((stats.written, stats.skipped), (1, 1));
+        write_json_file(&output_file, &vec!["Fly"], &config, &mut stats).unwrap();
+        assert_eq!((stats.written, stats.skipped), (2, 1));
+        assert_eq!(fs::read_to_string(&output_file).unwrap(), r#"["Fly"]"#);
+        fs::remove_dir_all(&output_path).unwrap();
+    }
+
+    #[test]
+    fn output_error_test() {
+        let mut config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        let output_path =
+            std::env::temp_dir().join(format!("powers_output_error_test_{}", std::process::id()));
+        fs::create_dir_all(&output_path).unwrap();
+        let mut stats = WriteStats::default();
+
+        // JSON object keys have to be strings
+        let mut value = std::collections::BTreeMap::new();
+        value.insert((1, 2), "Fly");
+        let output_file = output_path.join(JSON_FILE);
+        match write_json_file(&output_file, &value, &config, &mut stats) {
+            Err(OutputError::Serialize { path, .. }) => assert_eq!(path, output_file),
+            other => panic!("expected a serialize error, got {:?}", other),
+        }
+
+        let output_file = output_path.join("missing").join(JSON_FILE);
+        match write_json_file(&output_file, &vec!["Fly"], &config, &mut stats) {
+            Err(OutputError::Io { path, source }) => {
+                assert_eq!(path, output_file);
+                assert_eq!(source.kind(), ErrorKind::NotFound);
+            }
+            other => panic!("expected an I/O error, got {:?}", other),
+        }
+
+        fs::write(output_path.join(JSON_FILE), b"{}").unwrap();
+        config.output_path = output_path.to_string_lossy().into_owned();
+        config.overwrite = OverwritePolicy::Never;
+        let powers_dict = PowersDictionary::from_power_categories(Vec::new());
+        match write_powers_dictionary(powers_dict, &config) {
+            Err(OutputError::PathError { path, source }) => {
+                assert_eq!(path, output_path);
+                assert_eq!(source.kind(), ErrorKind::AlreadyExists);
+            }
+            other => panic!("expected a path error, got {:?}", other),
+        }
+        fs::remove_dir_all(&output_path).unwrap();
+    }
+}
@@ -0,0 +1,120 @@
+//! Generates JSON Schema files describing the "api" output format. Only built with the
+//! "schema" feature.
+
+use super::structs::*;
+use super::{create_output_file, OutputError};
+use crate::structs::config::LineEndingConfig;
+use log::info;
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+use std::fs;
+use std::io::prelude::*;
+use std::path::Path;
+
+/// Writes a JSON Schema for each kind of file in the "api" output format.
+///
+/// # Arguments:
+///
+/// * `dir` - The directory to write the schema files to. Created if it doesn't exist.
+///
+/// # Returns:
+///
+/// Nothing if the operation was successful. Otherwise, an `OutputError` containing the error information.
+///
+/// # Notes:
+///
+/// Colors are described in the default `rgba_format` ("hex_string").
+pub fn write_json_schema(dir: &Path) -> Result<(), OutputError> {
+    fs::create_dir_all(dir).map_err(OutputError::path(dir))?;
+    write_schema(dir, "root", schema_for!(RootOutput))?;
+    write_schema(dir, "archetypes", schema_for!(ArchetypesOutput))?;
+    write_schema(dir, "boost_sets", schema_for!(BoostSetsOutput))?;
+    write_schema(dir, "inherents", schema_for!(InherentsOutput))?;
+    write_schema(dir, "power_category", schema_for!(PowerCategoryOutput))?;
+    write_schema(dir, "powerset", schema_for!(PowerSetOutput))?;
+    write_schema(dir, "power", schema_for!(PowerOutput))?;
+    Ok(())
+}
+
+/// Writes `schema` to `<name>.json` in `dir`.
+fn write_schema(dir: &Path, name: &str, schema: RootSchema) -> Result<(), OutputError> {
+    let output_file = dir.join(format!("{}.json", name));
+    info!("Writing: {} ...", output_file.display());
+    // no config is loaded for `--schema`, so the default line endings are used
+    let mut f = create_output_file(&output_file, LineEndingConfig::default())
+        .map_err(OutputError::io(&output_file))?;
+    serde_json::to_writer_pretty(&mut f, &schema).map_err(|source| {
+        if source.is_io() {
+            OutputError::Io {
+                path: output_file.clone(),
+                source: source.into(),
+            }
+        } else {
+            OutputError::serialize(&output_file)(source)
+        }
+    })?;
+    f.flush().map_err(OutputError::io(&output_file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::config::{PowersConfig, MINIMAL_CONFIG};
+    use crate::structs::*;
+    use jsonschema::JSONSchema;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn power_set_schema_test() {
+        let mut config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        config.extract_date = Some(chrono::Local::now());
+        let mut power_set = BasePowerSet::new();
+        power_set.pch_full_name = Some(NameKey::new("Pool.Flight"));
+        power_set.pch_name = Some(String::from("Flight"));
+        power_set.pch_display_name = Some(String::from("Flight"));
+        let mut fly = BasePower::new();
+        fly.pch_full_name = Some(NameKey::new("Pool.Flight.Fly"));
+        fly.pch_display_name = Some(String::from("Fly"));
+        fly.include_in_output = true;
+        fly.e_type = PowerType::kPowerType_Toggle;
+        fly.f_endurance_cost = 0.26;
+        fly.f_activate_period = 0.5;
+        let mut redirect = PowerRedirect::new();
+        redirect.pch_name = Some(NameKey::new("Pool.Flight.Fly_Combat"));
+        fly.pp_redirect.push(redirect);
+        let mut attrib_names = AttribNames::new();
+        let mut attrib_name = AttribName::new();
+        attrib_name.pch_display_name = Some(String::from("Smashing"));
+        attrib_names.pp_defense.push(attrib_name);
+        let mut at = Archetype::new();
+        at.pch_display_name = Some(String::from("Blaster"));
+        let mut table = NamedTable::new();
+        table.pf_values = vec![0.1; 50];
+        at.pp_named_tables
+            .insert(String::from("melee_buff_def"), table);
+        fly.archetypes.push(Rc::new(RefCell::new(at)));
+        let mut template = AttribModTemplate::new();
+        template
+            .p_attrib
+            .push(CharacterAttrib(CharacterAttributes::OFFSET_DEF_0 as i32));
+        template.e_target = ModTarget::kModTarget_Caster;
+        template.pch_table = Some(String::from("Melee_Buff_Def"));
+        template.f_scale = 0.5;
+        let mut effect_group = EffectGroup::new();
+        effect_group.f_chance = 1.0;
+        effect_group.pp_templates.push(template);
+        fly.pp_effects.push(Rc::new(RefCell::new(effect_group)));
+        power_set.pp_powers.push(Rc::new(RefCell::new(fly)));
+        let pset = PowerSetOutput::from_base_power_set(&power_set, &attrib_names, &config, None);
+        let instance = serde_json::to_value(&pset).unwrap();
+
+        let schema = serde_json::to_value(&schema_for!(PowerSetOutput)).unwrap();
+        let compiled = JSONSchema::compile(&schema).unwrap();
+        let errors: Vec<String> = match compiled.validate(&instance) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors.map(|e| e.to_string()).collect(),
+        };
+        assert!(errors.is_empty(), "{}", errors.join("\n"));
+    }
+}
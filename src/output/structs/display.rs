@@ -38,6 +38,7 @@ macro_rules! g_str {
 /// * `effect_group` - The `EffectGroupOutput` that owns `attrib_mod`.
 /// * `effect_requires` - The original requirements clause from `effect_group`.
 /// * `attrib_names` - An `AttribNames`.
+/// * `decimal_places` - Number of decimal places durations are rounded to.
 ///
 /// # Returns:
 /// On exit, the `display_info` in each `scaled` value in `attrib_mod` may contain a human
@@ -51,6 +52,7 @@ pub fn describe_attrib_mod(
     effect_group: &EffectGroupOutput,
     effect_requires: &Vec<String>,
     attrib_names: &AttribNames,
+    decimal_places: u8,
 ) {
     // TODO: pet effects would be nice but requires looking up the entity def
 
@@ -92,7 +94,7 @@ pub fn describe_attrib_mod(
 
     // Delay
     let after_delay = if attrib_mod.after_delay_seconds.is_normal() {
-        let duration = get_pretty_duration(attrib_mod.after_delay_seconds);
+        let duration = get_pretty_duration(attrib_mod.after_delay_seconds, decimal_places);
         Some(format!(" after {} delay", duration))
     } else {
         None
@@ -105,7 +107,7 @@ pub fn describe_attrib_mod(
     let mut is_critical = false;
     let mut tags = Vec::new();
     for tag in &effect_group.tags {
-        match *tag {
+        match &tag[..] {
             "DualPistolsLethalMode" => tags.push("Only using Standard Ammo"),
             "DualPistolsFireMode" => tags.push("Only using Incendiary Ammo"),
             "DualPistolsColdMode" => tags.push("Only using Cryo Ammo"),
@@ -151,7 +153,9 @@ pub fn describe_attrib_mod(
             ScaledUnit::Damage(dmg) => format!("{:.2}", dmg),
             ScaledUnit::Healing(healing) => format!("{:.2}", healing),
             ScaledUnit::Distance(distance) => format!("{:.2} ft.", distance),
-            ScaledUnit::DurationSeconds(duration) => get_pretty_duration(duration),
+            ScaledUnit::DurationSeconds(duration) => {
+                get_pretty_duration(duration, decimal_places)
+            }
             ScaledUnit::Magnitude(mag) => format!("{:.1}", mag),
             ScaledUnit::Percent(percent) => format!("{:.2}%", percent),
             ScaledUnit::Value(val) => format!("{:.2}", val),
@@ -167,9 +171,9 @@ pub fn describe_attrib_mod(
         };
         let over_time = if duration.is_normal() {
             if ticks > 1 {
-                Some(format!(" over {}", get_pretty_duration(duration)))
+                Some(format!(" over {}", get_pretty_duration(duration, decimal_places)))
             } else {
-                Some(format!(" for {}", get_pretty_duration(duration)))
+                Some(format!(" for {}", get_pretty_duration(duration, decimal_places)))
             }
         } else {
             None
@@ -288,8 +292,8 @@ fn get_effect_types(
             }
         }
         // status effects
-        else if matches!(a, CharacterAttributes::OFFSET_CONFUSED..=CharacterAttributes::OFFSET_ONLY_AFFECTS_SELF |
-                CharacterAttributes::OFFSET_KNOCKUP..=CharacterAttributes::OFFSET_REPEL)
+        else if attrib.is_boolean_status()
+            || matches!(a, CharacterAttributes::OFFSET_KNOCKUP..=CharacterAttributes::OFFSET_REPEL)
         {
             match attrib_mod.attr_type {
                 Some(AttribType::kAttribType_Mod) | Some(AttribType::kAttribType_Cur) => {
@@ -337,14 +341,15 @@ fn get_effect_types(
     effect_types
 }
 
-/// Formats `time` in seconds to a display string.
-fn get_pretty_duration(time: f32) -> String {
+/// Formats `time` in seconds to a display string. Times under a minute are rounded to
+/// `decimal_places`.
+fn get_pretty_duration(time: f32, decimal_places: u8) -> String {
     const MIN_SECS: f32 = 60.0;
     const HOUR_SECS: f32 = 3600.0;
     const DAY_SECS: f32 = 86400.0;
     if time < MIN_SECS {
         // less than a minute
-        format!("{:.}s", super::normalize(time))
+        format!("{:.}s", super::round_to(time, decimal_places))
     } else if time < HOUR_SECS {
         // include minutes
         let seconds = time % MIN_SECS;
@@ -519,6 +524,8 @@ fn add_requires_tags(tags: &mut Vec<&'static str>, requires: &Vec<String>) {
 /// # Arguments:
 /// * `power` - A `PowerOutput` value.
 /// * `base_power` - The `BasePower` that was the basis for `power`.
+/// * `attrib_names` - An `AttribNames`.
+/// * `decimal_places` - Number of decimal places durations are rounded to.
 ///
 /// # Returns:
 /// On exit, the `display_info` in `power` may contain a human
@@ -526,31 +533,37 @@ fn add_requires_tags(tags: &mut Vec<&'static str>, requires: &Vec<String>) {
 ///
 /// # Notes:
 /// See `Game/UI/uiPowerInfo.c` for the basis for this information.
-pub fn describe_power(power: &mut PowerOutput, base_power: &BasePower, attrib_names: &AttribNames) {
+pub fn describe_power(
+    power: &mut PowerOutput,
+    base_power: &BasePower,
+    attrib_names: &AttribNames,
+    decimal_places: u8,
+) {
     // activation traits
     if power.activate.cast_time.is_normal() {
         power.display_info.insert(
-            "Activation Time",
-            Cow::Owned(get_pretty_duration(power.activate.cast_time)),
+            "Activation Time".into(),
+            Cow::Owned(get_pretty_duration(power.activate.cast_time, decimal_places)),
         );
     }
     if power.activate.recharge_time.is_normal() {
         power.display_info.insert(
-            "Recharge Time",
-            Cow::Owned(get_pretty_duration(power.activate.recharge_time)),
+            "Recharge Time".into(),
+            Cow::Owned(get_pretty_duration(power.activate.recharge_time, decimal_places)),
         );
     }
     if power.activate.endurance_cost.is_normal() {
         match base_power.e_type {
             PowerType::kPowerType_Toggle if power.activate.auto_cast_interval.is_normal() => {
                 let end_cost = power.activate.endurance_cost / power.activate.auto_cast_interval;
-                power
-                    .display_info
-                    .insert("Endurance Cost", Cow::Owned(format!("{:.2}/s", end_cost)));
+                power.display_info.insert(
+                    "Endurance Cost".into(),
+                    Cow::Owned(format!("{:.2}/s", end_cost)),
+                );
             }
             _ => {
                 power.display_info.insert(
-                    "Endurance Cost",
+                    "Endurance Cost".into(),
                     Cow::Owned(format!("{:.2}", power.activate.endurance_cost)),
                 );
             }
@@ -558,31 +571,35 @@ pub fn describe_power(power: &mut PowerOutput, base_power: &BasePower, attrib_na
     }
     // acc
     if base_power.p_auto_hit.len() == 0 && power.accuracy.is_normal() {
-        power
-            .display_info
-            .insert("Accuracy", Cow::Owned(format!("{:.2}x", power.accuracy)));
+        power.display_info.insert(
+            "Accuracy".into(),
+            Cow::Owned(format!("{:.2}x", power.accuracy)),
+        );
     }
     // target characteristics
-    power
-        .display_info
-        .insert("Power Type", Cow::Borrowed(base_power.e_type.get_string()));
+    power.display_info.insert(
+        "Power Type".into(),
+        Cow::Borrowed(base_power.e_type.get_string()),
+    );
     if let Some(s) = describe_target_type(&base_power.e_target_type) {
-        power.display_info.insert("Target Type", Cow::Borrowed(s));
+        power
+            .display_info
+            .insert("Target Type".into(), Cow::Borrowed(s));
     }
     if let Some(s) = describe_target_type(&base_power.e_target_type_secondary) {
         power
             .display_info
-            .insert("Secondary Target Type", Cow::Borrowed(s));
+            .insert("Secondary Target Type".into(), Cow::Borrowed(s));
     }
     if power.effect_area.range_feet.is_normal() {
         power.display_info.insert(
-            "Power Range",
+            "Power Range".into(),
             Cow::Owned(format!("{} ft.", power.effect_area.range_feet)),
         );
     }
     if power.effect_area.range_feet_secondary.is_normal() {
         power.display_info.insert(
-            "Secondary Power Range",
+            "Secondary Power Range".into(),
             Cow::Owned(format!("{} ft.", power.effect_area.range_feet_secondary)),
         );
     }
@@ -590,12 +607,12 @@ pub fn describe_power(power: &mut PowerOutput, base_power: &BasePower, attrib_na
         EffectArea::kEffectArea_Character => {
             power
                 .display_info
-                .insert("Effect Area", Cow::Borrowed("Single Target"));
+                .insert("Effect Area".into(), Cow::Borrowed("Single Target"));
         }
         EffectArea::kEffectArea_Location => {
             power
                 .display_info
-                .insert("Effect Area", Cow::Borrowed("Location"));
+                .insert("Effect Area".into(), Cow::Borrowed("Location"));
         }
         EffectArea::kEffectArea_Chain => {
             let mut effect_area = String::with_capacity(64);
@@ -615,7 +632,7 @@ pub fn describe_power(power: &mut PowerOutput, base_power: &BasePower, attrib_na
             }
             power
                 .display_info
-                .insert("Effect Area", Cow::Owned(effect_area));
+                .insert("Effect Area".into(), Cow::Owned(effect_area));
         }
         EffectArea::kEffectArea_Cone | EffectArea::kEffectArea_Sphere => {
             let mut effect_area = String::with_capacity(64);
@@ -635,7 +652,7 @@ pub fn describe_power(power: &mut PowerOutput, base_power: &BasePower, attrib_na
             }
             power
                 .display_info
-                .insert("Effect Area", Cow::Owned(effect_area));
+                .insert("Effect Area".into(), Cow::Owned(effect_area));
         }
         _ => (),
     }
@@ -659,13 +676,13 @@ pub fn describe_power(power: &mut PowerOutput, base_power: &BasePower, attrib_na
         if attack_types.len() > 0 {
             power
                 .display_info
-                .insert("Attack Types", Cow::Owned(attack_types.join(", ")));
+                .insert("Attack Types".into(), Cow::Owned(attack_types.join(", ")));
         }
     }
     match base_power.e_ai_report {
         AIReport::kAIReport_Never => {
             power.display_info.insert(
-                "Aggro Type",
+                "Aggro Type".into(),
                 Cow::Borrowed("Enemies will not notice this attack"),
             );
         }
@@ -673,6 +690,135 @@ pub fn describe_power(power: &mut PowerOutput, base_power: &BasePower, attrib_na
     }
 }
 
+/// Anything with a range at or below this is considered a melee attack for summaries.
+const MELEE_RANGE_FEET: f32 = 20.0;
+
+/// Damage magnitude buckets used in power summaries, as (upper bound of the total damage
+/// scale, label). Anything at or above the last bound is `Superior`.
+const DAMAGE_MAGNITUDES: &'static [(f32, &'static str)] =
+    &[(0.8, "Minor"), (1.2, "Moderate"), (1.8, "High")];
+
+/// Creates a one line summary of a power's primary effects, in the style of the in-game short
+/// help, e.g. "Ranged, Moderate DMG(Fire), Foe -Defense".
+///
+/// # Arguments:
+/// * `effect_groups` - The power's effect groups, as built for its `PowerOutput`.
+/// * `base_power` - The `BasePower` the effect groups came from.
+///
+/// # Returns:
+/// The summary, or `None` if there was nothing to summarize.
+///
+/// # Notes:
+/// Only PvE effects are considered. Damage is the total scale of all damage done to foes
+/// (including ticks and chance to hit), bucketed into `Minor` (below 0.8), `Moderate`
+/// (below 1.2), `High` (below 1.8), or `Superior`.
+pub fn summarize_power(
+    effect_groups: &[EffectGroupOutput],
+    base_power: &BasePower,
+) -> Option<String> {
+    let mut parts = Vec::new();
+    if let Some(range) = describe_range(base_power) {
+        parts.push(Cow::Borrowed(range));
+    }
+    let mut damage_types = Vec::new();
+    let mut damage_scale = 0.0;
+    let mut debuffs = Vec::new();
+    for group in effect_groups {
+        if group.pve_or_pvp.as_deref() == Some(PVP_TAG) {
+            continue;
+        }
+        for effect in &group.effects {
+            if matches!(
+                effect.target_type.as_deref(),
+                Some("Self") | Some("SelfAndPets")
+            ) {
+                continue;
+            }
+            let scaled = match effect.scaled.get(0) {
+                Some(scaled) => scaled,
+                None => continue,
+            };
+            match scaled.scaled_effect {
+                ScaledUnit::Damage(_) => {
+                    let ticks = effect.ticks.unwrap_or(1) as f32;
+                    damage_scale += scaled.scale.abs() * ticks * group.chance_percent / 100.0;
+                    for attrib in &effect.attributes {
+                        let damage_type = attrib.trim_end_matches("_Dmg");
+                        if !damage_types.contains(&damage_type) {
+                            damage_types.push(damage_type);
+                        }
+                    }
+                }
+                ScaledUnit::Percent(v) | ScaledUnit::Value(v) if v < 0.0 => {
+                    for attrib in &effect.attributes {
+                        let debuff = describe_debuff(attrib);
+                        if !debuffs.contains(&debuff) {
+                            debuffs.push(debuff);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    if !damage_types.is_empty() {
+        let magnitude = DAMAGE_MAGNITUDES
+            .iter()
+            .find(|(bound, _)| damage_scale < *bound)
+            .map(|(_, label)| *label)
+            .unwrap_or("Superior");
+        parts.push(Cow::Owned(format!(
+            "{} DMG({})",
+            magnitude,
+            damage_types.join("/")
+        )));
+    }
+    for debuff in debuffs {
+        parts.push(Cow::Owned(format!("Foe -{}", debuff)));
+    }
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join(", "))
+    }
+}
+
+/// Describes how a power reaches its targets for `summarize_power`.
+fn describe_range(base_power: &BasePower) -> Option<&'static str> {
+    if matches!(base_power.e_target_type, TargetType::kTargetType_Caster)
+        && matches!(base_power.e_effect_area, EffectArea::kEffectArea_Character)
+    {
+        return Some("Self");
+    }
+    let melee = base_power.f_range <= MELEE_RANGE_FEET;
+    match base_power.e_effect_area {
+        EffectArea::kEffectArea_Character if melee => Some("Melee"),
+        EffectArea::kEffectArea_Character => Some("Ranged"),
+        EffectArea::kEffectArea_Cone if melee => Some("Melee (Cone)"),
+        EffectArea::kEffectArea_Cone => Some("Ranged (Cone)"),
+        EffectArea::kEffectArea_Sphere if !base_power.f_range.is_normal() => Some("PBAoE"),
+        EffectArea::kEffectArea_Sphere => Some("Ranged (Targeted AoE)"),
+        EffectArea::kEffectArea_Location => Some("Ranged (Location AoE)"),
+        EffectArea::kEffectArea_Chain => Some("Ranged (Chain)"),
+        _ => None,
+    }
+}
+
+/// Gets a short name for a debuffed attribute, grouping the typed defenses together.
+fn describe_debuff(attrib: &str) -> &str {
+    if attrib.ends_with("_Def") {
+        "Defense"
+    } else if attrib.ends_with("_Dmg") {
+        "Damage"
+    } else {
+        match attrib {
+            "RunningSpeed" | "FlyingSpeed" | "JumpingSpeed" | "SwimmingSpeed" => "Speed",
+            "RechargeTime" => "Recharge",
+            _ => attrib,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -680,44 +826,49 @@ mod tests {
     #[test]
     fn get_pretty_duration_seconds_test() {
         let time = 2.543f32;
-        let output = get_pretty_duration(time);
+        let output = get_pretty_duration(time, 2);
         assert_eq!(output, "2.54s");
 
         let time = 19.987f32;
-        let output = get_pretty_duration(time);
+        let output = get_pretty_duration(time, 2);
         assert_eq!(output, "19.99s");
+
+        let output = get_pretty_duration(time, 1);
+        assert_eq!(output, "20s");
+        let output = get_pretty_duration(2.543, 3);
+        assert_eq!(output, "2.543s");
     }
 
     #[test]
     fn get_pretty_duration_minutes_test() {
         let time = 105.4f32;
-        let output = get_pretty_duration(time);
+        let output = get_pretty_duration(time, 2);
         assert_eq!(output, "1m 45s");
 
         let time = 320.0f32;
-        let output = get_pretty_duration(time);
+        let output = get_pretty_duration(time, 2);
         assert_eq!(output, "5m 20s");
     }
 
     #[test]
     fn get_pretty_duration_hours_test() {
         let time = 5544.0f32;
-        let output = get_pretty_duration(time);
+        let output = get_pretty_duration(time, 2);
         assert_eq!(output, "1h 32m");
 
         let time = 22032.0f32;
-        let output = get_pretty_duration(time);
+        let output = get_pretty_duration(time, 2);
         assert_eq!(output, "6h 7m");
     }
 
     #[test]
     fn get_pretty_duration_days_test() {
         let time = 475_200.0f32;
-        let output = get_pretty_duration(time);
+        let output = get_pretty_duration(time, 2);
         assert_eq!(output, "5d 12h");
 
         let time = 321_408.0f32;
-        let output = get_pretty_duration(time);
+        let output = get_pretty_duration(time, 2);
         assert_eq!(output, "3d 17h");
     }
 }
@@ -1,24 +1,15 @@
-use super::powers::make_power_ref_url;
 use super::*;
 use crate::structs::{Archetype, AttribModParam, AttribModTemplate, AttribNames, EffectGroup};
 use display;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::collections::HashSet;
 
-// Tags PvP vs PvE rules.
-const PVE_TAG: &'static str = "PVE";
-const PVP_TAG: &'static str = "PVP";
-
-// Offsets into character tables.
-const OFFSET_MODIFIERS: u32 = 0;
-const OFFSET_MAXIMUM: u32 = 8;
-const OFFSET_STRENGTH: u32 = 16;
-const OFFSET_RESIST: u32 = 24;
-const OFFSET_ABSOLUTE: u32 = 32;
+/// Archetype table used as the baseline when comparing damage across archetypes.
+const BASE_DAMAGE_TABLE: &'static str = "melee_damage";
 
 /// Describes the different types of scaled effects.
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ScaledUnit {
     Damage(f32),
@@ -30,7 +21,54 @@ pub enum ScaledUnit {
     Distance(f32),
 }
 
-#[derive(Serialize)]
+/// Written by hand because `ScaledUnit` is flattened into `AttribModScaled`, and the derived
+/// schema wouldn't allow the other fields next to the variant.
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for ScaledUnit {
+    fn schema_name() -> String {
+        String::from("ScaledUnit")
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        use schemars::schema::*;
+        let variants = [
+            "damage",
+            "healing",
+            "percent",
+            "duration_seconds",
+            "magnitude",
+            "value",
+            "distance",
+        ];
+        let one_of = variants
+            .iter()
+            .map(|variant| {
+                let mut object = ObjectValidation::default();
+                object
+                    .properties
+                    .insert(variant.to_string(), gen.subschema_for::<f32>());
+                object.required.insert(variant.to_string());
+                SchemaObject {
+                    instance_type: Some(InstanceType::Object.into()),
+                    object: Some(Box::new(object)),
+                    ..Default::default()
+                }
+                .into()
+            })
+            .collect();
+        SchemaObject {
+            subschemas: Some(Box::new(SubschemaValidation {
+                one_of: Some(one_of),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AttribModParamPowerOutput {
     #[serde(skip_serializing_if = "Option::is_none")]
     category: Option<String>,
@@ -40,13 +78,15 @@ pub struct AttribModParamPowerOutput {
     power: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AttribModParamScriptValueOutput {
     id: Option<String>,
     value: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AttribModParamPowerRefAndUrl {
     pub name: Option<NameKey>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -55,7 +95,8 @@ pub struct AttribModParamPowerRefAndUrl {
     pub url: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum AttribModParamOutput {
     Costume {
@@ -69,9 +110,9 @@ pub enum AttribModParamOutput {
         name: Option<NameKey>,
         #[serde(skip_serializing_if = "Option::is_none")]
         display_name: Option<String>,
-        #[serde(skip_serializing_if = "Vec::is_empty")]
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
         powers: Vec<AttribModParamPowerRefAndUrl>,
-        #[serde(skip_serializing_if = "Vec::is_empty")]
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
         power_names: Vec<NameKey>,
     },
     Phase {
@@ -99,6 +140,26 @@ pub enum AttribModParamOutput {
     ScriptValue {
         values: Vec<AttribModParamScriptValueOutput>,
     },
+    Knock {
+        /// "Knockback", "Knockup", or "Repel", depending on which way the target is pushed.
+        /// `None` if the target isn't pushed at all.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        label: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        velocity: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        velocity_magnitude: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        height: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        height_magnitude: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pitch: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        yaw: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        rotation: Option<f32>,
+    },
 }
 
 impl AttribModParamOutput {
@@ -121,7 +182,7 @@ impl AttribModParamOutput {
                         powers.push(AttribModParamPowerRefAndUrl {
                             name: Some(power.clone()),
                             display_name: None, // TODO
-                            url: make_power_ref_url(Some(power), config),
+                            url: namekey_to_url(power, UrlKind::Power, config),
                         });
                     }
                     Some(AttribModParamOutput::CreateEntity {
@@ -150,7 +211,7 @@ impl AttribModParamOutput {
                     powers.push(AttribModParamPowerRefAndUrl {
                         name: Some(power_name.to_owned()),
                         display_name: None, // TODO
-                        url: make_power_ref_url(Some(power_name), config),
+                        url: namekey_to_url(power_name, UrlKind::Power, config),
                     });
                 }
                 let count = if p.i_count > 1 { Some(p.i_count) } else { None };
@@ -178,34 +239,76 @@ impl AttribModParamOutput {
                 }
                 Some(AttribModParamOutput::ScriptValue { values })
             }
-            AttribModParam::Knock(_) => {
-                // TODO: not sure this is super informative to output
-                None
+            AttribModParam::Knock(k) => {
+                let non_zero = |val: f32| {
+                    if val != 0.0 {
+                        Some(round_to(val, config.decimal_places))
+                    } else {
+                        None
+                    }
+                };
+                Some(AttribModParamOutput::Knock {
+                    label: knock_label(k.fVelocity, k.fHeight as f32).map(String::from),
+                    velocity: non_zero(k.fVelocity),
+                    velocity_magnitude: non_zero(k.fVelocityMagnitude),
+                    height: non_zero(k.fHeight as f32),
+                    height_magnitude: non_zero(k.fHeightMagnitude),
+                    pitch: non_zero(k.fPitch),
+                    yaw: non_zero(k.fYaw),
+                    rotation: non_zero(k.fRotation),
+                })
             }
         }
     }
 }
 
-#[derive(Serialize)]
+/// Names the kind of knock an `AttribModParam_Knock` applies: mostly upward is a knockup, a
+/// push with no height at all is a repel, and anything else is a knockback. Returns `None` if
+/// the knock doesn't move the target.
+fn knock_label(velocity: f32, height: f32) -> Option<&'static str> {
+    if velocity == 0.0 && height == 0.0 {
+        None
+    } else if height.abs() > velocity.abs() {
+        Some("Knockup")
+    } else if height == 0.0 {
+        Some("Repel")
+    } else {
+        Some("Knockback")
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AttribModScaled {
     pub archetype: Option<String>,
     #[serde(flatten)]
     pub scaled_effect: ScaledUnit,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub average: f32,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub per_activation: f32,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub per_cast_cycle: f32,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub display_info: Vec<Cow<'static, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<Cow<'static, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_value: Option<String>,
     pub base_value: f32,
     pub scale: f32,
+    #[serde(default, skip_serializing_if = "not_normal")]
+    pub damage_scale: f32,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub permanent: bool,
 }
 
-#[derive(Default, Serialize)]
+#[derive(Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct StackingOutput {
-    pub behavior: &'static str,
+    pub behavior: Cow<'static, str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_behavior: Option<Cow<'static, str>>,
     pub by_caster: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub limit: Option<i32>,
@@ -219,7 +322,8 @@ impl StackingOutput {
         attrib_names: &AttribNames,
     ) -> Self {
         let mut stacking = StackingOutput {
-            behavior: attrib_mod.e_stack.get_string(),
+            behavior: attrib_mod.e_stack.get_string().into(),
+            duration_behavior: attrib_mod.e_stack.get_duration_behavior().map(Cow::from),
             by_caster: matches!(
                 attrib_mod.e_caster_stack,
                 CasterStackType::kCasterStackType_Individual
@@ -244,49 +348,54 @@ impl StackingOutput {
     }
 }
 
-#[derive(Default, Serialize)]
+#[derive(Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SuppressEventOutput {
-    pub event: Option<&'static str>,
-    #[serde(skip_serializing_if = "not_normal")]
+    pub event: Option<Cow<'static, str>>,
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub after_delay_seconds: f32,
     pub always: bool,
+    pub description: String,
 }
 
-#[derive(Default, Serialize)]
+#[derive(Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AttribModOutput {
     pub attributes: Vec<Cow<'static, str>>,
-    pub applies_to: Option<&'static str>,
-    pub application_type: Option<&'static str>,
+    pub applies_to: Option<Cow<'static, str>>,
+    pub application_type: Option<Cow<'static, str>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tick_chance_percent: Option<f32>,
-    pub target_type: Option<&'static str>,
+    pub target_type: Option<Cow<'static, str>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub magnitude: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub magnitude_expression: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub duration: Option<&'static str>,
+    pub duration: Option<Cow<'static, str>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub duration_seconds: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub duration_expression: Option<String>,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub after_delay_seconds: f32,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub continuous_apply_seconds: f32,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ticks: Option<i32>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub flags: Vec<&'static str>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<Cow<'static, str>>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub requires_ground: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parameter: Option<AttribModParamOutput>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stacking: Option<StackingOutput>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub suppress_events: Vec<SuppressEventOutput>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub cancel_events: Vec<&'static str>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cancel_events: Vec<Cow<'static, str>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub scaled: Vec<AttribModScaled>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mode: Option<String>,
@@ -303,18 +412,28 @@ impl AttribModOutput {
         config: &PowersConfig,
     ) -> Self {
         let mut output = AttribModOutput {
-            application_type: Some(attrib_mod.e_application_type.get_string()),
-            tick_chance_percent: Some(normalize(attrib_mod.f_tick_chance * 100.0)),
-            target_type: Some(attrib_mod.e_target.get_string()),
-            after_delay_seconds: normalize(attrib_mod.f_delay),
-            continuous_apply_seconds: normalize(attrib_mod.f_period),
-            flags: attrib_mod.i_flags.get_strings(),
+            application_type: Some(attrib_mod.e_application_type.get_string().into()),
+            tick_chance_percent: Some(round_to(
+                attrib_mod.f_tick_chance * 100.0,
+                config.decimal_places,
+            )),
+            target_type: Some(attrib_mod.e_target.get_string().into()),
+            after_delay_seconds: round_to(attrib_mod.f_delay, config.decimal_places),
+            continuous_apply_seconds: round_to(attrib_mod.f_period, config.decimal_places),
+            flags: attrib_mod
+                .i_flags
+                .get_strings()
+                .into_iter()
+                .map(Cow::from)
+                .collect(),
+            // only applies while the target is on the ground, separate from the power's own flag
+            requires_ground: attrib_mod.i_flags.contains(AttribModFlag::NearGround),
             ..Default::default()
         };
         // Additional flags
         for special_flag in &attrib_mod.i_flags_special
         {
-            output.flags.push(special_flag.get_string());
+            output.flags.push(special_flag.get_string().into());
         }
         // Stacking rules
         if !matches!(attrib_mod.e_stack, StackType::kStackType_Ignore) {
@@ -326,13 +445,14 @@ impl AttribModOutput {
         // Suppress and cancel events
         for suppress in &attrib_mod.pp_suppress {
             output.suppress_events.push(SuppressEventOutput {
-                event: Some(suppress.idx_event.get_string()),
+                event: Some(suppress.idx_event.get_string().into()),
                 after_delay_seconds: suppress.ul_seconds as f32,
                 always: suppress.b_always,
+                description: suppress.describe(),
             });
         }
         for cancel in &attrib_mod.pi_cancel_events {
-            output.cancel_events.push(cancel.get_string());
+            output.cancel_events.push(cancel.get_string().into());
         }
         // Handle different expressions
         if attrib_mod.ppch_magnitude.len() > 0 {
@@ -344,27 +464,27 @@ impl AttribModOutput {
             match attrib_mod.f_duration {
                 // describing InSeconds(0) as Instant is probably easier to parse
                 ModDuration::InSeconds(secs) if not_normal(&secs) => {
-                    output.duration = Some(ModDuration::kModDuration_Instant.get_string());
+                    output.duration = Some(ModDuration::kModDuration_Instant.get_string().into());
                     match attrib_mod.e_application_type {
                         // If the effect doesn't have a duration, the tick qualities don't matter.
                         ModApplicationType::kModApplicationType_OnTick
                             if attrib_mod.f_tick_chance == 1.0 =>
                         {
-                            output.application_type = Some("Immediate");
+                            output.application_type = Some("Immediate".into());
                             output.tick_chance_percent = None;
                         }
                         _ => (),
                     }
                 }
                 ModDuration::InSeconds(secs) => {
-                    output.duration = Some(attrib_mod.f_duration.get_string());
+                    output.duration = Some(attrib_mod.f_duration.get_string().into());
                     output.duration_seconds = Some(secs);
                     if output.continuous_apply_seconds.is_normal() {
                         output.ticks =
                             Some((secs / output.continuous_apply_seconds).floor() as i32 + 1);
                     }
                 }
-                _ => output.duration = Some(attrib_mod.f_duration.get_string()),
+                _ => output.duration = Some(attrib_mod.f_duration.get_string().into()),
             }
         }
         // attribs
@@ -380,22 +500,21 @@ impl AttribModOutput {
                 output.attr_type = attrib_type(attrib_mod.off_aspect, a.0);
             }
         }
-        output.applies_to = Some(output.attr_type.as_ref().unwrap().get_string());
+        output.applies_to = Some(output.attr_type.as_ref().unwrap().get_string().into());
         // special cases for "booleans"
         if let Some(attrib) = attrib_mod.p_attrib.get(0) {
-            if attrib.usize() >= CharacterAttributes::OFFSET_CONFUSED
-                && attrib.usize() <= CharacterAttributes::OFFSET_ONLY_AFFECTS_SELF
-            {
+            if attrib.is_boolean_status() {
                 // base magnitude is only relevant if this is a boolean attribute
-                output.magnitude = Some(normalize(attrib_mod.f_magnitude));
+                output.magnitude = Some(round_to(attrib_mod.f_magnitude, config.decimal_places));
             }
             match attrib_mod.e_type {
                 // if the mod is of type duration, it's scaled effect will be the duration
                 ModType::kModType_Duration => {
                     // duration is calculated
-                    output.duration = Some("InSecondsScaled");
+                    output.duration = Some("InSecondsScaled".into());
                     // probably got overwritten above
-                    output.application_type = Some(attrib_mod.e_application_type.get_string());
+                    output.application_type =
+                        Some(attrib_mod.e_application_type.get_string().into());
                 }
                 _ => (),
             }
@@ -418,7 +537,12 @@ impl AttribModOutput {
             output.parameter = AttribModParamOutput::from_attrib_mod_param(param, config);
         }
         // scaling per archetype
-        output.add_effect_scales(attrib_mod, archetypes, config.at_level);
+        output.add_effect_scales(
+            attrib_mod,
+            archetypes,
+            config.at_level,
+            config.decimal_places,
+        );
         if let Some(scaled) = output.scaled.get(0) {
             match scaled.scaled_effect {
                 // Reduce confusion by blanking the base magnitude (would always be 1.0 in this case anyways)
@@ -435,18 +559,39 @@ impl AttribModOutput {
         attrib_mod: &AttribModTemplate,
         archetypes: &Vec<ObjRef<Archetype>>,
         at_level: i32,
+        decimal_places: u8,
     ) {
         if let Some(table_name) = &attrib_mod.pch_table {
             for at in archetypes.iter().map(|at| at.borrow()) {
                 // calculate scaled effect for each archetype attached to this power
-                if let Some(named_table) = at.pp_named_tables.get(&table_name.to_lowercase()) {
-                    let base_value = named_table.pf_values[(at_level - 1) as usize];
+                if let Some(base_value) = at
+                    .table(table_name)
+                    .and_then(|named_table| named_table.value_at(at_level as usize))
+                {
                     let scaled_value = base_value * attrib_mod.f_scale;
                     if let Some(scaled_effect) = get_scaled_effect(
                         attrib_mod,
                         self.attr_type.as_ref().unwrap(),
                         scaled_value,
+                        decimal_places,
                     ) {
+                        let permanent = is_permanent(attrib_mod, &scaled_effect);
+                        let style = get_attrib_style(
+                            attrib_mod,
+                            self.attr_type.as_ref().unwrap(),
+                            &scaled_effect,
+                        );
+                        // damage is shown as a positive number, like it is in `ScaledUnit`
+                        let raw_value = match scaled_effect {
+                            ScaledUnit::Damage(_) => scaled_value.abs(),
+                            _ => scaled_value,
+                        };
+                        let display_value = match scaled_effect {
+                            ScaledUnit::DurationSeconds(_) => {
+                                format!("{} s", style.format_value(raw_value))
+                            }
+                            _ => style.format_value(raw_value),
+                        };
                         self.scaled.push(AttribModScaled {
                             archetype: at.pch_display_name.clone(),
                             scaled_effect,
@@ -454,9 +599,19 @@ impl AttribModOutput {
                             per_activation: 0.0,
                             per_cast_cycle: 0.0,
                             display_info: Vec::new(),
-                            base_value: normalize4(base_value),
-                            scale: normalize4(attrib_mod.f_scale),
+                            style: Some(style.get_string().into()),
+                            display_value: Some(display_value),
+                            base_value: round_to(base_value, fine_places(decimal_places)),
+                            scale: round_to(attrib_mod.f_scale, fine_places(decimal_places)),
+                            damage_scale: 0.0,
+                            permanent,
                         });
+                        if let Some(scaled) = self.scaled.last_mut() {
+                            if matches!(scaled.scaled_effect, ScaledUnit::Damage(_)) {
+                                scaled.damage_scale =
+                                    get_damage_scale(&*at, scaled_value, at_level, decimal_places);
+                            }
+                        }
                     }
                 }
             }
@@ -464,29 +619,32 @@ impl AttribModOutput {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct EffectGroupOutput {
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub pve_or_pvp: Option<&'static str>,
-    #[serde(skip_serializing_if = "HashSet::is_empty")]
-    pub tags: HashSet<&'static str>,
+    pub pve_or_pvp: Option<Cow<'static, str>>,
+    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
+    pub tags: HashSet<Cow<'static, str>>,
     pub visible_in_info_window: bool,
     pub chance_percent: f32,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub procs_per_minute: f32,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub after_delay_seconds: f32,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub radius_inner: f32,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub radius_outer: f32,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub requires: Vec<String>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub flags: Vec<&'static str>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<Cow<'static, str>>,
     pub effects: Vec<AttribModOutput>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub child_effect_groups: Vec<EffectGroupOutput>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub truncated: bool,
 }
 
 impl EffectGroupOutput {
@@ -496,33 +654,57 @@ impl EffectGroupOutput {
         base_power: &BasePower,
         archetypes: &Vec<ObjRef<Archetype>>,
         config: &PowersConfig,
+    ) -> Self {
+        Self::from_effect_group_at_depth(effect, attrib_names, base_power, archetypes, config, 1)
+    }
+
+    /// Converts an `EffectGroup` nested `depth` levels deep (top level groups are 1). If this is
+    /// deeper than `config.max_effect_depth`, the group's effects and children are left out and
+    /// it is marked as truncated.
+    fn from_effect_group_at_depth(
+        effect: &EffectGroup,
+        attrib_names: &AttribNames,
+        base_power: &BasePower,
+        archetypes: &Vec<ObjRef<Archetype>>,
+        config: &PowersConfig,
+        depth: usize,
     ) -> Self {
         let mut group = EffectGroupOutput {
-            pve_or_pvp: get_pve_or_pvp(&effect.ppch_tags, &effect.i_flags, &effect.ppch_requires),
+            pve_or_pvp: effect.pve_or_pvp().map(Cow::from),
             tags: HashSet::new(),
             visible_in_info_window: true,
-            chance_percent: normalize(effect.f_chance * 100.0),
-            procs_per_minute: normalize(effect.f_procs_per_minute),
-            after_delay_seconds: normalize(effect.f_delay),
+            chance_percent: round_to(effect.f_chance * 100.0, config.decimal_places),
+            procs_per_minute: round_to(effect.f_procs_per_minute, config.decimal_places),
+            after_delay_seconds: round_to(effect.f_delay, config.decimal_places),
             radius_inner: 0.0,
             radius_outer: 0.0,
             requires: Vec::new(),
-            flags: effect.i_flags.get_strings(),
+            flags: effect
+                .i_flags
+                .get_strings()
+                .into_iter()
+                .map(Cow::from)
+                .collect(),
             effects: Vec::new(),
             child_effect_groups: Vec::new(),
+            truncated: false,
         };
         if effect.f_radius_inner == 0.0 && effect.f_radius_outer == 0.0 {
             // HACK: fake a MainTargetOnly flag (I accept this since the 0/0 radius is also a hack on the game's part)
-            group.flags.push("MainTargetOnly");
+            group.flags.push("MainTargetOnly".into());
         } else if effect.f_radius_inner > -1.0 && effect.f_radius_outer > -1.0 {
-            group.radius_inner = normalize(effect.f_radius_inner);
-            group.radius_outer = normalize(effect.f_radius_outer);
+            group.radius_inner = round_to(effect.f_radius_inner, config.decimal_places);
+            group.radius_outer = round_to(effect.f_radius_outer, config.decimal_places);
         }
         if let Some(rule) = requires_to_string(&effect.ppch_requires) {
             group.requires.push(rule);
         }
         check_tags_group(&mut group, &effect.ppch_tags);
         check_special_requires(&mut group, &effect.ppch_requires);
+        if matches!(config.max_effect_depth, Some(max_depth) if depth > max_depth) {
+            group.truncated = true;
+            return group;
+        }
         let filtered_archetypes = filter_archetypes_eg(effect, archetypes);
         for attrib_mod in &effect.pp_templates {
             let mut attrib_mod_output = AttribModOutput::from_attrib_mod_template(
@@ -538,33 +720,63 @@ impl EffectGroupOutput {
                 &group,
                 &effect.ppch_requires,
                 &attrib_names,
+                config.decimal_places,
+            );
+            calculate_damage(
+                &mut attrib_mod_output,
+                &group,
+                attrib_mod,
+                base_power,
+                config.decimal_places,
             );
-            calculate_damage(&mut attrib_mod_output, &group, attrib_mod, base_power);
             group.effects.push(attrib_mod_output);
         }
         for child_group in &effect.pp_effects {
             group
                 .child_effect_groups
-                .push(EffectGroupOutput::from_effect_group(
+                .push(EffectGroupOutput::from_effect_group_at_depth(
                     child_group,
                     attrib_names,
                     base_power,
                     archetypes,
                     config,
+                    depth + 1,
                 ));
         }
         group
     }
 }
 
+/// Tests whether an effect lasts until the target is killed or the power is shut off, either
+/// because of its duration or because its scaled duration hits the "forever" sentinel.
+fn is_permanent(attrib_mod: &AttribModTemplate, scaled_effect: &ScaledUnit) -> bool {
+    let forever = |duration: &ModDuration| {
+        matches!(
+            duration,
+            ModDuration::kModDuration_UntilKilled | ModDuration::kModDuration_UntilShutOff
+        )
+    };
+    if let ScaledUnit::DurationSeconds(secs) = scaled_effect {
+        // either "forever" duration counts here, so the power type doesn't matter
+        forever(&ModDuration::from_f32(*secs, &PowerType::kPowerType_Click))
+    } else {
+        attrib_mod.ppch_duration.is_empty() && forever(&attrib_mod.f_duration)
+    }
+}
+
 /// Derives additional damage stats on an `attrib_mod`.
+/// Permanent effects that tick have no meaningful total, so they're left without one.
 fn calculate_damage(
     attrib_mod: &mut AttribModOutput,
     effect_group: &EffectGroupOutput,
     template: &AttribModTemplate,
     base_power: &BasePower,
+    decimal_places: u8,
 ) {
     for scaled in &mut attrib_mod.scaled {
+        if scaled.permanent && attrib_mod.continuous_apply_seconds.is_normal() {
+            continue;
+        }
         // look for damage/healing attributes
         if matches!(attrib_mod.attr_type, Some(AttribType::kAttribType_Abs))
             && (matches!(scaled.scaled_effect, ScaledUnit::Damage(_) |
@@ -593,7 +805,7 @@ fn calculate_damage(
             }
 
             // total
-            scaled.average = normalize(if tick_chance < 1.0 {
+            let average = if tick_chance < 1.0 {
                 // cancel on miss average
                 let mut avg_ticks = 0.0;
                 for k in 1..ticks {
@@ -604,15 +816,20 @@ fn calculate_damage(
             } else {
                 // consistent damage
                 amount * (effect_group.chance_percent / 100.0)
-            });
+            };
+            scaled.average = round_to(average, decimal_places);
 
             // derived
             if base_power.f_time_to_activate.is_normal() {
-                scaled.per_activation = normalize(scaled.average / base_power.f_time_to_activate);
+                scaled.per_activation = round_to(
+                    scaled.average / base_power.f_time_to_activate,
+                    decimal_places,
+                );
                 if base_power.f_recharge_time.is_normal() {
-                    scaled.per_cast_cycle = normalize(
+                    scaled.per_cast_cycle = round_to(
                         scaled.average
                             / (base_power.f_time_to_activate + base_power.f_recharge_time),
+                        decimal_places,
                     );
                 }
             }
@@ -658,47 +875,6 @@ fn filter_archetypes_eg(
     }
 }
 
-/// Checks the requires clause of an effect group to see if it applies to pve, pvp, or both.
-fn get_pve_or_pvp(
-    tags: &Vec<String>,
-    flags: &EffectGroupFlag,
-    requires: &Vec<String>,
-) -> Option<&'static str> {
-    // check the mode flags first, they're the most explicit
-    if flags.contains(EffectGroupFlag::PVEOnly) {
-        return Some(PVE_TAG);
-    } else if flags.contains(EffectGroupFlag::PVPOnly) {
-        return Some(PVP_TAG);
-    }
-
-    let requires_str = requires.iter().map(|s| &**s).collect::<Vec<_>>();
-
-    // check for the MLCrit and BossCrit effects, they use player to test for non-pvp in most cases
-    // but may have an explicit expression
-    if is_critical_by_tags(tags) {
-        match requires_str[..] {
-            ["enttype", "target>", "critter", "eq"] => return Some(PVE_TAG),
-            ["enttype", "target>", "player", "eq"] => return Some(PVP_TAG),
-            _ => (),
-        }
-        // NOTE: may false positive
-        return Some(PVE_TAG);
-    }
-
-    // iterate through requirements to find common ways of checking for PVP/PVE
-    // NOTE: can potentially result in false positives, especially the first 2 clauses
-    for i in 0..requires_str.len() {
-        match requires_str[i..] {
-            ["enttype", "target>", "critter", "eq", ..] => return Some(PVE_TAG),
-            ["enttype", "target>", "player", "eq", ..] => return Some(PVP_TAG),
-            ["isPVPMap?", "!", ..] => return Some(PVE_TAG),
-            ["isPVPMap?", ..] => return Some(PVP_TAG),
-            _ => (),
-        }
-    }
-    None
-}
-
 /// Modifies `effect_group` based on the content of `requires`.
 fn check_special_requires(effect_group: &mut EffectGroupOutput, requires: &Vec<String>) {
     let requires_str = requires.iter().map(|s| &**s).collect::<Vec<_>>();
@@ -706,17 +882,17 @@ fn check_special_requires(effect_group: &mut EffectGroupOutput, requires: &Vec<S
         match &requires_str[i..] {
             // domination
             ["kStealth", "source>", "0.5", ">", ..] => {
-                effect_group.tags.insert("Domination");
+                effect_group.tags.insert("Domination".into());
             }
             // scourge
             ["kHitPoints%", "target>", "10", "-", "100", "*", "50", "10", "-", "/", "0", "100", "minmax", "rand", "100", "*", ..] =>
             {
-                effect_group.tags.insert("Scourge");
+                effect_group.tags.insert("Scourge".into());
             }
             // containment
             ["kImmobilized", "target>", "0", ">", "kHeld", "target>", "0", ">", "||", "kSleep", "target>", "0", ">", "||", "kStunned", "target>", "0", ">", "||", ..] =>
             {
-                effect_group.tags.insert("Containment");
+                effect_group.tags.insert("Containment".into());
             }
             _ => (),
         }
@@ -726,14 +902,14 @@ fn check_special_requires(effect_group: &mut EffectGroupOutput, requires: &Vec<S
 /// Modifies `effect_group` based on the content of `tags`.
 fn check_tags_group(effect_group: &mut EffectGroupOutput, tags: &Vec<String>) {
     if is_critical_by_tags(&tags) {
-        effect_group.tags.insert("Critical");
+        effect_group.tags.insert("Critical".into());
     }
     for tag in tags {
         // several tags modify the chance of an effect, these refer to "global chance mods"
         // that are handled in code
         match &tag[..] {
             "FieryEmbrace" => {
-                effect_group.tags.insert("FieryEmbrace");
+                effect_group.tags.insert("FieryEmbrace".into());
                 effect_group.chance_percent = 100.0;
             }
             "Lethal" | "FireDamage" | "ColdDamage" | "ToxicDamage" => {
@@ -762,19 +938,19 @@ fn check_tags_group(effect_group: &mut EffectGroupOutput, tags: &Vec<String>) {
             | "LethalKB70"
             | "HailofBulletsKnockdown"
             | "HailofBulletsEndKnockback" => {
-                effect_group.tags.insert("DualPistolsLethalMode");
+                effect_group.tags.insert("DualPistolsLethalMode".into());
             }
             "FireDamage" | "FireDamageDoT" | "HailofBulletsFire" => {
-                effect_group.tags.insert("DualPistolsFireMode");
+                effect_group.tags.insert("DualPistolsFireMode".into());
             }
             "ColdDamage" | "HailofBulletsCold" => {
-                effect_group.tags.insert("DualPistolsColdMode");
+                effect_group.tags.insert("DualPistolsColdMode".into());
             }
             "ToxicDamage" | "HailofBulletsToxic" => {
-                effect_group.tags.insert("DualPistolsToxicMode");
+                effect_group.tags.insert("DualPistolsToxicMode".into());
             }
             "SoundBoost" => {
-                effect_group.tags.insert("SoundBoost");
+                effect_group.tags.insert("SoundBoost".into());
             }
             _ => (),
         }
@@ -791,18 +967,6 @@ fn check_tags_effect(effect: &mut AttribModOutput, tags: &Vec<String>) {
     }
 }
 
-/// Searches `tags` for any of the known critical hit tags.
-fn is_critical_by_tags(tags: &Vec<String>) -> bool {
-    for tag in tags {
-        match &tag[..] {
-            "MLCrit" | "BossCrit" | "PlayerCrit" | "ECCritModPlayer" | "ECCritModSmall"
-            | "ECCritModLarge" => return true,
-            _ => (),
-        }
-    }
-    false
-}
-
 /// Converts the offset of the character attributes to a type
 /// which indicates what we're modifying.
 /// See Common/entity/character_attribs.h CharacterAttribSet
@@ -844,7 +1008,9 @@ fn get_scaled_effect(
     attrib_mod: &AttribModTemplate,
     attrib_type: &AttribType,
     scaled_value: f32,
+    decimal_places: u8,
 ) -> Option<ScaledUnit> {
+    let round = |val: f32| round_to(val, decimal_places);
     // special attributes require special handling
     if let Some(attrib) = attrib_mod.p_attrib.get(0) {
         if let Some(special) = attrib.as_special_attrib() {
@@ -852,7 +1018,7 @@ fn get_scaled_effect(
                 // global/power chance mods need cumulative chance value
                 SpecialAttrib::kSpecialAttrib_GlobalChanceMod
                 | SpecialAttrib::kSpecialAttrib_PowerChanceMod => {
-                    return Some(ScaledUnit::Percent(normalize(scaled_value * 100.0)));
+                    return Some(ScaledUnit::Percent(round(scaled_value * 100.0)));
                 }
                 // discard all other special attribs
                 _ => return None,
@@ -861,14 +1027,14 @@ fn get_scaled_effect(
     }
     // duration in seconds
     if matches!(attrib_mod.e_type, ModType::kModType_Duration) {
-        return Some(ScaledUnit::DurationSeconds(normalize(scaled_value)));
+        return Some(ScaledUnit::DurationSeconds(round(scaled_value)));
     }
     // strengths and resists are always percent
     if matches!(
         attrib_type,
         AttribType::kAttribType_Str | AttribType::kAttribType_Res
     ) {
-        return Some(ScaledUnit::Percent(normalize(scaled_value * 100.0)));
+        return Some(ScaledUnit::Percent(round(scaled_value * 100.0)));
     }
     // character attributes depend on what we're modifying
     if let Some(attrib) = attrib_mod.p_attrib.get(0) {
@@ -879,9 +1045,9 @@ fn get_scaled_effect(
             | CharacterAttributes::OFFSET_HIT_POINTS
             | CharacterAttributes::OFFSET_ABSORB => {
                 if scaled_value < 0.0 {
-                    return Some(ScaledUnit::Damage(normalize(scaled_value.abs())));
+                    return Some(ScaledUnit::Damage(round(scaled_value.abs())));
                 } else {
-                    return Some(ScaledUnit::Healing(normalize(scaled_value)));
+                    return Some(ScaledUnit::Healing(round(scaled_value)));
                 }
             }
             // Percentage based attributes
@@ -894,7 +1060,7 @@ fn get_scaled_effect(
             | CharacterAttributes::OFFSET_ACCURACY..=CharacterAttributes::OFFSET_RANGE
             | CharacterAttributes::OFFSET_ELUSIVITY_0
                 ..=CharacterAttributes::OFFSET_ELUSIVITY_BASE => {
-                return Some(ScaledUnit::Percent(normalize(scaled_value * 100.0)));
+                return Some(ScaledUnit::Percent(round(scaled_value * 100.0)));
             }
             CharacterAttributes::OFFSET_ENDURANCE
                 if matches!(
@@ -902,7 +1068,7 @@ fn get_scaled_effect(
                     AttribType::kAttribType_Cur | AttribType::kAttribType_Mod
                 ) =>
             {
-                return Some(ScaledUnit::Percent(normalize(scaled_value * 100.0)));
+                return Some(ScaledUnit::Percent(round(scaled_value * 100.0)));
             }
             // Distance based attributes
             CharacterAttributes::OFFSET_STEALTH_RADIUS_PVE
@@ -910,24 +1076,402 @@ fn get_scaled_effect(
                 if matches!(attrib_type, AttribType::kAttribType_Cur) =>
             {
                 // if current value, they're actually % instead of dist
-                return Some(ScaledUnit::Percent(normalize(scaled_value * 100.0)));
+                return Some(ScaledUnit::Percent(round(scaled_value * 100.0)));
             }
             CharacterAttributes::OFFSET_STEALTH_RADIUS_PVE
                 ..=CharacterAttributes::OFFSET_PERCEPTION_RADIUS => {
-                return Some(ScaledUnit::Distance(normalize(scaled_value)));
+                return Some(ScaledUnit::Distance(round(scaled_value)));
             }
             // The following are "boolean".. which actually means that the magnitude
             // of total effects are reduced by the total magnitude of protection, and then if the
             // result is >0, the status is applied to the character.
-            CharacterAttributes::OFFSET_CONFUSED
-                ..=CharacterAttributes::OFFSET_ONLY_AFFECTS_SELF
-            | CharacterAttributes::OFFSET_KNOCKUP..=CharacterAttributes::OFFSET_REPEL => {
-                return Some(ScaledUnit::Magnitude(normalize(scaled_value)));
+            _ if attrib.is_boolean_status() => {
+                return Some(ScaledUnit::Magnitude(round(scaled_value)));
+            }
+            CharacterAttributes::OFFSET_KNOCKUP..=CharacterAttributes::OFFSET_REPEL => {
+                return Some(ScaledUnit::Magnitude(round(scaled_value)));
             }
             // Any other character attribute is a raw value to be applied.
-            _ => return Some(ScaledUnit::Value(normalize(scaled_value))),
+            _ => return Some(ScaledUnit::Value(round(scaled_value))),
         }
     }
     // anything else is a special case and doesn't use scaling (creating entities, granting powers, etc.)
     None
 }
+
+/// Picks the `AttribStyle` the game UI would use to display a scaled effect, based on the
+/// attribute and aspect being modified. Like `get_scaled_effect`, there's no standard way to
+/// determine this, so attributes without a specific style fall back to the scaled effect's unit.
+///
+/// # Notes:
+///
+/// No attribute maps to `kAttribStyle_PercentMinus100` or `kAttribStyle_InversePercent`, since
+/// neither can be told apart from a regular percentage using the modifier alone.
+fn get_attrib_style(
+    attrib_mod: &AttribModTemplate,
+    attrib_type: &AttribType,
+    scaled_effect: &ScaledUnit,
+) -> AttribStyle {
+    let attrib = match attrib_mod.p_attrib.get(0) {
+        Some(attrib) if attrib.as_special_attrib().is_none() => attrib,
+        _ => return get_unit_style(scaled_effect),
+    };
+    if matches!(attrib_mod.e_type, ModType::kModType_Duration) {
+        return get_unit_style(scaled_effect);
+    }
+    match attrib_type {
+        AttribType::kAttribType_Str => AttribStyle::kAttribStyle_Percent,
+        AttribType::kAttribType_Res => match attrib.usize() {
+            // resistance to status effects reduces how long they last
+            _ if attrib.is_boolean_status() => AttribStyle::kAttribStyle_ResistanceDuration,
+            CharacterAttributes::OFFSET_TAUNT | CharacterAttributes::OFFSET_PLACATE => {
+                AttribStyle::kAttribStyle_ResistanceDuration
+            }
+            // resistance to knockback and friends reduces how far they throw
+            CharacterAttributes::OFFSET_KNOCKUP..=CharacterAttributes::OFFSET_REPEL => {
+                AttribStyle::kAttribStyle_ResistanceDistance
+            }
+            // resistance to endurance drain
+            CharacterAttributes::OFFSET_ENDURANCE | CharacterAttributes::OFFSET_RECOVERY => {
+                AttribStyle::kAttribStyle_EnduranceReduction
+            }
+            _ => AttribStyle::kAttribStyle_Percent,
+        },
+        _ => match attrib.usize() {
+            // absolute movement speeds (e.g. speed caps)
+            CharacterAttributes::OFFSET_RUNNING_SPEED
+                ..=CharacterAttributes::OFFSET_JUMPING_SPEED
+                if matches!(
+                    attrib_type,
+                    AttribType::kAttribType_Max | AttribType::kAttribType_Abs
+                ) =>
+            {
+                AttribStyle::kAttribStyle_Speed
+            }
+            CharacterAttributes::OFFSET_JUMP_HEIGHT
+                if matches!(
+                    attrib_type,
+                    AttribType::kAttribType_Max | AttribType::kAttribType_Abs
+                ) =>
+            {
+                AttribStyle::kAttribStyle_Distance
+            }
+            // absolute regeneration and recovery are amounts per second
+            CharacterAttributes::OFFSET_REGENERATION
+                ..=CharacterAttributes::OFFSET_INSIGHT_RECOVERY
+                if matches!(attrib_type, AttribType::kAttribType_Abs) =>
+            {
+                AttribStyle::kAttribStyle_PerSecond
+            }
+            CharacterAttributes::OFFSET_THREAT_LEVEL => AttribStyle::kAttribStyle_Multiply,
+            CharacterAttributes::OFFSET_INSIGHT => AttribStyle::kAttribStyle_Integer,
+            // costs are divided by 1 + discount
+            CharacterAttributes::OFFSET_ENDURANCE_DISCOUNT
+            | CharacterAttributes::OFFSET_INSIGHT_DISCOUNT => {
+                AttribStyle::kAttribStyle_EnduranceReduction
+            }
+            _ => get_unit_style(scaled_effect),
+        },
+    }
+}
+
+/// Picks the `AttribStyle` matching the unit of a scaled effect.
+fn get_unit_style(scaled_effect: &ScaledUnit) -> AttribStyle {
+    match scaled_effect {
+        ScaledUnit::Percent(_) => AttribStyle::kAttribStyle_Percent,
+        ScaledUnit::Distance(_) => AttribStyle::kAttribStyle_Distance,
+        ScaledUnit::Damage(_) | ScaledUnit::Healing(_) | ScaledUnit::Magnitude(_) => {
+            AttribStyle::kAttribStyle_Magnitude
+        }
+        ScaledUnit::DurationSeconds(_) | ScaledUnit::Value(_) => AttribStyle::kAttribStyle_None,
+    }
+}
+
+/// Gets the damage of an effect relative to the archetype's base damage modifier, so that
+/// the same attack compares equally across archetypes (1.0 is a "normal" hit).
+fn get_damage_scale(at: &Archetype, scaled_value: f32, at_level: i32, decimal_places: u8) -> f32 {
+    if let Some(base_table) = at.table(BASE_DAMAGE_TABLE) {
+        if let Some(base_damage) = base_table.value_at(at_level.max(1) as usize) {
+            if base_damage.is_normal() {
+                return round_to(scaled_value / base_damage, fine_places(decimal_places));
+            }
+        }
+    }
+    0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::config::MINIMAL_CONFIG;
+    use crate::structs::AttribModParam_Knock;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn stacking_refresh_duration_behavior_test() {
+        let mut attrib_mod = AttribModTemplate::new();
+        attrib_mod.e_stack = StackType::kStackType_Refresh;
+        let stacking =
+            StackingOutput::from_attrib_mod_template(&attrib_mod, &AttribNames::new());
+        assert_eq!(stacking.behavior, "Refresh");
+        assert_eq!(
+            stacking.duration_behavior.as_deref(),
+            Some("Resets the duration of all existing copies, then adds a new copy.")
+        );
+        assert!(stacking.limit.is_none());
+    }
+
+    #[test]
+    fn knock_param_test() {
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        let mut knock = AttribModParam_Knock::new();
+        knock.fVelocity = 12.345;
+        knock.fHeight = 4;
+        knock.fPitch = 0.5;
+        let param = AttribModParamOutput::from_attrib_mod_param(
+            &AttribModParam::Knock(knock),
+            &config,
+        );
+        assert_eq!(
+            serde_json::to_string(&param).unwrap(),
+            "{\"knock\":{\"label\":\"Knockback\",\"velocity\":12.35,\"height\":4.0,\"pitch\":0.5}}"
+        );
+
+        assert_eq!(knock_label(2.0, 10.0), Some("Knockup"));
+        assert_eq!(knock_label(8.0, 0.0), Some("Repel"));
+        assert_eq!(knock_label(8.0, 2.0), Some("Knockback"));
+        assert_eq!(knock_label(0.0, 0.0), None);
+
+        let mut knock = AttribModParam_Knock::new();
+        knock.fYaw = 1.5;
+        let param = AttribModParamOutput::from_attrib_mod_param(
+            &AttribModParam::Knock(knock),
+            &config,
+        );
+        assert_eq!(
+            serde_json::to_string(&param).unwrap(),
+            "{\"knock\":{\"yaw\":1.5}}"
+        );
+    }
+
+    #[test]
+    fn requires_ground_test() {
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        let make_template = |flags| {
+            let mut attrib_mod = AttribModTemplate::new();
+            attrib_mod.p_attrib.push(CharacterAttrib(CharacterAttributes::OFFSET_FLY as i32));
+            attrib_mod.i_flags = flags;
+            attrib_mod
+        };
+        let mut effect_group = EffectGroup::new();
+        effect_group.pp_templates.push(make_template(AttribModFlag::NearGround));
+        effect_group.pp_templates.push(make_template(AttribModFlag::empty()));
+
+        // the power-level flag shouldn't change what each effect reports
+        for near_ground in &[false, true] {
+            let mut power = BasePower::new();
+            power.b_near_ground = *near_ground;
+            let output = EffectGroupOutput::from_effect_group(
+                &effect_group,
+                &AttribNames::new(),
+                &power,
+                &Vec::new(),
+                &config,
+            );
+            let requires_ground: Vec<_> =
+                output.effects.iter().map(|e| e.requires_ground).collect();
+            assert_eq!(requires_ground, vec![true, false]);
+        }
+    }
+
+    #[test]
+    fn damage_scale_test() {
+        let make_at = |name: &str, damage: f32| {
+            let mut at = Archetype::new();
+            at.pch_display_name = Some(name.to_string());
+            let mut table = NamedTable::new();
+            table.pf_values = vec![damage; 50];
+            at.pp_named_tables.insert(BASE_DAMAGE_TABLE.to_string(), table);
+            Rc::new(RefCell::new(at))
+        };
+        let archetypes = vec![make_at("Blaster", -62.56), make_at("Scrapper", -55.61)];
+        let mut attrib_mod = AttribModTemplate::new();
+        attrib_mod.p_attrib.push(CharacterAttrib(CharacterAttributes::OFFSET_DMG_0 as i32));
+        attrib_mod.pch_table = Some(String::from("Melee_Damage"));
+        attrib_mod.f_scale = 1.64;
+
+        let mut output = AttribModOutput::default();
+        output.attr_type = Some(AttribType::kAttribType_Cur);
+        output.add_effect_scales(&attrib_mod, &archetypes, 50, 2);
+        assert_eq!(output.scaled.len(), 2);
+        assert!(matches!(output.scaled[0].scaled_effect, ScaledUnit::Damage(d) if d == 102.6));
+        assert!(matches!(output.scaled[1].scaled_effect, ScaledUnit::Damage(d) if d == 91.2));
+        assert_eq!(output.scaled[0].damage_scale, 1.64);
+        assert_eq!(output.scaled[1].damage_scale, 1.64);
+    }
+
+    #[test]
+    fn scaled_display_value_test() {
+        let mut at = Archetype::new();
+        let mut table = NamedTable::new();
+        table.pf_values = vec![0.1; 50];
+        at.pp_named_tables.insert(String::from("melee_buff_def"), table);
+        let archetypes = vec![Rc::new(RefCell::new(at))];
+        let mut attrib_mod = AttribModTemplate::new();
+        attrib_mod.p_attrib.push(CharacterAttrib(CharacterAttributes::OFFSET_DEF_0 as i32));
+        attrib_mod.pch_table = Some(String::from("Melee_Buff_Def"));
+        attrib_mod.f_scale = 1.5;
+
+        let mut output = AttribModOutput::default();
+        output.attr_type = Some(AttribType::kAttribType_Cur);
+        output.add_effect_scales(&attrib_mod, &archetypes, 50, 2);
+        assert_eq!(output.scaled[0].style.as_deref(), Some("Percent"));
+        assert_eq!(output.scaled[0].display_value.as_deref(), Some("15%"));
+
+        attrib_mod.e_type = ModType::kModType_Duration;
+        let mut output = AttribModOutput::default();
+        output.attr_type = Some(AttribType::kAttribType_Cur);
+        output.add_effect_scales(&attrib_mod, &archetypes, 50, 2);
+        assert_eq!(output.scaled[0].style.as_deref(), Some("None"));
+        assert_eq!(output.scaled[0].display_value.as_deref(), Some("0.15 s"));
+    }
+
+    #[test]
+    fn attrib_style_test() {
+        let style_of = |offset: usize, attrib_type: AttribType, value: f32| {
+            let mut attrib_mod = AttribModTemplate::new();
+            attrib_mod.p_attrib.push(CharacterAttrib(offset as i32));
+            let scaled_effect = get_scaled_effect(&attrib_mod, &attrib_type, value, 2).unwrap();
+            let style = get_attrib_style(&attrib_mod, &attrib_type, &scaled_effect);
+            (style.get_string(), style.format_value(value))
+        };
+        use AttribType::*;
+        assert_eq!(
+            style_of(CharacterAttributes::OFFSET_DMG_0, kAttribType_Abs, -50.0),
+            ("Magnitude", String::from("-50"))
+        );
+        assert_eq!(
+            style_of(CharacterAttributes::OFFSET_DEF_0, kAttribType_Cur, 0.075),
+            ("Percent", String::from("7.5%"))
+        );
+        assert_eq!(
+            style_of(CharacterAttributes::OFFSET_RECHARGE_TIME, kAttribType_Str, 0.2),
+            ("Percent", String::from("20%"))
+        );
+        assert_eq!(
+            style_of(CharacterAttributes::OFFSET_HELD, kAttribType_Res, 1.0),
+            ("ResistanceDuration", String::from("50%"))
+        );
+        assert_eq!(
+            style_of(CharacterAttributes::OFFSET_KNOCKBACK, kAttribType_Res, 3.0),
+            ("ResistanceDistance", String::from("75%"))
+        );
+        assert_eq!(
+            style_of(CharacterAttributes::OFFSET_ENDURANCE, kAttribType_Res, 0.25),
+            ("EnduranceReduction", String::from("20%"))
+        );
+        assert_eq!(
+            style_of(CharacterAttributes::OFFSET_RUNNING_SPEED, kAttribType_Max, 58.65),
+            ("Speed", String::from("58.65 ft./s"))
+        );
+        assert_eq!(
+            style_of(CharacterAttributes::OFFSET_JUMP_HEIGHT, kAttribType_Max, 20.0),
+            ("Distance", String::from("20 ft."))
+        );
+        assert_eq!(
+            style_of(CharacterAttributes::OFFSET_PERCEPTION_RADIUS, kAttribType_Mod, 30.0),
+            ("Distance", String::from("30 ft."))
+        );
+        assert_eq!(
+            style_of(CharacterAttributes::OFFSET_REGENERATION, kAttribType_Abs, 2.5),
+            ("PerSecond", String::from("2.5/s"))
+        );
+        assert_eq!(
+            style_of(CharacterAttributes::OFFSET_THREAT_LEVEL, kAttribType_Cur, 4.0),
+            ("Multiply", String::from("4x"))
+        );
+        assert_eq!(
+            style_of(CharacterAttributes::OFFSET_INSIGHT, kAttribType_Cur, 2.6),
+            ("Integer", String::from("3"))
+        );
+        assert_eq!(
+            style_of(CharacterAttributes::OFFSET_ENDURANCE_DISCOUNT, kAttribType_Cur, 1.0),
+            ("EnduranceReduction", String::from("50%"))
+        );
+        assert_eq!(
+            style_of(CharacterAttributes::OFFSET_HELD, kAttribType_Mod, 3.0),
+            ("Magnitude", String::from("3"))
+        );
+        assert_eq!(
+            style_of(CharacterAttributes::OFFSET_METER, kAttribType_Cur, 0.5),
+            ("None", String::from("0.5"))
+        );
+    }
+
+    #[test]
+    fn permanent_total_test() {
+        let mut at = Archetype::new();
+        let mut table = NamedTable::new();
+        table.pf_values = vec![0.1; 50];
+        at.pp_named_tables.insert(String::from("melee_buff_def"), table);
+        let archetypes = vec![Rc::new(RefCell::new(at))];
+        let mut attrib_mod = AttribModTemplate::new();
+        attrib_mod.p_attrib.push(CharacterAttrib(CharacterAttributes::OFFSET_DMG_0 as i32));
+        attrib_mod.pch_table = Some(String::from("Melee_Buff_Def"));
+        attrib_mod.f_scale = 1.0;
+        attrib_mod.f_duration = ModDuration::kModDuration_UntilShutOff;
+
+        let mut output = AttribModOutput::default();
+        output.attr_type = Some(AttribType::kAttribType_Abs);
+        output.continuous_apply_seconds = 0.5;
+        output.add_effect_scales(&attrib_mod, &archetypes, 50, 2);
+        let mut group = EffectGroupOutput::default();
+        group.chance_percent = 100.0;
+        let mut power = BasePower::new();
+        power.f_time_to_activate = 1.0;
+        calculate_damage(&mut output, &group, &attrib_mod, &power, 2);
+
+        assert!(output.scaled[0].permanent);
+        assert_eq!(output.scaled[0].average, 0.0);
+        let json = serde_json::to_string(&output.scaled[0]).unwrap();
+        assert!(json.contains(r#""permanent":true"#));
+        assert!(!json.contains("average"));
+        assert!(!json.contains("99999"));
+    }
+
+    #[test]
+    fn max_effect_depth_test() {
+        let toml = format!("max_effect_depth = 2\n{}", MINIMAL_CONFIG);
+        let config: PowersConfig = toml::from_str(&toml).unwrap();
+        // build 4 levels of nested effect groups
+        let mut effect = EffectGroup::new();
+        for _ in 0..3 {
+            let mut parent = EffectGroup::new();
+            parent.pp_effects.push(effect);
+            effect = parent;
+        }
+
+        let level1 = EffectGroupOutput::from_effect_group(
+            &effect,
+            &AttribNames::new(),
+            &BasePower::new(),
+            &Vec::new(),
+            &config,
+        );
+        assert!(!level1.truncated);
+        let level2 = &level1.child_effect_groups[0];
+        assert!(!level2.truncated);
+        let level3 = &level2.child_effect_groups[0];
+        assert!(level3.truncated);
+        assert!(level3.child_effect_groups.is_empty());
+    }
+
+    #[test]
+    fn stacking_replace_duration_behavior_test() {
+        let attrib_mod = AttribModTemplate::new();
+        let stacking =
+            StackingOutput::from_attrib_mod_template(&attrib_mod, &AttribNames::new());
+        assert!(stacking.duration_behavior.is_none());
+    }
+}
@@ -3,18 +3,23 @@ mod effects;
 mod powers;
 
 use super::{make_file_name, JSON_FILE};
-use crate::structs::config::{AssetsConfig, PowersConfig};
+use crate::structs::config::{AssetsConfig, PowerSortConfig, PowersConfig};
 use crate::structs::*;
-use powers::PowerOutput;
-use serde::Serialize;
+pub use effects::{AttribModOutput, EffectGroupOutput};
+pub use powers::PowerOutput;
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::collections::HashMap;
 
 /// Used when joining parts of an URL together.
 const URL_SEP: char = '/';
 
+/// The power category containing inherent powers (Brawl, Rest, Sprint, etc.).
+const INHERENT_CATEGORY: &'static str = "Inherent";
+
 /// Common fields added to other structs.
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct HeaderOutput {
     pub issue: Option<String>,
     pub source: Option<String>,
@@ -34,24 +39,42 @@ impl HeaderOutput {
 
 /// Additional fields to include in `ArchetypeOutput` if we're dumping a full
 /// view of the archetypes.
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ExtendedArchetypeOutput {
     #[serde(skip_serializing_if = "Option::is_none")]
     display_help: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     display_short_help: Option<String>,
     allowed_origins: Vec<String>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     restrictions: Vec<String>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     level_up_respecs: Vec<i32>,
     primary_category: Option<NameKey>,
     secondary_category: Option<NameKey>,
+    #[serde(default, skip_serializing_if = "not_normal")]
+    base_insight: f32,
+    #[serde(default, skip_serializing_if = "not_normal")]
+    insight_recovery: f32,
+    #[serde(default, skip_serializing_if = "not_normal")]
+    elusivity_base: f32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    elusivity: Vec<ArchetypeElusivityOutput>,
+}
+
+/// PvP elusivity base value and cap for a single defense type.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ArchetypeElusivityOutput {
+    pub name: String,
+    pub base: f32,
+    pub cap: f32,
 }
 
 impl ExtendedArchetypeOutput {
     /// Creates an `ExtendedArchetypeOutput` from an `Archetype`.
-    fn from_archetype(at: &Archetype) -> Self {
+    fn from_archetype(at: &Archetype, decimal_places: u8) -> Self {
         ExtendedArchetypeOutput {
             display_help: at.pch_display_help.clone(),
             display_short_help: at.pch_display_short_help.clone(),
@@ -60,12 +83,51 @@ impl ExtendedArchetypeOutput {
             level_up_respecs: at.pi_level_up_respecs.clone(),
             primary_category: at.pch_primary_category.clone(),
             secondary_category: at.pch_secondary_category.clone(),
+            base_insight: at
+                .pp_attrib_base
+                .first()
+                .map_or(0.0, |base| round_to(base.f_insight, decimal_places)),
+            insight_recovery: at
+                .pp_attrib_base
+                .first()
+                .map_or(0.0, |base| round_to(base.f_insight_recovery, decimal_places)),
+            elusivity_base: at
+                .pp_attrib_base
+                .first()
+                .map_or(0.0, |base| round_to(base.f_elusivity_base, decimal_places)),
+            elusivity: Vec::new(),
+        }
+    }
+
+    /// Fills in the per-type elusivity values for an `Archetype`.
+    ///
+    /// The base comes from the archetype's base attributes, and the cap is the
+    /// highest level entry of the archetype's max attribute table. Types without
+    /// a name in `attrib_names.pp_elusivity` or without any values are skipped.
+    fn add_elusivity(&mut self, at: &Archetype, attrib_names: &AttribNames, decimal_places: u8) {
+        let base = at.pp_attrib_base.first();
+        let max = at.pp_attrib_temp_max_max.first();
+        for (i, attrib_name) in attrib_names.pp_elusivity.iter().enumerate() {
+            if let Some(name) = &attrib_name.pch_display_name {
+                let base_value = base.map_or(0.0, |b| b.f_elusivity[i]);
+                let cap = max
+                    .and_then(|m| m.pf_elusivity[i].last().copied())
+                    .unwrap_or(0.0);
+                if base_value.is_normal() || cap.is_normal() {
+                    self.elusivity.push(ArchetypeElusivityOutput {
+                        name: name.clone(),
+                        base: round_to(base_value, decimal_places),
+                        cap: round_to(cap, decimal_places),
+                    });
+                }
+            }
         }
     }
 }
 
 /// Serializable representation of an archetype.
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ArchetypeOutput {
     pub name: Option<String>,
     pub display_name: Option<String>,
@@ -96,7 +158,7 @@ impl ArchetypeOutput {
                 PrimarySecondary::None => None,
             },
             extended: if extended {
-                Some(ExtendedArchetypeOutput::from_archetype(at))
+                Some(ExtendedArchetypeOutput::from_archetype(at, config.decimal_places))
             } else {
                 None
             },
@@ -110,7 +172,8 @@ impl ArchetypeOutput {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ArchetypesOutput {
     #[serde(flatten)]
     pub header: HeaderOutput,
@@ -119,25 +182,243 @@ pub struct ArchetypesOutput {
 
 impl ArchetypesOutput {
     /// Creates an `ArchetypesOuput` from an array of `Archetype`.
-    pub fn from_archetypes(ats: &Keyed<Archetype>, config: &PowersConfig) -> Self {
+    pub fn from_archetypes(
+        ats: &Keyed<Archetype>,
+        attrib_names: &AttribNames,
+        config: &PowersConfig,
+    ) -> Self {
         let mut ats_out = ArchetypesOutput {
             header: HeaderOutput::from_config(config),
             archetypes: Vec::new(),
         };
         for at in ats.values() {
-            ats_out.archetypes.push(ArchetypeOutput::from_archetype(
-                &*at.borrow(),
-                &PrimarySecondary::None,
-                true,
-                config,
-            ));
+            let at = at.borrow();
+            let mut at_out =
+                ArchetypeOutput::from_archetype(&*at, &PrimarySecondary::None, true, config);
+            if let Some(extended) = &mut at_out.extended {
+                extended.add_elusivity(&*at, attrib_names, config.decimal_places);
+            }
+            ats_out.archetypes.push(at_out);
         }
         ats_out
     }
 }
 
+/// Serializable representation of a bonus tier in a boost set.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BoostSetBonusOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    pub min_boosts: i32,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub max_boosts: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requires: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub auto_powers: Vec<NameKey>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bonus_power: Option<NameKey>,
+}
+
+impl BoostSetBonusOutput {
+    /// Creates a `BoostSetBonusOutput` from a `BoostSetBonus`.
+    fn from_boost_set_bonus(bonus: &BoostSetBonus) -> Self {
+        BoostSetBonusOutput {
+            display_name: bonus.pch_display_name.clone(),
+            min_boosts: bonus.i_min_boosts,
+            max_boosts: bonus.i_max_boosts,
+            requires: requires_to_string(&bonus.ppch_requires),
+            auto_powers: bonus.ppch_auto_powers.clone(),
+            bonus_power: bonus.pch_bonus_power.clone(),
+        }
+    }
+}
+
+/// Serializable representation of a boost (enhancement) set.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BoostSetOutput {
+    pub name: Option<NameKey>,
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_name: Option<String>,
+    pub min_level: i32,
+    pub max_level: i32,
+    pub boosts: Vec<NameKey>,
+    pub bonuses: Vec<BoostSetBonusOutput>,
+}
+
+impl BoostSetOutput {
+    /// Creates a `BoostSetOutput` from a `BoostSet`.
+    fn from_boost_set(boost_set: &BoostSet) -> Self {
+        BoostSetOutput {
+            name: boost_set.pch_name.clone(),
+            display_name: boost_set.pch_display_name.clone(),
+            group_name: boost_set.pch_group_name.clone(),
+            min_level: boost_set.i_min_level,
+            max_level: boost_set.i_max_level,
+            boosts: boost_set
+                .pp_boost_lists
+                .iter()
+                .flat_map(|list| list.ppch_boosts.iter().cloned())
+                .collect(),
+            bonuses: boost_set
+                .pp_bonuses
+                .iter()
+                .map(BoostSetBonusOutput::from_boost_set_bonus)
+                .collect(),
+        }
+    }
+}
+
+/// Serializable representation of all boost sets.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BoostSetsOutput {
+    #[serde(flatten)]
+    pub header: HeaderOutput,
+    pub boost_sets: Vec<BoostSetOutput>,
+}
+
+impl BoostSetsOutput {
+    /// Creates a `BoostSetsOutput` from all of the `BoostSet`s, sorted by name.
+    pub fn from_boost_sets(boost_sets: &Keyed<BoostSet>, config: &PowersConfig) -> Self {
+        let mut sets_out = BoostSetsOutput {
+            header: HeaderOutput::from_config(config),
+            boost_sets: boost_sets
+                .values()
+                .map(|bs| BoostSetOutput::from_boost_set(&*bs.borrow()))
+                .collect(),
+        };
+        sets_out.boost_sets.sort_by_cached_key(|bs| {
+            bs.name.as_ref().map(|n| n.get().to_ascii_lowercase())
+        });
+        sets_out
+    }
+}
+
+/// Serializable representation of an inherent power.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct InherentPowerOutput {
+    pub name: Option<NameKey>,
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// Serializable representation of the inherent powers granted to an archetype.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct InherentArchetypeOutput {
+    pub archetype: Option<String>,
+    pub display_name: Option<String>,
+    pub powers: Vec<InherentPowerOutput>,
+}
+
+/// Serializable representation of the inherent powers in a power system.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct InherentSystemOutput {
+    pub system: Cow<'static, str>,
+    pub archetypes: Vec<InherentArchetypeOutput>,
+}
+
+/// Serializable representation of all auto-issued inherent powers.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct InherentsOutput {
+    #[serde(flatten)]
+    pub header: HeaderOutput,
+    pub systems: Vec<InherentSystemOutput>,
+}
+
+impl InherentsOutput {
+    /// Collects the auto-issued powers in the inherent power category, grouped by power
+    /// system and then by archetype. Powers available to every archetype are listed under
+    /// an archetype of `null`.
+    pub fn from_power_categories(
+        power_categories: &Vec<ObjRef<PowerCategory>>,
+        config: &PowersConfig,
+    ) -> Self {
+        let inherent_key = NameKey::new(INHERENT_CATEGORY);
+        let mut systems: Vec<InherentSystemOutput> = Vec::new();
+        for pcat in power_categories.iter().map(|p| p.borrow()) {
+            if pcat.pch_name.as_ref() != Some(&inherent_key) {
+                continue;
+            }
+            for pset in pcat.pp_power_sets.iter().map(|p| p.borrow()) {
+                for power in pset.pp_powers.iter().map(|p| p.borrow()) {
+                    if !power.b_auto_issue || !power.include_in_output {
+                        continue;
+                    }
+                    let system_name = power.e_system.get_string();
+                    let system = match systems.iter().position(|s| s.system == system_name) {
+                        Some(i) => &mut systems[i],
+                        None => {
+                            systems.push(InherentSystemOutput {
+                                system: system_name.into(),
+                                archetypes: Vec::new(),
+                            });
+                            systems.last_mut().unwrap()
+                        }
+                    };
+                    let mut archetypes = powers::filter_archetypes_pwr(&*power, &power.archetypes)
+                        .iter()
+                        .map(|at| {
+                            let at = at.borrow();
+                            (at.pch_name.clone(), at.pch_display_name.clone())
+                        })
+                        .collect::<Vec<_>>();
+                    if archetypes.is_empty() {
+                        archetypes.push((None, None));
+                    }
+                    for (at_name, at_display_name) in archetypes {
+                        let inherent_at = match system
+                            .archetypes
+                            .iter()
+                            .position(|a| a.archetype == at_name)
+                        {
+                            Some(i) => &mut system.archetypes[i],
+                            None => {
+                                system.archetypes.push(InherentArchetypeOutput {
+                                    archetype: at_name,
+                                    display_name: at_display_name,
+                                    powers: Vec::new(),
+                                });
+                                system.archetypes.last_mut().unwrap()
+                            }
+                        };
+                        inherent_at.powers.push(InherentPowerOutput {
+                            name: power.pch_full_name.clone(),
+                            display_name: power.pch_display_name.clone(),
+                            url: power.pch_full_name.as_ref().and_then(|name| {
+                                namekey_to_url(name, UrlKind::PowerFromTopLevel, config)
+                            }),
+                        });
+                    }
+                }
+            }
+        }
+        for system in &mut systems {
+            system.archetypes.sort_by(|a, b| a.archetype.cmp(&b.archetype));
+            for inherent_at in &mut system.archetypes {
+                inherent_at.powers.sort_by_cached_key(|p| {
+                    p.name.as_ref().map(|n| n.get().to_ascii_lowercase())
+                });
+            }
+        }
+        InherentsOutput {
+            header: HeaderOutput::from_config(config),
+            systems,
+        }
+    }
+}
+
 /// Serializable representation of a power category in the root index.
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RootPowerCategory {
     pub name: Option<NameKey>,
     pub display_name: Option<String>,
@@ -147,11 +428,14 @@ pub struct RootPowerCategory {
 }
 
 /// Serializable representation of the root index.
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RootOutput {
     #[serde(flatten)]
     pub header: HeaderOutput,
     pub archetypes: String,
+    pub boost_sets: String,
+    pub inherents: String,
     pub power_categories: Vec<RootPowerCategory>,
 }
 
@@ -170,39 +454,26 @@ impl RootOutput {
         power_categories: &Vec<ObjRef<PowerCategory>>,
         config: &PowersConfig,
     ) -> Self {
-        let mut at_url = String::new();
-        if let Some(base_url) = config.base_json_url.as_ref() {
-            at_url.push_str(base_url);
-        }
-        at_url.push_str(&make_file_name("archetypes"));
-        at_url.push(URL_SEP);
-        if config.base_json_url.is_none() {
-            at_url.push_str(JSON_FILE);
-        }
         let mut root = RootOutput {
             header: HeaderOutput::from_config(config),
-            archetypes: at_url,
+            archetypes: namekey_to_url(&NameKey::new("archetypes"), UrlKind::Category, config)
+                .unwrap(),
+            boost_sets: namekey_to_url(&NameKey::new("boost-sets"), UrlKind::Category, config)
+                .unwrap(),
+            inherents: namekey_to_url(&NameKey::new("inherents"), UrlKind::Category, config)
+                .unwrap(),
             power_categories: Vec::new(),
         };
         for pcat in power_categories.iter().map(|p| p.borrow()) {
             if !pcat.top_level || !pcat.include_in_output {
                 continue;
             }
-            let mut url = String::new();
-            if let Some(base_url) = config.base_json_url.as_ref() {
-                url.push_str(base_url);
-            }
             if let Some(pcat_name) = pcat.pch_name.as_ref() {
-                url.push_str(&make_file_name(pcat_name.get()));
-                url.push(URL_SEP);
-                if config.base_json_url.is_none() {
-                    url.push_str(JSON_FILE);
-                }
                 let mut rpc = RootPowerCategory {
                     name: Some(pcat_name.clone()),
                     display_name: pcat.pch_display_name.clone(),
                     archetype: None,
-                    url,
+                    url: namekey_to_url(pcat_name, UrlKind::Category, config).unwrap_or_default(),
                 };
                 if pcat.archetypes.len() == 1 {
                     // if there's only 1 archetype attached, then this is a group of sets intended for that archetype
@@ -221,7 +492,8 @@ impl RootOutput {
 }
 
 /// Serializable representation of a power set in a power category.
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PowerCategoryPowerSetOutput {
     pub name: Option<NameKey>,
     pub display_name: Option<String>,
@@ -229,7 +501,8 @@ pub struct PowerCategoryPowerSetOutput {
 }
 
 /// Serializable representation of a power category.
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PowerCategoryOutput {
     #[serde(flatten)]
     pub header: HeaderOutput,
@@ -270,27 +543,13 @@ impl PowerCategoryOutput {
             if !pset.include_in_output {
                 continue;
             }
-            let mut url = String::new();
-            if let Some(base_url) = config.base_json_url.as_ref() {
-                url.push_str(base_url);
-            }
-            if config.base_json_url.is_some() {
-                if let Some(pcat_name) = &pcat.name {
-                    url.push_str(&make_file_name(pcat_name.get()));
-                }
-                url.push(URL_SEP);
-            }
-            if let Some(name) = &pset.pch_name {
-                url.push_str(&make_file_name(name));
-            }
-            url.push(URL_SEP);
-            if config.base_json_url.is_none() {
-                url.push_str(JSON_FILE);
-            }
             pcat.power_sets.push(PowerCategoryPowerSetOutput {
                 name: pset.pch_full_name.clone(),
                 display_name: pset.pch_display_name.clone(),
-                url: Some(url),
+                url: pset
+                    .pch_full_name
+                    .as_ref()
+                    .and_then(|name| namekey_to_url(name, UrlKind::PowerSet, config)),
             });
         }
         pcat
@@ -298,7 +557,8 @@ impl PowerCategoryOutput {
 }
 
 /// Serializable representation of a power set.
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PowerSetOutput {
     #[serde(flatten)]
     header: HeaderOutput,
@@ -308,6 +568,7 @@ pub struct PowerSetOutput {
     display_help: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     icon: Option<String>,
+    system: Cow<'static, str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     specialize_at_level: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -331,6 +592,7 @@ impl PowerSetOutput {
     /// * `power_set` - A `BasePowerSet`.
     /// * `attrib_names` - An `AttribNames`.
     /// * `config` - Configuration information.
+    /// * `powers_dict` - Used to look up redirect targets if `config.inline_redirects` is set.
     ///
     /// Returns:
     ///
@@ -339,6 +601,7 @@ impl PowerSetOutput {
         power_set: &BasePowerSet,
         attrib_names: &AttribNames,
         config: &PowersConfig,
+        powers_dict: Option<&PowersDictionary>,
     ) -> Self {
         let mut pset = PowerSetOutput {
             header: HeaderOutput::from_config(config),
@@ -346,6 +609,7 @@ impl PowerSetOutput {
             display_name: power_set.pch_display_name.clone(),
             display_help: power_set.pch_display_help.clone(),
             icon: None,
+            system: power_set.e_system.get_string().into(),
             specialize_at_level: None,
             specialize_requires: requires_to_string(&power_set.pp_specialize_requires),
             show_in_inventory: match power_set.e_show_in_inventory {
@@ -375,28 +639,25 @@ impl PowerSetOutput {
         for power in power_set.pp_powers.iter().map(|p| p.borrow()) {
             // skip disabled powers
             if power.include_in_output {
-                pset.powers
-                    .push(PowerOutput::from_base_power(&*power, attrib_names, config));
+                pset.powers.push(PowerOutput::from_base_power(
+                    &*power,
+                    attrib_names,
+                    config,
+                    powers_dict,
+                ));
             }
         }
         // copy minimum levels
-        let mut powers_to_levels = HashMap::new();
-        power_set
-            .pp_power_names
-            .iter()
-            .zip(&power_set.pi_available)
-            .for_each(|(pwr_name, level)| {
-                powers_to_levels.insert(pwr_name.clone(), *level);
-            });
+        let powers_to_levels: HashMap<_, _> = power_set.available_levels().collect();
         for power in &mut pset.powers {
             if let Some(power_name) = &power.name {
                 if let Some(level) = powers_to_levels.get(power_name) {
-                    power.available_at_level = *level + 1;
+                    power.available_at_level = *level;
                 }
             }
             // now that we have minimum level info, we can add display info for available level
             power.display_info.insert(
-                "Available Level",
+                "Available Level".into(),
                 Cow::Owned(power.available_at_level.to_string()),
             );
         }
@@ -428,11 +689,36 @@ impl PowerSetOutput {
             })
             .cloned()
             .collect();
-        // sort powers
-        pset.powers
-            .sort_by(|a, b| a.available_at_level.cmp(&b.available_at_level));
+        sort_powers(&mut pset.powers, config.power_sort);
         pset
     }
+
+    /// Gets the number of powers in the power set.
+    #[allow(dead_code)]
+    pub fn power_count(&self) -> usize {
+        self.powers.len()
+    }
+
+    /// Gets the powers in the power set.
+    pub fn powers(&self) -> &[PowerOutput] {
+        &self.powers
+    }
+}
+
+/// Sorts the powers in a power set according to `power_sort`.
+fn sort_powers(powers: &mut Vec<PowerOutput>, power_sort: PowerSortConfig) {
+    match power_sort {
+        PowerSortConfig::Level => {
+            powers.sort_by(|a, b| a.available_at_level.cmp(&b.available_at_level))
+        }
+        PowerSortConfig::Alphabetical => powers.sort_by_cached_key(|pwr| {
+            pwr.display_name
+                .as_ref()
+                .or(pwr.name.as_ref().map(|n| n.get_string()))
+                .map(|n| n.to_ascii_lowercase())
+        }),
+        PowerSortConfig::Original => (),
+    }
 }
 
 /// Rewrites an icon name from a .bin file into a file name with new extension and
@@ -481,36 +767,142 @@ fn format_power_icon_to_asset(icon: &str, assets: &AssetsConfig) -> String {
     url
 }
 
+/// Resolves a power's icon for output.
+///
+/// # Arguments:
+///
+/// * `icon` - The icon name from the .bin file, if any.
+/// * `assets` - The assets configuration, if any.
+///
+/// # Returns:
+///
+/// If `assets` is configured, a full URL to the icon (or to the configured fallback icon if
+/// `icon` is missing). Otherwise, the raw icon name from the .bin file.
+fn make_power_icon(icon: Option<&String>, assets: Option<&AssetsConfig>) -> Option<String> {
+    match (icon, assets) {
+        (Some(icon), Some(assets)) => Some(format_power_icon_to_asset(icon, assets)),
+        (Some(icon), None) => Some(icon.to_owned()),
+        (None, Some(assets)) => assets
+            .fallback_power_icon
+            .as_ref()
+            .map(|fallback| format_power_icon_to_asset(fallback, assets)),
+        (None, None) => None,
+    }
+}
+
 /// Returns true if `val` is 0.
 fn is_zero(val: &i32) -> bool {
     *val == 0
 }
 
+/// Returns true if `val` is false.
+fn is_false(val: &bool) -> bool {
+    !*val
+}
+
 /// Returns true if `val` is 0, infinite, or NaN.
 fn not_normal(val: &f32) -> bool {
     !val.is_normal()
 }
 
-/// Trims `val` to 2 decimal places via rounding.
-fn normalize(val: f32) -> f32 {
+/// Trims `val` to `places` decimal places via rounding. Zero, subnormal, infinite, and NaN
+/// values are returned unchanged, as are values too large to scale by `places`.
+pub(crate) fn round_to(val: f32, places: u8) -> f32 {
     if val.is_normal() {
-        (val * 100.0).round() / 100.0
+        let factor = 10f32.powi(places as i32);
+        let scaled = val * factor;
+        if scaled.is_finite() {
+            scaled.round() / factor
+        } else {
+            val
+        }
     } else {
         val
     }
 }
 
+/// Gets the number of decimal places used for values that need more precision than the rest of
+/// the output, such as table values and scales.
+fn fine_places(decimal_places: u8) -> u8 {
+    decimal_places.saturating_add(2)
+}
+
+/// Trims `val` to 2 decimal places via rounding.
+#[deprecated(note = "use `round_to` with `PowersConfig::decimal_places`")]
+#[allow(dead_code)]
+pub(crate) fn normalize(val: f32) -> f32 {
+    round_to(val, 2)
+}
+
 /// Trims `val` to 4 decimal places via rounding.
+#[deprecated(note = "use `round_to` with `PowersConfig::decimal_places`")]
+#[allow(dead_code)]
 fn normalize4(val: f32) -> f32 {
-    if val.is_normal() {
-        (val * 10000.0).round() / 10000.0
+    round_to(val, 4)
+}
+
+/// The kind of object a URL points to. Without a `base_json_url`, URLs are relative to the
+/// file that contains them, so this also determines where the link is written from.
+pub enum UrlKind {
+    /// A power category (or other top-level data set), linked from the root index.
+    Category,
+    /// A power set, linked from its own power category.
+    PowerSet,
+    /// A power set containing a power (or the power set itself), linked from any other power set.
+    Power,
+    /// A power set containing a power, linked from a top-level data set (e.g. inherents).
+    PowerFromTopLevel,
+}
+
+/// Creates a URL pointing to the .json file containing the object named `key`.
+///
+/// # Arguments:
+///
+/// * `key` - The full name of the category, power set, or power.
+/// * `kind` - What `key` refers to and where the link is written from.
+/// * `config` - Configuration information.
+///
+/// # Returns:
+///
+/// The URL, or `None` if `key` doesn't have enough parts for `kind` (e.g. a power set
+/// name without a category).
+pub fn namekey_to_url(key: &NameKey, kind: UrlKind, config: &PowersConfig) -> Option<String> {
+    let name_parts = key.split();
+    let needed_parts = match kind {
+        UrlKind::Category => 1,
+        UrlKind::PowerSet | UrlKind::Power | UrlKind::PowerFromTopLevel => 2,
+    };
+    if name_parts.len() < needed_parts {
+        return None;
+    }
+    let mut url = String::new();
+    let mut path_parts = &name_parts[..needed_parts];
+    if let Some(base_url) = config.base_json_url.as_ref() {
+        url.push_str(base_url);
     } else {
-        val
+        match kind {
+            UrlKind::Category => (),
+            // relative to the category folder the power set is already in
+            UrlKind::PowerSet => path_parts = &name_parts[1..needed_parts],
+            // relative to some other power set's folder
+            UrlKind::Power => url.push_str("../../"),
+            // relative to the top-level data set's folder
+            UrlKind::PowerFromTopLevel => url.push_str("../"),
+        }
+    }
+    for part in path_parts {
+        url.push_str(&make_file_name(part));
+        url.push(URL_SEP);
+    }
+    if config.base_json_url.is_none() {
+        url.push_str(JSON_FILE);
     }
+    Some(url)
 }
 
 /// Converts a stacked requirements expression into a concise string representation.
-fn requires_to_string(requires: &Vec<String>) -> Option<String> {
+/// To evaluate one instead, see `crate::structs::requires::eval_requires`.
+pub(crate) fn requires_to_string(requires: &Vec<String>) -> Option<String> {
     if requires.len() == 1 && requires[0] == "1" {
         // always evaluates to true, dump it
         return None;
@@ -624,10 +1016,13 @@ where
             }
             _ => {
                 if token.ends_with('>') {
-                    // struct pointer
+                    // struct pointer, which may be chained (e.g. source>owner>kHeldMag)
                     let mut combined = token.to_owned();
-                    if let Some(next_token) = requires.next() {
+                    while let Some(next_token) = requires.next() {
                         combined.push_str(next_token);
+                        if !next_token.ends_with('>') {
+                            break;
+                        }
                     }
                     return Some(combined);
                 } else if token.ends_with('?') {
@@ -653,3 +1048,330 @@ where
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::config::MINIMAL_CONFIG;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn round_to_test() {
+        assert_eq!(round_to(1.23456789, 0), 1.0);
+        assert_eq!(round_to(2.5, 0), 3.0);
+        assert_eq!(round_to(1.23456789, 2), 1.23);
+        assert_eq!(round_to(-0.005, 2), -0.01);
+        assert_eq!(round_to(1.23456789, 6), 1.234568);
+        assert_eq!(round_to(0.0, 2), 0.0);
+        assert!(round_to(f32::NAN, 2).is_nan());
+        assert_eq!(round_to(f32::INFINITY, 0), f32::INFINITY);
+        // too many places to scale, left as-is rather than turning into NaN
+        assert_eq!(round_to(1.5, 39), 1.5);
+        assert_eq!(round_to(3.0e30, 10), 3.0e30);
+
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        assert_eq!(config.decimal_places, 2);
+        assert_eq!(fine_places(config.decimal_places), 4);
+    }
+
+    fn make_assets_config() -> AssetsConfig {
+        AssetsConfig {
+            base_asset_url: String::from("http://localhost/assets/"),
+            ext: String::from(".png"),
+            archetype_icon_format: String::from("archetype/{icon}"),
+            powers_icon_format: String::from("powers/{icon}"),
+            fallback_power_icon: None,
+        }
+    }
+
+    #[test]
+    fn make_power_icon_without_assets_test() {
+        let icon = String::from("Fire_Blast.tga");
+        assert_eq!(
+            make_power_icon(Some(&icon), None),
+            Some(String::from("Fire_Blast.tga"))
+        );
+        assert_eq!(make_power_icon(None, None), None);
+    }
+
+    #[test]
+    fn make_power_icon_with_assets_test() {
+        let icon = String::from("Fire_Blast.tga");
+        let mut assets = make_assets_config();
+        assert_eq!(
+            make_power_icon(Some(&icon), Some(&assets)),
+            Some(String::from("http://localhost/assets/powers/fire_blast.png"))
+        );
+        assert_eq!(make_power_icon(None, Some(&assets)), None);
+
+        assets.fallback_power_icon = Some(String::from("Unknown.tga"));
+        assert_eq!(
+            make_power_icon(None, Some(&assets)),
+            Some(String::from("http://localhost/assets/powers/unknown.png"))
+        );
+    }
+
+    #[test]
+    fn namekey_to_url_relative_test() {
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        let power = NameKey::new("Tanker_Melee.Super_Strength.Foot_Stomp");
+        assert_eq!(
+            namekey_to_url(&NameKey::new("Tanker_Melee"), UrlKind::Category, &config),
+            Some(String::from("tanker-melee/index.json"))
+        );
+        assert_eq!(
+            namekey_to_url(
+                &NameKey::new("Tanker_Melee.Super_Strength"),
+                UrlKind::PowerSet,
+                &config
+            ),
+            Some(String::from("super-strength/index.json"))
+        );
+        assert_eq!(
+            namekey_to_url(&power, UrlKind::Power, &config),
+            Some(String::from("../../tanker-melee/super-strength/index.json"))
+        );
+        assert!(namekey_to_url(&NameKey::new("Tanker_Melee"), UrlKind::Power, &config).is_none());
+    }
+
+    #[test]
+    fn namekey_to_url_base_url_test() {
+        let toml = format!("base_json_url = \"http://localhost/\"\n{}", MINIMAL_CONFIG);
+        let config: PowersConfig = toml::from_str(&toml).unwrap();
+        let power = NameKey::new("Tanker_Melee.Super_Strength.Foot_Stomp");
+        assert_eq!(
+            namekey_to_url(&NameKey::new("Tanker_Melee"), UrlKind::Category, &config),
+            Some(String::from("http://localhost/tanker-melee/"))
+        );
+        assert_eq!(
+            namekey_to_url(
+                &NameKey::new("Tanker_Melee.Super_Strength"),
+                UrlKind::PowerSet,
+                &config
+            ),
+            Some(String::from("http://localhost/tanker-melee/super-strength/"))
+        );
+        assert_eq!(
+            namekey_to_url(&power, UrlKind::Power, &config),
+            Some(String::from("http://localhost/tanker-melee/super-strength/"))
+        );
+    }
+
+    #[test]
+    fn archetype_elusivity_test() {
+        let mut base = CharacterAttributes::new();
+        base.f_elusivity_base = 0.05;
+        base.f_elusivity[1] = 0.1;
+        let mut max = CharacterAttributesTable::new();
+        max.pf_elusivity[1] = vec![0.2, 0.45];
+        let mut at = Archetype::new();
+        at.pp_attrib_base.push(base);
+        at.pp_attrib_temp_max_max.push(max);
+
+        let mut attrib_names = AttribNames::new();
+        for name in &["Melee", "Ranged", "AoE"] {
+            let mut attrib_name = AttribName::new();
+            attrib_name.pch_display_name = Some(String::from(*name));
+            attrib_names.pp_elusivity.push(attrib_name);
+        }
+
+        let mut extended = ExtendedArchetypeOutput::from_archetype(&at, 2);
+        extended.add_elusivity(&at, &attrib_names, 2);
+        assert_eq!(extended.elusivity_base, 0.05);
+        assert_eq!(extended.elusivity.len(), 1);
+        assert_eq!(extended.elusivity[0].name, "Ranged");
+        assert_eq!(extended.elusivity[0].base, 0.1);
+        assert_eq!(extended.elusivity[0].cap, 0.45);
+    }
+
+    fn tokens(expression: &str) -> Vec<String> {
+        expression.split(' ').map(String::from).collect()
+    }
+
+    #[test]
+    fn requires_struct_pointer_test() {
+        let requires = tokens("kHeldMag target>");
+        assert_eq!(
+            requires_to_string_inner(&mut requires.iter().rev()).as_deref(),
+            Some("target>kHeldMag")
+        );
+        let requires = tokens("kHeldMag owner> source>");
+        assert_eq!(
+            requires_to_string_inner(&mut requires.iter().rev()).as_deref(),
+            Some("source>owner>kHeldMag")
+        );
+        let requires = tokens("kHeldMag owner> owner> source> 0 >");
+        assert_eq!(
+            requires_to_string(&requires).as_deref(),
+            Some("source>owner>owner>kHeldMag > 0")
+        );
+        let requires = tokens("kHeldMag owner> source> kStunned target> &&");
+        assert_eq!(
+            requires_to_string(&requires).as_deref(),
+            Some("source>owner>kHeldMag && target>kStunned")
+        );
+    }
+
+    #[test]
+    fn sort_powers_test() {
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        let make_powers = || {
+            [("Fire_Blast", "Fire Blast", 0), ("Blaze", "Blaze", 17), ("Flares", "Flares", 0)]
+                .iter()
+                .map(|(name, display_name, level)| {
+                    let mut power = BasePower::new();
+                    power.pch_full_name = Some(NameKey::new(*name));
+                    power.pch_display_name = Some(String::from(*display_name));
+                    let mut pwr = PowerOutput::from_base_power(&power, &AttribNames::new(), &config, None);
+                    pwr.available_at_level = level + 1;
+                    pwr
+                })
+                .collect::<Vec<_>>()
+        };
+        let sorted_names = |power_sort| {
+            let mut powers = make_powers();
+            sort_powers(&mut powers, power_sort);
+            powers
+                .iter()
+                .map(|p| p.display_name.clone().unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            sorted_names(PowerSortConfig::Level),
+            vec!["Fire Blast", "Flares", "Blaze"]
+        );
+        assert_eq!(
+            sorted_names(PowerSortConfig::Alphabetical),
+            vec!["Blaze", "Fire Blast", "Flares"]
+        );
+        assert_eq!(
+            sorted_names(PowerSortConfig::Original),
+            vec!["Fire Blast", "Blaze", "Flares"]
+        );
+    }
+
+    #[test]
+    fn inherents_test() {
+        let mut config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        config.extract_date = Some(chrono::Local::now());
+        let make_at = |name: &str, class_key: &str| {
+            let mut at = Archetype::new();
+            at.pch_name = Some(String::from(name));
+            at.class_key = Some(NameKey::new(class_key));
+            Rc::new(RefCell::new(at))
+        };
+        let archetypes = vec![
+            make_at("Class_Blaster", "Class_Blaster"),
+            make_at("Class_Tanker", "Class_Tanker"),
+        ];
+        let make_power = |name: &str, requires: Vec<&str>| {
+            let mut power = BasePower::new();
+            power.pch_full_name = Some(NameKey::new(name));
+            power.b_auto_issue = true;
+            power.include_in_output = true;
+            power.ppch_buy_requires = requires.iter().map(|r| r.to_string()).collect();
+            power.archetypes = archetypes.clone();
+            Rc::new(RefCell::new(power))
+        };
+        let mut pset = BasePowerSet::new();
+        pset.pp_powers.push(make_power(
+            "Inherent.Inherent.Defiance",
+            vec!["$archetype", "Class_Blaster", "eq"],
+        ));
+        pset.pp_powers.push(make_power("Inherent.Inherent.Brawl", vec![]));
+        let mut pcat = PowerCategory::new();
+        pcat.pch_name = Some(NameKey::new("Inherent"));
+        pcat.pp_power_sets.push(Rc::new(RefCell::new(pset)));
+
+        let inherents =
+            InherentsOutput::from_power_categories(&vec![Rc::new(RefCell::new(pcat))], &config);
+        assert_eq!(inherents.systems.len(), 1);
+        let system = &inherents.systems[0];
+        assert_eq!(system.system, "Powers");
+        let power_names = |at: &str| {
+            system
+                .archetypes
+                .iter()
+                .find(|a| a.archetype.as_deref() == Some(at))
+                .unwrap()
+                .powers
+                .iter()
+                .map(|p| p.name.as_ref().unwrap().get().to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            power_names("Class_Blaster"),
+            vec!["Inherent.Inherent.Brawl", "Inherent.Inherent.Defiance"]
+        );
+        assert_eq!(power_names("Class_Tanker"), vec!["Inherent.Inherent.Brawl"]);
+        assert_eq!(
+            system.archetypes[0].powers[0].url.as_deref(),
+            Some("../inherent/inherent/index.json")
+        );
+    }
+
+    #[test]
+    fn power_set_round_trip_test() {
+        let mut config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        config.extract_date = Some(chrono::Local::now());
+        let mut power = BasePower::new();
+        power.pch_full_name = Some(NameKey::new("Blaster_Ranged.Fire_Blast.Fire_Breath"));
+        power.pch_display_name = Some(String::from("Fire Breath"));
+        power.include_in_output = true;
+        power.e_type = PowerType::kPowerType_Click;
+        power.e_effect_area = EffectArea::kEffectArea_Box;
+        power.vec_box_offset = Vec3 {
+            x: -5.0,
+            y: 0.0,
+            z: 10.5,
+        };
+        power.vec_box_size = Vec3 {
+            x: 10.0,
+            y: 8.0,
+            z: 20.0,
+        };
+        let mut fx = PowerFX::new();
+        fx.rgba_default_tint_primary = RGBA::new(255, 128, 0, 255);
+        power.p_fx = Some(fx);
+        let mut power_set = BasePowerSet::new();
+        power_set.pch_full_name = Some(NameKey::new("Blaster_Ranged.Fire_Blast"));
+        power_set.pch_display_name = Some(String::from("Fire Blast"));
+        power_set.pp_power_names = vec![power.pch_full_name.clone().unwrap()];
+        power_set.pi_available = vec![1];
+        power_set.pp_powers.push(Rc::new(RefCell::new(power)));
+
+        let pset = PowerSetOutput::from_base_power_set(&power_set, &AttribNames::new(), &config, None);
+        let json = serde_json::to_string(&pset).unwrap();
+        let parsed: PowerSetOutput = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.name, pset.name);
+        assert_eq!(parsed.display_name, pset.display_name);
+        assert_eq!(parsed.system, pset.system);
+        assert_eq!(parsed.header.extract_date, pset.header.extract_date);
+        assert_eq!(parsed.ordered_power_names, pset.ordered_power_names);
+        assert_eq!(parsed.powers.len(), 1);
+        let (power, parsed_power) = (&pset.powers[0], &parsed.powers[0]);
+        assert_eq!(parsed_power.name, power.name);
+        assert_eq!(parsed_power.power_type, power.power_type);
+        assert_eq!(parsed_power.available_at_level, 2);
+        assert_eq!(parsed_power.display_info, power.display_info);
+        let offset = parsed_power.effect_area.box_offset.unwrap();
+        assert_eq!((offset.x, offset.y, offset.z), (-5.0, 0.0, 10.5));
+        assert_eq!(parsed_power.effect_area.box_corners.len(), 8);
+        let tints = parsed_power.default_tints.as_ref().unwrap();
+        assert_eq!(
+            (
+                tints.primary.r(),
+                tints.primary.g(),
+                tints.primary.b(),
+                tints.primary.a()
+            ),
+            (255, 128, 0, 255)
+        );
+        // anything lost or changed along the way would show up here
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap(),
+            serde_json::from_str::<serde_json::Value>(&json).unwrap()
+        );
+    }
+}
@@ -2,16 +2,19 @@ use super::effects::*;
 use super::*;
 use crate::structs::*;
 use display;
-use serde::Serialize;
+use log::warn;
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 
 /// Serializable representation of crowd control flags.
-#[derive(Serialize)]
+#[derive(Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct StatusOptionsOutput {
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub cast_through: Vec<&'static str>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub toggle_ignores: Vec<&'static str>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cast_through: Vec<Cow<'static, str>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub toggle_ignores: Vec<Cow<'static, str>>,
 }
 
 impl StatusOptionsOutput {
@@ -22,25 +25,25 @@ impl StatusOptionsOutput {
             toggle_ignores: Vec::new(),
         };
         if power.b_cast_through_hold {
-            opts.cast_through.push("Hold");
+            opts.cast_through.push("Hold".into());
         }
         if power.b_cast_through_sleep {
-            opts.cast_through.push("Sleep");
+            opts.cast_through.push("Sleep".into());
         }
         if power.b_cast_through_stun {
-            opts.cast_through.push("Stun");
+            opts.cast_through.push("Stun".into());
         }
         if power.b_cast_through_terrorize {
-            opts.cast_through.push("Terrorize");
+            opts.cast_through.push("Terrorize".into());
         }
         if power.b_toggle_ignore_hold {
-            opts.toggle_ignores.push("Hold");
+            opts.toggle_ignores.push("Hold".into());
         }
         if power.b_toggle_ignore_sleep {
-            opts.cast_through.push("Sleep");
+            opts.cast_through.push("Sleep".into());
         }
         if power.b_toggle_ignore_stun {
-            opts.cast_through.push("Stun");
+            opts.cast_through.push("Stun".into());
         }
         opts
     }
@@ -52,103 +55,525 @@ impl StatusOptionsOutput {
 }
 
 /// Serializable representation of a power's area of effect and range.
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct EffectAreaOutput {
-    pub area: Option<&'static str>,
-    #[serde(skip_serializing_if = "is_zero")]
+    pub area: Option<Cow<'static, str>>,
+    #[serde(default, skip_serializing_if = "is_zero")]
     pub max_targets_hit: i32,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_targets_expression: Option<String>,
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub radius_feet: f32,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub jump_distance_feet: f32,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub arc_degrees: f32,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub chain_delay_time: f32,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub range_feet: f32,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub range_feet_secondary: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_selection: Option<TargetSelectionOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub box_offset: Option<Vec3>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub box_size: Option<Vec3>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub box_corners: Vec<Vec3>,
 }
 
 impl EffectAreaOutput {
     /// Reads fields from a `BasePower` to create an `EffectAreaOutput`.
-    fn from_base_power(power: &BasePower) -> Self {
+    fn from_base_power(power: &BasePower, decimal_places: u8) -> Self {
+        let is_box = matches!(power.e_effect_area, EffectArea::kEffectArea_Box);
         EffectAreaOutput {
-            area: Some(power.e_effect_area.get_string()),
+            area: Some(power.e_effect_area.get_string().into()),
             max_targets_hit: power.i_max_targets_hit,
+            max_targets_expression: requires_to_string(&power.ppch_max_targets_expr),
             radius_feet: if !matches!(power.e_effect_area, EffectArea::kEffectArea_Chain) {
-                normalize(power.f_radius)
+                round_to(power.f_radius, decimal_places)
             } else {
                 0.0
             },
             jump_distance_feet: if matches!(power.e_effect_area, EffectArea::kEffectArea_Chain) {
-                normalize(power.f_radius)
+                round_to(power.f_radius, decimal_places)
             } else {
                 0.0
             },
-            arc_degrees: normalize(power.f_arc.to_degrees()),
-            chain_delay_time: normalize(power.f_chain_delay),
-            range_feet: normalize(power.f_range),
-            range_feet_secondary: normalize(power.f_range_secondary),
+            arc_degrees: round_to(power.f_arc.to_degrees(), decimal_places),
+            chain_delay_time: round_to(power.f_chain_delay, decimal_places),
+            range_feet: round_to(power.f_range, decimal_places),
+            range_feet_secondary: round_to(power.f_range_secondary, decimal_places),
+            target_selection: TargetSelectionOutput::from_base_power(power, decimal_places),
+            box_offset: if is_box {
+                Some(power.vec_box_offset)
+            } else {
+                None
+            },
+            box_size: if is_box {
+                Some(power.vec_box_size)
+            } else {
+                None
+            },
+            box_corners: if is_box {
+                get_box_corners(&power.vec_box_offset, &power.vec_box_size, decimal_places)
+            } else {
+                Vec::new()
+            },
+        }
+    }
+}
+
+/// Rounds the distances and angles in `summary` to `decimal_places`.
+fn round_area_summary(summary: AreaSummary, decimal_places: u8) -> AreaSummary {
+    AreaSummary {
+        radius_feet: round_to(summary.radius_feet, decimal_places),
+        arc_degrees: summary
+            .arc_degrees
+            .map(|arc| round_to(arc, decimal_places)),
+        chain_delay_time: summary
+            .chain_delay_time
+            .map(|delay| round_to(delay, decimal_places)),
+        ..summary
+    }
+}
+
+/// Calculates the eight corners of an axis-aligned box, relative to the target. `offset` is
+/// the corner nearest the origin and `size` extends from it along each axis.
+fn get_box_corners(offset: &Vec3, size: &Vec3, decimal_places: u8) -> Vec<Vec3> {
+    let mut corners = Vec::with_capacity(8);
+    for &dx in &[0.0, size.x] {
+        for &dy in &[0.0, size.y] {
+            for &dz in &[0.0, size.z] {
+                corners.push(Vec3 {
+                    x: round_to(offset.x + dx, decimal_places),
+                    y: round_to(offset.y + dy, decimal_places),
+                    z: round_to(offset.z + dz, decimal_places),
+                });
+            }
+        }
+    }
+    corners
+}
+
+/// Serializable representation of how an area power picks its targets when
+/// there are more candidates than it is allowed to hit.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TargetSelectionOutput {
+    pub max_targets_hit: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_targets_expression: Option<String>,
+    pub selection_order: Cow<'static, str>,
+    #[serde(default, skip_serializing_if = "not_normal")]
+    pub radius_feet: f32,
+}
+
+impl TargetSelectionOutput {
+    /// Reads fields from a `BasePower` to create a `TargetSelectionOutput`.
+    /// Only cone and sphere powers limit their targets, so this returns `None` for anything else.
+    fn from_base_power(power: &BasePower, decimal_places: u8) -> Option<Self> {
+        if !matches!(
+            power.e_effect_area,
+            EffectArea::kEffectArea_Cone | EffectArea::kEffectArea_Sphere
+        ) {
+            return None;
         }
+        let max_targets_expression = requires_to_string(&power.ppch_max_targets_expr);
+        if power.i_max_targets_hit <= 0 && max_targets_expression.is_none() {
+            return None;
+        }
+        Some(TargetSelectionOutput {
+            max_targets_hit: power.i_max_targets_hit,
+            max_targets_expression,
+            // when not shuffled, the targets farthest from the target point are rejected first
+            selection_order: if power.b_shuffle_target_list {
+                "Shuffled".into()
+            } else {
+                "NearestFirst".into()
+            },
+            radius_feet: round_to(power.f_radius, decimal_places),
+        })
     }
 }
 
 /// Serializable representation of a power's activation time and cost.
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ActivationOutput {
     pub cast_time: f32,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub animation_time: f32,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub animation_time_before_hit: f32,
     pub recharge_time: f32,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub interrupt_time: f32,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     pub auto_cast_interval: f32,
     pub endurance_cost: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endurance_per_second: Option<f32>,
+    #[serde(default, skip_serializing_if = "not_normal")]
+    pub insight_cost: f32,
 }
 
 impl ActivationOutput {
     /// Reads fields from a `BasePower` to create an `ActivationOutput`.
-    fn from_base_power(power: &BasePower) -> Self {
+    fn from_base_power(power: &BasePower, decimal_places: u8) -> Self {
         let mut activate = ActivationOutput {
-            cast_time: normalize(power.f_time_to_activate),
+            cast_time: round_to(power.f_time_to_activate, decimal_places),
             animation_time: 0.0,
             animation_time_before_hit: 0.0,
-            recharge_time: normalize(power.f_recharge_time),
-            interrupt_time: normalize(power.f_interrupt_time),
-            auto_cast_interval: normalize(power.f_activate_period),
-            endurance_cost: normalize(power.f_endurance_cost),
+            recharge_time: round_to(power.f_recharge_time, decimal_places),
+            interrupt_time: round_to(power.f_interrupt_time, decimal_places),
+            auto_cast_interval: round_to(power.f_activate_period, decimal_places),
+            endurance_cost: round_to(power.f_endurance_cost, decimal_places),
+            endurance_per_second: power
+                .endurance_per_second()
+                .map(|eps| round_to(eps, decimal_places)),
+            insight_cost: round_to(power.f_insight_cost, decimal_places),
         };
         if let Some(fx) = &power.p_fx {
-            activate.animation_time = normalize(PowerFX::frames_as_seconds(fx.i_frames_attack));
+            activate.animation_time =
+                round_to(fx.frames_as_seconds(fx.i_frames_attack), decimal_places);
             activate.animation_time_before_hit =
-                normalize(PowerFX::frames_as_seconds(fx.i_frames_before_hit));
+                round_to(fx.frames_as_seconds(fx.i_frames_before_hit), decimal_places);
         }
         activate
     }
 }
 
-#[derive(Serialize)]
+/// Serializable representation of a single phase of a power's animation.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AnimationPhaseOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fx: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bits: Vec<i32>,
+    #[serde(default, skip_serializing_if = "not_normal")]
+    pub delay_seconds: f32,
+    #[serde(default, skip_serializing_if = "not_normal")]
+    pub duration_seconds: f32,
+}
+
+impl AnimationPhaseOutput {
+    /// Creates an `AnimationPhaseOutput` from the FX name, animation bits, and timing (in frames at
+    /// `power_fx`'s frame rate).
+    ///
+    /// # Returns:
+    ///
+    /// An `AnimationPhaseOutput`, or `None` if the phase has no FX or animation bits.
+    fn new(
+        power_fx: &PowerFX,
+        decimal_places: u8,
+        fx: &Option<String>,
+        bits: &Vec<i32>,
+        delay_frames: i32,
+        duration_frames: i32,
+    ) -> Option<Self> {
+        if fx.is_none() && bits.is_empty() {
+            return None;
+        }
+        Some(AnimationPhaseOutput {
+            fx: fx.clone(),
+            bits: bits.clone(),
+            delay_seconds: round_to(power_fx.frames_as_seconds(delay_frames), decimal_places),
+            duration_seconds: round_to(power_fx.frames_as_seconds(duration_frames), decimal_places),
+        })
+    }
+}
+
+/// Serializable representation of the animation phases of a power.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AnimationsOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activation: Option<AnimationPhaseOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wind_up: Option<AnimationPhaseOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initial_attack: Option<AnimationPhaseOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attack: Option<AnimationPhaseOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hit: Option<AnimationPhaseOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block: Option<AnimationPhaseOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub death: Option<AnimationPhaseOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deactivation: Option<AnimationPhaseOutput>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub fx_important: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub hide_original: bool,
+}
+
+impl AnimationsOutput {
+    /// Reads the FX from a `BasePower` to create an `AnimationsOutput`.
+    ///
+    /// # Returns:
+    ///
+    /// An `AnimationsOutput`, or `None` if the power has no FX, or none of the phases are used
+    /// and none of the FX flags are set.
+    fn from_base_power(power: &BasePower, decimal_places: u8) -> Option<Self> {
+        let fx = power.p_fx.as_ref()?;
+        let animations = AnimationsOutput {
+            activation: AnimationPhaseOutput::new(
+                fx,
+                decimal_places,
+                &fx.pch_activation_fx,
+                &fx.pi_activation_bits,
+                0,
+                0,
+            ),
+            wind_up: AnimationPhaseOutput::new(
+                fx,
+                decimal_places,
+                &fx.pch_wind_up_fx,
+                &fx.pi_wind_up_bits,
+                0,
+                0,
+            ),
+            initial_attack: AnimationPhaseOutput::new(
+                fx,
+                decimal_places,
+                &fx.pch_initial_attack_fx,
+                &fx.pi_initial_attack_bits,
+                fx.i_initial_attack_fx_frame_delay,
+                0,
+            ),
+            attack: AnimationPhaseOutput::new(
+                fx,
+                decimal_places,
+                &fx.pch_attack_fx,
+                &fx.pi_attack_bits,
+                0,
+                fx.i_frames_attack,
+            ),
+            hit: AnimationPhaseOutput::new(
+                fx,
+                decimal_places,
+                &fx.pch_hit_fx,
+                &fx.pi_hit_bits,
+                fx.i_frames_before_hit,
+                0,
+            ),
+            block: AnimationPhaseOutput::new(
+                fx,
+                decimal_places,
+                &fx.pch_block_fx,
+                &fx.pi_block_bits,
+                fx.i_frames_before_block,
+                0,
+            ),
+            death: AnimationPhaseOutput::new(
+                fx,
+                decimal_places,
+                &fx.pch_death_fx,
+                &fx.pi_death_bits,
+                0,
+                0,
+            ),
+            deactivation: AnimationPhaseOutput::new(
+                fx,
+                decimal_places,
+                &fx.pch_deactivation_fx,
+                &fx.pi_deactivation_bits,
+                0,
+                0,
+            ),
+            fx_important: fx.b_fx_important,
+            hide_original: fx.b_hide_original,
+        };
+        if animations.activation.is_none()
+            && animations.wind_up.is_none()
+            && animations.initial_attack.is_none()
+            && animations.attack.is_none()
+            && animations.hit.is_none()
+            && animations.block.is_none()
+            && animations.death.is_none()
+            && animations.deactivation.is_none()
+            && !animations.fx_important
+            && !animations.hide_original
+        {
+            return None;
+        }
+        Some(animations)
+    }
+}
+
+/// Serializable representation of the stance (animation mode) a power puts the character in.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct StanceOutput {
+    pub sets_stance: bool,
+    pub mode_bits: Vec<i32>,
+}
+
+impl StanceOutput {
+    /// Reads fields from a `BasePower` to create a `StanceOutput`.
+    ///
+    /// # Returns:
+    ///
+    /// A `StanceOutput`, or `None` if the power doesn't set any mode bits.
+    fn from_base_power(power: &BasePower) -> Option<Self> {
+        let mode_bits = match &power.p_fx {
+            Some(fx) if !fx.pi_mode_bits.is_empty() => fx.pi_mode_bits.clone(),
+            _ => return None,
+        };
+        Some(StanceOutput {
+            sets_stance: !power.b_dont_set_stance,
+            mode_bits,
+        })
+    }
+}
+
+/// Serializable representation of how a power is placed into the server-controlled power tray.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TrayPlacementOutput {
+    pub server_tray: bool,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub priority: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requires: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub requires_tray_override_mode: bool,
+}
+
+impl TrayPlacementOutput {
+    /// Reads fields from a `BasePower` to create a `TrayPlacementOutput`.
+    ///
+    /// # Returns:
+    ///
+    /// A `TrayPlacementOutput`, or `None` if the power is only placed in the normal trays.
+    fn from_base_power(power: &BasePower) -> Option<Self> {
+        // mode 0 is hard-coded to ServerTrayOverride
+        let requires_tray_override_mode = power.pe_modes_required.iter().any(|m| m.0 == 0);
+        let requires = requires_to_string(&power.ppch_server_tray_requires);
+        if power.i_server_tray_priority == 0 && requires.is_none() && !requires_tray_override_mode {
+            return None;
+        }
+        Some(TrayPlacementOutput {
+            server_tray: power.i_server_tray_priority != 0 || requires.is_some(),
+            priority: power.i_server_tray_priority,
+            requires,
+            requires_tray_override_mode,
+        })
+    }
+}
+
+/// Serializable representation of the default tints used by a power's FX when it isn't customized.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DefaultTintsOutput {
+    pub primary: RGBA,
+    pub secondary: RGBA,
+}
+
+impl DefaultTintsOutput {
+    /// Reads fields from a `BasePower` to create a `DefaultTintsOutput`.
+    ///
+    /// # Returns:
+    ///
+    /// A `DefaultTintsOutput`, or `None` if the power has no FX or both tints are black.
+    fn from_base_power(power: &BasePower) -> Option<Self> {
+        match &power.p_fx {
+            Some(fx)
+                if !fx.rgba_default_tint_primary.is_black()
+                    || !fx.rgba_default_tint_secondary.is_black() =>
+            {
+                Some(DefaultTintsOutput {
+                    primary: fx.rgba_default_tint_primary,
+                    secondary: fx.rgba_default_tint_secondary,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Serializable representation of the confirmation dialog shown to targets of a power.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ConfirmOutput {
+    pub time_seconds: f32,
+    pub self_confirm: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requires: Option<String>,
+}
+
+/// Serializable representation of everything a player sees while casting a power:
+/// whether it can be interrupted or cancelled, and whether targets must confirm it.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CastUxOutput {
+    #[serde(default, skip_serializing_if = "not_normal")]
+    pub interrupt_time: f32,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub interrupt_like_sleep: bool,
+    pub cancelable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirm: Option<ConfirmOutput>,
+}
+
+impl CastUxOutput {
+    /// Reads fields from a `BasePower` to create a `CastUxOutput`.
+    ///
+    /// # Returns:
+    ///
+    /// A `CastUxOutput`, or `None` if the power can't be interrupted, cancelled, or confirmed.
+    fn from_base_power(power: &BasePower, decimal_places: u8) -> Option<Self> {
+        let interrupt_time = round_to(power.f_interrupt_time, decimal_places);
+        let confirm = if power.i_time_to_confirm > 0 {
+            Some(ConfirmOutput {
+                time_seconds: power.i_time_to_confirm as f32,
+                self_confirm: power.b_self_confirm,
+                message: power.pch_display_confirm.clone(),
+                requires: requires_to_string(&power.ppch_confirm_requires),
+            })
+        } else {
+            None
+        };
+        if !interrupt_time.is_normal() && !power.b_cancelable && confirm.is_none() {
+            return None;
+        }
+        Some(CastUxOutput {
+            interrupt_time,
+            // only meaningful if the power can be interrupted at all
+            interrupt_like_sleep: interrupt_time.is_normal() && power.b_interrupt_like_sleep,
+            cancelable: power.b_cancelable,
+            confirm,
+        })
+    }
+}
+
+#[derive(Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct UsageOutput {
     remove_on_limit: bool,
     extend_on_additional_grant: bool,
-    #[serde(skip_serializing_if = "is_zero")]
+    #[serde(default, skip_serializing_if = "is_zero")]
     charges: i32,
     #[serde(skip_serializing_if = "Option::is_none")]
     max_charges_on_extend: Option<i32>,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     toggle_usage_time: f32,
     #[serde(skip_serializing_if = "Option::is_none")]
     toggle_max_usage_time_on_extend: Option<f32>,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     lifetime: f32,
     #[serde(skip_serializing_if = "Option::is_none")]
     max_lifetime_on_extend: Option<f32>,
-    #[serde(skip_serializing_if = "not_normal")]
+    #[serde(default, skip_serializing_if = "not_normal")]
     in_game_lifetime: f32,
     #[serde(skip_serializing_if = "Option::is_none")]
     max_in_game_lifetime_on_extend: Option<f32>,
@@ -193,7 +618,60 @@ impl UsageOutput {
     }
 }
 
-#[derive(Serialize)]
+/// Serializable representation of the chat and floater messages a power can display.
+#[derive(Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PowerMessagesOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target_help: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target_short_help: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attacker_attack: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attacker_attack_float: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attacker_hit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    victim_hit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confirm: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reward_float: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    defense_float: Option<String>,
+}
+
+impl PowerMessagesOutput {
+    fn from_base_power(power: &BasePower) -> Self {
+        PowerMessagesOutput {
+            target_help: power.pch_display_target_help.clone(),
+            target_short_help: power.pch_display_target_short_help.clone(),
+            attacker_attack: power.pch_display_attacker_attack.clone(),
+            attacker_attack_float: power.pch_display_attacker_attack_floater.clone(),
+            attacker_hit: power.pch_display_attacker_hit.clone(),
+            victim_hit: power.pch_display_victim_hit.clone(),
+            confirm: power.pch_display_confirm.clone(),
+            reward_float: power.pch_display_float_rewarded.clone(),
+            defense_float: power.pch_display_defense_float.clone(),
+        }
+    }
+
+    fn is_empty(messages: &PowerMessagesOutput) -> bool {
+        messages.target_help.is_none()
+            && messages.target_short_help.is_none()
+            && messages.attacker_attack.is_none()
+            && messages.attacker_attack_float.is_none()
+            && messages.attacker_hit.is_none()
+            && messages.victim_hit.is_none()
+            && messages.confirm.is_none()
+            && messages.reward_float.is_none()
+            && messages.defense_float.is_none()
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PowerRedirectOutput {
     pub name: Option<NameKey>,
     pub fallback: bool,
@@ -201,6 +679,103 @@ pub struct PowerRedirectOutput {
     pub requires: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<RedirectTargetOutput>,
+}
+
+/// Summary of the power a redirect points to, embedded when `inline_redirects` is set.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RedirectTargetOutput {
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requires: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effect_summary: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redirects: Vec<PowerRedirectOutput>,
+}
+
+impl RedirectTargetOutput {
+    /// Looks up the target of `redirect` and summarizes it, including its own redirects.
+    ///
+    /// # Arguments:
+    ///
+    /// * `visited` - Full names of the powers that led to this redirect. Redirects back to any of
+    ///   these aren't followed, so self-referential chains can't loop forever.
+    ///
+    /// # Returns:
+    ///
+    /// A `RedirectTargetOutput`, or `None` if the target can't be found or was already visited.
+    fn from_power_redirect(
+        redirect: &PowerRedirect,
+        attrib_names: &AttribNames,
+        config: &PowersConfig,
+        powers_dict: &PowersDictionary,
+        visited: &mut Vec<NameKey>,
+    ) -> Option<Self> {
+        let target_name = redirect.pch_name.as_ref()?;
+        if visited.contains(target_name) {
+            return None;
+        }
+        let target = powers_dict.find_power(target_name)?;
+        let target = target.borrow();
+        let archetypes = filter_archetypes_pwr(&*target, &target.archetypes);
+        let effect_groups: Vec<_> = target
+            .pp_effects
+            .iter()
+            .map(|effect_group| {
+                EffectGroupOutput::from_effect_group(
+                    &*effect_group.borrow(),
+                    attrib_names,
+                    &*target,
+                    &archetypes,
+                    config,
+                )
+            })
+            .collect();
+        Some(RedirectTargetOutput {
+            display_name: target.pch_display_name.clone(),
+            requires: requires_to_string(&target.ppch_buy_requires),
+            effect_summary: display::summarize_power(&effect_groups, &*target),
+            redirects: redirect_outputs(&*target, attrib_names, config, Some(powers_dict), visited),
+        })
+    }
+}
+
+/// Converts the redirects of `power`, inlining their targets if `config.inline_redirects` is set.
+///
+/// # Arguments:
+///
+/// * `visited` - Full names of the powers that led to `power`.
+fn redirect_outputs(
+    power: &BasePower,
+    attrib_names: &AttribNames,
+    config: &PowersConfig,
+    powers_dict: Option<&PowersDictionary>,
+    visited: &mut Vec<NameKey>,
+) -> Vec<PowerRedirectOutput> {
+    let mut redirects = Vec::new();
+    for redirect in &power.pp_redirect {
+        let mut redirect_output = PowerRedirectOutput::from_power_redirect(&redirect, config);
+        if let Some(powers_dict) = powers_dict {
+            if config.inline_redirects && redirect.b_show_in_info {
+                visited.extend(power.pch_full_name.clone());
+                redirect_output.target = RedirectTargetOutput::from_power_redirect(
+                    redirect,
+                    attrib_names,
+                    config,
+                    powers_dict,
+                    visited,
+                );
+                if power.pch_full_name.is_some() {
+                    visited.pop();
+                }
+            }
+        }
+        redirects.push(redirect_output);
+    }
+    redirects
 }
 
 impl PowerRedirectOutput {
@@ -210,29 +785,34 @@ impl PowerRedirectOutput {
             fallback: redirect.ppch_requires.len() == 0
                 || (redirect.ppch_requires.len() == 1 && redirect.ppch_requires[0] == "1"),
             requires: requires_to_string(&redirect.ppch_requires),
-            url: make_power_ref_url(redirect.pch_name.as_ref(), config),
+            url: redirect
+                .pch_name
+                .as_ref()
+                .and_then(|name| namekey_to_url(name, UrlKind::Power, config)),
+            target: None,
         }
     }
 }
 
 // Serializable representation of chain effects.
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ChainEffectOutput {
     /// I might deprecate the value in effect area in v3.
     #[serde(skip)]
     pub chain_delay_time: f32,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub chain_effectiveness: Vec<String>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub chain_target_expression: Vec<String>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub chain_fork: Vec<i32>,
 }
 
 impl ChainEffectOutput {
-    fn from_base_power(power: &BasePower) -> Self {
+    fn from_base_power(power: &BasePower, decimal_places: u8) -> Self {
         let mut output = ChainEffectOutput {
-            chain_delay_time: normalize(power.f_chain_delay),
+            chain_delay_time: round_to(power.f_chain_delay, decimal_places),
             chain_effectiveness: Vec::new(),
             chain_target_expression: Vec::new(),
             chain_fork: power.pi_chain_fork.clone(),
@@ -247,8 +827,273 @@ impl ChainEffectOutput {
     }
 }
 
+/// Serializable representation of everything about a power that matters in PvP.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PvpOutput {
+    pub shoot_through_untouchable: bool,
+    pub targets_through_vision_phase: bool,
+    #[serde(default, skip_serializing_if = "is_zero_usize")]
+    pub pvp_only_effect_groups: usize,
+    #[serde(default, skip_serializing_if = "is_zero_usize")]
+    pub pve_only_effect_groups: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub elusivity_attributes: Vec<Cow<'static, str>>,
+}
+
+impl PvpOutput {
+    /// Gathers the PvP-relevant parts of a power from the `BasePower` and its already
+    /// converted effect groups.
+    ///
+    /// # Returns:
+    ///
+    /// A `PvpOutput`, or `None` if nothing about the power is specific to PvP.
+    fn from_base_power(power: &BasePower, effect_groups: &Vec<EffectGroupOutput>) -> Option<Self> {
+        let mut pvp = PvpOutput {
+            shoot_through_untouchable: power.b_shoot_through_untouchable,
+            targets_through_vision_phase: power.b_targets_through_vision_phase,
+            pvp_only_effect_groups: 0,
+            pve_only_effect_groups: 0,
+            elusivity_attributes: Vec::new(),
+        };
+        pvp.add_effect_groups(effect_groups);
+        if pvp.shoot_through_untouchable
+            || pvp.targets_through_vision_phase
+            || pvp.pvp_only_effect_groups > 0
+            || pvp.pve_only_effect_groups > 0
+            || !pvp.elusivity_attributes.is_empty()
+        {
+            Some(pvp)
+        } else {
+            None
+        }
+    }
+
+    /// Counts PvE/PvP effect groups and collects elusivity attributes, including child groups.
+    fn add_effect_groups(&mut self, effect_groups: &Vec<EffectGroupOutput>) {
+        for group in effect_groups {
+            match group.pve_or_pvp.as_deref() {
+                Some(PVP_TAG) => self.pvp_only_effect_groups += 1,
+                Some(PVE_TAG) => self.pve_only_effect_groups += 1,
+                _ => (),
+            }
+            for effect in &group.effects {
+                for attrib in &effect.attributes {
+                    if attrib.contains("Elusivity") && !self.elusivity_attributes.contains(attrib) {
+                        self.elusivity_attributes.push(attrib.clone());
+                    }
+                }
+            }
+            self.add_effect_groups(&group.child_effect_groups);
+        }
+    }
+}
+
+/// Serializable representation of how a power interacts with leagues (large groups of teams).
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LeagueOutput {
+    pub refreshes_on_active_player_change: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub leaguemate_targets: Vec<Cow<'static, str>>,
+}
+
+impl LeagueOutput {
+    /// Reads fields from a `BasePower` to create a `LeagueOutput`.
+    ///
+    /// # Returns:
+    ///
+    /// A `LeagueOutput`, or `None` if nothing about the power is specific to leagues.
+    fn from_base_power(power: &BasePower) -> Option<Self> {
+        let mut league = LeagueOutput {
+            refreshes_on_active_player_change: power.b_refreshes_on_active_player_change,
+            leaguemate_targets: Vec::new(),
+        };
+        let target_types = [&power.e_target_type, &power.e_target_type_secondary];
+        for target in target_types
+            .iter()
+            .cloned()
+            .chain(power.p_auto_hit.iter())
+            .chain(power.p_affected.iter())
+        {
+            let target_name = match target {
+                TargetType::kTargetType_Leaguemate => "Leaguemate",
+                TargetType::kTargetType_DeadLeaguemate => "DeadLeaguemate",
+                TargetType::kTargetType_DeadOrAliveLeaguemate => "DeadOrAliveLeaguemate",
+                _ => continue,
+            };
+            if !league.leaguemate_targets.iter().any(|t| t == target_name) {
+                league.leaguemate_targets.push(target_name.into());
+            }
+        }
+        if league.refreshes_on_active_player_change || !league.leaguemate_targets.is_empty() {
+            Some(league)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns true if `val` is 0.
+fn is_zero_usize(val: &usize) -> bool {
+    *val == 0
+}
+
+/// Attributes that indicate a power is used for getting around.
+const TRAVEL_ATTRIBUTES: &'static [&'static str] = &[
+    "RunningSpeed",
+    "FlyingSpeed",
+    "JumpingSpeed",
+    "JumpHeight",
+    "Fly",
+    "Jump Pack",
+    "Teleport",
+];
+
+/// Effects found in a power that are used to guess its role.
+#[derive(Default)]
+struct RoleTraits {
+    summon: bool,
+    damage: bool,
+    control: bool,
+    healing: bool,
+    travel: bool,
+    defense: bool,
+    affects_target: bool,
+}
+
+impl RoleTraits {
+    /// Walks the effect groups (including child groups) looking for effects that hint at a role.
+    fn add_effect_groups(&mut self, effect_groups: &Vec<EffectGroupOutput>) {
+        for group in effect_groups {
+            for effect in &group.effects {
+                let on_self = matches!(
+                    effect.target_type.as_deref(),
+                    Some("Self") | Some("SelfAndPets")
+                );
+                if !on_self {
+                    self.affects_target = true;
+                }
+                if matches!(effect.parameter, Some(AttribModParamOutput::CreateEntity { .. })) {
+                    self.summon = true;
+                }
+                for scaled in &effect.scaled {
+                    match scaled.scaled_effect {
+                        ScaledUnit::Damage(_) if !on_self => self.damage = true,
+                        ScaledUnit::Healing(_) => self.healing = true,
+                        // protection is a negative magnitude, so only positive values are control
+                        ScaledUnit::Magnitude(mag) if !on_self && mag > 0.0 => self.control = true,
+                        _ => (),
+                    }
+                }
+                for attrib in &effect.attributes {
+                    if TRAVEL_ATTRIBUTES.contains(&attrib.as_ref()) {
+                        self.travel = true;
+                    } else if attrib == "Defense" || attrib.ends_with("_Def") {
+                        self.defense = true;
+                    }
+                }
+            }
+            self.add_effect_groups(&group.child_effect_groups);
+        }
+    }
+}
+
+/// Makes a best guess at what a power is used for, based on its effects.
+///
+/// The first matching rule wins:
+///
+/// 1. `Summon` - creates an entity.
+/// 2. `Attack` - damages a foe.
+/// 3. `Control` - applies a status effect (hold, sleep, knockback, etc.) to a foe.
+/// 4. `Heal` - restores hit points to a friend or the caster.
+/// 5. `Travel` - improves movement of a friend or the caster.
+/// 6. `Toggle Defense` - a toggle granting defense to a friend or the caster.
+/// 7. `Debuff` - any other effect targeting a foe.
+/// 8. `Buff` - any other effect targeting a friend or the caster.
+///
+/// # Returns:
+///
+/// The role, or `None` if the power has no effects.
+fn classify_role(power: &BasePower, effect_groups: &Vec<EffectGroupOutput>) -> Option<&'static str> {
+    let mut traits = RoleTraits::default();
+    traits.add_effect_groups(effect_groups);
+    let hostile = matches!(
+        power.e_target_type,
+        TargetType::kTargetType_Foe
+            | TargetType::kTargetType_DeadOrAliveFoe
+            | TargetType::kTargetType_Villain
+            | TargetType::kTargetType_NPC
+    );
+    if traits.summon {
+        Some("Summon")
+    } else if traits.damage && hostile {
+        Some("Attack")
+    } else if traits.control && hostile {
+        Some("Control")
+    } else if traits.healing && !hostile {
+        Some("Heal")
+    } else if traits.travel && !hostile {
+        Some("Travel")
+    } else if traits.defense
+        && !hostile
+        && matches!(power.e_type, PowerType::kPowerType_Toggle)
+    {
+        Some("Toggle Defense")
+    } else if traits.affects_target && hostile {
+        Some("Debuff")
+    } else if !effect_groups.is_empty() {
+        Some("Buff")
+    } else {
+        None
+    }
+}
+
+/// Total damage done by a power for one archetype.
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PowerDamageOutput {
+    pub archetype: Option<String>,
+    pub damage: f32,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub incomplete: bool,
+}
+
+impl PowerDamageOutput {
+    /// Adds up the total damage `power` does for each of `archetypes`, at `config.at_level`.
+    ///
+    /// # Returns:
+    ///
+    /// A `PowerDamageOutput` for each archetype the power does damage for. An `EffectDepthError`
+    /// if the power's effect groups are nested deeper than `config.max_effect_depth`, since the
+    /// total would be missing whatever the deeper groups do.
+    fn from_base_power(
+        power: &BasePower,
+        archetypes: &Vec<ObjRef<Archetype>>,
+        attrib_names: &AttribNames,
+        config: &PowersConfig,
+    ) -> Result<Vec<Self>, EffectDepthError> {
+        let max_depth = config.max_effect_depth;
+        let incomplete = power.has_expression_damage(max_depth)?;
+        let mut total_damage = Vec::new();
+        for at in archetypes.iter().map(|at| at.borrow()) {
+            let damage =
+                power.total_damage(attrib_names, &*at, config.at_level as usize, max_depth)?;
+            if damage.is_normal() {
+                total_damage.push(PowerDamageOutput {
+                    archetype: at.pch_display_name.clone(),
+                    damage: round_to(damage, config.decimal_places),
+                    incomplete,
+                });
+            }
+        }
+        Ok(total_damage)
+    }
+}
+
 /// Serializable representation of a power.
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PowerOutput {
     pub name: Option<NameKey>,
     pub display_name: Option<String>,
@@ -258,63 +1103,106 @@ pub struct PowerOutput {
     pub display_help: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub display_short_help: Option<String>,
-    #[serde(skip_serializing_if = "HashMap::is_empty")]
-    pub display_info: HashMap<&'static str, Cow<'static, str>>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub display_info: HashMap<Cow<'static, str>, Cow<'static, str>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub requires: Option<String>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub attack_types: Vec<Option<Cow<'static, str>>>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub enhancements_allowed: Vec<String>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub enhancement_set_categories_allowed: Vec<String>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub max_boosts: i32,
     pub available_at_level: i32,
     pub auto_issue: bool,
-    pub power_type: Option<&'static str>,
+    /// Names of the power's `b_` flags that are set. Only filled in if `compact_bool_flags`
+    /// is set in the config.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<Cow<'static, str>>,
+    pub power_type: Option<Cow<'static, str>>,
+    pub system: Cow<'static, str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<Cow<'static, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effect_summary: Option<String>,
     pub accuracy: f32,
     pub effect_area: EffectAreaOutput,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub target_type_tags: Vec<&'static str>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub target_type_secondary_tags: Vec<&'static str>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub display_target_type: Option<&'static str>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub display_target_type_secondary: Option<&'static str>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub target_auto_hit_tags: Vec<Vec<&'static str>>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub display_target_auto_hit: Vec<&'static str>,
+    pub area_summary: AreaSummary,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub target_type_tags: Vec<Cow<'static, str>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub target_type_secondary_tags: Vec<Cow<'static, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_target_type: Option<Cow<'static, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_target_type_secondary: Option<Cow<'static, str>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub target_auto_hit_tags: Vec<Vec<Cow<'static, str>>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub display_target_auto_hit: Vec<Cow<'static, str>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub target_affected_tags: Vec<Vec<Cow<'static, str>>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub display_target_affected: Vec<Cow<'static, str>>,
     pub requires_line_of_sight: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub chain: Option<ChainEffectOutput>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub modes_required: Vec<String>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub modes_disallowed: Vec<String>,
-    #[serde(skip_serializing_if = "StatusOptionsOutput::is_empty")]
+    #[serde(default, skip_serializing_if = "StatusOptionsOutput::is_empty")]
     pub status_interaction: StatusOptionsOutput,
     pub activate: ActivationOutput,
-    #[serde(skip_serializing_if = "UsageOutput::is_empty")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cast_cycle_seconds: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cast_ux: Option<CastUxOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stance: Option<StanceOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_tints: Option<DefaultTintsOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub animations: Option<AnimationsOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tray_placement: Option<TrayPlacementOutput>,
+    #[serde(default, skip_serializing_if = "UsageOutput::is_empty")]
     pub usage: UsageOutput,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "PowerMessagesOutput::is_empty")]
+    pub messages: PowerMessagesOutput,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub strengths_disallowed: Vec<Cow<'static, str>>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub global_strengths_disallowed: Vec<Cow<'static, str>>,
     pub effect_groups: Vec<EffectGroupOutput>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub total_damage: Vec<PowerDamageOutput>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub activate_effect_groups: Vec<EffectGroupOutput>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub redirects: Vec<PowerRedirectOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pvp: Option<PvpOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub league: Option<LeagueOutput>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub introduced_in: BTreeMap<Cow<'static, str>, Cow<'static, str>>,
 }
 
 impl PowerOutput {
     /// Converts a `BasePower` to a `PowerOutput` ready for serialization.
+    ///
+    /// If `config.inline_redirects` is set, redirects shown in the power info are looked up in
+    /// `powers_dict` and summarized in the output.
     pub fn from_base_power(
         power: &BasePower,
         attrib_names: &AttribNames,
         config: &PowersConfig,
+        powers_dict: Option<&PowersDictionary>,
     ) -> Self {
+        let decimal_places = config.decimal_places;
         let mut pwr = PowerOutput {
             name: power.pch_full_name.clone(),
             display_name: power.pch_display_name.clone(),
@@ -330,19 +1218,38 @@ impl PowerOutput {
                 .iter()
                 .cloned()
                 .collect(),
+            max_boosts: power.i_max_boosts,
             available_at_level: 0,
             auto_issue: power.b_auto_issue,
-            power_type: Some(power.e_type.get_string()),
-            accuracy: normalize(power.f_accuracy),
-            effect_area: EffectAreaOutput::from_base_power(power),
-            target_type_tags: power.e_target_type.get_strings(),
-            target_type_secondary_tags: power.e_target_type_secondary.get_strings(),
-            display_target_type: display::describe_target_type(&power.e_target_type),
+            flags: Vec::new(),
+            power_type: Some(power.e_type.get_string().into()),
+            system: power.e_system.get_string().into(),
+            role: None,
+            effect_summary: None,
+            accuracy: round_to(power.f_accuracy, decimal_places),
+            effect_area: EffectAreaOutput::from_base_power(power, decimal_places),
+            area_summary: round_area_summary(power.area_summary(), decimal_places),
+            target_type_tags: power
+                .e_target_type
+                .get_strings()
+                .into_iter()
+                .map(Cow::from)
+                .collect(),
+            target_type_secondary_tags: power
+                .e_target_type_secondary
+                .get_strings()
+                .into_iter()
+                .map(Cow::from)
+                .collect(),
+            display_target_type: display::describe_target_type(&power.e_target_type).map(Cow::from),
             display_target_type_secondary: display::describe_target_type(
                 &power.e_target_type_secondary,
-            ),
+            )
+            .map(Cow::from),
             target_auto_hit_tags: Vec::new(),
             display_target_auto_hit: Vec::new(),
+            target_affected_tags: Vec::new(),
+            display_target_affected: Vec::new(),
             requires_line_of_sight: match power.e_target_visibility {
                 TargetVisibility::kTargetVisibility_LineOfSight => true,
                 TargetVisibility::kTargetVisibility_None => false,
@@ -351,21 +1258,32 @@ impl PowerOutput {
             modes_required: Vec::new(),
             modes_disallowed: Vec::new(),
             status_interaction: StatusOptionsOutput::from_base_power(power),
-            activate: ActivationOutput::from_base_power(power),
+            activate: ActivationOutput::from_base_power(power, decimal_places),
+            cast_cycle_seconds: power
+                .cast_cycle_seconds()
+                .map(|secs| round_to(secs, decimal_places)),
+            cast_ux: CastUxOutput::from_base_power(power, decimal_places),
+            stance: StanceOutput::from_base_power(power),
+            default_tints: DefaultTintsOutput::from_base_power(power),
+            animations: AnimationsOutput::from_base_power(power, decimal_places),
+            tray_placement: TrayPlacementOutput::from_base_power(power),
             usage: UsageOutput::from_base_power(power),
+            messages: PowerMessagesOutput::from_base_power(power),
             strengths_disallowed: Vec::new(),
             global_strengths_disallowed: Vec::new(),
             effect_groups: Vec::new(),
+            total_damage: Vec::new(),
             activate_effect_groups: Vec::new(),
             redirects: Vec::new(),
+            pvp: None,
+            league: LeagueOutput::from_base_power(power),
+            introduced_in: BTreeMap::new(),
         };
         // power icon
-        if let Some(icon) = power.pch_icon_name.as_ref() {
-            if let Some(assets_config) = config.assets.as_ref() {
-                pwr.icon = Some(format_power_icon_to_asset(icon, assets_config));
-            } else {
-                pwr.icon = Some(icon.to_owned());
-            }
+        pwr.icon = make_power_icon(power.pch_icon_name.as_ref(), config.assets.as_ref());
+        // boolean flags
+        if config.compact_bool_flags {
+            pwr.flags = power.flag_strings().into_iter().map(Cow::from).collect();
         }
         // attack types
         for atk in &power.pe_attack_types {
@@ -402,15 +1320,30 @@ impl PowerOutput {
         // auto hit tags
         for target in &power.p_auto_hit {
             if !matches!(target, TargetType::kTargetType_None) {
-                pwr.target_auto_hit_tags.push(target.get_strings());
+                pwr.target_auto_hit_tags
+                    .push(target.get_strings().into_iter().map(Cow::from).collect());
                 if let Some(s) = display::describe_target_type(target) {
-                    pwr.display_target_auto_hit.push(s);
+                    pwr.display_target_auto_hit.push(s.into());
+                }
+            }
+        }
+        // affected tags (these still need a to-hit roll, so anything auto-hit is left out)
+        for target in &power.p_affected {
+            let auto_hit = power
+                .p_auto_hit
+                .iter()
+                .any(|auto_hit| *auto_hit as u32 == *target as u32);
+            if !auto_hit && !matches!(target, TargetType::kTargetType_None) {
+                pwr.target_affected_tags
+                    .push(target.get_strings().into_iter().map(Cow::from).collect());
+                if let Some(s) = display::describe_target_type(target) {
+                    pwr.display_target_affected.push(s.into());
                 }
             }
         }
         // chain parameters
         if matches!(power.e_effect_area, EffectArea::kEffectArea_Chain) {
-            pwr.chain = Some(ChainEffectOutput::from_base_power(power));
+            pwr.chain = Some(ChainEffectOutput::from_base_power(power, decimal_places));
         }
         // filter archetypes to only those that can purchase this power, if necessary
         let archetypes = filter_archetypes_pwr(power, &power.archetypes);
@@ -424,20 +1357,69 @@ impl PowerOutput {
                 config,
             ));
         }
-        // redirected powers
-        for redirect in &power.pp_redirect {
-            pwr.redirects
-                .push(PowerRedirectOutput::from_power_redirect(&redirect, config));
+        // total damage per archetype
+        match PowerDamageOutput::from_base_power(power, &archetypes, attrib_names, config) {
+            Ok(total_damage) => pwr.total_damage = total_damage,
+            Err(e) => warn!(
+                "Total damage left out for {}. {}",
+                power.pch_full_name.as_ref().map_or("(unnamed)", |name| name.get()),
+                e
+            ),
         }
+        // best guess at the power's role
+        pwr.role = classify_role(power, &pwr.effect_groups).map(Cow::from);
+        // PvP summary
+        pwr.pvp = PvpOutput::from_base_power(power, &pwr.effect_groups);
+        // redirected powers
+        pwr.redirects = redirect_outputs(power, attrib_names, config, powers_dict, &mut Vec::new());
         // set display information
-        display::describe_power(&mut pwr, &power, attrib_names);
+        display::describe_power(&mut pwr, &power, attrib_names, config.decimal_places);
+        pwr.effect_summary = display::summarize_power(&pwr.effect_groups, &power);
+        if config.annotate_introduced_fields {
+            pwr.annotate_introduced_fields();
+        }
         pwr
     }
+
+    /// Tags any output fields this power uses that were added in later issues.
+    fn annotate_introduced_fields(&mut self) {
+        let mut used_fields = Vec::new();
+        if !self.global_strengths_disallowed.is_empty() {
+            used_fields.push(("global_strengths_disallowed", "p_global_strengths_disallowed"));
+        }
+        if matches!(&self.chain, Some(chain) if !chain.chain_target_expression.is_empty()) {
+            used_fields.push(("chain.chain_target_expression", "ppch_chain_target_expr"));
+        }
+        if matches!(&self.effect_area.target_selection, Some(sel) if sel.max_targets_expression.is_some())
+        {
+            used_fields.push((
+                "effect_area.target_selection.max_targets_expression",
+                "ppch_max_targets_expr",
+            ));
+        }
+        // only listed when `compact_bool_flags` is set
+        if self.flags.iter().any(|flag| flag == "TargetUntargetable") {
+            used_fields.push(("flags.TargetUntargetable", "b_target_untargetable"));
+        }
+        if let Some(animations) = &self.animations {
+            if animations.fx_important {
+                used_fields.push(("animations.fx_important", "b_fx_important"));
+            }
+            if animations.hide_original {
+                used_fields.push(("animations.hide_original", "b_hide_original"));
+            }
+        }
+        for (output_field, field) in used_fields {
+            if let Some(issue) = field_introduced(field) {
+                self.introduced_in.insert(output_field.into(), issue.into());
+            }
+        }
+    }
 }
 
 /// Filters the archetypes vector based on any purchase requirements specified in `power`.
 /// If `power` has no requirements, all archetypes passed in will be returned.
-fn filter_archetypes_pwr(power: &BasePower, archetypes: &Vec<ObjRef<Archetype>>) -> Vec<ObjRef<Archetype>> {
+pub(super) fn filter_archetypes_pwr(power: &BasePower, archetypes: &Vec<ObjRef<Archetype>>) -> Vec<ObjRef<Archetype>> {
     if power
         .ppch_buy_requires
         .iter()
@@ -463,28 +1445,671 @@ fn filter_archetypes_pwr(power: &BasePower, archetypes: &Vec<ObjRef<Archetype>>)
     }
 }
 
-/// Creates a URL link to be used inside a power to another power set in an external file.
-/// `power_ref` must have at least 2 parts (category & set) or this will return `None`.
-pub fn make_power_ref_url(power_ref: Option<&NameKey>, config: &PowersConfig) -> Option<String> {
-    if power_ref.is_none() {
-        return None;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::config::MINIMAL_CONFIG;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn target_selection_static_cap_shuffled_test() {
+        let mut power = BasePower::new();
+        power.e_effect_area = EffectArea::kEffectArea_Sphere;
+        power.i_max_targets_hit = 10;
+        power.f_radius = 15.0;
+        power.b_shuffle_target_list = true;
+
+        let selection = TargetSelectionOutput::from_base_power(&power, 2).unwrap();
+        assert_eq!(selection.max_targets_hit, 10);
+        assert!(selection.max_targets_expression.is_none());
+        assert_eq!(selection.selection_order, "Shuffled");
+        assert_eq!(selection.radius_feet, 15.0);
     }
-    let name_parts = power_ref.unwrap().split();
-    if name_parts.len() < 2 {
-        return None;
+
+    #[test]
+    fn box_corners_test() {
+        let mut power = BasePower::new();
+        power.e_effect_area = EffectArea::kEffectArea_Box;
+        power.vec_box_offset = Vec3 {
+            x: -5.0,
+            y: 0.0,
+            z: 10.0,
+        };
+        power.vec_box_size = Vec3 {
+            x: 10.0,
+            y: 8.0,
+            z: 20.0,
+        };
+
+        let area = EffectAreaOutput::from_base_power(&power, 2);
+        let corners: Vec<_> = area.box_corners.iter().map(|c| (c.x, c.y, c.z)).collect();
+        assert_eq!(
+            corners,
+            vec![
+                (-5.0, 0.0, 10.0),
+                (-5.0, 0.0, 30.0),
+                (-5.0, 8.0, 10.0),
+                (-5.0, 8.0, 30.0),
+                (5.0, 0.0, 10.0),
+                (5.0, 0.0, 30.0),
+                (5.0, 8.0, 10.0),
+                (5.0, 8.0, 30.0),
+            ]
+        );
+        assert!(area.box_offset.is_some());
+
+        let area = EffectAreaOutput::from_base_power(&BasePower::new(), 2);
+        assert!(area.box_corners.is_empty());
     }
-    let mut url = String::new();
-    if let Some(base_url) = config.base_json_url.as_ref() {
-        url.push_str(base_url);
-    } else {
-        url.push_str("../../");
+
+    #[test]
+    fn activation_insight_cost_test() {
+        let mut power = BasePower::new();
+        power.f_endurance_cost = 5.2;
+        power.f_insight_cost = 2.0;
+
+        let activate = ActivationOutput::from_base_power(&power, 2);
+        assert_eq!(activate.endurance_cost, 5.2);
+        assert_eq!(activate.insight_cost, 2.0);
     }
-    url.push_str(&make_file_name(name_parts[0]));
-    url.push(URL_SEP);
-    url.push_str(&make_file_name(name_parts[1]));
-    url.push(URL_SEP);
-    if config.base_json_url.is_none() {
-        url.push_str(JSON_FILE);
+
+    #[test]
+    fn activation_endurance_per_second_test() {
+        let mut power = BasePower::new();
+        power.e_type = PowerType::kPowerType_Toggle;
+        power.f_endurance_cost = 0.26;
+        power.f_activate_period = 0.5;
+
+        let activate = ActivationOutput::from_base_power(&power, 2);
+        assert_eq!(activate.endurance_per_second, Some(0.52));
+        power.f_activate_period = 0.0;
+        let activate = ActivationOutput::from_base_power(&power, 2);
+        assert_eq!(activate.endurance_per_second, None);
+    }
+
+    #[test]
+    fn cast_cycle_seconds_test() {
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        let mut power = BasePower::new();
+        power.e_type = PowerType::kPowerType_Click;
+        power.f_time_to_activate = 1.0;
+        power.f_recharge_time = 8.0;
+        power.f_activate_period = 2.0;
+        let output = PowerOutput::from_base_power(&power, &AttribNames::new(), &config, None);
+        assert_eq!(output.cast_cycle_seconds, Some(9.0));
+
+        power.e_type = PowerType::kPowerType_Toggle;
+        let output = PowerOutput::from_base_power(&power, &AttribNames::new(), &config, None);
+        assert_eq!(output.cast_cycle_seconds, Some(2.0));
+
+        power.e_type = PowerType::kPowerType_Inspiration;
+        let output = PowerOutput::from_base_power(&power, &AttribNames::new(), &config, None);
+        assert_eq!(output.cast_cycle_seconds, None);
+        let json = serde_json::to_value(&output).unwrap();
+        assert!(json.get("cast_cycle_seconds").is_none());
+    }
+
+    #[test]
+    fn power_system_test() {
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        let power = BasePower::new();
+        let output = PowerOutput::from_base_power(&power, &AttribNames::new(), &config, None);
+        let json = serde_json::to_value(&output).unwrap();
+        assert_eq!(json["system"], "Powers");
+    }
+
+    #[test]
+    fn auto_hit_not_affected_test() {
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        let mut power = BasePower::new();
+        power.p_auto_hit.push(TargetType::kTargetType_Foe);
+        power.p_affected.push(TargetType::kTargetType_Foe);
+        power.p_affected.push(TargetType::kTargetType_Player);
+        let output = PowerOutput::from_base_power(&power, &AttribNames::new(), &config, None);
+        assert_eq!(
+            output.target_auto_hit_tags,
+            vec![TargetType::kTargetType_Foe.get_strings()]
+        );
+        assert_eq!(
+            output.target_affected_tags,
+            vec![TargetType::kTargetType_Player.get_strings()]
+        );
+        assert_eq!(output.display_target_affected.len(), 1);
+    }
+
+    #[test]
+    fn stance_test() {
+        let mut fx = PowerFX::new();
+        fx.pi_mode_bits.push(12);
+        let mut power = BasePower::new();
+        power.p_fx = Some(fx);
+
+        let stance = StanceOutput::from_base_power(&power).unwrap();
+        assert!(stance.sets_stance);
+        assert_eq!(stance.mode_bits, vec![12]);
+
+        power.b_dont_set_stance = true;
+        let stance = StanceOutput::from_base_power(&power).unwrap();
+        assert!(!stance.sets_stance);
+        assert!(StanceOutput::from_base_power(&BasePower::new()).is_none());
+    }
+
+    #[test]
+    fn modes_disallowed_test() {
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        let mut attrib_names = AttribNames::new();
+        for name in &["ServerTrayOverride", "Disable_All", "Dwarf"] {
+            let mut attrib_name = AttribName::new();
+            attrib_name.pch_name = Some(String::from(*name));
+            attrib_names.pp_mode.push(attrib_name);
+        }
+        let mut power = BasePower::new();
+        power.pe_modes_required.push(ModeAttrib(2));
+        power.pe_modes_disallowed.push(ModeAttrib(1));
+
+        let output = PowerOutput::from_base_power(&power, &attrib_names, &config, None);
+        assert_eq!(output.modes_required, vec!["Dwarf"]);
+        assert_eq!(output.modes_disallowed, vec!["Disable_All"]);
+    }
+
+    #[test]
+    fn tray_placement_test() {
+        let mut power = BasePower::new();
+        assert!(TrayPlacementOutput::from_base_power(&power).is_none());
+
+        power.i_server_tray_priority = 5;
+        power.ppch_server_tray_requires = vec![String::from("source.isPvPMap?")];
+        power.pe_modes_required.push(ModeAttrib(0));
+        let tray = TrayPlacementOutput::from_base_power(&power).unwrap();
+        assert!(tray.server_tray);
+        assert_eq!(tray.priority, 5);
+        assert_eq!(tray.requires.as_deref(), Some("source.isPvPMap?()"));
+        assert!(tray.requires_tray_override_mode);
+    }
+
+    #[test]
+    fn reward_float_message_test() {
+        let mut power = BasePower::new();
+        assert!(PowerMessagesOutput::is_empty(&PowerMessagesOutput::from_base_power(&power)));
+
+        power.pch_display_float_rewarded = Some(String::from("Got Inspired!"));
+        let messages = PowerMessagesOutput::from_base_power(&power);
+        assert!(!PowerMessagesOutput::is_empty(&messages));
+        assert_eq!(
+            serde_json::to_string(&messages).unwrap(),
+            r#"{"reward_float":"Got Inspired!"}"#
+        );
+    }
+
+    #[test]
+    fn animations_test() {
+        let mut fx = PowerFX::new();
+        fx.pch_attack_fx = Some(String::from("POWERS/Fire/FireBlast.fx"));
+        fx.pi_attack_bits = vec![3, 7];
+        fx.i_frames_attack = 30;
+        fx.pch_hit_fx = Some(String::from("POWERS/Fire/FireHit.fx"));
+        fx.i_frames_before_hit = 15;
+        let mut power = BasePower::new();
+        power.p_fx = Some(fx);
+
+        let animations = AnimationsOutput::from_base_power(&power, 2).unwrap();
+        let attack = animations.attack.unwrap();
+        assert_eq!(attack.fx.as_deref(), Some("POWERS/Fire/FireBlast.fx"));
+        assert_eq!(attack.bits, vec![3, 7]);
+        assert_eq!(attack.duration_seconds, 1.0);
+        let hit = animations.hit.unwrap();
+        assert_eq!(hit.fx.as_deref(), Some("POWERS/Fire/FireHit.fx"));
+        assert_eq!(hit.delay_seconds, 0.5);
+        assert!(animations.block.is_none());
+
+        power.p_fx = Some(PowerFX::new());
+        assert!(AnimationsOutput::from_base_power(&power, 2).is_none());
+    }
+
+    #[test]
+    fn default_tints_test() {
+        let mut fx = PowerFX::new();
+        fx.rgba_default_tint_primary = RGBA::new(255, 128, 0, 255);
+        fx.rgba_default_tint_secondary = RGBA::new(0, 0, 16, 255);
+        let mut power = BasePower::new();
+        power.p_fx = Some(fx);
+
+        let tints = DefaultTintsOutput::from_base_power(&power).unwrap();
+        assert_eq!(
+            serde_json::to_string(&tints).unwrap(),
+            r##"{"primary":"#FF8000FF","secondary":"#000010FF"}"##
+        );
+
+        power.p_fx = Some(PowerFX::new());
+        assert!(DefaultTintsOutput::from_base_power(&power).is_none());
+    }
+
+    #[test]
+    fn compact_bool_flags_test() {
+        let mut power = BasePower::new();
+        power.b_cast_through_hold = true;
+        power.b_toggle_ignore_sleep = true;
+        power.b_boost_tradeable = true;
+
+        let mut config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        let output = PowerOutput::from_base_power(&power, &AttribNames::new(), &config, None);
+        assert!(output.flags.is_empty());
+        assert!(!serde_json::to_string(&output)
+            .unwrap()
+            .contains("\"flags\""));
+
+        config.compact_bool_flags = true;
+        let output = PowerOutput::from_base_power(&power, &AttribNames::new(), &config, None);
+        assert_eq!(
+            output.flags,
+            vec!["CastThroughHold", "ToggleIgnoreSleep", "BoostTradeable"]
+        );
+        assert!(serde_json::to_string(&output)
+            .unwrap()
+            .contains(r#""flags":["CastThroughHold","ToggleIgnoreSleep","BoostTradeable"]"#));
+    }
+
+    #[test]
+    fn annotate_introduced_fields_test() {
+        let mut fx = PowerFX::new();
+        fx.b_fx_important = true;
+        fx.b_hide_original = true;
+        let mut power = BasePower::new();
+        power.p_fx = Some(fx);
+        power.b_target_untargetable = true;
+
+        let mut config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        let output = PowerOutput::from_base_power(&power, &AttribNames::new(), &config, None);
+        assert!(output.introduced_in.is_empty());
+
+        config.annotate_introduced_fields = true;
+        config.compact_bool_flags = true;
+        let output = PowerOutput::from_base_power(&power, &AttribNames::new(), &config, None);
+        let introduced: Vec<_> = output
+            .introduced_in
+            .iter()
+            .map(|(k, v)| (k.as_ref(), v.as_ref()))
+            .collect();
+        assert_eq!(
+            introduced,
+            vec![
+                ("animations.fx_important", "i26p5"),
+                ("animations.hide_original", "i26p5"),
+                ("flags.TargetUntargetable", "i27"),
+            ]
+        );
+    }
+
+    #[test]
+    fn total_damage_depth_test() {
+        let mut effect_group = EffectGroup::new();
+        effect_group.pp_effects.push(EffectGroup::new());
+        let mut power = BasePower::new();
+        power.pp_effects.push(Rc::new(RefCell::new(effect_group)));
+
+        let mut config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        assert!(
+            PowerDamageOutput::from_base_power(&power, &Vec::new(), &AttribNames::new(), &config)
+                .is_ok()
+        );
+        config.max_effect_depth = Some(1);
+        let err =
+            PowerDamageOutput::from_base_power(&power, &Vec::new(), &AttribNames::new(), &config)
+                .err()
+                .unwrap();
+        assert_eq!(err.max_depth, 1);
+    }
+
+    #[test]
+    fn number_format_test() {
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        let mut power = BasePower::new();
+        power.i_max_boosts = 6;
+        power.f_recharge_time = 12.0;
+        let output = PowerOutput::from_base_power(&power, &AttribNames::new(), &config, None);
+        let json = serde_json::to_string(&output).unwrap();
+        // integer fields never have a decimal, float fields always do
+        assert!(json.contains("\"max_boosts\":6,"));
+        assert!(json.contains("\"recharge_time\":12.0"));
+    }
+
+    #[test]
+    fn cast_ux_test() {
+        let mut power = BasePower::new();
+        power.f_interrupt_time = 1.234;
+        power.b_cancelable = true;
+        power.i_time_to_confirm = 15;
+        power.pch_display_confirm = Some(String::from("Accept teleport?"));
+
+        let cast_ux = CastUxOutput::from_base_power(&power, 2).unwrap();
+        assert_eq!(cast_ux.interrupt_time, 1.23);
+        assert!(cast_ux.cancelable);
+        let confirm = cast_ux.confirm.unwrap();
+        assert_eq!(confirm.time_seconds, 15.0);
+        assert_eq!(confirm.message.as_deref(), Some("Accept teleport?"));
+        assert!(CastUxOutput::from_base_power(&BasePower::new(), 2).is_none());
+    }
+
+    #[test]
+    fn pvp_gathers_flags_and_effects_test() {
+        let mut power = BasePower::new();
+        power.b_shoot_through_untouchable = true;
+
+        let mut elusivity = AttribModOutput::default();
+        elusivity.attributes.push(Cow::Borrowed("Melee_Elusivity"));
+        let mut child = EffectGroupOutput::default();
+        child.effects.push(elusivity);
+        let mut group = EffectGroupOutput::default();
+        group.pve_or_pvp = Some(PVP_TAG.into());
+        group.child_effect_groups.push(child);
+        let groups = vec![group, EffectGroupOutput::default()];
+
+        let pvp = PvpOutput::from_base_power(&power, &groups).unwrap();
+        assert!(pvp.shoot_through_untouchable);
+        assert!(!pvp.targets_through_vision_phase);
+        assert_eq!(pvp.pvp_only_effect_groups, 1);
+        assert_eq!(pvp.pve_only_effect_groups, 0);
+        assert_eq!(pvp.elusivity_attributes, vec!["Melee_Elusivity"]);
+    }
+
+    #[test]
+    fn league_test() {
+        let mut power = BasePower::new();
+        power.b_refreshes_on_active_player_change = true;
+        power.e_target_type = TargetType::kTargetType_Leaguemate;
+        power.p_affected.push(TargetType::kTargetType_Leaguemate);
+        power.p_affected.push(TargetType::kTargetType_DeadOrAliveLeaguemate);
+
+        let league = LeagueOutput::from_base_power(&power).unwrap();
+        assert!(league.refreshes_on_active_player_change);
+        assert_eq!(
+            league.leaguemate_targets,
+            vec!["Leaguemate", "DeadOrAliveLeaguemate"]
+        );
+        assert!(LeagueOutput::from_base_power(&BasePower::new()).is_none());
+    }
+
+    #[test]
+    fn pvp_not_relevant_test() {
+        let power = BasePower::new();
+        assert!(PvpOutput::from_base_power(&power, &Vec::new()).is_none());
+    }
+
+    #[test]
+    fn pvp_pve_only_groups_test() {
+        // effects left out of PvP matter for PvP too
+        let mut group = EffectGroupOutput::default();
+        group.pve_or_pvp = Some(PVE_TAG.into());
+        let pvp = PvpOutput::from_base_power(&BasePower::new(), &vec![group]).unwrap();
+        assert_eq!(pvp.pvp_only_effect_groups, 0);
+        assert_eq!(pvp.pve_only_effect_groups, 1);
+    }
+
+    #[test]
+    fn target_selection_single_target_test() {
+        let mut power = BasePower::new();
+        power.i_max_targets_hit = 1;
+        assert!(TargetSelectionOutput::from_base_power(&power, 2).is_none());
+    }
+
+    fn scaled_effect(scaled_effect: ScaledUnit) -> AttribModScaled {
+        AttribModScaled {
+            archetype: None,
+            scaled_effect,
+            average: 0.0,
+            per_activation: 0.0,
+            per_cast_cycle: 0.0,
+            display_info: Vec::new(),
+            style: None,
+            display_value: None,
+            base_value: 0.0,
+            scale: 0.0,
+            damage_scale: 0.0,
+            permanent: false,
+        }
+    }
+
+    #[test]
+    fn role_attack_test() {
+        let mut power = BasePower::new();
+        power.e_target_type = TargetType::kTargetType_Foe;
+
+        let mut damage = AttribModOutput::default();
+        damage.target_type = Some("Target".into());
+        damage.attributes.push(Cow::Borrowed("Smashing_Dmg"));
+        damage.scaled.push(scaled_effect(ScaledUnit::Damage(50.0)));
+        let mut group = EffectGroupOutput::default();
+        group.effects.push(damage);
+
+        assert_eq!(classify_role(&power, &vec![group]), Some("Attack"));
+    }
+
+    #[test]
+    fn role_self_heal_test() {
+        let mut power = BasePower::new();
+        power.e_target_type = TargetType::kTargetType_Caster;
+
+        let mut heal = AttribModOutput::default();
+        heal.target_type = Some("Self".into());
+        heal.attributes.push(Cow::Borrowed("HitPoints"));
+        heal.scaled.push(scaled_effect(ScaledUnit::Healing(200.0)));
+        let mut group = EffectGroupOutput::default();
+        group.effects.push(heal);
+
+        assert_eq!(classify_role(&power, &vec![group]), Some("Heal"));
+        assert_eq!(classify_role(&power, &Vec::new()), None);
+    }
+
+    #[test]
+    fn effect_area_cone_test() {
+        let mut power = BasePower::new();
+        power.e_effect_area = EffectArea::kEffectArea_Cone;
+        power.f_radius = 40.0;
+        power.f_arc = std::f32::consts::FRAC_PI_2;
+        power.i_max_targets_hit = 10;
+
+        let area = EffectAreaOutput::from_base_power(&power, 2);
+        assert_eq!(area.area.as_deref(), Some("Cone"));
+        assert_eq!(area.radius_feet, 40.0);
+        assert_eq!(area.arc_degrees, 90.0);
+        assert_eq!(area.max_targets_hit, 10);
+        assert!(area.max_targets_expression.is_none());
+    }
+
+    #[test]
+    fn effect_area_sphere_test() {
+        let mut power = BasePower::new();
+        power.e_effect_area = EffectArea::kEffectArea_Sphere;
+        power.f_radius = 15.0;
+        power.ppch_max_targets_expr = vec![String::from("5"), String::from("3"), String::from("+")];
+
+        let area = EffectAreaOutput::from_base_power(&power, 2);
+        assert_eq!(area.area.as_deref(), Some("AoE"));
+        assert_eq!(area.radius_feet, 15.0);
+        assert_eq!(area.max_targets_expression.as_deref(), Some("5 + 3"));
+
+        let area = EffectAreaOutput::from_base_power(&BasePower::new(), 2);
+        assert_eq!(area.area.as_deref(), Some("SingleTarget"));
+    }
+
+    #[test]
+    fn effect_area_chain_test() {
+        let mut power = BasePower::new();
+        power.e_effect_area = EffectArea::kEffectArea_Chain;
+        power.f_radius = 20.0;
+        power.i_max_targets_hit = 5;
+        power.f_chain_delay = 0.25;
+
+        let area = EffectAreaOutput::from_base_power(&power, 2);
+        assert_eq!(area.area.as_deref(), Some("Chain"));
+        assert_eq!(area.radius_feet, 0.0);
+        assert_eq!(area.jump_distance_feet, 20.0);
+        assert_eq!(area.chain_delay_time, 0.25);
+        assert_eq!(area.max_targets_hit, 5);
+    }
+
+    #[test]
+    fn area_summary_cone_test() {
+        let mut power = BasePower::new();
+        power.e_effect_area = EffectArea::kEffectArea_Cone;
+        power.f_radius = 40.0;
+        power.f_arc = std::f32::consts::FRAC_PI_2;
+        power.i_max_targets_hit = 10;
+        power.f_chain_delay = 0.5;
+
+        let summary = round_area_summary(power.area_summary(), 2);
+        assert_eq!(summary.area, "Cone");
+        assert!(summary.is_aoe);
+        assert_eq!(summary.radius_feet, 40.0);
+        assert_eq!(summary.arc_degrees, Some(90.0));
+        assert_eq!(summary.max_targets_hit, 10);
+        assert!(summary.max_targets_expression.is_none());
+        assert!(summary.chain_delay_time.is_none());
+        assert!(summary.chain_fork.is_none());
+    }
+
+    #[test]
+    fn area_summary_sphere_test() {
+        let mut power = BasePower::new();
+        power.e_effect_area = EffectArea::kEffectArea_Sphere;
+        power.f_radius = 15.0;
+        power.f_arc = 1.0;
+        power.ppch_max_targets_expr = vec![String::from("5"), String::from("3"), String::from("+")];
+
+        let summary = power.area_summary();
+        assert_eq!(summary.area, "AoE");
+        assert!(summary.is_aoe);
+        assert_eq!(summary.radius_feet, 15.0);
+        assert!(summary.arc_degrees.is_none());
+        assert_eq!(summary.max_targets_expression.as_deref(), Some("5 + 3"));
+
+        let summary = BasePower::new().area_summary();
+        assert_eq!(summary.area, "SingleTarget");
+        assert!(!summary.is_aoe);
+
+        // "Self" powers only affect the caster
+        power.e_effect_area = EffectArea::kEffectArea_Volume;
+        assert!(!power.area_summary().is_aoe);
+    }
+
+    #[test]
+    fn area_summary_chain_test() {
+        let mut power = BasePower::new();
+        power.e_effect_area = EffectArea::kEffectArea_Chain;
+        power.f_radius = 20.0;
+        power.i_max_targets_hit = 5;
+        power.f_chain_delay = 0.25;
+        power.pi_chain_fork = vec![1, 3];
+
+        let summary = power.area_summary();
+        assert_eq!(summary.area, "Chain");
+        assert!(summary.is_aoe);
+        assert_eq!(summary.chain_delay_time, Some(0.25));
+        assert_eq!(summary.chain_fork, Some(vec![1, 3]));
+        assert_eq!(summary.max_targets_hit, 5);
+        let json = serde_json::to_value(&summary).unwrap();
+        assert!(json.get("arc_degrees").is_none());
+    }
+
+    #[test]
+    fn effect_summary_test() {
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        let mut attrib_names = AttribNames::new();
+        for name in &["Smashing", "Fire"] {
+            let mut attrib_name = AttribName::new();
+            attrib_name.pch_display_name = Some(String::from(*name));
+            attrib_names.pp_damage.push(attrib_name);
+            let mut attrib_name = AttribName::new();
+            attrib_name.pch_display_name = Some(String::from(*name));
+            attrib_names.pp_defense.push(attrib_name);
+        }
+        let mut at = Archetype::new();
+        let mut table = NamedTable::new();
+        table.pf_values = vec![-62.56; 50];
+        at.pp_named_tables.insert(String::from("ranged_damage"), table);
+        let mut table = NamedTable::new();
+        table.pf_values = vec![0.1; 50];
+        at.pp_named_tables.insert(String::from("ranged_debuff_def"), table);
+
+        let mut damage = AttribModTemplate::new();
+        damage.p_attrib.push(CharacterAttrib(CharacterAttributes::OFFSET_DMG_0 as i32 + 4));
+        damage.pch_table = Some(String::from("Ranged_Damage"));
+        damage.f_scale = 1.0;
+        let mut debuff = AttribModTemplate::new();
+        debuff.p_attrib.push(CharacterAttrib(CharacterAttributes::OFFSET_DEF_0 as i32));
+        debuff.p_attrib.push(CharacterAttrib(CharacterAttributes::OFFSET_DEF_0 as i32 + 4));
+        debuff.pch_table = Some(String::from("Ranged_Debuff_Def"));
+        debuff.f_scale = -0.5;
+        let mut effect = EffectGroup::new();
+        effect.f_chance = 1.0;
+        effect.pp_templates.push(damage);
+        effect.pp_templates.push(debuff);
+
+        let mut power = BasePower::new();
+        power.e_target_type = TargetType::kTargetType_Foe;
+        power.f_range = 80.0;
+        power.archetypes.push(Rc::new(RefCell::new(at)));
+        power.pp_effects.push(Rc::new(RefCell::new(effect)));
+
+        let output = PowerOutput::from_base_power(&power, &attrib_names, &config, None);
+        let summary = output.effect_summary.unwrap();
+        assert!(summary.starts_with("Ranged, "));
+        assert!(summary.contains("DMG(Fire)"));
+        assert_eq!(summary, "Ranged, Moderate DMG(Fire), Foe -Defense");
+
+        power.f_range = 7.0;
+        let output = PowerOutput::from_base_power(&power, &attrib_names, &config, None);
+        assert!(output.effect_summary.unwrap().starts_with("Melee, "));
+    }
+
+    #[test]
+    fn inline_redirects_test() {
+        let mut config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        config.inline_redirects = true;
+        let attrib_names = AttribNames::new();
+        let make_power = |name: &str, display_name: &str, target: &str| {
+            let mut power = BasePower::new();
+            power.pch_full_name = Some(NameKey::new(name));
+            power.pch_display_name = Some(String::from(display_name));
+            let mut redirect = PowerRedirect::new();
+            redirect.pch_name = Some(NameKey::new(target));
+            redirect.b_show_in_info = true;
+            power.pp_redirect.push(redirect);
+            Rc::new(RefCell::new(power))
+        };
+        // A -> B -> C -> A, and D -> D
+        let mut pset = BasePowerSet::new();
+        pset.pch_full_name = Some(NameKey::new("Pool.Test"));
+        pset.pp_powers.push(make_power("Pool.Test.A", "Power A", "Pool.Test.B"));
+        pset.pp_powers.push(make_power("Pool.Test.B", "Power B", "Pool.Test.C"));
+        pset.pp_powers.push(make_power("Pool.Test.C", "Power C", "Pool.Test.A"));
+        pset.pp_powers.push(make_power("Pool.Test.D", "Power D", "Pool.Test.D"));
+        pset.pp_powers[1].borrow_mut().ppch_buy_requires = vec![String::from("source.Mode?")];
+        let mut pcat = PowerCategory::new();
+        pcat.pch_name = Some(NameKey::new("Pool"));
+        pcat.pp_power_sets.push(Rc::new(RefCell::new(pset)));
+        let powers_dict = PowersDictionary::from_power_categories(vec![Rc::new(RefCell::new(pcat))]);
+
+        let power_a = powers_dict.find_power(&NameKey::new("Pool.Test.A")).unwrap();
+        let output =
+            PowerOutput::from_base_power(&*power_a.borrow(), &attrib_names, &config, Some(&powers_dict));
+        let target_b = output.redirects[0].target.as_ref().unwrap();
+        assert_eq!(target_b.display_name.as_deref(), Some("Power B"));
+        assert_eq!(target_b.requires.as_deref(), Some("source.Mode?()"));
+        let target_c = target_b.redirects[0].target.as_ref().unwrap();
+        assert_eq!(target_c.display_name.as_deref(), Some("Power C"));
+        // C redirects back to A, which is already being expanded
+        assert!(target_c.redirects[0].target.is_none());
+
+        let power_d = powers_dict.find_power(&NameKey::new("Pool.Test.D")).unwrap();
+        let output =
+            PowerOutput::from_base_power(&*power_d.borrow(), &attrib_names, &config, Some(&powers_dict));
+        assert!(output.redirects[0].target.is_none());
+
+        config.inline_redirects = false;
+        let output =
+            PowerOutput::from_base_power(&*power_a.borrow(), &attrib_names, &config, Some(&powers_dict));
+        assert!(output.redirects[0].target.is_none());
     }
-    Some(url)
 }
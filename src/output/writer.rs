@@ -0,0 +1,189 @@
+use crate::structs::config::LineEndingConfig;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+
+/// The UTF-8 byte order mark, which should never appear in output files.
+const UTF8_BOM: &'static [u8] = &[0xEF, 0xBB, 0xBF];
+
+/// Wraps a `Write` so that everything written through it uses consistent line endings
+/// and never starts with a byte order mark.
+///
+/// Carriage returns are always dropped from the input stream, and each line feed is then
+/// written as either `\n` or `\r\n` depending on `line_endings`. This is safe for JSON
+/// because line breaks inside of strings are always escaped.
+///
+/// Bytes at the start of the stream that might be a BOM are held back until it's clear whether
+/// they are one, so call `flush` before `into_inner`.
+pub struct NormalizedWriter<W: Write> {
+    inner: W,
+    line_endings: LineEndingConfig,
+    /// Bytes at the start of the stream that match the start of a BOM so far.
+    bom_prefix: Vec<u8>,
+    /// Set once the start of the stream has been checked for a BOM.
+    bom_checked: bool,
+}
+
+impl<W: Write> NormalizedWriter<W> {
+    /// Creates a new `NormalizedWriter` around `inner`.
+    pub fn new(inner: W, line_endings: LineEndingConfig) -> Self {
+        NormalizedWriter {
+            inner,
+            line_endings,
+            bom_prefix: Vec::with_capacity(UTF8_BOM.len()),
+            bom_checked: false,
+        }
+    }
+
+    /// Writes `data` to the inner writer, converting the line endings.
+    fn write_normalized(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut out = Vec::with_capacity(data.len());
+        for &b in data {
+            match b {
+                b'\r' => (),
+                b'\n' => {
+                    if matches!(self.line_endings, LineEndingConfig::Crlf) {
+                        out.push(b'\r');
+                    }
+                    out.push(b'\n');
+                }
+                _ => out.push(b),
+            }
+        }
+        self.inner.write_all(&out)
+    }
+
+    /// Stops checking for a BOM, writing out any bytes that were held back.
+    fn finish_bom_check(&mut self) -> io::Result<()> {
+        self.bom_checked = true;
+        let prefix = std::mem::take(&mut self.bom_prefix);
+        self.write_normalized(&prefix)
+    }
+
+    /// Consumes the writer, returning the wrapped `Write`.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for NormalizedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut data = buf;
+        // strip a BOM if one is written at the very start of the stream, which may be split
+        // across several writes
+        while !self.bom_checked && !data.is_empty() {
+            if data[0] == UTF8_BOM[self.bom_prefix.len()] {
+                self.bom_prefix.push(data[0]);
+                data = &data[1..];
+                if self.bom_prefix.len() == UTF8_BOM.len() {
+                    self.bom_prefix.clear();
+                    self.bom_checked = true;
+                }
+            } else {
+                // not a BOM, so keep whatever matched so far
+                self.finish_bom_check()?;
+            }
+        }
+        self.write_normalized(data)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.bom_prefix.is_empty() {
+            // the stream can't be a BOM if it's cut short here
+            self.finish_bom_check()?;
+        }
+        self.inner.flush()
+    }
+}
+
+/// Creates (or truncates) the text file at `path`, wrapped so that everything written to it
+/// uses `line_endings`.
+///
+/// # Notes:
+///
+/// Binary formats (MessagePack, SQLite) must not be written through this, since carriage returns
+/// are dropped from the stream.
+pub fn create_output_file(
+    path: &Path,
+    line_endings: LineEndingConfig,
+) -> io::Result<NormalizedWriter<io::BufWriter<File>>> {
+    let f = File::create(path)?;
+    Ok(NormalizedWriter::new(io::BufWriter::new(f), line_endings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn normalized_writer_lf_test() {
+        let value = json!({ "name": "test", "values": [1, 2, 3] });
+        let mut writer = NormalizedWriter::new(Vec::new(), LineEndingConfig::Lf);
+        serde_json::to_writer_pretty(&mut writer, &value).unwrap();
+        let output = writer.into_inner();
+        assert!(output.contains(&b'\n'));
+        assert!(!output.contains(&b'\r'));
+    }
+
+    #[test]
+    fn normalized_writer_crlf_test() {
+        let value = json!({ "name": "test", "values": [1, 2, 3] });
+        let mut writer = NormalizedWriter::new(Vec::new(), LineEndingConfig::Crlf);
+        serde_json::to_writer_pretty(&mut writer, &value).unwrap();
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(output.contains("\r\n"));
+        assert_eq!(output.matches('\n').count(), output.matches("\r\n").count());
+    }
+
+    #[test]
+    fn normalized_writer_bom_test() {
+        let mut writer = NormalizedWriter::new(Vec::new(), LineEndingConfig::Lf);
+        writer.write_all(b"\xEF\xBB\xBF{}\r\n").unwrap();
+        assert_eq!(writer.into_inner(), b"{}\n");
+    }
+
+    #[test]
+    fn normalized_writer_split_bom_test() {
+        let mut writer = NormalizedWriter::new(Vec::new(), LineEndingConfig::Lf);
+        writer.write_all(b"\xEF").unwrap();
+        writer.write_all(b"\xBB\xBF{}").unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.into_inner(), b"{}");
+    }
+
+    #[test]
+    fn normalized_writer_partial_bom_test() {
+        let mut writer = NormalizedWriter::new(Vec::new(), LineEndingConfig::Lf);
+        writer.write_all(b"\xEF\xBB").unwrap();
+        writer.write_all(b"\x41\r\n").unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.into_inner(), b"\xEF\xBB\x41\n");
+
+        // cut short before the rest of the BOM
+        let mut writer = NormalizedWriter::new(Vec::new(), LineEndingConfig::Lf);
+        writer.write_all(b"\xEF\xBB").unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.into_inner(), b"\xEF\xBB");
+    }
+
+    #[test]
+    fn create_output_file_test() {
+        let output_file = std::env::temp_dir().join(format!(
+            "powers_line_endings_test_{}.dot",
+            std::process::id()
+        ));
+        let mut w = create_output_file(&output_file, LineEndingConfig::Crlf).unwrap();
+        writeln!(w, "digraph powers {{").unwrap();
+        writeln!(w, "}}").unwrap();
+        w.flush().unwrap();
+        drop(w);
+        assert_eq!(
+            std::fs::read(&output_file).unwrap(),
+            b"digraph powers {\r\n}\r\n"
+        );
+        std::fs::remove_file(&output_file).unwrap();
+    }
+}
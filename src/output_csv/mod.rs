@@ -0,0 +1,175 @@
+use crate::output::{create_output_file, round_to, OutputError};
+use crate::structs::config::PowersConfig;
+use crate::structs::*;
+use log::info;
+use std::collections::HashMap;
+use std::fs;
+use std::io::prelude::*;
+use std::path::Path;
+
+/// Name of the .csv file written to the output path.
+const CSV_FILE: &'static str = "powers.csv";
+
+/// Column headers, in the order the fields are written.
+const CSV_HEADERS: &'static [&'static str] = &[
+    "full_name",
+    "display_name",
+    "power_type",
+    "accuracy",
+    "recharge_time",
+    "endurance_cost",
+    "range_feet",
+    "radius_feet",
+    "arc_degrees",
+    "max_targets_hit",
+    "available_at_level",
+];
+
+/// Writes every power in the powers dictionary to a single .csv file, one row per power.
+///
+/// # Arguments:
+///
+/// * `powers_dict` - A `PowersDictionary` containing a hierarchy of categories, power sets, and powers.
+/// * `config` - Configuration information.
+///
+/// # Returns:
+///
+/// Nothing if the operation was successful. Otherwise, an `OutputError` containing the error information.
+///
+/// # Notes:
+///
+/// Only categories, power sets, and powers marked `include_in_output` are written. Numbers are
+/// rounded the same way as the JSON output.
+pub fn write_powers_csv(
+    powers_dict: &PowersDictionary,
+    config: &PowersConfig,
+) -> Result<(), OutputError> {
+    fs::create_dir_all(&config.output_path)
+        .map_err(OutputError::path(Path::new(&config.output_path)))?;
+    let output_file = config.join_to_output_path(CSV_FILE);
+    info!("Writing: {} ...", output_file.display());
+    let f = create_output_file(&output_file, config.line_endings)
+        .map_err(OutputError::io(&output_file))?;
+    let row_count = write_power_rows(powers_dict, f, config).map_err(|e| {
+        if e.is_io_error() {
+            match e.into_kind() {
+                csv::ErrorKind::Io(source) => OutputError::io(&output_file)(source),
+                _ => unreachable!(),
+            }
+        } else {
+            OutputError::serialize(&output_file)(e)
+        }
+    })?;
+    info!("{} powers written.", row_count);
+    Ok(())
+}
+
+/// Writes the header and one row per power to `writer`.
+///
+/// # Returns:
+///
+/// The number of powers written.
+fn write_power_rows<W: Write>(
+    powers_dict: &PowersDictionary,
+    writer: W,
+    config: &PowersConfig,
+) -> csv::Result<usize> {
+    // line endings are handled by the output file's `NormalizedWriter`
+    let mut csv_writer = csv::WriterBuilder::new()
+        .terminator(csv::Terminator::Any(b'\n'))
+        .from_writer(writer);
+    csv_writer.write_record(CSV_HEADERS)?;
+    let mut row_count = 0;
+    for power_set in powers_dict.iter_power_sets(true) {
+        let power_set = power_set.borrow();
+        let powers_to_levels: HashMap<_, _> = power_set.available_levels().collect();
+        for power in power_set.pp_powers.iter().map(|p| p.borrow()) {
+            if !power.include_in_output {
+                continue;
+            }
+            let available_at_level = power
+                .pch_full_name
+                .as_ref()
+                .and_then(|name| powers_to_levels.get(name))
+                .copied()
+                .unwrap_or(0);
+            csv_writer.write_record(&[
+                power
+                    .pch_full_name
+                    .as_ref()
+                    .map(|name| name.get())
+                    .unwrap_or_default(),
+                power.pch_display_name.as_deref().unwrap_or_default(),
+                power.e_type.get_string(),
+                &round_to(power.f_accuracy, config.decimal_places).to_string(),
+                &round_to(power.f_recharge_time, config.decimal_places).to_string(),
+                &round_to(power.f_endurance_cost, config.decimal_places).to_string(),
+                &round_to(power.f_range, config.decimal_places).to_string(),
+                &round_to(power.f_radius, config.decimal_places).to_string(),
+                &round_to(power.f_arc.to_degrees(), config.decimal_places).to_string(),
+                &power.i_max_targets_hit.to_string(),
+                &available_at_level.to_string(),
+            ])?;
+            row_count += 1;
+        }
+    }
+    csv_writer.flush()?;
+    Ok(row_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::config::MINIMAL_CONFIG;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn write_power_rows_test() {
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        let make_power = |name: &str, display_name: &str, include_in_output| {
+            let mut power = BasePower::new();
+            power.pch_full_name = Some(NameKey::new(name));
+            power.pch_display_name = Some(String::from(display_name));
+            power.f_accuracy = 1.2;
+            power.f_recharge_time = 8.0;
+            power.f_endurance_cost = 8.528;
+            power.f_range = 80.0;
+            power.include_in_output = include_in_output;
+            Rc::new(RefCell::new(power))
+        };
+        let mut pset = BasePowerSet::new();
+        pset.include_in_output = true;
+        pset.pp_power_names = vec![
+            NameKey::new("Blaster_Ranged.Fire_Blast.Fire_Blast"),
+            NameKey::new("Blaster_Ranged.Fire_Blast.Hidden"),
+        ];
+        pset.pi_available = vec![1, 0];
+        pset.pp_powers.push(make_power(
+            "Blaster_Ranged.Fire_Blast.Fire_Blast",
+            "Fire Blast, \"Hot\"",
+            true,
+        ));
+        pset.pp_powers.push(make_power(
+            "Blaster_Ranged.Fire_Blast.Hidden",
+            "Hidden",
+            false,
+        ));
+        let mut pcat = PowerCategory::new();
+        pcat.include_in_output = true;
+        pcat.pp_power_sets.push(Rc::new(RefCell::new(pset)));
+        let powers_dict = PowersDictionary::from_power_categories(vec![Rc::new(RefCell::new(pcat))]);
+
+        let mut output = Vec::new();
+        let row_count = write_power_rows(&powers_dict, &mut output, &config).unwrap();
+        assert_eq!(row_count, 1);
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines[0], CSV_HEADERS.join(","));
+        assert_eq!(
+            lines[1],
+            r#"Blaster_Ranged.Fire_Blast.Fire_Blast,"Fire Blast, ""Hot""",Click,1.2,8,8.53,80,0,0,0,2"#
+        );
+        assert_eq!(lines.len(), 2);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::output::{create_output_file, OutputError};
+use crate::structs::config::PowersConfig;
+use crate::structs::*;
+use log::info;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+
+/// Name of the .dot file written to the output path.
+const DOT_FILE: &'static str = "hierarchy.dot";
+
+/// Writes the power hierarchy to a GraphViz .dot file in the output path.
+///
+/// # Arguments:
+///
+/// * `powers_dict` - A `PowersDictionary` containing a hierarchy of categories, power sets, and powers.
+/// * `config` - Configuration information.
+///
+/// # Returns:
+///
+/// Nothing if the operation was successful. Otherwise, an `OutputError` containing the error information.
+pub fn write_powers_dot(
+    powers_dict: &PowersDictionary,
+    config: &PowersConfig,
+) -> Result<(), OutputError> {
+    fs::create_dir_all(&config.output_path)
+        .map_err(OutputError::path(Path::new(&config.output_path)))?;
+    let output_file = config.join_to_output_path(DOT_FILE);
+    info!("Writing: {} ...", output_file.display());
+    let mut w = create_output_file(&output_file, config.line_endings)
+        .map_err(OutputError::io(&output_file))?;
+    write_hierarchy_dot(powers_dict, &mut w).map_err(OutputError::io(&output_file))?;
+    w.flush().map_err(OutputError::io(&output_file))
+}
+
+/// Writes the category -> power set -> power hierarchy as a GraphViz DOT digraph.
+///
+/// # Arguments:
+///
+/// * `powers_dict` - A `PowersDictionary` containing a hierarchy of categories, power sets, and powers.
+/// * `w` - Where to write the graph.
+///
+/// # Returns:
+///
+/// Nothing if the operation was successful. Otherwise, an `io::Error` containing the error information.
+///
+/// # Notes:
+///
+/// Only objects marked `include_in_output` are written. Nodes are identified by their lowercased
+/// full names, and power redirects are drawn as dashed edges to the redirect target.
+pub fn write_hierarchy_dot(powers_dict: &PowersDictionary, w: &mut impl Write) -> io::Result<()> {
+    writeln!(w, "digraph powers {{")?;
+    writeln!(w, "    rankdir=LR;")?;
+    for power_cat in powers_dict.power_categories.iter().map(|p| p.borrow()) {
+        if !power_cat.include_in_output {
+            continue;
+        }
+        let cat_id = node_id(power_cat.pch_name.as_ref());
+        let cat_label = node_label(
+            power_cat.pch_display_name.as_ref(),
+            power_cat.pch_name.as_ref().map(|n| n.get()),
+        );
+        writeln!(w, "    {} [label={}, shape=folder];", cat_id, cat_label)?;
+        for power_set in power_cat.pp_power_sets.iter().map(|p| p.borrow()) {
+            if !power_set.include_in_output {
+                continue;
+            }
+            let set_id = node_id(power_set.pch_full_name.as_ref());
+            let set_label = node_label(
+                power_set.pch_display_name.as_ref(),
+                power_set.pch_name.as_deref(),
+            );
+            writeln!(w, "    {} [label={}, shape=box];", set_id, set_label)?;
+            writeln!(w, "    {} -> {};", cat_id, set_id)?;
+            for power in power_set.pp_powers.iter().map(|p| p.borrow()) {
+                if !power.include_in_output {
+                    continue;
+                }
+                let power_id = node_id(power.pch_full_name.as_ref());
+                let power_label =
+                    node_label(power.pch_display_name.as_ref(), power.pch_name.as_deref());
+                writeln!(w, "    {} [label={}];", power_id, power_label)?;
+                writeln!(w, "    {} -> {};", set_id, power_id)?;
+                for redirect in &power.pp_redirect {
+                    if let Some(target) = &redirect.pch_name {
+                        writeln!(
+                            w,
+                            "    {} -> {} [style=dashed];",
+                            power_id,
+                            node_id(Some(target))
+                        )?;
+                    }
+                }
+            }
+        }
+    }
+    writeln!(w, "}}")
+}
+
+/// Creates a quoted DOT node ID from a full name.
+fn node_id(name: Option<&NameKey>) -> String {
+    quote(&name.map(|n| n.get().to_lowercase()).unwrap_or_default())
+}
+
+/// Creates a quoted DOT label from the display name, falling back to the internal name.
+fn node_label(display_name: Option<&String>, name: Option<&str>) -> String {
+    quote(display_name.map(|s| &s[..]).or(name).unwrap_or_default())
+}
+
+/// Quotes `s` as a DOT string.
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn write_hierarchy_dot_test() {
+        let make_power = |name: &str, display_name: Option<&str>, include_in_output| {
+            let mut power = BasePower::new();
+            power.pch_name = name.rsplit('.').next().map(String::from);
+            power.pch_full_name = Some(NameKey::new(name));
+            power.pch_display_name = display_name.map(String::from);
+            power.include_in_output = include_in_output;
+            power
+        };
+        let mut pistols = make_power("Blaster_Ranged.Dual_Pistols.Pistols", Some("Pistols"), true);
+        let mut redirect = PowerRedirect::new();
+        redirect.pch_name = Some(NameKey::new("Blaster_Ranged.Dual_Pistols.Pistols_Fire"));
+        pistols.pp_redirect.push(redirect);
+        let mut pset = BasePowerSet::new();
+        pset.pch_name = Some(String::from("Dual_Pistols"));
+        pset.pch_full_name = Some(NameKey::new("Blaster_Ranged.Dual_Pistols"));
+        pset.include_in_output = true;
+        pset.pp_powers.push(Rc::new(RefCell::new(pistols)));
+        pset.pp_powers.push(Rc::new(RefCell::new(make_power(
+            "Blaster_Ranged.Dual_Pistols.Pistols_Fire",
+            None,
+            true,
+        ))));
+        pset.pp_powers.push(Rc::new(RefCell::new(make_power(
+            "Blaster_Ranged.Dual_Pistols.Hidden",
+            None,
+            false,
+        ))));
+        let mut pcat = PowerCategory::new();
+        pcat.pch_name = Some(NameKey::new("Blaster_Ranged"));
+        pcat.pch_display_name = Some(String::from("Blaster \"Ranged\""));
+        pcat.include_in_output = true;
+        pcat.pp_power_sets.push(Rc::new(RefCell::new(pset)));
+        let powers_dict = PowersDictionary::from_power_categories(vec![Rc::new(RefCell::new(pcat))]);
+
+        let mut output = Vec::new();
+        write_hierarchy_dot(&powers_dict, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("digraph powers {"));
+        assert!(output.contains(r#""blaster_ranged" [label="Blaster \"Ranged\"", shape=folder];"#));
+        assert!(
+            output.contains(r#""blaster_ranged.dual_pistols" [label="Dual_Pistols", shape=box];"#)
+        );
+        assert!(output.contains(r#""blaster_ranged" -> "blaster_ranged.dual_pistols";"#));
+        assert!(output.contains(
+            r#""blaster_ranged.dual_pistols" -> "blaster_ranged.dual_pistols.pistols";"#
+        ));
+        assert!(output
+            .contains(r#""blaster_ranged.dual_pistols.pistols_fire" [label="Pistols_Fire"];"#));
+        assert!(output.contains(
+            r#""blaster_ranged.dual_pistols.pistols" -> "blaster_ranged.dual_pistols.pistols_fire" [style=dashed];"#
+        ));
+        assert!(!output.contains("hidden"));
+        assert!(output.trim_end().ends_with('}'));
+    }
+}
@@ -0,0 +1,259 @@
+use crate::output::{create_output_file, make_file_name, make_file_name_opt};
+use crate::output::{prepare_output_path, OutputError, PowerSetOutput};
+use crate::output::{AttribModOutput, EffectGroupOutput, PowerOutput};
+use crate::structs::config::PowersConfig;
+use crate::structs::*;
+use log::info;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+
+/// Extension for the Markdown files.
+const MD_EXT: &'static str = "md";
+
+/// Writes a Markdown page for every power in the powers dictionary.
+///
+/// # Arguments:
+///
+/// * `powers_dict` - A `PowersDictionary` containing a hierarchy of categories, power sets, and powers.
+/// * `config` - Configuration information.
+///
+/// # Returns:
+///
+/// Nothing if the operation was successful. Otherwise, an `OutputError` containing the error information.
+///
+/// # Notes:
+///
+/// Each power set gets the same folder as in the "api" format, with one `<power>.md` file per
+/// power instead of an `index.json`.
+pub fn write_markdown(
+    powers_dict: &PowersDictionary,
+    config: &PowersConfig,
+) -> Result<(), OutputError> {
+    // setup the output directory
+    prepare_output_path(config).map_err(OutputError::path(Path::new(&config.output_path)))?;
+
+    for category in powers_dict.power_categories.iter().map(|c| c.borrow()) {
+        if !category.include_in_output {
+            continue;
+        }
+        if let Some(pcat_name) = category.pch_name.as_ref() {
+            let category_path =
+                config.join_to_output_path(&make_file_name_opt(Some(pcat_name.get_string())));
+            for set in category.pp_power_sets.iter().map(|p| p.borrow()) {
+                if !set.include_in_output {
+                    continue;
+                }
+                let output_path = category_path.join(&make_file_name_opt(set.pch_name.as_ref()));
+                fs::create_dir_all(&output_path).map_err(OutputError::io(&output_path))?;
+                let pset = PowerSetOutput::from_base_power_set(
+                    &*set,
+                    &powers_dict.attrib_names,
+                    config,
+                    Some(powers_dict),
+                );
+                for power in pset.powers() {
+                    // the file is named after the last part of the full name, like a power set's folder
+                    let power_name = match power.name.as_ref() {
+                        Some(name) => name.get().rsplit('.').next().unwrap_or_default(),
+                        None => continue,
+                    };
+                    let output_file = output_path
+                        .join(make_file_name(power_name))
+                        .with_extension(MD_EXT);
+                    info!("\tWriting: {} ...", output_file.display());
+                    let mut f = create_output_file(&output_file, config.line_endings)
+                        .map_err(OutputError::io(&output_file))?;
+                    write_power_markdown(power, &mut f).map_err(OutputError::io(&output_file))?;
+                    f.flush().map_err(OutputError::io(&output_file))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes a Markdown page describing `power`.
+///
+/// # Arguments:
+///
+/// * `power` - A `PowerOutput`.
+/// * `w` - Where to write the page.
+///
+/// # Returns:
+///
+/// Nothing if the operation was successful. Otherwise, an `io::Error` containing the error information.
+///
+/// # Notes:
+///
+/// The page has a heading with the display name, a table of the basic stats, a list of the
+/// effects as described in the power info, and the purchase requirements (if any) in a code block.
+pub fn write_power_markdown(power: &PowerOutput, w: &mut impl Write) -> io::Result<()> {
+    let title = power
+        .display_name
+        .as_deref()
+        .or(power.name.as_ref().map(|n| n.get()))
+        .unwrap_or_default();
+    writeln!(w, "# {}", title)?;
+    if let Some(help) = &power.display_short_help {
+        writeln!(w)?;
+        writeln!(w, "{}", help)?;
+    }
+
+    writeln!(w)?;
+    writeln!(w, "| Stat | Value |")?;
+    writeln!(w, "| --- | --- |")?;
+    let rows = [
+        (
+            "Type",
+            power.power_type.as_deref().unwrap_or_default().to_owned(),
+        ),
+        ("Accuracy", power.accuracy.to_string()),
+        ("Recharge", format!("{}s", power.activate.recharge_time)),
+        ("Endurance", power.activate.endurance_cost.to_string()),
+        ("Range", format!("{} ft.", power.effect_area.range_feet)),
+    ];
+    for (stat, value) in &rows {
+        writeln!(w, "| {} | {} |", stat, escape_table_cell(value))?;
+    }
+
+    let mut effects = Vec::new();
+    for effect_group in &power.effect_groups {
+        collect_effect_lines(effect_group, &mut effects);
+    }
+    if !effects.is_empty() {
+        writeln!(w)?;
+        writeln!(w, "## Effects")?;
+        writeln!(w)?;
+        for effect in &effects {
+            writeln!(w, "- {}", effect)?;
+        }
+    }
+
+    if let Some(requires) = &power.requires {
+        writeln!(w)?;
+        writeln!(w, "## Requires")?;
+        writeln!(w)?;
+        writeln!(w, "```")?;
+        writeln!(w, "{}", requires)?;
+        writeln!(w, "```")?;
+    }
+    Ok(())
+}
+
+/// Appends a line for each effect in `effect_group` (and its children) that's shown in the
+/// power info.
+fn collect_effect_lines(effect_group: &EffectGroupOutput, lines: &mut Vec<String>) {
+    if !effect_group.visible_in_info_window {
+        return;
+    }
+    for effect in &effect_group.effects {
+        lines.extend(describe_effect(effect));
+    }
+    for child in &effect_group.child_effect_groups {
+        collect_effect_lines(child, lines);
+    }
+}
+
+/// Gets the lines describing `effect`. Uses the power info description for the first archetype
+/// when there is one, otherwise just lists the attributes.
+fn describe_effect(effect: &AttribModOutput) -> Vec<String> {
+    match effect.scaled.get(0) {
+        Some(scaled) if !scaled.display_info.is_empty() => scaled
+            .display_info
+            .iter()
+            .map(|line| line.trim().to_owned())
+            .filter(|line| !line.is_empty())
+            .collect(),
+        _ if !effect.attributes.is_empty() => vec![effect.attributes.join(", ")],
+        _ => Vec::new(),
+    }
+}
+
+/// Escapes characters that would break a Markdown table cell.
+fn escape_table_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::config::MINIMAL_CONFIG;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn power_markdown_snapshot_test() {
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        let mut attrib_names = AttribNames::new();
+        let mut attrib_name = AttribName::new();
+        attrib_name.pch_display_name = Some(String::from("Fire"));
+        attrib_names.pp_damage.push(attrib_name);
+        attrib_names.attr_names.insert(
+            CharacterAttributes::OFFSET_DMG_0,
+            Some(String::from("Fire Damage")),
+        );
+        let mut at = Archetype::new();
+        at.pch_display_name = Some(String::from("Blaster"));
+        let mut table = NamedTable::new();
+        table.pf_values = vec![-62.56; 50];
+        at.pp_named_tables
+            .insert(String::from("ranged_damage"), table);
+
+        let mut damage = AttribModTemplate::new();
+        damage
+            .p_attrib
+            .push(CharacterAttrib(CharacterAttributes::OFFSET_DMG_0 as i32));
+        damage.pch_table = Some(String::from("Ranged_Damage"));
+        damage.f_scale = 1.0;
+        damage.off_aspect = 32;
+        damage.e_target = ModTarget::kModTarget_Affected;
+        let mut effect = EffectGroup::new();
+        effect.f_chance = 1.0;
+        effect.pp_templates.push(damage);
+
+        let mut power = BasePower::new();
+        power.pch_full_name = Some(NameKey::new("Blaster_Ranged.Fire_Blast.Flares"));
+        power.pch_display_name = Some(String::from("Flares"));
+        power.pch_display_short_help = Some(String::from("Ranged, Moderate DMG(Fire)"));
+        power.e_type = PowerType::kPowerType_Click;
+        power.e_target_type = TargetType::kTargetType_Foe;
+        power.f_accuracy = 1.2;
+        power.f_recharge_time = 2.0;
+        power.f_endurance_cost = 5.2;
+        power.f_range = 80.0;
+        power.ppch_buy_requires = vec![String::from("source.isPvPMap?"), String::from("!")];
+        power.archetypes.push(Rc::new(RefCell::new(at)));
+        power.pp_effects.push(Rc::new(RefCell::new(effect)));
+        let output = PowerOutput::from_base_power(&power, &attrib_names, &config, None);
+
+        let mut data = Vec::new();
+        write_power_markdown(&output, &mut data).unwrap();
+        let markdown = String::from_utf8(data).unwrap();
+        let expected = "\
+# Flares
+
+Ranged, Moderate DMG(Fire)
+
+| Stat | Value |
+| --- | --- |
+| Type | Click |
+| Accuracy | 1.2 |
+| Recharge | 2s |
+| Endurance | 5.2 |
+| Range | 80 ft. |
+
+## Effects
+
+- 62.56 Fire Damage on target
+
+## Requires
+
+```
+!source.isPvPMap?()
+```
+";
+        assert_eq!(markdown, expected);
+    }
+}
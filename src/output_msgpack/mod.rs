@@ -0,0 +1,141 @@
+use crate::output::{make_file_name_opt, prepare_output_path, OutputError, PowerSetOutput};
+use crate::structs::config::PowersConfig;
+use crate::structs::*;
+use log::info;
+use serde::Serialize;
+use std::fs;
+use std::io::prelude::*;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Default name for the .msgpack files.
+const MSGPACK_FILE: &'static str = "index.msgpack";
+
+/// Writes every power set in the powers dictionary to disk as MessagePack files.
+///
+/// # Arguments:
+///
+/// * `powers_dict` - A `PowersDictionary` containing a hierarchy of categories, power sets, and powers.
+/// * `config` - Configuration information.
+///
+/// # Returns:
+///
+/// Nothing if the operation was successful. Otherwise, an `OutputError` containing the error information.
+///
+/// # Notes:
+///
+/// Each power set is written to the same folder as the "api" format, but as `index.msgpack` instead of
+/// `index.json`. Structs are written as maps with named fields, and attributes are written as names
+/// just like the JSON output.
+pub fn write_powers_dictionary_msgpack(
+    powers_dict: &PowersDictionary,
+    config: &PowersConfig,
+) -> Result<(), OutputError> {
+    // setup the output directory
+    prepare_output_path(config).map_err(OutputError::path(Path::new(&config.output_path)))?;
+
+    // same scopes as the JSON output, so the custom serializers behave identically
+    let _attrib_names_scope = AttribNamesScope::enter(Rc::clone(&powers_dict.attrib_names));
+    let _rgba_format_scope = RgbaFormatScope::enter(config.rgba_format);
+
+    for category in powers_dict.power_categories.iter().map(|c| c.borrow()) {
+        if !category.include_in_output {
+            continue;
+        }
+        if let Some(pcat_name) = category.pch_name.as_ref() {
+            let category_path =
+                config.join_to_output_path(&make_file_name_opt(Some(pcat_name.get_string())));
+            for set in category.pp_power_sets.iter().map(|p| p.borrow()) {
+                if set.include_in_output {
+                    let output_path =
+                        category_path.join(&make_file_name_opt(set.pch_name.as_ref()));
+                    let pset = PowerSetOutput::from_base_power_set(
+                        &*set,
+                        &powers_dict.attrib_names,
+                        config,
+                        Some(powers_dict),
+                    );
+                    write_msgpack(&output_path, &pset)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes `value` to `MSGPACK_FILE` in `output_path`, creating the folder if necessary.
+///
+/// # Notes:
+///
+/// `value` is serialized in memory before the file is created, so a serialization failure doesn't
+/// leave a truncated file behind.
+fn write_msgpack<T>(output_path: &Path, value: &T) -> Result<(), OutputError>
+where
+    T: Serialize + ?Sized,
+{
+    fs::create_dir_all(output_path).map_err(OutputError::io(output_path))?;
+    let output_file = output_path.join(MSGPACK_FILE);
+    info!("\tWriting: {} ...", output_file.display());
+    let mut data = Vec::new();
+    to_msgpack_writer(&mut data, value).map_err(OutputError::serialize(&output_file))?;
+    fs::write(&output_file, &data).map_err(OutputError::io(&output_file))
+}
+
+/// Serializes `value` as MessagePack to `w`.
+///
+/// # Notes:
+///
+/// The serializer is marked human readable, otherwise `CharacterAttrib` and friends would be written as
+/// raw offsets instead of names.
+fn to_msgpack_writer<W, T>(w: &mut W, value: &T) -> Result<(), rmp_serde::encode::Error>
+where
+    W: Write,
+    T: Serialize + ?Sized,
+{
+    let mut serializer = rmp_serde::Serializer::new(w)
+        .with_struct_map()
+        .with_human_readable();
+    value.serialize(&mut serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::config::MINIMAL_CONFIG;
+    use std::cell::RefCell;
+
+    #[test]
+    fn power_set_msgpack_test() {
+        let mut config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        config.extract_date = Some(chrono::Local::now());
+        let mut power_set = BasePowerSet::new();
+        power_set.pch_full_name = Some(NameKey::new("Pool.Flight"));
+        for name in &["Pool.Flight.Hover", "Pool.Flight.Fly"] {
+            let mut power = BasePower::new();
+            power.pch_full_name = Some(NameKey::new(*name));
+            power.include_in_output = true;
+            power_set.pp_powers.push(Rc::new(RefCell::new(power)));
+        }
+        let pset = PowerSetOutput::from_base_power_set(&power_set, &AttribNames::new(), &config, None);
+        let mut data = Vec::new();
+        to_msgpack_writer(&mut data, &pset).unwrap();
+        let mut deserializer = rmp_serde::Deserializer::new(&data[..]).with_human_readable();
+        let parsed: PowerSetOutput = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(parsed.power_count(), 2);
+    }
+
+    #[test]
+    fn attrib_msgpack_test() {
+        let mut attrib_names = AttribNames::new();
+        for name in &["ServerTrayOverride", "Nova"] {
+            let mut attrib_name = AttribName::new();
+            attrib_name.pch_name = Some(String::from(*name));
+            attrib_names.pp_mode.push(attrib_name);
+        }
+        let _scope = AttribNamesScope::enter(Rc::new(attrib_names));
+        let mut data = Vec::new();
+        to_msgpack_writer(&mut data, &ModeAttrib(1)).unwrap();
+        let mode: String = rmp_serde::from_slice(&data).unwrap();
+        assert_eq!(mode, "Nova");
+    }
+}
@@ -1,15 +1,23 @@
+use crate::output::{prepare_output_path, NormalizedWriter, OutputError};
 use crate::structs::config::{OutputStyleConfig, PowersConfig};
 use crate::structs::*;
+use log::info;
+use rayon::prelude::*;
+use serde::Serialize;
 use std::collections::HashSet;
 use std::fs;
 use std::io;
 use std::io::prelude::*;
-use std::io::{Error, ErrorKind};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// Default extension for the .json files.
 const JSON_EXT: &'static str = ".json";
 
+/// Extension for the .yaml files.
+const YAML_EXT: &'static str = ".yaml";
+
 /// Begins the process of writing the entire powers dictionary to disk as .json files.
 ///
 /// # Arguments:
@@ -19,7 +27,7 @@ const JSON_EXT: &'static str = ".json";
 ///
 /// # Returns:
 ///
-/// Nothing if the operation was successful. Otherwise, an `io::Error` containing the error information.
+/// Nothing if the operation was successful. Otherwise, an `OutputError` containing the error information.
 ///
 /// # Notes:
 ///
@@ -31,74 +39,28 @@ const JSON_EXT: &'static str = ".json";
 pub fn write_powers_dictionary(
     powers_dict: PowersDictionary,
     config: &PowersConfig,
-) -> io::Result<()> {
+) -> Result<(), OutputError> {
     // setup the output directory
-    let output_path = Path::new(&config.output_path);
-    fs::create_dir_all(&output_path)?;
-    if output_path.read_dir()?.count() > 0 {
-        print!(
-            "WARNING! The output path {} is not empty. Overwrite? (y/n)",
-            output_path.display()
-        );
-        io::stdout().flush()?;
-        //TODO: better input handling
-        for c in io::stdin().lock().bytes() {
-            match c? {
-                b'y' | b'Y' => break,
-                b'n' | b'N' => return Err(Error::from(ErrorKind::Interrupted)),
-                _ => (),
-            }
-        }
-        println!();
-    }
+    prepare_output_path(config).map_err(OutputError::path(Path::new(&config.output_path)))?;
+
+    // attribute names are looked up from this scope while serializing the raw structs
+    let _attrib_names_scope = AttribNamesScope::enter(Rc::clone(&powers_dict.attrib_names));
+    let _rgba_format_scope = RgbaFormatScope::enter(config.rgba_format);
+    let _enum_format_scope = EnumFormatScope::enter(config.enum_as_int);
+    let _attribs_verbose_scope = AttribsVerboseScope::enter(config.attribs_verbose);
 
     // write powers
+    let mut queue = WriteQueue::new(config.parallel_output, Path::new(&config.output_path));
     let mut fx_cache = HashSet::new();
-    let mut file_count = 0;
     for power_cat in powers_dict.power_categories.iter().map(|p| p.borrow()) {
         if power_cat.include_in_output {
-            write_power_category(&*power_cat, config)?;
-            file_count += 1;
+            queue.push(
+                OutputKind::Category,
+                power_category_job(&*power_cat, config)?,
+            )?;
             for power_set in power_cat.pp_power_sets.iter().map(|p| p.borrow()) {
                 if power_set.include_in_output {
-                    write_power_set(&*power_set, config)?;
-                    file_count += 1;
-                    let powers: Vec<_> = power_set
-                        .pp_powers
-                        .iter()
-                        .filter(|p| p.borrow().include_in_output)
-                        .collect();
-                    if powers.len() > 0 {
-                        // write all powers in the power set
-                        write_powers(&powers, config)?;
-                        file_count += 1;
-
-                        // write all the FX blocks, checking for duplicates
-                        for p in powers.iter().map(|p| p.borrow()) {
-                            if let Some(fx) = &p.p_fx {
-                                if let Some(source) = &fx.pch_source_file {
-                                    let source = source.to_lowercase();
-                                    if !fx_cache.contains(&source) {
-                                        fx_cache.insert(source);
-                                        write_fx(fx, config)?;
-                                        file_count += 1;
-                                    }
-                                }
-                            }
-                            for cfx in &p.pp_custom_fx {
-                                if let Some(custom_fx) = &cfx.p_fx {
-                                    if let Some(source) = &custom_fx.pch_source_file {
-                                        let source = source.to_lowercase();
-                                        if !fx_cache.contains(&source) {
-                                            fx_cache.insert(source);
-                                            write_fx(custom_fx, config)?;
-                                            file_count += 1;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
+                    queue_power_set(&mut queue, &*power_set, &mut fx_cache, config)?;
                 }
             }
         }
@@ -108,98 +70,386 @@ pub fn write_powers_dictionary(
     // the original has everything in one def file, but that results in a massive unwieldy
     // file because of all the computed tables that end up in the bin
     for archetype in powers_dict.archetypes.values() {
-        write_archetype(&*archetype.borrow(), config)?;
-        file_count += 1;
+        queue.push(
+            OutputKind::Archetype,
+            archetype_job(&*archetype.borrow(), config)?,
+        )?;
     }
 
     // write attribute names
-    write_attrib_names(&powers_dict.attrib_names, config)?;
-    file_count += 1;
+    queue.push(
+        OutputKind::AttribNames,
+        attrib_names_job(&powers_dict.attrib_names, config)?,
+    )?;
+
+    let (file_count, entries) = queue.finish()?;
+
+    // write the index of everything above
+    index_job(&entries, config)?.write()?;
+    info!("{} output files written.", file_count + 1);
+
+    Ok(())
+}
+
+/// Writes the raw output for just one power set, its powers, and their FX. Useful for debugging a
+/// single set without writing the whole tree.
+///
+/// # Arguments:
+///
+/// * `powers_dict` - A `PowersDictionary` containing a hierarchy of categories, power sets, and powers.
+/// * `set_name` - The full name of the power set (e.g. "Pool.Flight"), ignoring case.
+/// * `config` - Configuration information.
+/// * `to_stdout` - If `true`, the contents of each file are written to stdout (each followed by a
+///   line feed) instead of to the output path.
+///
+/// # Returns:
+///
+/// Nothing if the operation was successful. Otherwise, an `OutputError` containing the error
+/// information. This is `PowerSetNotFound` if there's no power set called `set_name`.
+pub fn dump_single_power_set(
+    powers_dict: &PowersDictionary,
+    set_name: &NameKey,
+    config: &PowersConfig,
+    to_stdout: bool,
+) -> Result<(), OutputError> {
+    let file_count = write_single_power_set(powers_dict, set_name, config, to_stdout)?;
+    info!("{} output files written.", file_count);
+    Ok(())
+}
+
+/// Writes the raw output for the power set called `set_name` in `powers_dict`, along with its
+/// powers and their FX.
+///
+/// # Returns:
+///
+/// The number of files written, or `OutputError::PowerSetNotFound` if there's no power set
+/// called `set_name`.
+fn write_single_power_set(
+    powers_dict: &PowersDictionary,
+    set_name: &NameKey,
+    config: &PowersConfig,
+    to_stdout: bool,
+) -> Result<usize, OutputError> {
+    let power_set =
+        powers_dict
+            .find_power_set(set_name)
+            .ok_or_else(|| OutputError::PowerSetNotFound {
+                name: set_name.to_string(),
+            })?;
+
+    // setup the output directory
+    if !to_stdout {
+        prepare_output_path(config).map_err(OutputError::path(Path::new(&config.output_path)))?;
+    }
+
+    // same scopes as `write_powers_dictionary`
+    let _attrib_names_scope = AttribNamesScope::enter(Rc::clone(&powers_dict.attrib_names));
+    let _rgba_format_scope = RgbaFormatScope::enter(config.rgba_format);
+    let _enum_format_scope = EnumFormatScope::enter(config.enum_as_int);
+    let _attribs_verbose_scope = AttribsVerboseScope::enter(config.attribs_verbose);
+
+    let mut queue = if to_stdout {
+        WriteQueue::stdout(Path::new(&config.output_path))
+    } else {
+        WriteQueue::new(config.parallel_output, Path::new(&config.output_path))
+    };
+    queue_power_set(
+        &mut queue,
+        &*power_set.borrow(),
+        &mut HashSet::new(),
+        config,
+    )?;
+    let (file_count, _) = queue.finish()?;
+    Ok(file_count)
+}
 
-    println!("{} output files written.", file_count);
+/// Queues a power set, the powers in it that are included in the output, and their FX.
+///
+/// # Arguments:
+///
+/// * `queue` - The queue to add the files to.
+/// * `power_set` - The power set to write.
+/// * `fx_cache` - Source files of the FX already queued, so that shared FX are only written once.
+/// * `config` - Configuration information.
+fn queue_power_set(
+    queue: &mut WriteQueue,
+    power_set: &BasePowerSet,
+    fx_cache: &mut HashSet<String>,
+    config: &PowersConfig,
+) -> Result<(), OutputError> {
+    queue.push(OutputKind::Powerset, power_set_job(power_set, config)?)?;
+    let mut powers: Vec<_> = power_set
+        .pp_powers
+        .iter()
+        .filter(|p| p.borrow().include_in_output)
+        .collect();
+    sort_powers_by_name(&mut powers);
+    if powers.len() > 0 {
+        // write all powers in the power set
+        for job in powers_jobs(&powers, config)? {
+            queue.push(OutputKind::Powers, job)?;
+        }
 
+        // write all the FX blocks, checking for duplicates
+        for p in powers.iter().map(|p| p.borrow()) {
+            if let Some(fx) = &p.p_fx {
+                if let Some(source) = &fx.pch_source_file {
+                    let source = source.to_lowercase();
+                    if !fx_cache.contains(&source) {
+                        fx_cache.insert(source);
+                        queue.push(OutputKind::Fx, fx_job(fx, config)?)?;
+                    }
+                }
+            }
+            for cfx in &p.pp_custom_fx {
+                if let Some(custom_fx) = &cfx.p_fx {
+                    if let Some(source) = &custom_fx.pch_source_file {
+                        let source = source.to_lowercase();
+                        if !fx_cache.contains(&source) {
+                            fx_cache.insert(source);
+                            queue.push(OutputKind::Fx, fx_job(custom_fx, config)?)?;
+                        }
+                    }
+                }
+            }
+        }
+    }
     Ok(())
 }
 
-fn write_power_category(power_cat: &PowerCategory, config: &PowersConfig) -> io::Result<()> {
+/// The kind of data in an output file, as listed in `index.json`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum OutputKind {
+    Category,
+    Powerset,
+    Powers,
+    Fx,
+    Archetype,
+    AttribNames,
+}
+
+/// An entry in `index.json` describing one output file.
+#[derive(Debug, Serialize)]
+struct OutputEntry {
+    /// Path to the file, relative to `output_path`.
+    path: String,
+    kind: OutputKind,
+    /// Size of the file in bytes.
+    bytes: usize,
+}
+
+/// A serialized output file waiting to be written to disk.
+struct WriteJob {
+    path: PathBuf,
+    data: Vec<u8>,
+}
+
+impl WriteJob {
+    /// Serializes `value` in the configured style and line endings.
+    fn new<T>(path: PathBuf, value: &T, config: &PowersConfig) -> Result<Self, OutputError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let mut w = NormalizedWriter::new(Vec::new(), config.line_endings);
+        match config.output_style {
+            OutputStyleConfig::Pretty => {
+                serde_json::to_writer_pretty(&mut w, value).map_err(OutputError::serialize(&path))?
+            }
+            OutputStyleConfig::Compact => {
+                serde_json::to_writer(&mut w, value).map_err(OutputError::serialize(&path))?
+            }
+            OutputStyleConfig::Yaml => {
+                serde_yaml::to_writer(&mut w, value).map_err(OutputError::serialize(&path))?
+            }
+        }
+        w.flush().map_err(OutputError::io(&path))?;
+        Ok(WriteJob {
+            path,
+            data: w.into_inner(),
+        })
+    }
+
+    /// Writes the file, creating its parent directories if necessary.
+    fn write(&self) -> Result<(), OutputError> {
+        ensure_path_exists(&self.path).map_err(OutputError::io(&self.path))?;
+        fs::write(&self.path, &self.data).map_err(OutputError::io(&self.path))
+    }
+}
+
+/// Writes output files either immediately or, in parallel mode, all at once at the end.
+///
+/// The raw structs can't be shared between threads, so serialization always happens on
+/// the calling thread and only the file writes are done in parallel. In parallel mode every
+/// serialized file is held in memory until `finish`, which for a full dump is roughly the size
+/// of the whole output directory.
+struct WriteQueue {
+    parallel: bool,
+    /// If `true`, files are written to stdout instead of to disk.
+    stdout: bool,
+    root: PathBuf,
+    pending: Vec<WriteJob>,
+    entries: Vec<OutputEntry>,
+    written: AtomicUsize,
+}
+
+impl WriteQueue {
+    /// Creates a new queue for files written under `root`.
+    fn new(parallel: bool, root: &Path) -> Self {
+        WriteQueue {
+            parallel,
+            stdout: false,
+            root: root.to_path_buf(),
+            pending: Vec::new(),
+            entries: Vec::new(),
+            written: AtomicUsize::new(0),
+        }
+    }
+
+    /// Creates a new queue that writes the contents of each file to stdout, in order, followed
+    /// by a line feed. Paths are still relative to `root` in the index entries.
+    fn stdout(root: &Path) -> Self {
+        WriteQueue {
+            stdout: true,
+            ..WriteQueue::new(false, root)
+        }
+    }
+
+    /// Adds a file to the queue. If not in parallel mode, it's written right away.
+    fn push(&mut self, kind: OutputKind, job: WriteJob) -> Result<(), OutputError> {
+        let relative = job.path.strip_prefix(&self.root).unwrap_or(&job.path);
+        self.entries.push(OutputEntry {
+            path: relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            kind,
+            bytes: job.data.len(),
+        });
+        if self.stdout {
+            let mut out = io::stdout().lock();
+            out.write_all(&job.data)
+                .and_then(|_| out.write_all(b"\n"))
+                .and_then(|_| out.flush())
+                .map_err(OutputError::io(&job.path))?;
+            self.written.fetch_add(1, Ordering::Relaxed);
+        } else if self.parallel {
+            self.pending.push(job);
+        } else {
+            job.write()?;
+            self.written.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Writes any pending files.
+    ///
+    /// # Returns:
+    ///
+    /// The total number of files written, and an index entry for each of them.
+    fn finish(self) -> Result<(usize, Vec<OutputEntry>), OutputError> {
+        let written = self.written;
+        self.pending.par_iter().try_for_each(|job| {
+            job.write()?;
+            written.fetch_add(1, Ordering::Relaxed);
+            Ok::<(), OutputError>(())
+        })?;
+        Ok((written.into_inner(), self.entries))
+    }
+}
+
+/// Gets the file extension for the configured output style.
+fn output_ext(config: &PowersConfig) -> &'static str {
+    match config.output_style {
+        OutputStyleConfig::Yaml => YAML_EXT,
+        _ => JSON_EXT,
+    }
+}
+
+fn power_category_job(power_cat: &PowerCategory, config: &PowersConfig) -> Result<WriteJob, OutputError> {
     let output_file = config.join_to_output_path(
         format!(
             "{}{}",
             power_cat.pch_source_file.as_ref().unwrap().to_lowercase(),
-            JSON_EXT
+            output_ext(config)
         )
         .as_str(),
     );
-    println!("Writing: {} ...", output_file.display());
-    ensure_path_exists(&output_file)?;
-    let mut f = fs::File::create(&output_file)?;
-    match config.output_style {
-        OutputStyleConfig::Pretty => serde_json::to_writer_pretty(&mut f, power_cat)?,
-        OutputStyleConfig::Compact => serde_json::to_writer(&mut f, power_cat)?,
-    }
-    Ok(())
+    info!("Writing: {} ...", output_file.display());
+    WriteJob::new(output_file, power_cat, config)
 }
 
-fn write_power_set(power_set: &BasePowerSet, config: &PowersConfig) -> io::Result<()> {
+fn power_set_job(power_set: &BasePowerSet, config: &PowersConfig) -> Result<WriteJob, OutputError> {
     let output_file = config.join_to_output_path(
         format!(
             "{}{}",
             power_set.pch_source_file.as_ref().unwrap().to_lowercase(),
-            JSON_EXT
+            output_ext(config)
         )
         .as_str(),
     );
-    println!("\tWriting: {} ...", output_file.display());
-    ensure_path_exists(&output_file)?;
-    let mut f = fs::File::create(&output_file)?;
-    match config.output_style {
-        OutputStyleConfig::Pretty => serde_json::to_writer_pretty(&mut f, power_set)?,
-        OutputStyleConfig::Compact => serde_json::to_writer(&mut f, power_set)?,
-    }
-    Ok(())
+    info!("\tWriting: {} ...", output_file.display());
+    WriteJob::new(output_file, power_set, config)
 }
 
-fn write_powers(powers: &Vec<&ObjRef<BasePower>>, config: &PowersConfig) -> io::Result<()> {
-    // NOTE: is it true that all powers in a set share same the source file?
-    let source_file = powers
-        .first()
-        .unwrap()
-        .borrow()
-        .source_file
-        .as_ref()
-        .unwrap()
-        .to_lowercase();
-    let output_file = config.join_to_output_path(format!("{}{}", source_file, JSON_EXT).as_str());
-    println!("\tWriting: {} ...", output_file.display());
-    ensure_path_exists(&output_file)?;
-    let mut f = fs::File::create(&output_file)?;
-    match config.output_style {
-        OutputStyleConfig::Pretty => serde_json::to_writer_pretty(&mut f, powers)?,
-        OutputStyleConfig::Compact => serde_json::to_writer(&mut f, powers)?,
+/// Creates one job per source file for the powers in a power set. Usually they all share the same
+/// source file, but not always.
+///
+/// # Returns:
+///
+/// The jobs in the order their source files first appear in `powers`, or
+/// `OutputError::MissingSourceFile` if a power has no source file.
+fn powers_jobs(
+    powers: &Vec<&ObjRef<BasePower>>,
+    config: &PowersConfig,
+) -> Result<Vec<WriteJob>, OutputError> {
+    let mut by_source_file: Vec<(String, Vec<&ObjRef<BasePower>>)> = Vec::new();
+    for power in powers {
+        let source_file = match &power.borrow().source_file {
+            Some(source_file) => source_file.to_lowercase(),
+            None => {
+                return Err(OutputError::MissingSourceFile {
+                    name: power
+                        .borrow()
+                        .pch_full_name
+                        .as_ref()
+                        .map(|name| name.get())
+                        .unwrap_or("(unnamed)")
+                        .to_owned(),
+                })
+            }
+        };
+        match by_source_file.iter_mut().find(|(s, _)| *s == source_file) {
+            Some((_, group)) => group.push(power),
+            None => by_source_file.push((source_file, vec![power])),
+        }
     }
-    Ok(())
+    by_source_file
+        .into_iter()
+        .map(|(source_file, group)| {
+            let output_file = config
+                .join_to_output_path(format!("{}{}", source_file, output_ext(config)).as_str());
+            info!("\tWriting: {} ...", output_file.display());
+            WriteJob::new(output_file, &group, config)
+        })
+        .collect()
 }
 
-fn write_fx(fx: &PowerFX, config: &PowersConfig) -> io::Result<()> {
+fn fx_job(fx: &PowerFX, config: &PowersConfig) -> Result<WriteJob, OutputError> {
     let output_file = config.join_to_output_path(
         format!(
             "{}{}",
             fx.pch_source_file.as_ref().unwrap().to_lowercase(),
-            JSON_EXT
+            output_ext(config)
         )
         .as_str(),
     );
-    println!("\t\tWriting: {} ...", output_file.display());
-    ensure_path_exists(&output_file)?;
-    let mut f = fs::File::create(&output_file)?;
-    match config.output_style {
-        OutputStyleConfig::Pretty => serde_json::to_writer_pretty(&mut f, fx)?,
-        OutputStyleConfig::Compact => serde_json::to_writer(&mut f, fx)?,
-    }
-    Ok(())
+    info!("\t\tWriting: {} ...", output_file.display());
+    WriteJob::new(output_file, fx, config)
 }
 
-fn write_archetype(archetype: &Archetype, config: &PowersConfig) -> io::Result<()> {
+fn archetype_job(archetype: &Archetype, config: &PowersConfig) -> Result<WriteJob, OutputError> {
     let output_file = config.join_to_output_path(
         format!(
             "defs/classes/{}{}",
@@ -209,30 +459,35 @@ fn write_archetype(archetype: &Archetype, config: &PowersConfig) -> io::Result<(
                 .unwrap()
                 .to_lowercase()
                 .replace(' ', "_"),
-            JSON_EXT
+            output_ext(config)
         )
         .as_str(),
     );
-    println!("Writing: {} ...", output_file.display());
-    ensure_path_exists(&output_file)?;
-    let mut f = fs::File::create(&output_file)?;
-    match config.output_style {
-        OutputStyleConfig::Pretty => serde_json::to_writer_pretty(&mut f, archetype)?,
-        OutputStyleConfig::Compact => serde_json::to_writer(&mut f, archetype)?,
-    }
-    Ok(())
+    info!("Writing: {} ...", output_file.display());
+    WriteJob::new(output_file, archetype, config)
 }
 
-fn write_attrib_names(attrib_names: &AttribNames, config: &PowersConfig) -> io::Result<()> {
-    let output_file = config.join_to_output_path(format!("defs/attrib_names{}", JSON_EXT).as_str());
-    println!("Writing: {} ...", output_file.display());
-    ensure_path_exists(&output_file)?;
-    let mut f = fs::File::create(&output_file)?;
-    match config.output_style {
-        OutputStyleConfig::Pretty => serde_json::to_writer_pretty(&mut f, attrib_names)?,
-        OutputStyleConfig::Compact => serde_json::to_writer(&mut f, attrib_names)?,
-    }
-    Ok(())
+fn attrib_names_job(attrib_names: &AttribNames, config: &PowersConfig) -> Result<WriteJob, OutputError> {
+    let output_file =
+        config.join_to_output_path(format!("defs/attrib_names{}", output_ext(config)).as_str());
+    info!("Writing: {} ...", output_file.display());
+    WriteJob::new(output_file, attrib_names, config)
+}
+
+fn index_job(entries: &Vec<OutputEntry>, config: &PowersConfig) -> Result<WriteJob, OutputError> {
+    let output_file = config.join_to_output_path(format!("index{}", output_ext(config)).as_str());
+    info!("Writing: {} ...", output_file.display());
+    WriteJob::new(output_file, entries, config)
+}
+
+/// Sorts `powers` by their full names so combined files are written in a stable order.
+fn sort_powers_by_name(powers: &mut Vec<&ObjRef<BasePower>>) {
+    powers.sort_by_cached_key(|p| {
+        p.borrow()
+            .pch_full_name
+            .as_ref()
+            .map(|name| name.get().to_lowercase())
+    });
 }
 
 fn ensure_path_exists(path: &Path) -> io::Result<()> {
@@ -241,3 +496,181 @@ fn ensure_path_exists(path: &Path) -> io::Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    fn make_power(name: &str) -> ObjRef<BasePower> {
+        let mut power = BasePower::new();
+        power.pch_full_name = Some(NameKey::new(name));
+        Rc::new(RefCell::new(power))
+    }
+
+    fn sorted_names(powers: &Vec<ObjRef<BasePower>>) -> Vec<String> {
+        let mut refs: Vec<_> = powers.iter().collect();
+        sort_powers_by_name(&mut refs);
+        refs.iter()
+            .map(|p| p.borrow().pch_full_name.as_ref().unwrap().get().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn sort_powers_by_name_test() {
+        let run1 = vec![
+            make_power("Pool.Flight.Fly"),
+            make_power("Pool.Flight.Air_Superiority"),
+            make_power("Pool.Flight.Hover"),
+        ];
+        let run2 = vec![
+            make_power("Pool.Flight.Hover"),
+            make_power("Pool.Flight.Fly"),
+            make_power("Pool.Flight.Air_Superiority"),
+        ];
+        assert_eq!(sorted_names(&run1), sorted_names(&run2));
+        assert_eq!(
+            sorted_names(&run1),
+            vec![
+                "Pool.Flight.Air_Superiority",
+                "Pool.Flight.Fly",
+                "Pool.Flight.Hover"
+            ]
+        );
+    }
+
+    #[test]
+    fn parallel_write_queue_test() {
+        let output_path =
+            std::env::temp_dir().join(format!("powers_write_queue_test_{}", std::process::id()));
+        let make_jobs = |dir: &str| {
+            (0..20)
+                .map(|i| WriteJob {
+                    path: output_path
+                        .join(dir)
+                        .join(format!("sub{}/{}.json", i % 3, i)),
+                    data: format!("{{\"index\":{}}}", i).into_bytes(),
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut counts = Vec::new();
+        for (dir, parallel) in &[("sequential", false), ("parallel", true)] {
+            let mut queue = WriteQueue::new(*parallel, &output_path.join(dir));
+            for job in make_jobs(dir) {
+                queue.push(OutputKind::Powers, job).unwrap();
+            }
+            let (count, entries) = queue.finish().unwrap();
+            assert_eq!(entries.len(), count);
+            assert_eq!(entries[4].path, "sub1/4.json");
+            assert_eq!(entries[4].kind, OutputKind::Powers);
+            assert_eq!(entries[4].bytes, "{\"index\":4}".len());
+            counts.push(count);
+        }
+        assert_eq!(counts, vec![20, 20]);
+        for (sequential, parallel) in make_jobs("sequential")
+            .iter()
+            .zip(make_jobs("parallel").iter())
+        {
+            assert_eq!(
+                fs::read(&sequential.path).unwrap(),
+                fs::read(&parallel.path).unwrap()
+            );
+        }
+        fs::remove_dir_all(&output_path).unwrap();
+    }
+
+    #[test]
+    fn yaml_output_style_test() {
+        use crate::structs::config::MINIMAL_CONFIG;
+
+        let config: PowersConfig =
+            toml::from_str(&format!("output_style = \"yaml\"\n{}", MINIMAL_CONFIG)).unwrap();
+        let entries = vec![OutputEntry {
+            path: "defs/attrib_names.yaml".to_owned(),
+            kind: OutputKind::AttribNames,
+            bytes: 42,
+        }];
+        let job = index_job(&entries, &config).unwrap();
+        assert!(job.path.ends_with("index.yaml"));
+        let yaml = String::from_utf8(job.data).unwrap();
+        assert!(yaml.contains("path: defs/attrib_names.yaml"));
+        assert!(yaml.contains("bytes: 42"));
+    }
+
+    #[test]
+    fn yaml_attrib_names_test() {
+        use crate::structs::config::MINIMAL_CONFIG;
+
+        let config: PowersConfig =
+            toml::from_str(&format!("output_style = \"yaml\"\n{}", MINIMAL_CONFIG)).unwrap();
+        let mut attrib_names = AttribNames::new();
+        let mut fire = AttribName::new();
+        fire.pch_name = Some(String::from("Fire"));
+        fire.pch_display_name = Some(String::from("Fire Damage"));
+        attrib_names.pp_damage.push(fire);
+        let job = attrib_names_job(&attrib_names, &config).unwrap();
+        assert!(job.path.ends_with("defs/attrib_names.yaml"));
+        let yaml = String::from_utf8(job.data).unwrap();
+        let value: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(value["pp_damage"][0]["pch_name"].as_str(), Some("Fire"));
+        assert_eq!(
+            value["pp_damage"][0]["pch_display_name"].as_str(),
+            Some("Fire Damage")
+        );
+    }
+
+    #[test]
+    fn powers_jobs_test() {
+        use crate::structs::config::MINIMAL_CONFIG;
+
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        let _scope = AttribNamesScope::enter(Rc::new(AttribNames::new()));
+        let powers: Vec<_> = [
+            ("Pool.Flight.Air_Superiority", "Powers/Pool/Flight.powers"),
+            ("Pool.Flight.Fly", "Powers/Pool/Flight_Issue2.powers"),
+            ("Pool.Flight.Hover", "powers/pool/flight.powers"),
+        ]
+        .iter()
+        .map(|(name, source_file)| {
+            let power = make_power(name);
+            power.borrow_mut().source_file = Some(String::from(*source_file));
+            power
+        })
+        .collect();
+        let mut refs: Vec<_> = powers.iter().collect();
+
+        let jobs = powers_jobs(&refs, &config).unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert!(jobs[0].path.ends_with("powers/pool/flight.powers.json"));
+        assert!(jobs[1]
+            .path
+            .ends_with("powers/pool/flight_issue2.powers.json"));
+        let first: serde_json::Value = serde_json::from_slice(&jobs[0].data).unwrap();
+        assert_eq!(first.as_array().unwrap().len(), 2);
+
+        let missing = make_power("Pool.Flight.Afterburner");
+        refs.push(&missing);
+        match powers_jobs(&refs, &config) {
+            Err(OutputError::MissingSourceFile { name }) => {
+                assert_eq!(name, "Pool.Flight.Afterburner")
+            }
+            other => panic!("expected a missing source file error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn write_single_power_set_missing_test() {
+        use crate::structs::config::MINIMAL_CONFIG;
+
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        let powers_dict = PowersDictionary::from_power_categories(Vec::new());
+        let err =
+            write_single_power_set(&powers_dict, &NameKey::new("Pool.Fligth"), &config, false)
+                .err()
+                .unwrap();
+        assert!(matches!(err, OutputError::PowerSetNotFound { .. }));
+        assert!(err
+            .to_string()
+            .starts_with("No power set named Pool.Fligth was found."));
+    }
+}
@@ -0,0 +1,351 @@
+use crate::output::{requires_to_string, OutputError};
+use crate::structs::config::PowersConfig;
+use crate::structs::*;
+use log::info;
+use rusqlite::{params, Connection, Transaction};
+use std::fs;
+use std::path::Path;
+
+/// Name of the SQLite database written to the output path.
+const SQLITE_FILE: &'static str = "powers.sqlite";
+
+/// Creates the tables, replacing any that already exist.
+const SCHEMA: &'static str = "
+    DROP TABLE IF EXISTS power_redirects;
+    DROP TABLE IF EXISTS effects;
+    DROP TABLE IF EXISTS powers;
+    DROP TABLE IF EXISTS power_sets;
+    DROP TABLE IF EXISTS categories;
+    CREATE TABLE categories (
+        id INTEGER PRIMARY KEY,
+        name TEXT COLLATE NOCASE,
+        display_name TEXT
+    );
+    CREATE TABLE power_sets (
+        id INTEGER PRIMARY KEY,
+        category_id INTEGER NOT NULL REFERENCES categories (id),
+        full_name TEXT COLLATE NOCASE,
+        display_name TEXT,
+        system TEXT NOT NULL,
+        specialize_requires TEXT,
+        set_buy_requires TEXT
+    );
+    CREATE TABLE powers (
+        id INTEGER PRIMARY KEY,
+        power_set_id INTEGER NOT NULL REFERENCES power_sets (id),
+        full_name TEXT COLLATE NOCASE,
+        display_name TEXT,
+        power_type TEXT NOT NULL,
+        available_at_level INTEGER,
+        accuracy REAL NOT NULL,
+        activate_period REAL NOT NULL,
+        cast_time REAL NOT NULL,
+        recharge_time REAL NOT NULL,
+        endurance_cost REAL NOT NULL,
+        range REAL NOT NULL,
+        radius REAL NOT NULL,
+        arc REAL NOT NULL,
+        max_targets_hit INTEGER NOT NULL,
+        buy_requires TEXT,
+        activate_requires TEXT,
+        target_requires TEXT
+    );
+    CREATE TABLE effects (
+        id INTEGER PRIMARY KEY,
+        power_id INTEGER NOT NULL REFERENCES powers (id),
+        chance REAL NOT NULL,
+        requires TEXT,
+        attribs TEXT,
+        application_type TEXT NOT NULL,
+        mod_type TEXT NOT NULL,
+        target TEXT NOT NULL,
+        table_name TEXT,
+        scale REAL NOT NULL,
+        magnitude REAL NOT NULL,
+        duration REAL NOT NULL,
+        delay REAL NOT NULL,
+        period REAL NOT NULL,
+        tick_chance REAL NOT NULL
+    );
+    CREATE TABLE power_redirects (
+        power_id INTEGER NOT NULL REFERENCES powers (id),
+        target_power_id INTEGER REFERENCES powers (id),
+        target_name TEXT COLLATE NOCASE,
+        requires TEXT,
+        show_in_info INTEGER NOT NULL
+    );
+";
+
+/// Writes the powers dictionary to a SQLite database in the output path.
+///
+/// # Arguments:
+///
+/// * `powers_dict` - A `PowersDictionary` containing a hierarchy of categories, power sets, and powers.
+/// * `config` - Configuration information.
+///
+/// # Returns:
+///
+/// Nothing if the operation was successful. Otherwise, an `OutputError` containing the error information.
+pub fn write_powers_sqlite(
+    powers_dict: &PowersDictionary,
+    config: &PowersConfig,
+) -> Result<(), OutputError> {
+    fs::create_dir_all(&config.output_path)
+        .map_err(OutputError::path(Path::new(&config.output_path)))?;
+    let output_file = config.join_to_output_path(SQLITE_FILE);
+    info!("Writing: {} ...", output_file.display());
+    write_sqlite(powers_dict, &output_file).map_err(OutputError::database(&output_file))
+}
+
+/// Writes the powers dictionary to a SQLite database.
+///
+/// # Arguments:
+///
+/// * `powers_dict` - A `PowersDictionary` containing a hierarchy of categories, power sets, and powers.
+/// * `path` - The database file. Any tables written by a previous run are replaced.
+///
+/// # Returns:
+///
+/// Nothing if the operation was successful. Otherwise, a `rusqlite::Error` containing the error information.
+///
+/// # Notes:
+///
+/// Only objects marked `include_in_output` are written. The `categories`, `power_sets`, `powers`, and
+/// `effects` tables each reference their owner by id. Nested effect groups are flattened into `effects`.
+/// `power_redirects` links powers to the powers they redirect to, with `target_power_id` left null if
+/// the target wasn't written.
+pub fn write_sqlite(powers_dict: &PowersDictionary, path: &Path) -> rusqlite::Result<()> {
+    let mut conn = Connection::open(path)?;
+    // SQLite leaves foreign keys unchecked unless each connection asks for them
+    conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+    conn.execute_batch(SCHEMA)?;
+    let tx = conn.transaction()?;
+    for pcat in powers_dict.power_categories.iter().map(|p| p.borrow()) {
+        if !pcat.include_in_output {
+            continue;
+        }
+        tx.execute(
+            "INSERT INTO categories (name, display_name) VALUES (?1, ?2)",
+            params![
+                pcat.pch_name.as_ref().map(|n| n.get()),
+                pcat.pch_display_name
+            ],
+        )?;
+        let category_id = tx.last_insert_rowid();
+        for pset in pcat.pp_power_sets.iter().map(|p| p.borrow()) {
+            if pset.include_in_output {
+                insert_power_set(&tx, category_id, &*pset, &powers_dict.attrib_names)?;
+            }
+        }
+    }
+    // resolve redirects now that every power has an id
+    tx.execute(
+        "UPDATE power_redirects SET target_power_id =
+            (SELECT id FROM powers WHERE powers.full_name = power_redirects.target_name LIMIT 1)",
+        [],
+    )?;
+    tx.commit()
+}
+
+/// Inserts a power set and its included powers.
+fn insert_power_set(
+    tx: &Transaction,
+    category_id: i64,
+    pset: &BasePowerSet,
+    attrib_names: &AttribNames,
+) -> rusqlite::Result<()> {
+    tx.execute(
+        "INSERT INTO power_sets (category_id, full_name, display_name, system, specialize_requires,
+            set_buy_requires) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            category_id,
+            pset.pch_full_name.as_ref().map(|n| n.get()),
+            pset.pch_display_name,
+            pset.e_system.get_string(),
+            requires_to_string(&pset.pp_specialize_requires),
+            requires_to_string(&pset.ppch_set_buy_requires),
+        ],
+    )?;
+    let power_set_id = tx.last_insert_rowid();
+    for power in pset.pp_powers.iter().map(|p| p.borrow()) {
+        if !power.include_in_output {
+            continue;
+        }
+        let available_at_level = power.pch_full_name.as_ref().and_then(|name| {
+            pset.available_levels()
+                .find(|(n, _)| *n == name)
+                .map(|(_, level)| level)
+        });
+        tx.execute(
+            "INSERT INTO powers (power_set_id, full_name, display_name, power_type, available_at_level,
+                accuracy, activate_period, cast_time, recharge_time, endurance_cost, range, radius, arc,
+                max_targets_hit, buy_requires, activate_requires, target_requires)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+            params![
+                power_set_id,
+                power.pch_full_name.as_ref().map(|n| n.get()),
+                power.pch_display_name,
+                power.e_type.get_string(),
+                available_at_level,
+                power.f_accuracy,
+                power.f_activate_period,
+                power.f_time_to_activate,
+                power.f_recharge_time,
+                power.f_endurance_cost,
+                power.f_range,
+                power.f_radius,
+                power.f_arc,
+                power.i_max_targets_hit,
+                requires_to_string(&power.ppch_buy_requires),
+                requires_to_string(&power.ppch_activate_requires),
+                requires_to_string(&power.ppch_target_requires),
+            ],
+        )?;
+        let power_id = tx.last_insert_rowid();
+        for effect_group in &power.pp_effects {
+            insert_effect_group(tx, power_id, &*effect_group.borrow(), attrib_names)?;
+        }
+        for redirect in &power.pp_redirect {
+            tx.execute(
+                "INSERT INTO power_redirects (power_id, target_name, requires, show_in_info)
+                    VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    power_id,
+                    redirect.pch_name.as_ref().map(|n| n.get()),
+                    requires_to_string(&redirect.ppch_requires),
+                    redirect.b_show_in_info,
+                ],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Inserts one row per attrib mod in `effect_group` and any nested effect groups.
+fn insert_effect_group(
+    tx: &Transaction,
+    power_id: i64,
+    effect_group: &EffectGroup,
+    attrib_names: &AttribNames,
+) -> rusqlite::Result<()> {
+    let requires = requires_to_string(&effect_group.ppch_requires);
+    for template in &effect_group.pp_templates {
+        let attribs: Vec<_> = template
+            .p_attrib
+            .iter()
+            .filter_map(|a| a.get_string(attrib_names))
+            .collect();
+        tx.execute(
+            "INSERT INTO effects (power_id, chance, requires, attribs, application_type, mod_type, target,
+                table_name, scale, magnitude, duration, delay, period, tick_chance)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                power_id,
+                effect_group.f_chance,
+                requires,
+                if attribs.is_empty() {
+                    None
+                } else {
+                    Some(attribs.join(", "))
+                },
+                template.e_application_type.get_string(),
+                template.e_type.get_string(),
+                template.e_target.get_string(),
+                template.pch_table,
+                template.f_scale,
+                template.f_magnitude,
+                template.f_duration.to_f32(),
+                template.f_delay,
+                template.f_period,
+                template.f_tick_chance,
+            ],
+        )?;
+    }
+    for child in &effect_group.pp_effects {
+        insert_effect_group(tx, power_id, child, attrib_names)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn make_dict() -> PowersDictionary {
+        let mut power_set = BasePowerSet::new();
+        power_set.include_in_output = true;
+        power_set.pch_full_name = Some(NameKey::new("Pool.Flight"));
+        for name in &["Pool.Flight.Hover", "Pool.Flight.Fly", "Pool.Flight.Unused"] {
+            let mut power = BasePower::new();
+            power.pch_full_name = Some(NameKey::new(*name));
+            power.include_in_output = !name.ends_with("Unused");
+            let mut effect_group = EffectGroup::new();
+            effect_group.f_chance = 1.0;
+            effect_group.pp_templates.push(AttribModTemplate::new());
+            let mut child = EffectGroup::new();
+            child.ppch_requires = vec![String::from("enttype target>.Name eq 'Critter'")];
+            child.pp_templates.push(AttribModTemplate::new());
+            effect_group.pp_effects.push(child);
+            power.pp_effects.push(Rc::new(RefCell::new(effect_group)));
+            power_set.pp_powers.push(Rc::new(RefCell::new(power)));
+        }
+        let mut redirect = PowerRedirect::new();
+        redirect.pch_name = Some(NameKey::new("pool.flight.fly"));
+        power_set.pp_powers[0]
+            .borrow_mut()
+            .pp_redirect
+            .push(redirect);
+        let mut pcat = PowerCategory::new();
+        pcat.include_in_output = true;
+        pcat.pch_name = Some(NameKey::new("Pool"));
+        pcat.pp_power_sets.push(Rc::new(RefCell::new(power_set)));
+        PowersDictionary::from_power_categories(vec![Rc::new(RefCell::new(pcat))])
+    }
+
+    #[test]
+    fn write_sqlite_test() {
+        let path =
+            std::env::temp_dir().join(format!("powers_sqlite_test_{}.sqlite", std::process::id()));
+        let powers_dict = make_dict();
+        // writing twice replaces the tables instead of failing
+        write_sqlite(&powers_dict, &path).unwrap();
+        write_sqlite(&powers_dict, &path).unwrap();
+
+        let conn = Connection::open(&path).unwrap();
+        let count = |table: &str| -> i64 {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+        };
+        assert_eq!(count("categories"), 1);
+        assert_eq!(count("power_sets"), 1);
+        assert_eq!(count("powers"), 2);
+        assert_eq!(count("effects"), 4);
+        assert_eq!(count("power_redirects"), 1);
+        let target: String = conn
+            .query_row(
+                "SELECT p.full_name FROM power_redirects r JOIN powers p ON p.id = r.target_power_id",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(target, "Pool.Flight.Fly");
+        let requires: String = conn
+            .query_row(
+                "SELECT requires FROM effects WHERE requires IS NOT NULL",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(requires, "enttype target>.Name eq 'Critter'");
+        let broken_keys: i64 = conn
+            .query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(broken_keys, 0);
+        drop(conn);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,239 @@
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::fmt::Display;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+/// Destination for progress messages and warnings while loading or writing data.
+///
+/// Warnings always go to the error stream so they don't get mixed in with data. Progress
+/// messages normally go to the output stream, but in streaming mode (where the output
+/// stream carries the actual data) they're sent to the error stream as well.
+pub struct ProgressSink<O: Write, E: Write> {
+    out: O,
+    err: E,
+    streaming: bool,
+}
+
+/// A `ProgressSink` that writes to stdout/stderr.
+pub type StdioProgress = ProgressSink<io::Stdout, io::Stderr>;
+
+impl StdioProgress {
+    /// Creates a `ProgressSink` that writes to stdout/stderr.
+    pub fn stdio(streaming: bool) -> Self {
+        ProgressSink::new(io::stdout(), io::stderr(), streaming)
+    }
+}
+
+impl<O: Write, E: Write> ProgressSink<O, E> {
+    /// Creates a new `ProgressSink`.
+    ///
+    /// # Arguments:
+    ///
+    /// * `out` - Where progress messages are written.
+    /// * `err` - Where warnings are written.
+    /// * `streaming` - If `true`, `out` is reserved for data and progress messages are written
+    ///   to `err` instead.
+    pub fn new(out: O, err: E, streaming: bool) -> Self {
+        ProgressSink {
+            out,
+            err,
+            streaming,
+        }
+    }
+
+    /// Writes a progress message.
+    pub fn progress<T: Display>(&mut self, message: T) {
+        // failing to report progress shouldn't stop the run
+        let _ = if self.streaming {
+            writeln!(self.err, "{}", message)
+        } else {
+            writeln!(self.out, "{}", message)
+        };
+    }
+
+    /// Writes a warning message.
+    pub fn warning<T: Display>(&mut self, message: T) {
+        let _ = writeln!(self.err, "WARNING: {}", message);
+    }
+}
+
+/// A `log` backend that writes records to a `ProgressSink`. Warnings and errors are written as
+/// warnings, everything else as progress messages.
+pub struct ProgressLogger<O: Write + Send, E: Write + Send> {
+    sink: Mutex<ProgressSink<O, E>>,
+    level: LevelFilter,
+}
+
+impl ProgressLogger<io::Stdout, io::Stderr> {
+    /// Installs a `ProgressLogger` writing to stdout/stderr as the global logger.
+    ///
+    /// # Arguments:
+    ///
+    /// * `level` - The most verbose level that's written.
+    /// * `streaming` - If `true`, stdout is reserved for data and progress messages go to stderr.
+    ///
+    /// # Returns:
+    ///
+    /// Nothing if successful, or a `SetLoggerError` if a logger was already installed.
+    pub fn init_stdio(level: LevelFilter, streaming: bool) -> Result<(), SetLoggerError> {
+        log::set_boxed_logger(Box::new(ProgressLogger::new(
+            StdioProgress::stdio(streaming),
+            level,
+        )))?;
+        log::set_max_level(level);
+        Ok(())
+    }
+}
+
+impl<O: Write + Send, E: Write + Send> ProgressLogger<O, E> {
+    /// Creates a new `ProgressLogger` writing records up to `level` to `sink`.
+    pub fn new(sink: ProgressSink<O, E>, level: LevelFilter) -> Self {
+        ProgressLogger {
+            sink: Mutex::new(sink),
+            level,
+        }
+    }
+}
+
+impl<O: Write + Send, E: Write + Send> Log for ProgressLogger<O, E> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if let Ok(mut sink) = self.sink.lock() {
+            match record.level() {
+                Level::Error | Level::Warn => sink.warning(record.args()),
+                _ => sink.progress(record.args()),
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut sink) = self.sink.lock() {
+            let _ = sink.out.flush();
+            let _ = sink.err.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streaming_separation_test() {
+        let mut sink = ProgressSink::new(Vec::new(), Vec::new(), true);
+        sink.progress("Reading powers.bin ...");
+        sink.warning("Excluded power Pool.Test.Missing not found.");
+        serde_json::to_writer(&mut sink.out, &vec!["Pool.Flight.Fly"]).unwrap();
+
+        assert_eq!(
+            String::from_utf8(sink.out).unwrap(),
+            r#"["Pool.Flight.Fly"]"#
+        );
+        let err = String::from_utf8(sink.err).unwrap();
+        assert!(err.contains("Reading powers.bin ..."));
+        assert!(err.contains("WARNING: Excluded power Pool.Test.Missing not found."));
+    }
+
+    #[test]
+    fn non_streaming_separation_test() {
+        let mut sink = ProgressSink::new(Vec::new(), Vec::new(), false);
+        sink.progress("Merging dictionaries ...");
+        sink.warning("Something is off.");
+        assert_eq!(
+            String::from_utf8(sink.out).unwrap(),
+            "Merging dictionaries ...\n"
+        );
+        assert_eq!(
+            String::from_utf8(sink.err).unwrap(),
+            "WARNING: Something is off.\n"
+        );
+    }
+
+    #[test]
+    fn progress_logger_test() {
+        let logger = ProgressLogger::new(
+            ProgressSink::new(Vec::new(), Vec::new(), false),
+            LevelFilter::Info,
+        );
+        let log = |level: Level, message: &str| {
+            logger.log(
+                &Record::builder()
+                    .level(level)
+                    .args(format_args!("{}", message))
+                    .build(),
+            )
+        };
+        log(Level::Info, "Reading powers.bin ...");
+        log(Level::Debug, "Matched Blaster to primary Blaster_Ranged");
+        log(Level::Warn, "Excluded power Pool.Test.Missing not found.");
+
+        let sink = logger.sink.into_inner().unwrap();
+        assert_eq!(
+            String::from_utf8(sink.out).unwrap(),
+            "Reading powers.bin ...\n"
+        );
+        assert_eq!(
+            String::from_utf8(sink.err).unwrap(),
+            "WARNING: Excluded power Pool.Test.Missing not found.\n"
+        );
+    }
+
+    /// Set for the child process of `stdio_streaming_test`.
+    const STDIO_CHILD_VAR: &'static str = "POWERS_STDIO_STREAMING_CHILD";
+
+    /// Writes a progress message, a warning, and some JSON to the real stdout/stderr. Does nothing
+    /// unless run as the child process of `stdio_streaming_test`.
+    #[test]
+    fn stdio_streaming_child() {
+        if std::env::var_os(STDIO_CHILD_VAR).is_none() {
+            return;
+        }
+        let logger = ProgressLogger::new(StdioProgress::stdio(true), LevelFilter::Info);
+        logger.log(
+            &Record::builder()
+                .level(Level::Info)
+                .args(format_args!("Reading powers.bin ..."))
+                .build(),
+        );
+        logger.log(
+            &Record::builder()
+                .level(Level::Warn)
+                .args(format_args!("Excluded power Pool.Test.Missing not found."))
+                .build(),
+        );
+        let mut out = io::stdout();
+        serde_json::to_writer(&mut out, &vec!["Pool.Flight.Fly"]).unwrap();
+        writeln!(out).unwrap();
+        logger.flush();
+    }
+
+    #[test]
+    fn stdio_streaming_test() {
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(&[
+                "--exact",
+                "progress::tests::stdio_streaming_child",
+                "--nocapture",
+                "--test-threads=1",
+            ])
+            .env(STDIO_CHILD_VAR, "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        // stdout also has the test harness output, but none of the diagnostics
+        assert!(stdout.contains("[\"Pool.Flight.Fly\"]\n"));
+        assert!(!stdout.contains("Reading powers.bin"));
+        assert!(!stdout.contains("WARNING"));
+        assert!(stderr.contains("Reading powers.bin ...\n"));
+        assert!(stderr.contains("WARNING: Excluded power Pool.Test.Missing not found.\n"));
+        assert!(!stderr.contains("Pool.Flight.Fly"));
+    }
+}
@@ -1,13 +1,96 @@
-use super::AttribNames;
+use super::{AttribName, AttribNames};
+use serde::ser::SerializeStruct;
 use serde::{Serialize, Serializer};
 use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
-/// Global cache for the current `AttribNames` data. Some background on this... this is absolutely
-/// not the best way to do this, but a compromise. I didn't want to use `serde_state` as a dependency
-/// and a global variable seemed the easiest way to accomplish serializing with state without
-/// complicated dependencies.
-pub static mut GLOBAL_ATTRIB_NAMES: Option<Rc<AttribNames>> = None;
+thread_local! {
+    /// The `AttribNames` used by the attribute `Serialize` impls on this thread. The raw structs
+    /// only store indexes into the name tables, so the names have to be supplied out of band while
+    /// serializing. See `AttribNamesScope`.
+    static SCOPED_ATTRIB_NAMES: RefCell<Option<Rc<AttribNames>>> = RefCell::new(None);
+    /// Whether `CharacterAttrib` is serialized with its offset on this thread. See
+    /// `AttribsVerboseScope`.
+    static SCOPED_ATTRIBS_VERBOSE: Cell<bool> = Cell::new(false);
+}
+
+/// Guard that makes an `AttribNames` table available to the attribute `Serialize` impls
+/// (`CharacterAttrib`, `ModeAttrib`, and `BoostAttrib`) on the current thread until it's dropped.
+pub struct AttribNamesScope {
+    previous: Option<Rc<AttribNames>>,
+}
+
+impl AttribNamesScope {
+    /// Sets `attrib_names` as the name table for the current thread.
+    ///
+    /// # Returns:
+    /// A guard that restores the previous name table (normally none) when dropped.
+    pub fn enter(attrib_names: Rc<AttribNames>) -> Self {
+        let previous = SCOPED_ATTRIB_NAMES.with(|names| names.replace(Some(attrib_names)));
+        AttribNamesScope { previous }
+    }
+}
+
+impl Drop for AttribNamesScope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        SCOPED_ATTRIB_NAMES.with(|names| *names.borrow_mut() = previous);
+    }
+}
+
+/// Guard that makes `CharacterAttrib` serialize as an object with both its name and its offset
+/// (`{"name": ..., "offset": ...}`) instead of just the name on the current thread until it's
+/// dropped.
+pub struct AttribsVerboseScope {
+    previous: bool,
+}
+
+impl AttribsVerboseScope {
+    /// Sets whether attributes are serialized with their offsets for the current thread.
+    ///
+    /// # Returns:
+    /// A guard that restores the previous setting (normally `false`) when dropped.
+    pub fn enter(attribs_verbose: bool) -> Self {
+        let previous = SCOPED_ATTRIBS_VERBOSE.with(|f| f.replace(attribs_verbose));
+        AttribsVerboseScope { previous }
+    }
+}
+
+impl Drop for AttribsVerboseScope {
+    fn drop(&mut self) {
+        SCOPED_ATTRIBS_VERBOSE.with(|f| f.set(self.previous));
+    }
+}
+
+/// Serializes `value` with `attrib_names` as the name table for any attributes it contains.
+#[allow(dead_code)]
+pub fn serialize_with_attrib_names<T, S>(
+    value: &T,
+    attrib_names: &Rc<AttribNames>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    T: Serialize + ?Sized,
+    S: Serializer,
+{
+    let _scope = AttribNamesScope::enter(Rc::clone(attrib_names));
+    value.serialize(serializer)
+}
+
+/// Gets the name table for the current thread.
+///
+/// # Panics:
+/// If called outside of an `AttribNamesScope`.
+fn scoped_attrib_names() -> Rc<AttribNames> {
+    SCOPED_ATTRIB_NAMES.with(|names| {
+        names
+            .borrow()
+            .as_ref()
+            .map(Rc::clone)
+            .expect("attributes must be serialized inside an AttribNamesScope")
+    })
+}
 
 /// Used in attribute name tables.
 pub const ORIGINS_SIZE: usize = 5;
@@ -15,6 +98,14 @@ pub const ORIGINS_SIZE: usize = 5;
 /// Matches the width of pointers in the game structs (32 bits).
 pub const PTR_SIZE: usize = 4;
 
+// Offsets of each aspect in `CharacterAttribSet`.
+pub const OFFSET_MODIFIERS: u32 = 0;
+pub const OFFSET_MAXIMUM: u32 = 8;
+pub const OFFSET_STRENGTH: u32 = 16;
+pub const OFFSET_RESIST: u32 = 24;
+/// The "absolute" aspect, which is how damage is applied.
+pub const OFFSET_ABSOLUTE: u32 = 32;
+
 /// Defines the attributes which can be modified by effects.
 #[derive(Debug, Default, Serialize)]
 pub struct CharacterAttributes {
@@ -378,6 +469,42 @@ impl CharacterAttributes {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Gets the damage (resistance) value for the damage type called `name`.
+    ///
+    /// # Arguments:
+    ///
+    /// * `name` - The internal or display name of the damage type (e.g. "Smashing"), compared
+    ///   case-insensitively.
+    /// * `attrib_names` - The name tables used to find the index for `name`.
+    ///
+    /// # Returns:
+    ///
+    /// The value in `f_damage_type`, or `None` if `name` isn't in `attrib_names.pp_damage`.
+    pub fn damage_type(&self, name: &str, attrib_names: &AttribNames) -> Option<f32> {
+        find_named_index(&attrib_names.pp_damage, name)
+            .and_then(|i| self.f_damage_type.get(i).copied())
+    }
+
+    /// Gets the defense value for the defense type called `name`. See `damage_type`.
+    pub fn defense_type(&self, name: &str, attrib_names: &AttribNames) -> Option<f32> {
+        find_named_index(&attrib_names.pp_defense, name)
+            .and_then(|i| self.f_defense_type.get(i).copied())
+    }
+
+    /// Gets the elusivity value for the type called `name`. See `damage_type`.
+    pub fn elusivity(&self, name: &str, attrib_names: &AttribNames) -> Option<f32> {
+        find_named_index(&attrib_names.pp_elusivity, name)
+            .and_then(|i| self.f_elusivity.get(i).copied())
+    }
+}
+
+/// Finds the index of the entry in `names` whose internal or display name matches `name`.
+fn find_named_index(names: &[AttribName], name: &str) -> Option<usize> {
+    let matches = |n: &Option<String>| matches!(n, Some(n) if n.eq_ignore_ascii_case(name));
+    names
+        .iter()
+        .position(|n| matches(&n.pch_name) || matches(&n.pch_display_name))
 }
 
 /// Defines the attributes which can be modified by effects.
@@ -473,6 +600,17 @@ impl CharacterAttrib {
         }
     }
 
+    /// Returns true if this attribute is a "boolean" status (Held, Sleep, Fly, etc.). For these, the
+    /// magnitudes of all effects are summed (protection is negative) and the status applies to the
+    /// character if the total is above zero, so the stored value is a magnitude rather than a value
+    /// the attribute is set to.
+    pub fn is_boolean_status(&self) -> bool {
+        matches!(
+            self.usize(),
+            CharacterAttributes::OFFSET_CONFUSED..=CharacterAttributes::OFFSET_ONLY_AFFECTS_SELF
+        )
+    }
+
     /// Converts a character attribute to a human readable string.
     ///
     /// # Arguments:
@@ -606,12 +744,18 @@ impl Serialize for CharacterAttrib {
     where
         S: Serializer,
     {
-        let attrib_names = unsafe {
-            GLOBAL_ATTRIB_NAMES
-                .as_ref()
-                .expect("GLOBAL_ATTRIB_NAMES was not initialized")
-        };
-        if let Some(s) = self.get_string(attrib_names) {
+        if !serializer.is_human_readable() {
+            // no name lookups for non-human readable formats (e.g. `StructuralHasher`)
+            return serializer.serialize_i32(self.0);
+        }
+        let attrib_names = scoped_attrib_names();
+        let name = self.get_string(&attrib_names);
+        if SCOPED_ATTRIBS_VERBOSE.with(|f| f.get()) {
+            let mut s = serializer.serialize_struct("CharacterAttrib", 2)?;
+            s.serialize_field("name", &name)?;
+            s.serialize_field("offset", &self.0)?;
+            s.end()
+        } else if let Some(s) = name {
             serializer.serialize_str(&s)
         } else {
             serializer.serialize_none()
@@ -650,12 +794,12 @@ impl Serialize for ModeAttrib {
     where
         S: Serializer,
     {
-        let attrib_names = unsafe {
-            GLOBAL_ATTRIB_NAMES
-                .as_ref()
-                .expect("GLOBAL_ATTRIB_NAMES was not initialized")
-        };
-        if let Some(s) = self.get_string(attrib_names) {
+        if !serializer.is_human_readable() {
+            // no name lookups for non-human readable formats (e.g. `StructuralHasher`)
+            return serializer.serialize_i32(self.0);
+        }
+        let attrib_names = scoped_attrib_names();
+        if let Some(s) = self.get_string(&attrib_names) {
             serializer.serialize_str(&s)
         } else {
             serializer.serialize_none()
@@ -663,6 +807,17 @@ impl Serialize for ModeAttrib {
     }
 }
 
+/// The result of resolving a `BoostAttrib` against the boost name table.
+#[derive(Debug, PartialEq)]
+pub enum BoostIndex {
+    /// An index into `AttribNames::pp_boost`.
+    Boost(usize),
+    /// One of the origins that precede the boosts (0 through `ORIGINS_SIZE - 1`).
+    OriginSequence(u8),
+    /// Doesn't refer to anything in the table.
+    OutOfRange,
+}
+
 #[derive(Debug, Default)]
 pub struct BoostAttrib(pub i32);
 
@@ -671,6 +826,35 @@ impl BoostAttrib {
         self.0 as usize
     }
 
+    /// Works out what part of the boost name table this attribute refers to.
+    ///
+    /// # Arguments:
+    /// * `attrib_names` - The attribute name table.
+    ///
+    /// # Returns:
+    /// A `BoostIndex`.
+    ///
+    /// # Notes:
+    /// Boost attributes are offset by `ORIGINS_SIZE`. Why? Good question! Check this lovely note I found in the code:
+    ///
+    /// > mw 3.10.06 added guard here because it's everywhere else this calc is done,
+    /// > and there's reported crash here that I can't repro, so I'm doing this and hoping for the best
+    /// > (subtracting off the number of origins seems insane and neither Jered nor CW can remember why its needed)
+    ///
+    /// Coding is weird, folks :)
+    ///
+    /// Follow up: The weird 4..3..2..1..0 sequence seen in several powers (such as incarnates) appears to be
+    /// a reference to those origins, so they're reported as `OriginSequence` rather than being dropped.
+    pub fn resolve_index(&self, attrib_names: &AttribNames) -> BoostIndex {
+        match self.usize() {
+            i if i < ORIGINS_SIZE => BoostIndex::OriginSequence(i as u8),
+            i @ ORIGINS_SIZE..=99 if i - ORIGINS_SIZE < attrib_names.pp_boost.len() => {
+                BoostIndex::Boost(i - ORIGINS_SIZE)
+            }
+            _ => BoostIndex::OutOfRange,
+        }
+    }
+
     /// Converts a boost attribute to a human readable string.
     ///
     /// # Arguments:
@@ -679,25 +863,26 @@ impl BoostAttrib {
     /// # Returns:
     /// A String with a human readable name for the attribute.
     pub fn get_string(&self, attrib_names: &AttribNames) -> Option<String> {
-        match self.usize() {
-            i @ ORIGINS_SIZE..=99 => {
-                // Why do we subtract ORIGINS_SIZE? Good question! Check this lovely note I found in the code:
-                //
-                // > mw 3.10.06 added guard here because it's everywhere else this calc is done,
-                // > and there's reported crash here that I can't repro, so I'm doing this and hoping for the best
-                // > (subtracting off the number of origins seems insane and neither Jered nor CW can remember why its needed)
-                //
-                // Coding is weird, folks :)
-                //
-                // Follow up: It's possible the weird 4..3..2..1..0 sequence seen in several powers (such as incarnates) is a
-                // reference to those origins that's been trimmed out here.
-                if let Some(name) = attrib_names.pp_boost.get(i - ORIGINS_SIZE) {
-                    name.pch_display_name.clone()
-                } else {
-                    None
-                }
-            }
-            _ => None,
+        self.get_string_with_origins(attrib_names, false)
+    }
+
+    /// Converts a boost attribute to a human readable string.
+    ///
+    /// # Arguments:
+    /// * `attrib_names` - The attribute name table.
+    /// * `include_origins` - If true, origin indices are rendered as `Origin_N` instead of `None`.
+    ///
+    /// # Returns:
+    /// A String with a human readable name for the attribute.
+    pub fn get_string_with_origins(
+        &self,
+        attrib_names: &AttribNames,
+        include_origins: bool,
+    ) -> Option<String> {
+        match self.resolve_index(attrib_names) {
+            BoostIndex::Boost(i) => attrib_names.pp_boost[i].pch_display_name.clone(),
+            BoostIndex::OriginSequence(n) if include_origins => Some(format!("Origin_{}", n)),
+            BoostIndex::OriginSequence(_) | BoostIndex::OutOfRange => None,
         }
     }
 }
@@ -707,12 +892,12 @@ impl Serialize for BoostAttrib {
     where
         S: Serializer,
     {
-        let attrib_names = unsafe {
-            GLOBAL_ATTRIB_NAMES
-                .as_ref()
-                .expect("GLOBAL_ATTRIB_NAMES was not initialized")
-        };
-        if let Some(s) = self.get_string(attrib_names) {
+        if !serializer.is_human_readable() {
+            // no name lookups for non-human readable formats (e.g. `StructuralHasher`)
+            return serializer.serialize_i32(self.0);
+        }
+        let attrib_names = scoped_attrib_names();
+        if let Some(s) = self.get_string(&attrib_names) {
             serializer.serialize_str(&s)
         } else {
             serializer.serialize_none()
@@ -895,3 +1080,159 @@ impl SpecialAttrib {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn named_type_accessors_test() {
+        let mut attrib_names = AttribNames::new();
+        for table in &mut [
+            &mut attrib_names.pp_damage,
+            &mut attrib_names.pp_defense,
+            &mut attrib_names.pp_elusivity,
+        ] {
+            for name in &["Smashing", "Lethal"] {
+                let mut attrib_name = AttribName::new();
+                attrib_name.pch_name = Some(name.to_string());
+                attrib_name.pch_display_name = Some(name.to_string());
+                table.push(attrib_name);
+            }
+        }
+        let mut attribs = CharacterAttributes::new();
+        attribs.f_damage_type[0] = 0.1;
+        attribs.f_defense_type[0] = 0.2;
+        attribs.f_elusivity[0] = 0.3;
+        attribs.f_defense_type[1] = 0.4;
+
+        assert_eq!(attribs.damage_type("Smashing", &attrib_names), Some(0.1));
+        assert_eq!(attribs.defense_type("smashing", &attrib_names), Some(0.2));
+        assert_eq!(attribs.elusivity("Smashing", &attrib_names), Some(0.3));
+        assert_eq!(attribs.defense_type("Lethal", &attrib_names), Some(0.4));
+        assert_eq!(attribs.damage_type("Lethal", &attrib_names), Some(0.0));
+        assert_eq!(attribs.damage_type("Fire", &attrib_names), None);
+    }
+
+    #[test]
+    fn is_boolean_status_test() {
+        let held = CharacterAttrib(CharacterAttributes::OFFSET_HELD as i32);
+        let sleep = CharacterAttrib(CharacterAttributes::OFFSET_SLEEP as i32);
+        let hit_points = CharacterAttrib(CharacterAttributes::OFFSET_HIT_POINTS as i32);
+        let defense = CharacterAttrib(CharacterAttributes::OFFSET_DEFENSE as i32);
+        assert!(held.is_boolean_status());
+        assert!(sleep.is_boolean_status());
+        assert!(!hit_points.is_boolean_status());
+        assert!(!defense.is_boolean_status());
+    }
+
+    #[test]
+    fn attrib_names_scope_test() {
+        let make_names = |mode: &str| {
+            let mut attrib_names = AttribNames::new();
+            for name in &["ServerTrayOverride", mode] {
+                let mut attrib_name = AttribName::new();
+                attrib_name.pch_name = Some(String::from(*name));
+                attrib_names.pp_mode.push(attrib_name);
+            }
+            Rc::new(attrib_names)
+        };
+        let threads: Vec<_> = ["Dwarf", "Nova"]
+            .iter()
+            .map(|mode| {
+                std::thread::spawn(move || {
+                    let attrib_names = make_names(mode);
+                    for _ in 0..100 {
+                        let mut json = Vec::new();
+                        let mut serializer = serde_json::Serializer::new(&mut json);
+                        serialize_with_attrib_names(&ModeAttrib(1), &attrib_names, &mut serializer)
+                            .unwrap();
+                        assert_eq!(String::from_utf8(json).unwrap(), format!("\"{}\"", mode));
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        // dropping an inner scope puts the outer one back
+        let mode = ModeAttrib(1);
+        {
+            let _outer = AttribNamesScope::enter(make_names("Dwarf"));
+            assert_eq!(serde_json::to_string(&mode).unwrap(), r#""Dwarf""#);
+            {
+                let _inner = AttribNamesScope::enter(make_names("Nova"));
+                assert_eq!(serde_json::to_string(&mode).unwrap(), r#""Nova""#);
+            }
+            assert_eq!(serde_json::to_string(&mode).unwrap(), r#""Dwarf""#);
+            let mut json = Vec::new();
+            let mut serializer = serde_json::Serializer::new(&mut json);
+            serialize_with_attrib_names(&mode, &make_names("Nova"), &mut serializer).unwrap();
+            assert_eq!(serde_json::to_string(&mode).unwrap(), r#""Dwarf""#);
+        }
+        assert!(SCOPED_ATTRIB_NAMES.with(|names| names.borrow().is_none()));
+    }
+
+    #[test]
+    fn attribs_verbose_test() {
+        let _scope = AttribNamesScope::enter(Rc::new(AttribNames::new()));
+        let hit_points = CharacterAttrib(CharacterAttributes::OFFSET_HIT_POINTS as i32);
+        assert_eq!(hit_points.0, 80);
+        assert_eq!(serde_json::to_string(&hit_points).unwrap(), r#""HitPoints""#);
+        {
+            let _verbose_scope = AttribsVerboseScope::enter(true);
+            assert_eq!(
+                serde_json::to_string(&hit_points).unwrap(),
+                r#"{"name":"HitPoints","offset":80}"#
+            );
+        }
+        assert_eq!(serde_json::to_string(&hit_points).unwrap(), r#""HitPoints""#);
+    }
+
+    #[test]
+    fn attrib_names_scope_nested_test() {
+        let mut outer = AttribNames::new();
+        outer.pp_mode.push(AttribName::new());
+        let outer = Rc::new(outer);
+        let _outer_scope = AttribNamesScope::enter(Rc::clone(&outer));
+        {
+            let _inner_scope = AttribNamesScope::enter(Rc::new(AttribNames::new()));
+            assert!(scoped_attrib_names().pp_mode.is_empty());
+        }
+        assert_eq!(scoped_attrib_names().pp_mode.len(), 1);
+    }
+
+    #[test]
+    fn boost_resolve_index_test() {
+        let mut attrib_names = AttribNames::new();
+        for name in &["Accuracy", "Damage"] {
+            let mut attrib_name = AttribName::new();
+            attrib_name.pch_display_name = Some(String::from(*name));
+            attrib_names.pp_boost.push(attrib_name);
+        }
+        for i in 0..=4 {
+            let boost = BoostAttrib(i);
+            assert_eq!(
+                boost.resolve_index(&attrib_names),
+                BoostIndex::OriginSequence(i as u8)
+            );
+            assert_eq!(boost.get_string(&attrib_names), None);
+            assert_eq!(
+                boost.get_string_with_origins(&attrib_names, true),
+                Some(format!("Origin_{}", i))
+            );
+        }
+        let accuracy = BoostAttrib(5);
+        assert_eq!(accuracy.resolve_index(&attrib_names), BoostIndex::Boost(0));
+        assert_eq!(
+            accuracy.get_string(&attrib_names).as_deref(),
+            Some("Accuracy")
+        );
+        for i in &[7, 100, -1] {
+            let boost = BoostAttrib(*i);
+            assert_eq!(boost.resolve_index(&attrib_names), BoostIndex::OutOfRange);
+            assert_eq!(boost.get_string_with_origins(&attrib_names, true), None);
+        }
+    }
+}
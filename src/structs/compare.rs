@@ -0,0 +1,104 @@
+//! Field-by-field comparison of two powers' basic stats.
+
+use super::BasePower;
+use serde::Serialize;
+
+/// The differences between the common stats of two powers, as returned by
+/// `BasePower::compare`. Each field is `other - self`, or `None` if the values are equal.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct PowerComparison {
+    pub accuracy: Option<f32>,
+    pub recharge_time: Option<f32>,
+    pub endurance_cost: Option<f32>,
+    pub range: Option<f32>,
+    pub radius: Option<f32>,
+    pub arc: Option<f32>,
+    pub max_targets_hit: Option<f32>,
+    pub time_to_activate: Option<f32>,
+}
+
+impl PowerComparison {
+    /// Tests if every compared stat was equal.
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        *self == Default::default()
+    }
+}
+
+impl BasePower {
+    /// Compares the common scalar stats of this power with `other`, e.g. to see how a redirect
+    /// target differs from the power that redirects to it. This only looks at the power's own
+    /// fields, not its effects. Use `crate::diff` to compare whole dictionaries.
+    ///
+    /// # Returns:
+    ///
+    /// A `PowerComparison` holding `other - self` for each stat that differs.
+    #[allow(dead_code)]
+    pub fn compare(&self, other: &BasePower) -> PowerComparison {
+        PowerComparison {
+            accuracy: delta(self.f_accuracy, other.f_accuracy),
+            recharge_time: delta(self.f_recharge_time, other.f_recharge_time),
+            endurance_cost: delta(self.f_endurance_cost, other.f_endurance_cost),
+            range: delta(self.f_range, other.f_range),
+            radius: delta(self.f_radius, other.f_radius),
+            arc: delta(self.f_arc, other.f_arc),
+            max_targets_hit: delta(
+                self.i_max_targets_hit as f32,
+                other.i_max_targets_hit as f32,
+            ),
+            time_to_activate: delta(self.f_time_to_activate, other.f_time_to_activate),
+        }
+    }
+}
+
+/// Returns `to - from`, or `None` if they're the same.
+fn delta(from: f32, to: f32) -> Option<f32> {
+    if from == to {
+        None
+    } else {
+        Some(to - from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_test() {
+        let mut fire_blast = BasePower::new();
+        fire_blast.f_accuracy = 1.0;
+        fire_blast.f_recharge_time = 4.0;
+        fire_blast.f_endurance_cost = 5.2;
+        fire_blast.f_range = 80.0;
+        fire_blast.i_max_targets_hit = 1;
+        fire_blast.f_time_to_activate = 1.67;
+        let mut fire_breath = BasePower::new();
+        fire_breath.f_accuracy = 1.0;
+        fire_breath.f_recharge_time = 10.0;
+        fire_breath.f_endurance_cost = 5.2;
+        fire_breath.f_range = 40.0;
+        fire_breath.f_arc = 0.5236;
+        fire_breath.i_max_targets_hit = 10;
+        fire_breath.f_time_to_activate = 1.67;
+
+        let comparison = fire_blast.compare(&fire_breath);
+        assert_eq!(
+            comparison,
+            PowerComparison {
+                recharge_time: Some(6.0),
+                range: Some(-40.0),
+                arc: Some(0.5236),
+                max_targets_hit: Some(9.0),
+                ..Default::default()
+            }
+        );
+        assert!(!comparison.is_empty());
+        assert!(fire_blast.compare(&fire_blast).is_empty());
+        assert_eq!(
+            serde_json::to_string(&fire_breath.compare(&fire_blast)).unwrap(),
+            "{\"accuracy\":null,\"recharge_time\":-6.0,\"endurance_cost\":null,\"range\":40.0,\
+             \"radius\":null,\"arc\":-0.5236,\"max_targets_hit\":-9.0,\"time_to_activate\":null}"
+        );
+    }
+}
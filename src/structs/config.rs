@@ -1,6 +1,7 @@
-use super::NameKey;
+use super::{NameKey, PowerFX};
 use chrono::{DateTime, Local};
-use serde::Deserialize;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::{Error, ErrorKind, Result};
@@ -18,6 +19,9 @@ pub struct AssetsConfig {
     pub archetype_icon_format: String,
     /// A format string specifying the URL format for powers.
     pub powers_icon_format: String,
+    /// An icon file name to use for powers that don't specify one.
+    #[serde(default)]
+    pub fallback_power_icon: Option<String>,
 }
 
 
@@ -29,6 +33,16 @@ pub enum OutputFormatConfig {
     Api,
     /// Raw dump of the bins as represented in memory.
     Raw,
+    /// A single .csv file with one row per power.
+    Csv,
+    /// A GraphViz .dot file of the category -> power set -> power hierarchy.
+    Dot,
+    /// The "API" power sets as compact MessagePack files.
+    Msgpack,
+    /// A SQLite database with tables for categories, power sets, powers, and effects.
+    Sqlite,
+    /// One Markdown page per power, in the same folders as the "API" power sets.
+    Markdown,
 }
 
 impl Default for OutputFormatConfig {
@@ -45,6 +59,9 @@ pub enum OutputStyleConfig {
     Pretty,
     /// Saves space by removing unnecessary whitespace.
     Compact,
+    /// Writes YAML instead of JSON, which is easier to edit by hand. Only used by the raw
+    /// output format; the API format writes pretty JSON since its URLs refer to .json files.
+    Yaml,
 }
 
 impl Default for OutputStyleConfig {
@@ -53,6 +70,96 @@ impl Default for OutputStyleConfig {
     }
 }
 
+/// Configuration information for line endings in output files.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEndingConfig {
+    /// Unix-style line endings (`\n`).
+    Lf,
+    /// Windows-style line endings (`\r\n`).
+    Crlf,
+}
+
+impl Default for LineEndingConfig {
+    fn default() -> Self {
+        LineEndingConfig::Lf
+    }
+}
+
+/// Configuration information for the order of powers within a power set.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerSortConfig {
+    /// Sorted by the level the power becomes available at, otherwise in original order.
+    Level,
+    /// Sorted by display name.
+    Alphabetical,
+    /// The order the powers are listed in the power set.
+    Original,
+}
+
+impl Default for PowerSortConfig {
+    fn default() -> Self {
+        PowerSortConfig::Level
+    }
+}
+
+/// What to do if the output path already contains files.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverwritePolicy {
+    /// Ask on the console before overwriting.
+    Prompt,
+    /// Overwrite without asking.
+    Always,
+    /// Stop without writing anything.
+    Never,
+}
+
+impl Default for OverwritePolicy {
+    fn default() -> Self {
+        OverwritePolicy::Prompt
+    }
+}
+
+/// How `RGBA` colors are written in JSON output.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RgbaFormat {
+    /// A hex code string, e.g. `"#FF8000FF"`.
+    HexString,
+    /// An object with `r`, `g`, `b`, and `a` fields.
+    Object,
+    /// An array of `[r, g, b, a]`.
+    Array,
+}
+
+impl Default for RgbaFormat {
+    fn default() -> Self {
+        RgbaFormat::HexString
+    }
+}
+
+/// How strings read from the bins are converted to UTF-8. The game stores its text as
+/// ISO 8859-1, so accented characters aren't valid UTF-8 on their own.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StringEncoding {
+    /// Strings that are valid UTF-8 are kept as is. Otherwise, any bytes outside of 7-bit ASCII
+    /// are dropped.
+    Lossy,
+    /// Strings must already be valid UTF-8, otherwise reading fails.
+    Utf8Strict,
+    /// Each byte is mapped to the matching ISO 8859-1 character.
+    Latin1,
+}
+
+impl Default for StringEncoding {
+    fn default() -> Self {
+        StringEncoding::Lossy
+    }
+}
+
 /// Configuration information for the current run.
 #[derive(Debug, Deserialize)]
 pub struct PowersConfig {
@@ -69,6 +176,22 @@ pub struct PowersConfig {
     /// JSON output style.
     #[serde(default)]
     pub output_style: OutputStyleConfig,
+    /// Line endings used in output files.
+    #[serde(default)]
+    pub line_endings: LineEndingConfig,
+    /// How colors are written in JSON output.
+    #[serde(default)]
+    pub rgba_format: RgbaFormat,
+    /// If `true`, enums in the "raw" output are written as their numeric values instead of names.
+    #[serde(default)]
+    pub enum_as_int: bool,
+    /// If `true`, character attributes in the "raw" output are written as objects with both the
+    /// name and the struct offset instead of just the name.
+    #[serde(default)]
+    pub attribs_verbose: bool,
+    /// Order of powers within a power set.
+    #[serde(default)]
+    pub power_sort: PowerSortConfig,
     /// Determines the security level used for power calculations.
     pub at_level: i32,
     /// Set the base URL for generated JSON assets.
@@ -79,6 +202,9 @@ pub struct PowersConfig {
     pub input_path: String,
     /// Where the JSON files will be written.
     pub output_path: String,
+    /// What to do if `output_path` isn't empty.
+    #[serde(default)]
+    pub overwrite: OverwritePolicy,
     /// List of power categories to use as a filter. If empty, nothing will be filtered.
     pub power_categories: Vec<NameKey>,
     /// List of power categories to assign to all archetypes. Used to heal up some
@@ -86,7 +212,104 @@ pub struct PowersConfig {
     pub global_categories: Vec<NameKey>,
     /// List of power set partial name matches to filter. Used to get rid of some
     /// power sets we don't want that are part of included power categories.
+    /// Filters containing `*` are matched as globs against the whole name instead.
     pub filter_powersets: Vec<NameKey>,
+    /// List of full power names to exclude from the output, regardless of any other rules.
+    #[serde(default)]
+    pub exclude_powers: Vec<NameKey>,
+    /// If not empty, only powers whose display names contain one of these substrings (ignoring
+    /// case) are included in the output. Useful for quick inspection of a few powers.
+    #[serde(default)]
+    pub filter_powers_by_name: Vec<String>,
+    /// If not empty, only archetypes with these names (ignoring case) are loaded, and power
+    /// categories that none of them use are excluded unless they're in `global_categories`.
+    #[serde(default)]
+    pub filter_archetypes: Vec<String>,
+    /// If `true`, every power read from the bins is kept after loading, so the ones left out of
+    /// the output can be listed with `PowersDictionary::orphan_powers`.
+    #[serde(default)]
+    pub retain_orphans: bool,
+    /// If `false`, villain definitions are not loaded and summoned entities and granted powers
+    /// are left unresolved. Speeds up loading considerably for player-only extracts.
+    #[serde(default = "default_true")]
+    pub resolve_summons: bool,
+    /// If `true`, a missing villain_classes.bin or villaindef.bin is logged as a warning and
+    /// summoned entities are left unresolved, instead of stopping with an error.
+    #[serde(default)]
+    pub allow_missing_villains: bool,
+    /// If set, effect groups nested deeper than this are truncated in the output, and anything
+    /// else that walks the effect groups (e.g. total damage) stops at the same depth.
+    #[serde(default)]
+    pub max_effect_depth: Option<usize>,
+    /// If `true`, powers are tagged with the issue that any newer fields they use were introduced in.
+    #[serde(default)]
+    pub annotate_introduced_fields: bool,
+    /// If `true`, reports power sets where the number of available levels doesn't match
+    /// the number of powers in the set.
+    #[serde(default)]
+    pub validate_available_levels: bool,
+    /// If `true`, duplicate power names stop loading with an error instead of a warning.
+    #[serde(default)]
+    pub strict: bool,
+    /// If `true`, the `b_` flags that are set on each power are also listed together in a
+    /// `flags` array in the "api" output.
+    #[serde(default)]
+    pub compact_bool_flags: bool,
+    /// If `true`, redirects shown in a power's info include a summary of the target power.
+    #[serde(default)]
+    pub inline_redirects: bool,
+    /// If `true`, raw output files are written in parallel. Every file is kept in memory until
+    /// all of them have been serialized.
+    #[serde(default)]
+    pub parallel_output: bool,
+    /// Animation rate used to convert FX frame counts into seconds.
+    #[serde(default = "default_frame_rate")]
+    pub frame_rate: u32,
+    /// Number of decimal places numbers are rounded to in the output. Table values and scales
+    /// keep two more places than this. At most `MAX_DECIMAL_PLACES`.
+    #[serde(
+        default = "default_decimal_places",
+        deserialize_with = "deserialize_decimal_places"
+    )]
+    pub decimal_places: u8,
+    /// How strings in the bins are converted to UTF-8.
+    #[serde(default)]
+    pub string_encoding: StringEncoding,
+}
+
+/// Used by serde for fields that default to `true`.
+fn default_true() -> bool {
+    true
+}
+
+/// Used by serde for the `frame_rate` field.
+fn default_frame_rate() -> u32 {
+    PowerFX::DEFAULT_FRAME_RATE
+}
+
+/// Used by serde for the `decimal_places` field.
+fn default_decimal_places() -> u8 {
+    2
+}
+
+/// The most decimal places allowed in `decimal_places`. An `f32` only has about 7 significant
+/// digits, so anything more (plus the two extra places for table values) is just noise.
+pub const MAX_DECIMAL_PLACES: u8 = 5;
+
+/// Used by serde to reject `decimal_places` values above `MAX_DECIMAL_PLACES`.
+fn deserialize_decimal_places<'de, D>(deserializer: D) -> std::result::Result<u8, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let places = u8::deserialize(deserializer)?;
+    if places > MAX_DECIMAL_PLACES {
+        Err(D::Error::custom(format!(
+            "decimal_places must be between 0 and {} (inclusive)",
+            MAX_DECIMAL_PLACES
+        )))
+    } else {
+        Ok(places)
+    }
 }
 
 impl PowersConfig {
@@ -139,3 +362,78 @@ impl PowersConfig {
         Path::new(&self.output_path).join(path)
     }
 }
+
+/// The smallest valid configuration, for use in tests.
+#[cfg(test)]
+pub const MINIMAL_CONFIG: &'static str = r#"
+    issue = "test"
+    source = "test"
+    at_level = 50
+    input_path = "./bin"
+    output_path = "./dist"
+    power_categories = []
+    global_categories = []
+    filter_powersets = []
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_summons_default_test() {
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        assert!(config.resolve_summons);
+    }
+
+    #[test]
+    fn resolve_summons_disabled_test() {
+        let toml = format!("resolve_summons = false\n{}", MINIMAL_CONFIG);
+        let config: PowersConfig = toml::from_str(&toml).unwrap();
+        assert!(!config.resolve_summons);
+    }
+
+    #[test]
+    fn decimal_places_test() {
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        assert_eq!(config.decimal_places, 2);
+        let toml = format!("decimal_places = {}\n{}", MAX_DECIMAL_PLACES, MINIMAL_CONFIG);
+        let config: PowersConfig = toml::from_str(&toml).unwrap();
+        assert_eq!(config.decimal_places, MAX_DECIMAL_PLACES);
+        let toml = format!("decimal_places = {}\n{}", MAX_DECIMAL_PLACES + 1, MINIMAL_CONFIG);
+        let err = toml::from_str::<PowersConfig>(&toml).err().unwrap();
+        assert!(err.to_string().contains("decimal_places must be between 0 and"));
+        let toml = format!("decimal_places = 39\n{}", MINIMAL_CONFIG);
+        assert!(toml::from_str::<PowersConfig>(&toml).is_err());
+    }
+
+    #[test]
+    fn overwrite_policy_test() {
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        assert!(matches!(config.overwrite, OverwritePolicy::Prompt));
+        let toml = format!("overwrite = \"never\"\n{}", MINIMAL_CONFIG);
+        let config: PowersConfig = toml::from_str(&toml).unwrap();
+        assert!(matches!(config.overwrite, OverwritePolicy::Never));
+    }
+
+    #[test]
+    fn rgba_format_test() {
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        assert_eq!(config.rgba_format, RgbaFormat::HexString);
+        let toml = format!("rgba_format = \"object\"\n{}", MINIMAL_CONFIG);
+        let config: PowersConfig = toml::from_str(&toml).unwrap();
+        assert_eq!(config.rgba_format, RgbaFormat::Object);
+    }
+
+    #[test]
+    fn string_encoding_test() {
+        let config: PowersConfig = toml::from_str(MINIMAL_CONFIG).unwrap();
+        assert_eq!(config.string_encoding, StringEncoding::Lossy);
+        let toml = format!("string_encoding = \"latin1\"\n{}", MINIMAL_CONFIG);
+        let config: PowersConfig = toml::from_str(&toml).unwrap();
+        assert_eq!(config.string_encoding, StringEncoding::Latin1);
+        let toml = format!("string_encoding = \"utf8_strict\"\n{}", MINIMAL_CONFIG);
+        let config: PowersConfig = toml::from_str(&toml).unwrap();
+        assert_eq!(config.string_encoding, StringEncoding::Utf8Strict);
+    }
+}
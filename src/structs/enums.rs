@@ -1,8 +1,9 @@
 #![allow(dead_code)]
 #![allow(non_camel_case_types)]
 use super::{NameKey, ObjRef, Vec3, VillainDef};
-use num_enum::TryFromPrimitive;
-use serde::Serialize;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use serde::{Serialize, Serializer};
+use std::cell::Cell;
 
 macro_rules! default_new {
     ($type:ty) => {
@@ -25,19 +26,94 @@ macro_rules! default_val {
     };
 }
 
+thread_local! {
+    /// Whether enums are serialized as numbers on this thread. See `EnumFormatScope`.
+    static SCOPED_ENUM_AS_INT: Cell<bool> = Cell::new(false);
+}
+
+/// Guard that makes enums serialize as their numeric values (as in the bins) instead of their
+/// variant names on the current thread until it's dropped.
+pub struct EnumFormatScope {
+    previous: bool,
+}
+
+impl EnumFormatScope {
+    /// Sets whether enums are serialized as numbers for the current thread.
+    ///
+    /// # Returns:
+    /// A guard that restores the previous setting (normally `false`) when dropped.
+    pub fn enter(enum_as_int: bool) -> Self {
+        let previous = SCOPED_ENUM_AS_INT.with(|f| f.replace(enum_as_int));
+        EnumFormatScope { previous }
+    }
+}
+
+impl Drop for EnumFormatScope {
+    fn drop(&mut self) {
+        SCOPED_ENUM_AS_INT.with(|f| f.set(self.previous));
+    }
+}
+
+/// Serializes `value` as its `repr(u32)` discriminant if an `EnumFormatScope` asked for it,
+/// otherwise with `by_name` (the derived, variant name serializer).
+pub fn serialize_enum_numeric<T, S>(
+    value: &T,
+    serializer: S,
+    by_name: fn(&T, S) -> Result<S::Ok, S::Error>,
+) -> Result<S::Ok, S::Error>
+where
+    T: Copy + Into<u32>,
+    S: Serializer,
+{
+    if SCOPED_ENUM_AS_INT.with(|f| f.get()) {
+        serializer.serialize_u32((*value).into())
+    } else {
+        by_name(value, serializer)
+    }
+}
+
+/// Implements `Serialize` with `serialize_enum_numeric` for enums that derive `Serialize` with
+/// `#[serde(remote = "Self")]`.
+macro_rules! serialize_enum_numeric {
+    ($($type:ty),+) => {
+        $(
+            impl Serialize for $type {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: Serializer,
+                {
+                    serialize_enum_numeric(self, serializer, <$type>::serialize)
+                }
+            }
+        )+
+    };
+}
+
 /// A very large number of seconds which is essentially forever. This is used as a flag; anything larger than or
 /// equal to this value will be handled specially.
 const ATTRIBMOD_DURATION_FOREVER: f32 = 99999.0;
 
 /// Which power system to use for advancement, level lookup, etc.
-#[derive(Debug, Serialize, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, Serialize, IntoPrimitive, TryFromPrimitive)]
+#[serde(remote = "Self")]
 #[repr(u32)]
 pub enum PowerSystem {
     kPowerSystem_Powers = 0,
+    kPowerSystem_Skills,
 }
 default_val!(PowerSystem, kPowerSystem_Powers);
 
-#[derive(Debug, Serialize, TryFromPrimitive)]
+impl PowerSystem {
+    pub fn get_string(&self) -> &'static str {
+        match self {
+            PowerSystem::kPowerSystem_Powers => "Powers",
+            PowerSystem::kPowerSystem_Skills => "Skills",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, IntoPrimitive, TryFromPrimitive)]
+#[serde(remote = "Self")]
 #[repr(u32)]
 pub enum ShowPowerSetting {
     /// If on a powerset that the player owns, do not show this powerset or any powers in it (no matter what settings the powers have).
@@ -59,7 +135,8 @@ pub enum ShowPowerSetting {
 default_val!(ShowPowerSetting, kShowPowerSetting_Never);
 
 /// Defines if the power is auto, toggle, or click power.
-#[derive(Debug, Serialize, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, Serialize, IntoPrimitive, TryFromPrimitive)]
+#[serde(remote = "Self")]
 #[repr(u32)]
 pub enum PowerType {
     /// Click powers only activate when the user has activated them.
@@ -89,7 +166,8 @@ impl PowerType {
 }
 
 
-#[derive(Debug, Serialize, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, Serialize, IntoPrimitive, TryFromPrimitive)]
+#[serde(remote = "Self")]
 #[repr(u32)]
 pub enum DeathCastableSetting {
     kDeathCastableSetting_AliveOnly = 0, // old false.
@@ -98,7 +176,8 @@ pub enum DeathCastableSetting {
 }
 default_val!(DeathCastableSetting, kDeathCastableSetting_AliveOnly);
 
-#[derive(Debug, Serialize, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, Serialize, IntoPrimitive, TryFromPrimitive)]
+#[serde(remote = "Self")]
 #[repr(u32)]
 pub enum AIReport {
     /// Report on hit or miss.
@@ -113,7 +192,8 @@ pub enum AIReport {
 default_val!(AIReport, kAIReport_Always);
 
 /// The area effected by the power.
-#[derive(Debug, Serialize, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, Serialize, IntoPrimitive, TryFromPrimitive)]
+#[serde(remote = "Self")]
 #[repr(u32)]
 pub enum EffectArea {
     /// Any targeted entity
@@ -162,7 +242,8 @@ impl EffectArea {
 
 /// Defines what kind of visibility is required between the caster and
 /// the target for successful execution of the power.
-#[derive(Debug, Serialize, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, Serialize, IntoPrimitive, TryFromPrimitive)]
+#[serde(remote = "Self")]
 #[repr(u32)]
 pub enum TargetVisibility {
     /// The caster must have direct line of sight to the target.
@@ -173,7 +254,8 @@ pub enum TargetVisibility {
 default_val!(TargetVisibility, kTargetVisibility_LineOfSight);
 
 /// The thing which can be targetted. Used to specify which kinds of entities are affected, auto-hit, etc. by a power.
-#[derive(Debug, Serialize, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, Serialize, IntoPrimitive, TryFromPrimitive)]
+#[serde(remote = "Self")]
 #[repr(u32)]
 pub enum TargetType {
     kTargetType_None,
@@ -308,7 +390,8 @@ impl TargetType {
     }
 }
 
-#[derive(Debug, Serialize, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, Serialize, IntoPrimitive, TryFromPrimitive)]
+#[serde(remote = "Self")]
 #[repr(u32)]
 pub enum ModApplicationType {
     /// While the power is running.
@@ -339,7 +422,8 @@ impl ModApplicationType {
     }
 }
 
-#[derive(Debug, Serialize, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, Serialize, IntoPrimitive, TryFromPrimitive)]
+#[serde(remote = "Self")]
 #[repr(u32)]
 pub enum ModTarget {
     kModTarget_Caster,
@@ -366,7 +450,8 @@ impl ModTarget {
     }
 }
 
-#[derive(Debug, Serialize, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, Serialize, IntoPrimitive, TryFromPrimitive)]
+#[serde(remote = "Self")]
 #[repr(u32)]
 pub enum ModType {
     kModType_Duration,
@@ -398,12 +483,29 @@ pub enum ModDuration {
 }
 
 impl ModDuration {
-    pub fn from_f32(val: f32) -> Self {
+    /// Converts a duration from the bins.
+    ///
+    /// # Arguments:
+    /// * `val` - The duration in seconds, or one of the special duration values.
+    /// * `power_type` - The type of the power that owns the attrib mod.
+    ///
+    /// # Returns:
+    /// A `ModDuration`.
+    ///
+    /// # Notes:
+    /// UntilKilled and UntilShutOff have the same value in the bins. Mods lasting "forever" in
+    /// toggle and auto powers end when the power is shut off, so they're `kModDuration_UntilShutOff`.
+    /// Any others (e.g. a summon's mods) are `kModDuration_UntilKilled`.
+    pub fn from_f32(val: f32, power_type: &PowerType) -> Self {
         if val == -1.0 {
             ModDuration::kModDuration_Instant
         } else if val >= ATTRIBMOD_DURATION_FOREVER {
-            // UntilKilled and UntilShutoff have the same value
-            ModDuration::kModDuration_UntilKilled
+            match power_type {
+                PowerType::kPowerType_Toggle | PowerType::kPowerType_Auto => {
+                    ModDuration::kModDuration_UntilShutOff
+                }
+                _ => ModDuration::kModDuration_UntilKilled,
+            }
         } else {
             ModDuration::new(val)
         }
@@ -440,7 +542,8 @@ impl Default for ModDuration {
     }
 }
 
-#[derive(Debug, Serialize, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, Serialize, IntoPrimitive, TryFromPrimitive)]
+#[serde(remote = "Self")]
 #[repr(u32)]
 pub enum CasterStackType {
     /// Stacking is handled for each caster individually.
@@ -451,7 +554,8 @@ pub enum CasterStackType {
 default_val!(CasterStackType, kCasterStackType_Individual);
 
 /// Determines how multiple identical `AttribMod`s from the same power and caster are handled.
-#[derive(Debug, Serialize, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, Serialize, IntoPrimitive, TryFromPrimitive)]
+#[serde(remote = "Self")]
 #[repr(u32)]
 pub enum StackType {
     /// Stack up (allow multiples).
@@ -495,9 +599,22 @@ impl StackType {
             StackType::kStackType_Continuous => "Continuous",
         }
     }
+
+    /// Describes how re-applying an effect with this stack type changes the duration
+    /// of any copies already on the target. Only the stack types with non-obvious
+    /// duration interactions are described.
+    pub fn get_duration_behavior(&self) -> Option<&'static str> {
+        match self {
+            StackType::kStackType_Refresh => Some("Resets the duration of all existing copies, then adds a new copy."),
+            StackType::kStackType_RefreshToCount => Some("Resets the duration of all existing copies, then adds a new copy if below the stack limit."),
+            StackType::kStackType_Continuous => Some("Replaces an existing copy only when it is about to expire, so the effect continues without a gap."),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Debug, Serialize, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, Serialize, IntoPrimitive, TryFromPrimitive)]
+#[serde(remote = "Self")]
 #[repr(u32)]
 pub enum PowerEvent {
     // Invoke-related events.
@@ -712,7 +829,8 @@ pub enum AttribModParam {
     Knock(AttribModParam_Knock),
 }
 
-#[derive(Debug, Serialize, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, Serialize, IntoPrimitive, TryFromPrimitive)]
+#[serde(remote = "Self")]
 #[repr(u32)]
 pub enum ToggleDroppable {
     kToggleDroppable_Sometimes,
@@ -723,7 +841,8 @@ pub enum ToggleDroppable {
 }
 default_val!(ToggleDroppable, kToggleDroppable_Sometimes);
 
-#[derive(Debug, Serialize, TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, Serialize, IntoPrimitive, TryFromPrimitive)]
+#[serde(remote = "Self")]
 #[repr(u32)]
 pub enum ProcAllowed {
     kProcAllowed_All,
@@ -733,6 +852,25 @@ pub enum ProcAllowed {
 }
 default_val!(ProcAllowed, kProcAllowed_All);
 
+serialize_enum_numeric!(
+    PowerSystem,
+    ShowPowerSetting,
+    PowerType,
+    DeathCastableSetting,
+    AIReport,
+    EffectArea,
+    TargetVisibility,
+    TargetType,
+    ModApplicationType,
+    ModTarget,
+    ModType,
+    CasterStackType,
+    StackType,
+    PowerEvent,
+    ToggleDroppable,
+    ProcAllowed
+);
+
 #[derive(Debug, PartialEq, TryFromPrimitive)]
 #[repr(u32)]
 pub enum AttribType {
@@ -760,7 +898,8 @@ impl AttribType {
     }
 }
 
-#[derive(Debug, TryFromPrimitive)]
+/// How the game UI formats the magnitude of an attribute.
+#[derive(Clone, Copy, Debug, TryFromPrimitive)]
 #[repr(u32)]
 pub enum AttribStyle {
     kAttribStyle_None,
@@ -779,6 +918,71 @@ pub enum AttribStyle {
 }
 default_val!(AttribStyle, kAttribStyle_None);
 
+impl AttribStyle {
+    /// Get a human readable string representing this `AttribStyle`.
+    pub fn get_string(&self) -> &'static str {
+        match self {
+            AttribStyle::kAttribStyle_None => "None",
+            AttribStyle::kAttribStyle_Percent => "Percent",
+            AttribStyle::kAttribStyle_Magnitude => "Magnitude",
+            AttribStyle::kAttribStyle_Distance => "Distance",
+            AttribStyle::kAttribStyle_PercentMinus100 => "PercentMinus100",
+            AttribStyle::kAttribStyle_PerSecond => "PerSecond",
+            AttribStyle::kAttribStyle_Speed => "Speed",
+            AttribStyle::kAttribStyle_ResistanceDuration => "ResistanceDuration",
+            AttribStyle::kAttribStyle_Multiply => "Multiply",
+            AttribStyle::kAttribStyle_Integer => "Integer",
+            AttribStyle::kAttribStyle_EnduranceReduction => "EnduranceReduction",
+            AttribStyle::kAttribStyle_InversePercent => "InversePercent",
+            AttribStyle::kAttribStyle_ResistanceDistance => "ResistanceDistance",
+        }
+    }
+
+    /// Formats a raw attribute value the way the game UI displays it in this style.
+    ///
+    /// # Arguments:
+    /// * `raw` - The value as stored in the attribute (e.g. 0.75 for 75%).
+    ///
+    /// # Returns:
+    /// A `String` such as "75%", "20 ft.", or "2.5/s".
+    pub fn format_value(&self, raw: f32) -> String {
+        match self {
+            AttribStyle::kAttribStyle_None | AttribStyle::kAttribStyle_Magnitude => {
+                format_number(raw)
+            }
+            AttribStyle::kAttribStyle_Percent => format!("{}%", format_number(raw * 100.0)),
+            AttribStyle::kAttribStyle_Distance => format!("{} ft.", format_number(raw)),
+            AttribStyle::kAttribStyle_PercentMinus100 => {
+                format!("{}%", format_number((raw - 1.0) * 100.0))
+            }
+            AttribStyle::kAttribStyle_PerSecond => format!("{}/s", format_number(raw)),
+            AttribStyle::kAttribStyle_Speed => format!("{} ft./s", format_number(raw)),
+            // resistances to these are applied as a divisor, 1 / (1 + raw)
+            AttribStyle::kAttribStyle_ResistanceDuration
+            | AttribStyle::kAttribStyle_EnduranceReduction
+            | AttribStyle::kAttribStyle_ResistanceDistance => {
+                format!("{}%", format_number((1.0 - 1.0 / (1.0 + raw)) * 100.0))
+            }
+            AttribStyle::kAttribStyle_Multiply => format!("{}x", format_number(raw)),
+            AttribStyle::kAttribStyle_Integer => format!("{}", raw.round()),
+            AttribStyle::kAttribStyle_InversePercent => {
+                format!("{}%", format_number((1.0 - raw) * 100.0))
+            }
+        }
+    }
+}
+
+/// Formats `value` with at most 2 decimal places, dropping any trailing zeroes.
+fn format_number(value: f32) -> String {
+    let formatted = format!("{:.2}", value);
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    if trimmed == "-0" {
+        String::from("0")
+    } else {
+        trimmed.to_owned()
+    }
+}
+
 /// Rank of a villain. The "level" here is for conning purposes.
 #[derive(Debug, TryFromPrimitive)]
 #[repr(u32)]
@@ -818,3 +1022,87 @@ pub enum Gender {
     GENDER_FEMALE,
 }
 default_val!(Gender, GENDER_UNDEFINED);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mod_duration_from_f32_test() {
+        // a toggle power's tick mod runs until the toggle is shut off
+        assert!(matches!(
+            ModDuration::from_f32(ATTRIBMOD_DURATION_FOREVER, &PowerType::kPowerType_Toggle),
+            ModDuration::kModDuration_UntilShutOff
+        ));
+        assert!(matches!(
+            ModDuration::from_f32(ATTRIBMOD_DURATION_FOREVER, &PowerType::kPowerType_Auto),
+            ModDuration::kModDuration_UntilShutOff
+        ));
+        // a summon lasts until it's killed
+        assert!(matches!(
+            ModDuration::from_f32(ATTRIBMOD_DURATION_FOREVER, &PowerType::kPowerType_Click),
+            ModDuration::kModDuration_UntilKilled
+        ));
+        assert!(matches!(
+            ModDuration::from_f32(-1.0, &PowerType::kPowerType_Toggle),
+            ModDuration::kModDuration_Instant
+        ));
+        assert!(matches!(
+            ModDuration::from_f32(10.0, &PowerType::kPowerType_Toggle),
+            ModDuration::InSeconds(s) if s == 10.0
+        ));
+    }
+
+    #[test]
+    fn attrib_style_format_value_test() {
+        let cases = [
+            (AttribStyle::kAttribStyle_None, 1.5, "1.5"),
+            (AttribStyle::kAttribStyle_Percent, 0.75, "75%"),
+            (AttribStyle::kAttribStyle_Magnitude, 3.0, "3"),
+            (AttribStyle::kAttribStyle_Distance, 20.0, "20 ft."),
+            (AttribStyle::kAttribStyle_PercentMinus100, 1.25, "25%"),
+            (AttribStyle::kAttribStyle_PerSecond, 2.5, "2.5/s"),
+            (AttribStyle::kAttribStyle_Speed, 21.0, "21 ft./s"),
+            (AttribStyle::kAttribStyle_ResistanceDuration, 1.0, "50%"),
+            (AttribStyle::kAttribStyle_Multiply, 1.5, "1.5x"),
+            (AttribStyle::kAttribStyle_Integer, 62.56, "63"),
+            (AttribStyle::kAttribStyle_EnduranceReduction, 0.25, "20%"),
+            (AttribStyle::kAttribStyle_InversePercent, 0.3, "70%"),
+            (AttribStyle::kAttribStyle_ResistanceDistance, 3.0, "75%"),
+        ];
+        for (style, raw, expected) in &cases {
+            assert_eq!(style.format_value(*raw), *expected, "{}", style.get_string());
+        }
+        assert_eq!(AttribStyle::kAttribStyle_Percent.format_value(-0.00001), "0%");
+    }
+
+    #[test]
+    fn serialize_enum_numeric_test() {
+        let values = (
+            PowerType::kPowerType_Toggle,
+            TargetType::kTargetType_Foe,
+            vec![PowerEvent::kPowerEvent_Activate],
+        );
+        assert_eq!(
+            serde_json::to_string(&values).unwrap(),
+            r#"["kPowerType_Toggle","kTargetType_Foe",["kPowerEvent_Activate"]]"#
+        );
+        {
+            let _scope = EnumFormatScope::enter(true);
+            assert_eq!(
+                serde_json::to_string(&values).unwrap(),
+                format!(
+                    "[{},{},[{}]]",
+                    PowerType::kPowerType_Toggle as u32,
+                    TargetType::kTargetType_Foe as u32,
+                    PowerEvent::kPowerEvent_Activate as u32
+                )
+            );
+        }
+        // back to names once the scope ends
+        assert_eq!(
+            serde_json::to_string(&PowerType::kPowerType_Click).unwrap(),
+            r#""kPowerType_Click""#
+        );
+    }
+}
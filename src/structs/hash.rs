@@ -0,0 +1,407 @@
+use super::BasePower;
+use serde::ser::{self, Serialize, Serializer};
+use std::convert::TryInto;
+use std::fmt;
+
+/// A `Serializer` that feeds everything it sees into a deterministic hash.
+///
+/// Since it's driven by `Serialize`, any `#[serde(skip)]` fields are ignored and shared
+/// references (`Rc`) are hashed by value. Map entries are hashed independently and then
+/// combined, so iteration order doesn't affect the result. The hash is MD5-based rather
+/// than using `std::hash`, so it's stable between runs and Rust versions.
+///
+/// This reports itself as not human readable, which lets types that would normally
+/// serialize as a looked up name (e.g. `CharacterAttrib`) hash their raw value instead.
+pub struct StructuralHasher {
+    context: md5::Context,
+}
+
+impl StructuralHasher {
+    /// Creates a new `StructuralHasher`.
+    pub fn new() -> Self {
+        StructuralHasher {
+            context: md5::Context::new(),
+        }
+    }
+
+    /// Consumes the hasher and returns the hash of everything serialized into it.
+    pub fn finish(self) -> u64 {
+        let digest = self.context.compute();
+        u64::from_le_bytes(digest.0[..8].try_into().unwrap())
+    }
+
+    /// Writes a type tag followed by `bytes`.
+    fn write(&mut self, tag: u8, bytes: &[u8]) {
+        self.context.consume([tag]);
+        self.context.consume(bytes);
+    }
+
+    /// Writes a type tag followed by a length-prefixed `bytes`.
+    fn write_with_len(&mut self, tag: u8, bytes: &[u8]) {
+        self.write(tag, &(bytes.len() as u64).to_le_bytes());
+        self.context.consume(bytes);
+    }
+}
+
+/// An error raised by a `Serialize` implementation while hashing.
+#[derive(Debug)]
+pub struct HashError(String);
+
+impl fmt::Display for HashError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to hash value: {}", self.0)
+    }
+}
+
+impl std::error::Error for HashError {}
+
+impl ser::Error for HashError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        HashError(msg.to_string())
+    }
+}
+
+impl<'a> Serializer for &'a mut StructuralHasher {
+    type Ok = ();
+    type Error = HashError;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = MapHasher<'a>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<(), HashError> {
+        self.write(b'b', &[v as u8]);
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), HashError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), HashError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), HashError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), HashError> {
+        self.write(b'i', &v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), HashError> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), HashError> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), HashError> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), HashError> {
+        self.write(b'u', &v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), HashError> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), HashError> {
+        self.write(b'f', &v.to_bits().to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), HashError> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), HashError> {
+        self.write_with_len(b's', v.as_bytes());
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), HashError> {
+        self.write_with_len(b'y', v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), HashError> {
+        self.write(b'N', &[]);
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), HashError> {
+        self.write(b'S', &[]);
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), HashError> {
+        self.write(b'U', &[]);
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), HashError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<(), HashError> {
+        self.write(b'v', &variant_index.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), HashError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<(), HashError> {
+        self.write(b'V', &variant_index.to_le_bytes());
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self, HashError> {
+        self.write(b'[', &[]);
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self, HashError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Self, HashError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        len: usize,
+    ) -> Result<Self, HashError> {
+        self.write(b'V', &variant_index.to_le_bytes());
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapHasher<'a>, HashError> {
+        Ok(MapHasher {
+            parent: self,
+            entry: None,
+            sum: 0,
+            count: 0,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self, HashError> {
+        self.write(b'{', &[]);
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        len: usize,
+    ) -> Result<Self, HashError> {
+        self.write(b'V', &variant_index.to_le_bytes());
+        self.serialize_struct(name, len)
+    }
+}
+
+impl<'a> ser::SerializeSeq for &'a mut StructuralHasher {
+    type Ok = ();
+    type Error = HashError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), HashError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), HashError> {
+        self.write(b']', &[]);
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeTuple for &'a mut StructuralHasher {
+    type Ok = ();
+    type Error = HashError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), HashError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), HashError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for &'a mut StructuralHasher {
+    type Ok = ();
+    type Error = HashError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), HashError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), HashError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<'a> ser::SerializeTupleVariant for &'a mut StructuralHasher {
+    type Ok = ();
+    type Error = HashError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), HashError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), HashError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<'a> ser::SerializeStruct for &'a mut StructuralHasher {
+    type Ok = ();
+    type Error = HashError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), HashError> {
+        self.write_with_len(b'k', key.as_bytes());
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), HashError> {
+        self.write(b'}', &[]);
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for &'a mut StructuralHasher {
+    type Ok = ();
+    type Error = HashError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), HashError> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<(), HashError> {
+        ser::SerializeStruct::end(self)
+    }
+}
+
+/// Hashes map entries independently so that the order they're visited in doesn't matter.
+pub struct MapHasher<'a> {
+    parent: &'a mut StructuralHasher,
+    entry: Option<StructuralHasher>,
+    sum: u64,
+    count: u64,
+}
+
+impl<'a> ser::SerializeMap for MapHasher<'a> {
+    type Ok = ();
+    type Error = HashError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), HashError> {
+        let mut entry = StructuralHasher::new();
+        key.serialize(&mut entry)?;
+        self.entry = Some(entry);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), HashError> {
+        let mut entry = self.entry.take().unwrap_or_else(StructuralHasher::new);
+        value.serialize(&mut entry)?;
+        self.sum = self.sum.wrapping_add(entry.finish());
+        self.count += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), HashError> {
+        self.parent.write(b'm', &self.count.to_le_bytes());
+        self.parent.write(b'M', &self.sum.to_le_bytes());
+        Ok(())
+    }
+}
+
+impl BasePower {
+    /// Calculates a deterministic hash of this power's data, including its effects.
+    /// Non-data fields (e.g. `include_in_output` or the attached archetypes) are ignored,
+    /// so two copies of the same power loaded separately will hash the same.
+    #[allow(dead_code)]
+    pub fn structural_hash(&self) -> u64 {
+        let mut hasher = StructuralHasher::new();
+        self.serialize(&mut hasher)
+            .expect("BasePower should always be hashable");
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structs::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn make_power() -> BasePower {
+        let mut attrib_mod = AttribModTemplate::new();
+        attrib_mod.p_attrib.push(CharacterAttrib(CharacterAttributes::OFFSET_DMG_0 as i32));
+        attrib_mod.f_scale = 1.64;
+        let mut effect = EffectGroup::new();
+        effect.pp_templates.push(attrib_mod);
+        let mut power = BasePower::new();
+        power.pch_full_name = Some(NameKey::new("Blaster_Ranged.Fire_Blast.Flares"));
+        power.f_recharge_time = 4.0;
+        power.pp_effects.push(Rc::new(RefCell::new(effect)));
+        power
+    }
+
+    #[test]
+    fn structural_hash_test() {
+        let mut power = make_power();
+        let copy = make_power();
+        // non-data fields don't count
+        power.include_in_output = true;
+        power.archetypes.push(Rc::new(RefCell::new(Archetype::new())));
+        assert_eq!(power.structural_hash(), copy.structural_hash());
+
+        let modified = make_power();
+        modified.pp_effects[0].borrow_mut().pp_templates[0].f_scale = 1.0;
+        assert_ne!(power.structural_hash(), modified.structural_hash());
+    }
+}
@@ -15,8 +15,8 @@
 //! * `ul_` - An unsigned 32-bit integer. (C: `unsigned long int`, Rust: `u32`)
 //! * `b_` - A Boolean value. (C: `bool`, Rust: `bool`)
 //! * `pch_` - A string, potentially NUL. (C: `char *`, Rust: `Option<String>`) Important:
-//!    CoH uses ASCII strings (ISO 8859-1 code page), while Rust uses UTF-8. There will
-//!    be some lossy conversion in places.
+//!    CoH uses ASCII strings (ISO 8859-1 code page), while Rust uses UTF-8. By default
+//!    there will be some lossy conversion in places (see `config::StringEncoding`).
 //! * `ppch_` - An array of strings. (C: `char **`, Rust: `Vec<String>`)
 //! * `p_` - A pointer to another struct. Typically I just use direct ownership in Rust.
 //! * `pp_` - An array of pointers to another struct.
@@ -26,11 +26,19 @@
 //! * `vec_` - A `Vec3` value.
 mod attribs;
 mod boosts;
+pub mod compare;
 pub mod config;
 mod enums;
 mod flags;
+pub mod hash;
 mod namekey;
+pub mod requires;
+#[cfg(feature = "schema")]
+mod schema;
+pub mod search;
 mod strings;
+pub mod validate;
+mod versions;
 mod villains;
 
 pub use attribs::*;
@@ -38,15 +46,25 @@ pub use boosts::*;
 pub use enums::*;
 pub use flags::*;
 pub use namekey::*;
+use config::RgbaFormat;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::SerializeStruct;
 use serde::{Serialize, Serializer};
-use std::cell::RefCell;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet};
 use std::default::Default;
 use std::fmt;
 use std::rc::Rc;
+use thiserror::Error;
 pub use strings::*;
+pub use versions::*;
 pub use villains::*;
 
+// Tags PvP vs PvE rules.
+pub const PVE_TAG: &'static str = "PVE";
+pub const PVP_TAG: &'static str = "PVP";
+
 /// Short-hand for shareable references.
 pub type ObjRef<T> = Rc<RefCell<T>>;
 
@@ -117,6 +135,41 @@ impl NamedTable {
 	pub fn new() -> Self {
 		Default::default()
 	}
+
+	/// Gets the value for a character level.
+	///
+	/// # Arguments:
+	/// * `level` - The character level, starting at 1. Levels outside of the table are clamped
+	/// to the first or last entry.
+	///
+	/// # Returns:
+	/// The value at `level`, or `None` if the table is empty.
+	pub fn value_at(&self, level: usize) -> Option<f32> {
+		let last = self.pf_values.len().checked_sub(1)?;
+		self.pf_values.get(level.saturating_sub(1).min(last)).copied()
+	}
+
+	/// Gets the value for a fractional character level, such as an exemplared character's
+	/// effective level, by interpolating linearly between the adjacent levels.
+	///
+	/// # Arguments:
+	/// * `fractional_level` - The character level, starting at 1. Levels outside of the table
+	/// are clamped to the first or last entry.
+	///
+	/// # Returns:
+	/// The interpolated value, or `None` if the table is empty.
+	#[allow(dead_code)]
+	pub fn value_at_interpolated(&self, fractional_level: f32) -> Option<f32> {
+		let max_level = self.pf_values.len() as f32;
+		if max_level == 0.0 {
+			return None;
+		}
+		let level = fractional_level.max(1.0).min(max_level);
+		let lower = level.floor();
+		let low_value = self.value_at(lower as usize)?;
+		let high_value = self.value_at(lower as usize + 1)?;
+		Some(low_value + (high_value - low_value) * (level - lower))
+	}
 }
 
 /// Defines the character class (archetype), which sets up the allowable powers and
@@ -176,7 +229,8 @@ pub struct Archetype {
 	/// Only used temporarily by the game, but we have to account for it when reading the .bin.
 	pub pp_attrib_temp_resistance_max: Vec<CharacterAttributesTable>,
 	/// Tables used by powers for scaling powers by level.
-	/// Changed this to a `HashMap` to make it easier to lookup tables.
+	/// Changed this to a `HashMap` to make it easier to lookup tables. Keys are always lowercase,
+	/// use `Archetype::table()` to look up a table by name.
 	pub pp_named_tables: HashMap<String, NamedTable>,
 	/// Gang together hit points and status points. Modifications to hit points will affect status points and vice-versa. Hit points
 	/// are are set to be the same as status points.
@@ -229,6 +283,19 @@ impl Archetype {
 	pub fn new() -> Self {
 		Default::default()
 	}
+
+	/// Looks up a named table, ignoring case.
+	///
+	/// # Arguments:
+	///
+	/// * `name` - The table name, usually from `AttribModTemplate::pch_table`.
+	///
+	/// # Returns:
+	///
+	/// A reference to the `NamedTable` if the archetype has one with that name.
+	pub fn table(&self, name: &str) -> Option<&NamedTable> {
+		self.pp_named_tables.get(&name.to_lowercase())
+	}
 }
 
 /// Defines a set of powers which are group together and become available
@@ -312,6 +379,21 @@ impl BasePowerSet {
 	pub fn new() -> Self {
 		Default::default()
 	}
+
+	/// Iterates over the names of the powers in this set, paired with the level each one becomes
+	/// available at. (Unlike `pi_available`, levels start at 1.)
+	pub fn available_levels(&self) -> impl Iterator<Item = (&NameKey, i32)> + '_ {
+		self.pp_power_names.iter().zip(&self.pi_available).map(|(name, level)| (name, *level + 1))
+	}
+
+	/// Lists the powers in this set that can be picked at or before `level` (starting at 1).
+	#[allow(dead_code)]
+	pub fn powers_available_at(&self, level: i32) -> Vec<NameKey> {
+		self.available_levels()
+			.filter(|(_, available)| *available <= level)
+			.map(|(name, _)| name.clone())
+			.collect()
+	}
 }
 
 #[derive(Debug, Default, Serialize)]
@@ -338,6 +420,37 @@ pub struct Vec3 {
 	pub z: f32,
 }
 
+#[allow(dead_code)]
+impl Vec3 {
+	/// Gets the length (magnitude) of this vector.
+	pub fn length(&self) -> f32 {
+		(self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+	}
+
+	/// Adds `other` to this vector, component by component.
+	pub fn add(&self, other: &Vec3) -> Vec3 {
+		Vec3 {
+			x: self.x + other.x,
+			y: self.y + other.y,
+			z: self.z + other.z,
+		}
+	}
+
+	/// Multiplies each component of this vector by `factor`.
+	pub fn scale(&self, factor: f32) -> Vec3 {
+		Vec3 {
+			x: self.x * factor,
+			y: self.y * factor,
+			z: self.z * factor,
+		}
+	}
+
+	/// Tests if every component of this vector is zero.
+	pub fn is_zero(&self) -> bool {
+		self.x == 0.0 && self.y == 0.0 && self.z == 0.0
+	}
+}
+
 impl Serialize for Vec3 {
 	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
 	where
@@ -348,6 +461,30 @@ impl Serialize for Vec3 {
 	}
 }
 
+impl<'de> Deserialize<'de> for Vec3 {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		// (x, y, z)
+		let s: String = Deserialize::deserialize(deserializer)?;
+		let parts: Vec<_> = s
+			.trim()
+			.trim_start_matches('(')
+			.trim_end_matches(')')
+			.split(',')
+			.map(|part| part.trim().parse::<f32>())
+			.collect();
+		match parts[..] {
+			[Ok(x), Ok(y), Ok(z)] => Ok(Vec3 { x, y, z }),
+			_ => Err(de::Error::invalid_value(
+				de::Unexpected::Str(&s),
+				&"a vector like (x, y, z)",
+			)),
+		}
+	}
+}
+
 /// Extended targeting info.
 #[derive(Debug, Default, Serialize)]
 pub struct AttribModTargetInfo {
@@ -376,6 +513,16 @@ impl SuppressPair {
 	pub fn new() -> Self {
 		Default::default()
 	}
+
+	/// Describes the suppression window, e.g. "suppressed for 10s after Activate (always)".
+	pub fn describe(&self) -> String {
+		format!(
+			"suppressed for {}s after {} ({})",
+			self.ul_seconds,
+			self.idx_event.get_string(),
+			if self.b_always { "always" } else { "unless already applied" }
+		)
+	}
 }
 
 /// Messages
@@ -416,6 +563,7 @@ impl AttribModFX {
 	}
 }
 
+#[derive(Clone, Copy)]
 pub struct RGBA([u8; 4]);
 
 impl RGBA {
@@ -438,6 +586,11 @@ impl RGBA {
 	pub fn a(&self) -> u8 {
 		self.0[3]
 	}
+
+	/// Tests whether or not this color is black, ignoring the alpha channel.
+	pub fn is_black(&self) -> bool {
+		self.r() == 0 && self.g() == 0 && self.b() == 0
+	}
 }
 
 impl Default for RGBA {
@@ -459,20 +612,133 @@ impl fmt::Debug for RGBA {
 	}
 }
 
+thread_local! {
+	/// The format used by `RGBA`'s `Serialize` impl on this thread. See `RgbaFormatScope`.
+	static SCOPED_RGBA_FORMAT: Cell<RgbaFormat> = Cell::new(RgbaFormat::HexString);
+}
+
+/// Guard that sets the format `RGBA` values are serialized in on the current thread
+/// until it's dropped.
+pub struct RgbaFormatScope {
+	previous: RgbaFormat,
+}
+
+impl RgbaFormatScope {
+	/// Sets `format` as the `RGBA` format for the current thread.
+	///
+	/// # Returns:
+	/// A guard that restores the previous format (normally `HexString`) when dropped.
+	pub fn enter(format: RgbaFormat) -> Self {
+		let previous = SCOPED_RGBA_FORMAT.with(|f| f.replace(format));
+		RgbaFormatScope { previous }
+	}
+}
+
+impl Drop for RgbaFormatScope {
+	fn drop(&mut self) {
+		SCOPED_RGBA_FORMAT.with(|f| f.set(self.previous));
+	}
+}
+
 impl Serialize for RGBA {
 	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
 	where
 		S: Serializer,
+	{
+		match SCOPED_RGBA_FORMAT.with(|f| f.get()) {
+			RgbaFormat::HexString => {
+				// RRGGBBAA hex code
+				let value = format!(
+					"#{:02X}{:02X}{:02X}{:02X}",
+					self.r(),
+					self.g(),
+					self.b(),
+					self.a()
+				);
+				serializer.serialize_str(&value[..])
+			}
+			RgbaFormat::Object => {
+				let mut state = serializer.serialize_struct("RGBA", 4)?;
+				state.serialize_field("r", &self.r())?;
+				state.serialize_field("g", &self.g())?;
+				state.serialize_field("b", &self.b())?;
+				state.serialize_field("a", &self.a())?;
+				state.end()
+			}
+			RgbaFormat::Array => self.0.serialize(serializer),
+		}
+	}
+}
+
+/// Reads any of the formats `RGBA` can be serialized in.
+struct RgbaVisitor;
+
+impl<'de> Visitor<'de> for RgbaVisitor {
+	type Value = RGBA;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("a color like \"#RRGGBBAA\", {r, g, b, a}, or [r, g, b, a]")
+	}
+
+	fn visit_str<E>(self, s: &str) -> Result<RGBA, E>
+	where
+		E: de::Error,
 	{
 		// RRGGBBAA hex code
-		let value = format!(
-			"#{:02X}{:02X}{:02X}{:02X}",
-			self.r(),
-			self.g(),
-			self.b(),
-			self.a()
-		);
-		serializer.serialize_str(&value[..])
+		let hex = s.trim_start_matches('#');
+		match u32::from_str_radix(hex, 16) {
+			Ok(value) if hex.len() == 8 => Ok(RGBA(value.to_be_bytes())),
+			_ => Err(E::invalid_value(de::Unexpected::Str(s), &self)),
+		}
+	}
+
+	fn visit_seq<A>(self, mut seq: A) -> Result<RGBA, A::Error>
+	where
+		A: SeqAccess<'de>,
+	{
+		let mut rgba = [0u8; 4];
+		for (i, channel) in rgba.iter_mut().enumerate() {
+			*channel = seq
+				.next_element()?
+				.ok_or_else(|| de::Error::invalid_length(i, &self))?;
+		}
+		if seq.next_element::<de::IgnoredAny>()?.is_some() {
+			return Err(de::Error::invalid_length(5, &self));
+		}
+		Ok(RGBA(rgba))
+	}
+
+	fn visit_map<A>(self, mut map: A) -> Result<RGBA, A::Error>
+	where
+		A: MapAccess<'de>,
+	{
+		let mut rgba: [Option<u8>; 4] = [None; 4];
+		while let Some(key) = map.next_key::<String>()? {
+			let i = match key.as_str() {
+				"r" => 0,
+				"g" => 1,
+				"b" => 2,
+				"a" => 3,
+				_ => return Err(de::Error::unknown_field(&key, &["r", "g", "b", "a"])),
+			};
+			rgba[i] = Some(map.next_value()?);
+		}
+		match rgba {
+			[Some(r), Some(g), Some(b), Some(a)] => Ok(RGBA::new(r, g, b, a)),
+			_ => {
+				let missing = ["r", "g", "b", "a"][rgba.iter().position(Option::is_none).unwrap()];
+				Err(de::Error::missing_field(missing))
+			}
+		}
+	}
+}
+
+impl<'de> Deserialize<'de> for RGBA {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		deserializer.deserialize_any(RgbaVisitor)
 	}
 }
 
@@ -581,6 +847,134 @@ impl EffectGroup {
 	pub fn new() -> Self {
 		Default::default()
 	}
+
+	/// Visits this effect group and all of its children, depth first, in the same order as a
+	/// recursive traversal would.
+	///
+	/// # Arguments:
+	/// * `max_depth` - How many levels of effect groups to visit, counting this one, as with
+	///   `max_effect_depth` in the config. `None` visits every level.
+	/// * `visitor` - Called with each effect group and its depth (0 for this group).
+	///
+	/// # Returns:
+	/// An `EffectDepthError` if the groups are nested deeper than `max_depth`. Every group within
+	/// the limit has still been visited, only the deeper ones are skipped.
+	///
+	/// # Notes:
+	/// This uses an explicit stack rather than recursion, so badly nested data can't overflow
+	/// the call stack. Prefer it over writing new recursive traversals.
+	pub fn walk<F>(&self, max_depth: Option<usize>, visitor: &mut F) -> Result<(), EffectDepthError>
+	where
+		F: FnMut(&EffectGroup, usize),
+	{
+		let mut too_deep = None;
+		let mut stack = vec![(self, 0)];
+		while let Some((effect_group, depth)) = stack.pop() {
+			if let Some(max_depth) = max_depth {
+				if depth >= max_depth {
+					too_deep = Some(EffectDepthError { max_depth });
+					continue;
+				}
+			}
+			visitor(effect_group, depth);
+			// pushed in reverse so the first child is visited next
+			stack.extend(effect_group.pp_effects.iter().rev().map(|child| (child, depth + 1)));
+		}
+		too_deep.map_or(Ok(()), Err)
+	}
+
+	/// Checks the flags, tags, and requires clause of this effect group to see if it only applies
+	/// in PvE or PvP.
+	///
+	/// # Returns:
+	/// `PVE_TAG` or `PVP_TAG`, or `None` if the effect group applies to both.
+	///
+	/// # Notes:
+	/// The requires clause is matched against common ways of checking for PvE or PvP, so this can
+	/// give false positives.
+	pub fn pve_or_pvp(&self) -> Option<&'static str> {
+		// check the mode flags first, they're the most explicit
+		if self.i_flags.contains(EffectGroupFlag::PVEOnly) {
+			return Some(PVE_TAG);
+		} else if self.i_flags.contains(EffectGroupFlag::PVPOnly) {
+			return Some(PVP_TAG);
+		}
+
+		let requires_str = self.ppch_requires.iter().map(|s| &**s).collect::<Vec<_>>();
+
+		// check for the MLCrit and BossCrit effects, they use player to test for non-pvp in most cases
+		// but may have an explicit expression
+		if is_critical_by_tags(&self.ppch_tags) {
+			match requires_str[..] {
+				["enttype", "target>", "critter", "eq"] => return Some(PVE_TAG),
+				["enttype", "target>", "player", "eq"] => return Some(PVP_TAG),
+				_ => (),
+			}
+			// NOTE: may false positive
+			return Some(PVE_TAG);
+		}
+
+		// iterate through requirements to find common ways of checking for PVP/PVE
+		// NOTE: can potentially result in false positives, especially the first 2 clauses
+		for i in 0..requires_str.len() {
+			match requires_str[i..] {
+				["enttype", "target>", "critter", "eq", ..] => return Some(PVE_TAG),
+				["enttype", "target>", "player", "eq", ..] => return Some(PVP_TAG),
+				["isPVPMap?", "!", ..] => return Some(PVE_TAG),
+				["isPVPMap?", ..] => return Some(PVP_TAG),
+				_ => (),
+			}
+		}
+		None
+	}
+
+	/// Tests if this effect group only applies in PvP. See `pve_or_pvp`.
+	pub fn is_pvp_only(&self) -> bool {
+		self.pve_or_pvp() == Some(PVP_TAG)
+	}
+
+	/// Works out the chance this effect group is applied each time `power` activates.
+	///
+	/// If `f_procs_per_minute` is set, this uses the game's formula for procs:
+	/// `PPM * (recharge + cast time) / (60 * area factor)` for clicks, or `PPM * 10 / (60 * area factor)`
+	/// for toggles and autos, which check every 10 seconds. The area factor grows with the radius
+	/// (and arc, for cones) of AoE powers, so each target is less likely to be hit. The chance is
+	/// then kept between `5% + 1.5% * PPM` and 90%.
+	///
+	/// # Arguments:
+	/// * `power` - The power this effect group belongs to.
+	///
+	/// # Returns:
+	/// The chance from 0.0 to 1.0. If `f_procs_per_minute` is 0, this is just `f_chance`.
+	#[allow(dead_code)]
+	pub fn effective_chance(&self, power: &BasePower) -> f32 {
+		if self.f_procs_per_minute <= 0.0 {
+			return self.f_chance.max(0.0).min(1.0);
+		}
+		let ppm = self.f_procs_per_minute;
+		let arc_degrees = match power.e_effect_area {
+			EffectArea::kEffectArea_Cone => Some(power.f_arc.to_degrees()),
+			EffectArea::kEffectArea_Sphere | EffectArea::kEffectArea_Location => Some(360.0),
+			_ => None,
+		};
+		let area_factor = match arc_degrees {
+			Some(arc) if power.f_radius > 0.0 => 1.0 + 0.75 * power.f_radius * (11.0 * arc + 540.0) / 40000.0,
+			_ => 1.0,
+		};
+		let seconds = match power.e_type {
+			PowerType::kPowerType_Auto | PowerType::kPowerType_Toggle => 10.0,
+			_ => power.f_recharge_time + power.f_time_to_activate,
+		};
+		let chance = ppm * seconds / (60.0 * area_factor);
+		chance.max(0.05 + 0.015 * ppm).min(0.9).max(0.0).min(1.0)
+	}
+}
+
+/// Returned by `EffectGroup::walk` when effect groups are nested too deeply.
+#[derive(Debug, Error)]
+#[error("Effect groups are nested more than {max_depth} levels deep.")]
+pub struct EffectDepthError {
+	pub max_depth: usize,
 }
 
 #[derive(Debug, Default, Serialize)]
@@ -688,20 +1082,42 @@ pub struct PowerFX {
 	pub rgba_default_tint_secondary: RGBA,
 	/// Added i26p5. HideOriginal (maybe?)
 	pub b_hide_original: bool,
+	// Non-data fields.
+	/// Animation rate used to convert the frame counts above into seconds.
+	#[serde(skip)]
+	pub frame_rate: u32,
 }
 
 impl PowerFX {
+	/// The animation rate used by the game unless configured otherwise.
+	pub const DEFAULT_FRAME_RATE: u32 = 30;
+
 	pub fn new() -> Self {
-		Default::default()
+		PowerFX {
+			frame_rate: Self::DEFAULT_FRAME_RATE,
+			..Default::default()
+		}
 	}
 
-	/// Converts time expressed in frames into seconds.
-	pub fn frames_as_seconds(frames: i32) -> f32 {
-		const FRAME_TIME: f32 = 1.0 / 30.0; // 30fps
-		frames as f32 * FRAME_TIME
+	/// Converts time expressed in frames into seconds, using this FX's frame rate.
+	pub fn frames_as_seconds(&self, frames: i32) -> f32 {
+		frames_as_seconds_at(frames, self.frame_rate)
 	}
 }
 
+/// Converts time expressed in frames into seconds.
+///
+/// # Arguments:
+/// * `frames` - The number of frames.
+/// * `fps` - The animation rate in frames per second. 0 means use the default of 30.
+///
+/// # Returns:
+/// The time in seconds.
+pub fn frames_as_seconds_at(frames: i32, fps: u32) -> f32 {
+	let fps = if fps == 0 { PowerFX::DEFAULT_FRAME_RATE } else { fps };
+	frames as f32 / fps as f32
+}
+
 #[derive(Debug, Default, Serialize)]
 pub struct CustomPowerFX {
 	/// Shown in the customization menu.
@@ -728,6 +1144,28 @@ impl CustomPowerFX {
 	}
 }
 
+/// Summary of a power's area of effect, for telling single target powers apart from AoEs.
+#[derive(Debug, serde::Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AreaSummary {
+	/// The `EffectArea` as a string (e.g. "Cone").
+	pub area: Cow<'static, str>,
+	pub is_aoe: bool,
+	pub radius_feet: f32,
+	/// Only for cones.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub arc_degrees: Option<f32>,
+	pub max_targets_hit: i32,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub max_targets_expression: Option<String>,
+	/// Only for chains.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub chain_delay_time: Option<f32>,
+	/// Only for chains.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub chain_fork: Option<Vec<i32>>,
+}
+
 /// The basic definition of a power. This struct contains all the attributes of a power which are shared by all entities in the game. Character-specific
 /// differences (such as number of boosts, level, etc.) are kept in struct Power.
 #[derive(Debug, Default, Serialize)]
@@ -1070,10 +1508,326 @@ pub struct BasePower {
 	pub enhancement_set_categories_allowed: HashSet<String>,
 }
 
+/// Searches `tags` for any of the known critical hit tags.
+pub fn is_critical_by_tags(tags: &Vec<String>) -> bool {
+	for tag in tags {
+		match &tag[..] {
+			"MLCrit" | "BossCrit" | "PlayerCrit" | "ECCritModPlayer" | "ECCritModSmall"
+			| "ECCritModLarge" => return true,
+			_ => (),
+		}
+	}
+	false
+}
+
+/// Maps each of the `b_` flags on `BasePower` to a name, for `BasePower::flag_strings`.
+const BASE_POWER_FLAGS_TO_STRINGS: &'static [(fn(&BasePower) -> bool, &'static str)] = &[
+	(|p| p.b_auto_issue, "AutoIssue"),
+	(|p| p.b_auto_issue_save_level, "AutoIssueSaveLevel"),
+	(|p| p.b_free, "Free"),
+	(|p| p.b_near_ground, "NearGround"),
+	(|p| p.b_target_near_ground, "TargetNearGround"),
+	(|p| p.b_cast_through_hold, "CastThroughHold"),
+	(|p| p.b_cast_through_sleep, "CastThroughSleep"),
+	(|p| p.b_cast_through_stun, "CastThroughStun"),
+	(|p| p.b_cast_through_terrorize, "CastThroughTerrorize"),
+	(|p| p.b_toggle_ignore_hold, "ToggleIgnoreHold"),
+	(|p| p.b_toggle_ignore_sleep, "ToggleIgnoreSleep"),
+	(|p| p.b_toggle_ignore_stun, "ToggleIgnoreStun"),
+	(|p| p.b_ignore_level_bought, "IgnoreLevelBought"),
+	(|p| p.b_shoot_through_untouchable, "ShootThroughUntouchable"),
+	(|p| p.b_target_untargetable, "TargetUntargetable"),
+	(|p| p.b_interrupt_like_sleep, "InterruptLikeSleep"),
+	(|p| p.b_self_confirm, "SelfConfirm"),
+	(|p| p.b_destroy_on_limit, "DestroyOnLimit"),
+	(|p| p.b_stacking_usage, "StackingUsage"),
+	(|p| p.b_targets_through_vision_phase, "TargetsThroughVisionPhase"),
+	(|p| p.b_ignore_strength, "IgnoreStrength"),
+	(|p| p.b_show_buff_icon, "ShowBuffIcon"),
+	(|p| p.b_show_in_manage, "ShowInManage"),
+	(|p| p.b_show_in_info, "ShowInInfo"),
+	(|p| p.b_deletable, "Deletable"),
+	(|p| p.b_tradeable, "Tradeable"),
+	(|p| p.b_do_not_save, "DoNotSave"),
+	(|p| p.b_boost_ignore_effectiveness, "BoostIgnoreEffectiveness"),
+	(|p| p.b_boost_always_count_for_set, "BoostAlwaysCountForSet"),
+	(|p| p.b_boost_combinable, "BoostCombinable"),
+	(|p| p.b_boost_tradeable, "BoostTradeable"),
+	(|p| p.b_boost_account_bound, "BoostAccountBound"),
+	(|p| p.b_boost_boostable, "BoostBoostable"),
+	(|p| p.b_boost_use_player_level, "BoostUsePlayerLevel"),
+	(|p| p.b_use_non_boost_templates_on_main_target, "UseNonBoostTemplatesOnMainTarget"),
+	(|p| p.b_main_target_only, "MainTargetOnly"),
+	(|p| p.b_dont_set_stance, "DontSetStance"),
+	(|p| p.b_instance_locked, "InstanceLocked"),
+	(|p| p.b_is_environment_hit, "IsEnvironmentHit"),
+	(|p| p.b_shuffle_target_list, "ShuffleTargetList"),
+	(|p| p.b_refreshes_on_active_player_change, "RefreshesOnActivePlayerChange"),
+	(|p| p.b_cancelable, "Cancelable"),
+	(|p| p.b_ignore_toggle_max_distance, "IgnoreToggleMaxDistance"),
+	(|p| p.b_abusive_buff, "AbusiveBuff"),
+	(|p| p.b_face_target, "FaceTarget"),
+];
+
 impl BasePower {
 	pub fn new() -> Self {
 		Default::default()
 	}
+
+	/// Gets the names of all of the `b_` flags that are set on this power.
+	///
+	/// # Returns
+	/// A `Vec<&'static str>` containing zero or more values, in the order the fields are declared.
+	pub fn flag_strings(&self) -> Vec<&'static str> {
+		let mut strings = Vec::new();
+		for (is_set, s) in BASE_POWER_FLAGS_TO_STRINGS {
+			if is_set(self) {
+				strings.push(*s);
+			}
+		}
+		strings
+	}
+
+	/// Adds up the damage this power does to its targets in PvE.
+	///
+	/// # Arguments:
+	/// * `attrib_names` - The attribute name table, used to check for valid damage types.
+	/// * `archetype` - The archetype whose named tables are used to scale the damage.
+	/// * `level` - The combat level to scale the damage at (1-based).
+	/// * `max_depth` - How many levels of effect groups to follow, from `max_effect_depth` in the config.
+	///
+	/// # Returns:
+	/// The total damage of all damage effects, each weighted by the chance of its effect
+	/// group (and any parent groups). Damage over time is counted once per application.
+	/// An `EffectDepthError` if the effect groups are nested deeper than `max_depth`.
+	///
+	/// # Notes:
+	/// Templates with a calculated magnitude (`kModType_Expression`) can't be evaluated here
+	/// and are skipped. Use `has_expression_damage` to check if the total is incomplete.
+	pub fn total_damage(
+		&self,
+		attrib_names: &AttribNames,
+		archetype: &Archetype,
+		level: usize,
+		max_depth: Option<usize>,
+	) -> Result<f32, EffectDepthError> {
+		let mut total = 0.0;
+		for effect_group in &self.pp_effects {
+			for_each_damage_template(&*effect_group.borrow(), max_depth, &mut |template, chance| {
+				if matches!(template.e_type, ModType::kModType_Expression) {
+					return;
+				}
+				let valid_type = template.p_attrib.iter().any(|a| {
+					attrib_names.pp_damage.get(a.usize() / PTR_SIZE).is_some()
+				});
+				if !valid_type {
+					return;
+				}
+				let value = template
+					.pch_table
+					.as_ref()
+					.and_then(|table_name| archetype.table(table_name))
+					.and_then(|table| table.value_at(level));
+				if let Some(value) = value {
+					let scaled = value * template.f_scale;
+					// damage is a negative change to hit points, anything positive is healing
+					if scaled < 0.0 {
+						total += -scaled * chance;
+					}
+				}
+			})?;
+		}
+		Ok(total)
+	}
+
+	/// Tests if any of this power's PvE damage comes from a calculated magnitude, in which case
+	/// `total_damage` won't include it.
+	///
+	/// # Arguments:
+	/// * `max_depth` - How many levels of effect groups to follow, from `max_effect_depth` in the config.
+	///
+	/// # Returns:
+	/// An `EffectDepthError` if the effect groups are nested deeper than `max_depth`.
+	pub fn has_expression_damage(&self, max_depth: Option<usize>) -> Result<bool, EffectDepthError> {
+		let mut found = false;
+		for effect_group in &self.pp_effects {
+			for_each_damage_template(&*effect_group.borrow(), max_depth, &mut |template, _| {
+				if matches!(template.e_type, ModType::kModType_Expression) {
+					found = true;
+				}
+			})?;
+		}
+		Ok(found)
+	}
+
+	/// Gets the endurance this power uses per second.
+	///
+	/// # Returns:
+	/// For toggle and auto powers, the endurance cost of each activation period. For click powers,
+	/// the endurance cost spread over a full cycle of activating and recharging. `None` for boosts and
+	/// inspirations, or if the time is zero.
+	pub fn endurance_per_second(&self) -> Option<f32> {
+		match self.cast_cycle_seconds() {
+			Some(seconds) if seconds > 0.0 => Some(self.f_endurance_cost / seconds),
+			_ => None,
+		}
+	}
+
+	/// Gets the time between uses of this power.
+	///
+	/// # Returns:
+	/// For toggle and auto powers, the activation period. For click powers, the time to activate
+	/// plus the recharge time. `None` for boosts and inspirations.
+	pub fn cast_cycle_seconds(&self) -> Option<f32> {
+		match self.e_type {
+			PowerType::kPowerType_Toggle | PowerType::kPowerType_Auto => Some(self.f_activate_period),
+			PowerType::kPowerType_Click => Some(self.f_time_to_activate + self.f_recharge_time),
+			PowerType::kPowerType_Boost
+			| PowerType::kPowerType_Inspiration
+			| PowerType::kPowerType_GlobalBoost => None,
+		}
+	}
+
+	/// Checks whether this power can affect more than one target, as opposed to only its
+	/// focused target or the caster.
+	pub fn is_aoe(&self) -> bool {
+		!matches!(
+			self.e_effect_area,
+			EffectArea::kEffectArea_Character
+				| EffectArea::kEffectArea_Touch
+				| EffectArea::kEffectArea_Volume
+		)
+	}
+
+	/// Summarizes the area this power affects.
+	///
+	/// # Returns:
+	/// An `AreaSummary`. The arc is only filled in for cones, and the chain delay and forks
+	/// only for chains. Nothing is rounded.
+	pub fn area_summary(&self) -> AreaSummary {
+		let is_chain = matches!(self.e_effect_area, EffectArea::kEffectArea_Chain);
+		AreaSummary {
+			area: Cow::Borrowed(self.e_effect_area.get_string()),
+			is_aoe: self.is_aoe(),
+			radius_feet: self.f_radius,
+			arc_degrees: match self.e_effect_area {
+				EffectArea::kEffectArea_Cone => Some(self.f_arc.to_degrees()),
+				_ => None,
+			},
+			max_targets_hit: self.i_max_targets_hit,
+			max_targets_expression: crate::output::requires_to_string(&self.ppch_max_targets_expr),
+			chain_delay_time: if is_chain { Some(self.f_chain_delay) } else { None },
+			chain_fork: if is_chain { Some(self.pi_chain_fork.clone()) } else { None },
+		}
+	}
+
+	/// Gets the volume of the box this power affects.
+	///
+	/// # Returns:
+	/// The product of the absolute dimensions of `vec_box_size` in cubic feet, or `None` if
+	/// `e_effect_area` isn't Box.
+	#[allow(dead_code)]
+	pub fn box_volume(&self) -> Option<f32> {
+		match self.e_effect_area {
+			EffectArea::kEffectArea_Box => {
+				let size = &self.vec_box_size;
+				Some((size.x * size.y * size.z).abs())
+			}
+			_ => None,
+		}
+	}
+
+	/// Lists every character attribute this power modifies, across all of its effect groups.
+	///
+	/// # Arguments:
+	/// * `max_depth` - How many levels of effect groups to follow, from `max_effect_depth` in the config.
+	///
+	/// # Returns:
+	/// A `Vec<CharacterAttrib>` with each attribute offset from the templates' `p_attrib`,
+	/// in the order they were first found and without duplicates. An `EffectDepthError` if the
+	/// effect groups are nested deeper than `max_depth`.
+	#[allow(dead_code)]
+	pub fn modified_attribs(
+		&self,
+		max_depth: Option<usize>,
+	) -> Result<Vec<CharacterAttrib>, EffectDepthError> {
+		let mut offsets = Vec::new();
+		for effect_group in &self.pp_effects {
+			effect_group.borrow().walk(max_depth, &mut |effect_group, _| {
+				for template in &effect_group.pp_templates {
+					for attrib in &template.p_attrib {
+						if !offsets.contains(&attrib.0) {
+							offsets.push(attrib.0);
+						}
+					}
+				}
+			})?;
+		}
+		Ok(offsets.into_iter().map(CharacterAttrib).collect())
+	}
+
+	/// Same as `modified_attribs`, but converted to human readable names.
+	///
+	/// # Arguments:
+	/// * `attrib_names` - The attribute name table.
+	/// * `max_depth` - How many levels of effect groups to follow, from `max_effect_depth` in the config.
+	///
+	/// # Returns:
+	/// A `Vec<String>` of attribute names. Attributes without a name are skipped.
+	/// An `EffectDepthError` if the effect groups are nested deeper than `max_depth`.
+	#[allow(dead_code)]
+	pub fn modified_attrib_names(
+		&self,
+		attrib_names: &AttribNames,
+		max_depth: Option<usize>,
+	) -> Result<Vec<String>, EffectDepthError> {
+		Ok(self
+			.modified_attribs(max_depth)?
+			.iter()
+			.filter_map(|attrib| attrib.get_string(attrib_names))
+			.map(|name| name.into_owned())
+			.collect())
+	}
+}
+
+/// Calls `f` with each template in `effect_group` (and its children) that damages the target in
+/// PvE, along with the combined chance of the effect groups it's in. PvP only groups are skipped
+/// along with their children.
+///
+/// # Returns:
+/// An `EffectDepthError` if the groups are nested deeper than `max_depth`, see `EffectGroup::walk`.
+fn for_each_damage_template<F>(
+	effect_group: &EffectGroup,
+	max_depth: Option<usize>,
+	f: &mut F,
+) -> Result<(), EffectDepthError>
+where
+	F: FnMut(&AttribModTemplate, f32),
+{
+	// combined chance of the group at each depth of the current branch, `None` if it's skipped
+	let mut chances: Vec<Option<f32>> = Vec::new();
+	effect_group.walk(max_depth, &mut |effect_group, depth| {
+		chances.truncate(depth);
+		let parent_chance = chances.last().copied().unwrap_or(Some(1.0));
+		let chance = parent_chance
+			.filter(|_| !effect_group.is_pvp_only())
+			.map(|chance| chance * effect_group.f_chance);
+		chances.push(chance);
+		let chance = match chance {
+			Some(chance) => chance,
+			None => return,
+		};
+		for template in &effect_group.pp_templates {
+			let is_damage = template.off_aspect == OFFSET_ABSOLUTE
+				&& !matches!(template.e_target, ModTarget::kModTarget_Caster)
+				&& template.p_attrib.iter().any(|a| {
+					matches!(a.usize(), CharacterAttributes::OFFSET_DMG_0..=CharacterAttributes::OFFSET_DMG_19)
+				});
+			if is_damage {
+				f(template, chance);
+			}
+		}
+	})
 }
 
 /// Describes a power category as containing either primary or secondary sets.
@@ -1175,6 +1929,778 @@ pub struct PowersDictionary {
 	pub power_categories: Vec<ObjRef<PowerCategory>>,
 	/// All of the archetype data.
 	pub archetypes: Keyed<Archetype>,
+	/// All of the boost (enhancement) sets, including their bonus tiers.
+	pub boost_sets: Keyed<BoostSet>,
 	/// Character attribute names, mostly used for naming damage, defense, elusivity.
 	pub attrib_names: Rc<AttribNames>,
+	/// Lookup tables for `find_power` and friends, built on first use.
+	index: RefCell<Option<PowersIndex>>,
+	/// Every power read from the bins, whether it's in the hierarchy or not. Only kept if
+	/// `retain_orphans` is set in the config.
+	pub all_powers: Option<Keyed<BasePower>>,
+}
+
+/// Lookup tables from full names to the objects in a `PowersDictionary`.
+#[derive(Debug, Default)]
+pub struct PowersIndex {
+	categories: HashMap<NameKey, ObjRef<PowerCategory>>,
+	power_sets: HashMap<NameKey, ObjRef<BasePowerSet>>,
+	powers: HashMap<NameKey, ObjRef<BasePower>>,
+}
+
+impl PowersIndex {
+	/// Indexes every category, power set, and power in `power_categories` by full name.
+	fn from_power_categories(power_categories: &Vec<ObjRef<PowerCategory>>) -> Self {
+		let mut index = PowersIndex::default();
+		for pcat_ref in power_categories {
+			let pcat = pcat_ref.borrow();
+			if let Some(name) = &pcat.pch_name {
+				index.categories.insert(name.clone(), Rc::clone(pcat_ref));
+			}
+			for pset_ref in &pcat.pp_power_sets {
+				let pset = pset_ref.borrow();
+				if let Some(name) = &pset.pch_full_name {
+					index.power_sets.insert(name.clone(), Rc::clone(pset_ref));
+				}
+				for power_ref in &pset.pp_powers {
+					if let Some(name) = &power_ref.borrow().pch_full_name {
+						index.powers.insert(name.clone(), Rc::clone(power_ref));
+					}
+				}
+			}
+		}
+		index
+	}
+}
+
+impl PowersDictionary {
+	/// Creates a new `PowersDictionary`. The lookup tables are built on first use.
+	///
+	/// # Arguments:
+	/// * `power_categories` - The full hierarchy of power categories -> power sets -> powers.
+	/// * `archetypes` - All of the archetype data.
+	/// * `boost_sets` - All of the boost (enhancement) sets.
+	/// * `attrib_names` - Character attribute names.
+	/// * `all_powers` - Every power read from the bins, if `retain_orphans` is set.
+	pub fn new(
+		power_categories: Vec<ObjRef<PowerCategory>>,
+		archetypes: Keyed<Archetype>,
+		boost_sets: Keyed<BoostSet>,
+		attrib_names: Rc<AttribNames>,
+		all_powers: Option<Keyed<BasePower>>,
+	) -> Self {
+		PowersDictionary {
+			power_categories,
+			archetypes,
+			boost_sets,
+			attrib_names,
+			index: RefCell::new(None),
+			all_powers,
+		}
+	}
+
+	/// Creates a `PowersDictionary` holding just `power_categories`, for use in tests.
+	#[cfg(test)]
+	pub fn from_power_categories(power_categories: Vec<ObjRef<PowerCategory>>) -> Self {
+		PowersDictionary::new(
+			power_categories,
+			Keyed::new(),
+			Keyed::new(),
+			Rc::new(AttribNames::new()),
+			None,
+		)
+	}
+
+	/// Finds a power by its full name (e.g. "Pool.Flight.Fly"), ignoring case.
+	#[allow(dead_code)]
+	pub fn find_power(&self, key: &NameKey) -> Option<ObjRef<BasePower>> {
+		self.with_index(|index| index.powers.get(key).cloned())
+	}
+
+	/// Finds a power set by its full name (e.g. "Pool.Flight"), ignoring case.
+	#[allow(dead_code)]
+	pub fn find_power_set(&self, key: &NameKey) -> Option<ObjRef<BasePowerSet>> {
+		self.with_index(|index| index.power_sets.get(key).cloned())
+	}
+
+	/// Finds a power category by its name (e.g. "Pool"), ignoring case.
+	#[allow(dead_code)]
+	pub fn find_category(&self, key: &NameKey) -> Option<ObjRef<PowerCategory>> {
+		self.with_index(|index| index.categories.get(key).cloned())
+	}
+
+	/// Lists the powers that were read but aren't included in the output, because nothing
+	/// included references them or because a filter removed them.
+	///
+	/// # Returns:
+	/// A `Vec<NameKey>` with the full names of the orphaned powers, sorted by name. Always empty
+	/// unless the dictionary was loaded with `retain_orphans` set.
+	#[allow(dead_code)]
+	pub fn orphan_powers(&self) -> Vec<NameKey> {
+		let mut orphans: Vec<NameKey> = match &self.all_powers {
+			Some(powers) => powers
+				.0
+				.iter()
+				.filter(|(_, power)| !power.borrow().include_in_output)
+				.map(|(name, _)| name.clone())
+				.collect(),
+			None => Vec::new(),
+		};
+		orphans.sort_by(|a, b| a.get().cmp(b.get()));
+		orphans
+	}
+
+	/// Calls `f` with the lookup tables, building them first if necessary.
+	///
+	/// # Notes:
+	/// The tables are only built once, so objects added to the hierarchy after the first
+	/// lookup won't be found.
+	fn with_index<F, T>(&self, f: F) -> T
+	where
+		F: FnOnce(&PowersIndex) -> T,
+	{
+		if self.index.borrow().is_none() {
+			let index = PowersIndex::from_power_categories(&self.power_categories);
+			*self.index.borrow_mut() = Some(index);
+		}
+		// `f` may look things up again, so only hold a shared borrow while it runs
+		let index = self.index.borrow();
+		f(index.as_ref().unwrap())
+	}
+
+	/// Iterates over the power categories.
+	///
+	/// # Arguments:
+	/// * `included_only` - If `true`, categories that aren't included in the output are skipped.
+	pub fn iter_categories(
+		&self,
+		included_only: bool,
+	) -> impl Iterator<Item = ObjRef<PowerCategory>> + '_ {
+		self.power_categories
+			.iter()
+			.filter(move |pcat| !included_only || pcat.borrow().include_in_output)
+			.cloned()
+	}
+
+	/// Iterates over the power sets in every power category.
+	///
+	/// # Arguments:
+	/// * `included_only` - If `true`, power sets that aren't included in the output (or whose
+	///   category isn't) are skipped.
+	pub fn iter_power_sets(
+		&self,
+		included_only: bool,
+	) -> impl Iterator<Item = ObjRef<BasePowerSet>> + '_ {
+		self.iter_categories(included_only).flat_map(move |pcat| {
+			pcat.borrow()
+				.pp_power_sets
+				.iter()
+				.filter(|pset| !included_only || pset.borrow().include_in_output)
+				.cloned()
+				.collect::<Vec<_>>()
+		})
+	}
+
+	/// Iterates over the powers in every power set.
+	///
+	/// # Arguments:
+	/// * `included_only` - If `true`, powers that aren't included in the output (or whose
+	///   power set or category isn't) are skipped.
+	///
+	/// # Notes:
+	/// A power that appears in more than one power set is returned once for each.
+	pub fn iter_powers(&self, included_only: bool) -> impl Iterator<Item = ObjRef<BasePower>> + '_ {
+		self.iter_power_sets(included_only).flat_map(move |pset| {
+			pset.borrow()
+				.pp_powers
+				.iter()
+				.filter(|power| !included_only || power.borrow().include_in_output)
+				.cloned()
+				.collect::<Vec<_>>()
+		})
+	}
+
+	/// Builds a graph of the powers that must be owned before another power can be bought.
+	///
+	/// # Returns:
+	/// A `HashMap` keyed by the full name of each power that has prerequisites, with the
+	/// full names of the powers referenced by its `ppch_buy_requires` as values.
+	#[allow(dead_code)]
+	pub fn prerequisite_graph(&self) -> HashMap<NameKey, Vec<NameKey>> {
+		let mut graph = HashMap::new();
+		for power in self.iter_powers(false) {
+			let power = power.borrow();
+			if let Some(full_name) = &power.pch_full_name {
+				let prereqs = requires_power_names(&power.ppch_buy_requires);
+				if !prereqs.is_empty() {
+					graph.insert(full_name.clone(), prereqs);
+				}
+			}
+		}
+		graph
+	}
+}
+
+/// Extracts the power names referenced by a requires expression.
+///
+/// Requires expressions are stored in postfix order, so the argument to a function
+/// token (one ending in `?`, e.g. `source.ownPower?`) is the token just before it.
+/// Only arguments that look like full power names (category.set.power) are kept.
+pub fn requires_power_names(requires: &[String]) -> Vec<NameKey> {
+	let mut names: Vec<NameKey> = Vec::new();
+	for pair in requires.windows(2) {
+		let (arg, token) = (&pair[0], &pair[1]);
+		if token.ends_with('?') && arg.split('.').count() == 3 && !arg.ends_with('>') {
+			let name = NameKey::new(arg.as_str());
+			if !names.contains(&name) {
+				names.push(name);
+			}
+		}
+	}
+	names
+}
+
+/// Lists the powers from an archetype's primary and secondary power sets that can be picked at or
+/// before `level` (starting at 1).
+///
+/// # Arguments:
+/// * `powers_dict` - Used to look up the archetype's power categories.
+/// * `archetype` - The archetype to check.
+/// * `level` - The character level.
+///
+/// # Returns:
+/// A `Vec<NameKey>` with the full names of the powers, primary sets first, in the order they're
+/// listed in each set.
+#[allow(dead_code)]
+pub fn available_powers_at_level(powers_dict: &PowersDictionary, archetype: &Archetype, level: i32) -> Vec<NameKey> {
+	let mut names = Vec::new();
+	for category_name in archetype.pch_primary_category.iter().chain(&archetype.pch_secondary_category) {
+		if let Some(pcat) = powers_dict.find_category(category_name) {
+			for pset in &pcat.borrow().pp_power_sets {
+				names.extend(pset.borrow().powers_available_at(level));
+			}
+		}
+	}
+	names
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rgba_format_round_trip_test() {
+		let color = RGBA::new(255, 128, 0, 255);
+		for (format, expected) in &[
+			(RgbaFormat::HexString, r##""#FF8000FF""##),
+			(RgbaFormat::Object, r#"{"r":255,"g":128,"b":0,"a":255}"#),
+			(RgbaFormat::Array, "[255,128,0,255]"),
+		] {
+			let json = {
+				let _scope = RgbaFormatScope::enter(*format);
+				serde_json::to_string(&color).unwrap()
+			};
+			assert_eq!(json, *expected);
+			let parsed: RGBA = serde_json::from_str(&json).unwrap();
+			assert_eq!(parsed.0, color.0);
+		}
+		// the scope only lasts until it's dropped
+		assert_eq!(serde_json::to_string(&color).unwrap(), r##""#FF8000FF""##);
+		assert!(serde_json::from_str::<RGBA>(r#"{"r":255,"g":128,"b":0}"#).is_err());
+		assert!(serde_json::from_str::<RGBA>("[255,128,0]").is_err());
+		assert!(serde_json::from_str::<RGBA>(r##""#FF8000""##).is_err());
+	}
+
+	#[test]
+	fn prerequisite_graph_test() {
+		let mut fly = BasePower::new();
+		fly.pch_full_name = Some(NameKey::new("Pool.Flight.Fly"));
+		fly.ppch_buy_requires = vec![
+			String::from("Pool.Flight.Air_Superiority"),
+			String::from("source.ownPower?"),
+			String::from("Pool.Flight.Hover"),
+			String::from("source.ownPower?"),
+			String::from("||"),
+		];
+		let mut hover = BasePower::new();
+		hover.pch_full_name = Some(NameKey::new("Pool.Flight.Hover"));
+		let mut pset = BasePowerSet::new();
+		pset.pp_powers.push(Rc::new(RefCell::new(fly)));
+		pset.pp_powers.push(Rc::new(RefCell::new(hover)));
+		let mut pcat = PowerCategory::new();
+		pcat.pp_power_sets.push(Rc::new(RefCell::new(pset)));
+
+		let powers_dict = PowersDictionary::from_power_categories(vec![Rc::new(RefCell::new(pcat))]);
+		let graph = powers_dict.prerequisite_graph();
+		assert_eq!(graph.len(), 1);
+		assert_eq!(
+			graph.get(&NameKey::new("Pool.Flight.Fly")),
+			Some(&vec![
+				NameKey::new("Pool.Flight.Air_Superiority"),
+				NameKey::new("Pool.Flight.Hover")
+			])
+		);
+	}
+
+	#[test]
+	fn orphan_powers_test() {
+		let mut all_powers = Keyed::new();
+		for (name, included) in &[
+			("Pool.Flight.Fly", true),
+			("Pool.Flight.Unused_Test_Power", false),
+			("Pool.Flight.Excluded_Power", false),
+		] {
+			let mut power = BasePower::new();
+			power.pch_full_name = Some(NameKey::new(*name));
+			power.include_in_output = *included;
+			all_powers.insert(NameKey::new(*name), power);
+		}
+		let mut powers_dict = PowersDictionary::from_power_categories(Vec::new());
+		assert!(powers_dict.orphan_powers().is_empty());
+
+		powers_dict.all_powers = Some(all_powers);
+		assert_eq!(
+			powers_dict.orphan_powers(),
+			vec![
+				NameKey::new("Pool.Flight.Excluded_Power"),
+				NameKey::new("Pool.Flight.Unused_Test_Power")
+			]
+		);
+	}
+
+	#[test]
+	fn find_power_redirect_test() {
+		let mut redirect = PowerRedirect::new();
+		redirect.pch_name = Some(NameKey::new("Blaster_Ranged.Fire_Blast.Fire_Blast_Fire_Mode"));
+		let mut fire_blast = BasePower::new();
+		fire_blast.pch_full_name = Some(NameKey::new("Blaster_Ranged.Fire_Blast.Fire_Blast"));
+		fire_blast.pp_redirect.push(redirect);
+		let mut fire_mode = BasePower::new();
+		fire_mode.pch_full_name = Some(NameKey::new("Blaster_Ranged.Fire_Blast.Fire_Blast_Fire_Mode"));
+		fire_mode.pch_display_name = Some(String::from("Fire Blast (Fire Mode)"));
+		let mut pset = BasePowerSet::new();
+		pset.pch_full_name = Some(NameKey::new("Blaster_Ranged.Fire_Blast"));
+		pset.pp_powers.push(Rc::new(RefCell::new(fire_blast)));
+		pset.pp_powers.push(Rc::new(RefCell::new(fire_mode)));
+		let mut pcat = PowerCategory::new();
+		pcat.pch_name = Some(NameKey::new("Blaster_Ranged"));
+		pcat.pp_power_sets.push(Rc::new(RefCell::new(pset)));
+		let powers_dict = PowersDictionary::from_power_categories(vec![Rc::new(RefCell::new(pcat))]);
+
+		// source files are lowercased, so lookups shouldn't depend on case
+		let power = powers_dict
+			.find_power(&NameKey::new("blaster_ranged.fire_blast.fire_blast"))
+			.unwrap();
+		let target = power.borrow().pp_redirect[0].pch_name.clone().unwrap();
+		let redirected = powers_dict.find_power(&target).unwrap();
+		assert_eq!(
+			redirected.borrow().pch_display_name.as_deref(),
+			Some("Fire Blast (Fire Mode)")
+		);
+		assert!(powers_dict.find_power(&NameKey::new("Blaster_Ranged.Fire_Blast.Missing")).is_none());
+		assert!(powers_dict.find_power_set(&NameKey::new("BLASTER_RANGED.FIRE_BLAST")).is_some());
+		assert!(powers_dict.find_category(&NameKey::new("blaster_ranged")).is_some());
+
+		// lookups from inside the index closure must not trip the RefCell
+		let nested = powers_dict.with_index(|_| {
+			powers_dict.find_power(&NameKey::new("Blaster_Ranged.Fire_Blast.Fire_Blast")).is_some()
+		});
+		assert!(nested);
+	}
+
+	#[test]
+	fn total_damage_test() {
+		let mut attrib_names = AttribNames::new();
+		attrib_names.pp_damage.push(AttribName::new());
+		attrib_names.pp_damage.push(AttribName::new());
+		let mut at = Archetype::new();
+		let mut table = NamedTable::new();
+		table.pf_values = vec![-10.0, -20.0];
+		at.pp_named_tables.insert(String::from("ranged_damage"), table);
+		let make_template = |dmg_index: i32, scale: f32| {
+			let mut template = AttribModTemplate::new();
+			template.off_aspect = OFFSET_ABSOLUTE;
+			template.p_attrib.push(CharacterAttrib(CharacterAttributes::OFFSET_DMG_0 as i32 + dmg_index * 4));
+			template.pch_table = Some(String::from("Ranged_Damage"));
+			template.f_scale = scale;
+			template
+		};
+		// 1.0 scale smashing + 0.5 scale fire
+		let mut hit = EffectGroup::new();
+		hit.f_chance = 1.0;
+		hit.pp_templates.push(make_template(0, 1.0));
+		hit.pp_templates.push(make_template(1, 0.5));
+		// 50% chance for 1.0 scale fire
+		let mut proc_group = EffectGroup::new();
+		proc_group.f_chance = 0.5;
+		proc_group.pp_templates.push(make_template(1, 1.0));
+		hit.pp_effects.push(proc_group);
+		// PvP and self damage aren't counted
+		let mut pvp = EffectGroup::new();
+		pvp.f_chance = 1.0;
+		pvp.i_flags = EffectGroupFlag::PVPOnly;
+		pvp.pp_templates.push(make_template(0, 5.0));
+		let mut self_damage = make_template(0, 5.0);
+		self_damage.e_target = ModTarget::kModTarget_Caster;
+		hit.pp_templates.push(self_damage);
+		let mut pvp_requires = EffectGroup::new();
+		pvp_requires.f_chance = 1.0;
+		pvp_requires.ppch_requires = ["enttype", "target>", "player", "eq"].iter().map(|s| s.to_string()).collect();
+		pvp_requires.pp_templates.push(make_template(0, 5.0));
+		let mut power = BasePower::new();
+		power.pp_effects.push(Rc::new(RefCell::new(hit)));
+		power.pp_effects.push(Rc::new(RefCell::new(pvp)));
+		power.pp_effects.push(Rc::new(RefCell::new(pvp_requires)));
+
+		assert_eq!(power.total_damage(&attrib_names, &at, 2, None).unwrap(), 40.0);
+		assert_eq!(power.total_damage(&attrib_names, &at, 1, None).unwrap(), 20.0);
+		assert!(!power.has_expression_damage(None).unwrap());
+		// the proc group is one level down
+		assert!(power.total_damage(&attrib_names, &at, 2, Some(2)).is_ok());
+		let err = power.total_damage(&attrib_names, &at, 2, Some(1)).unwrap_err();
+		assert_eq!(err.max_depth, 1);
+
+		let mut expression = make_template(0, 1.0);
+		expression.e_type = ModType::kModType_Expression;
+		power.pp_effects[0].borrow_mut().pp_templates.push(expression);
+		assert_eq!(power.total_damage(&attrib_names, &at, 2, None).unwrap(), 40.0);
+		assert!(power.has_expression_damage(None).unwrap());
+	}
+
+	#[test]
+	fn named_table_value_at_test() {
+		let mut table = NamedTable::new();
+		table.pf_values = vec![1.0, 2.0, 4.0];
+		assert_eq!(table.value_at(0), Some(1.0));
+		assert_eq!(table.value_at(1), Some(1.0));
+		assert_eq!(table.value_at(2), Some(2.0));
+		assert_eq!(table.value_at(3), Some(4.0));
+		assert_eq!(table.value_at(50), Some(4.0));
+		assert_eq!(table.value_at_interpolated(0.0), Some(1.0));
+		assert_eq!(table.value_at_interpolated(1.5), Some(1.5));
+		assert_eq!(table.value_at_interpolated(2.25), Some(2.5));
+		assert_eq!(table.value_at_interpolated(3.0), Some(4.0));
+		assert_eq!(table.value_at_interpolated(50.5), Some(4.0));
+		assert_eq!(NamedTable::new().value_at(1), None);
+		assert_eq!(NamedTable::new().value_at_interpolated(1.0), None);
+	}
+
+	#[test]
+	fn archetype_table_test() {
+		let mut at = Archetype::new();
+		let mut table = NamedTable::new();
+		table.pf_values = vec![-10.0];
+		at.pp_named_tables.insert(String::from("ranged_damage"), table);
+		let mut template = AttribModTemplate::new();
+		template.pch_table = Some(String::from("Ranged_Damage"));
+		let table = template.pch_table.as_ref().and_then(|name| at.table(name));
+		assert_eq!(table.and_then(|t| t.value_at(1)), Some(-10.0));
+		assert!(at.table("RANGED_DAMAGE").is_some());
+		assert!(at.table("melee_damage").is_none());
+	}
+
+	#[test]
+	fn modified_attribs_test() {
+		let mut attrib_names = AttribNames::new();
+		for name in &["Smashing", "Lethal"] {
+			let mut attrib_name = AttribName::new();
+			attrib_name.pch_display_name = Some(String::from(*name));
+			attrib_names.pp_damage.push(attrib_name);
+		}
+		let make_template = |offsets: &[usize]| {
+			let mut template = AttribModTemplate::new();
+			for offset in offsets {
+				template.p_attrib.push(CharacterAttrib(*offset as i32));
+			}
+			template
+		};
+		let mut hit = EffectGroup::new();
+		hit.pp_templates.push(make_template(&[CharacterAttributes::OFFSET_DMG_0]));
+		hit.pp_templates.push(make_template(&[CharacterAttributes::OFFSET_DMG_0 + PTR_SIZE, CharacterAttributes::OFFSET_DMG_0]));
+		let mut child = EffectGroup::new();
+		child.pp_templates.push(make_template(&[CharacterAttributes::OFFSET_TOHIT]));
+		hit.pp_effects.push(child);
+		let mut power = BasePower::new();
+		power.pp_effects.push(Rc::new(RefCell::new(hit)));
+
+		let offsets: Vec<usize> = power.modified_attribs(None).unwrap().iter().map(|a| a.usize()).collect();
+		assert_eq!(
+			offsets,
+			vec![
+				CharacterAttributes::OFFSET_DMG_0,
+				CharacterAttributes::OFFSET_DMG_0 + PTR_SIZE,
+				CharacterAttributes::OFFSET_TOHIT
+			]
+		);
+		assert_eq!(
+			power.modified_attrib_names(&attrib_names, None).unwrap(),
+			vec!["Smashing_Dmg", "Lethal_Dmg", "ToHit"]
+		);
+		assert!(BasePower::new().modified_attribs(None).unwrap().is_empty());
+		assert!(power.modified_attribs(Some(1)).is_err());
+	}
+
+	#[test]
+	fn suppress_pair_describe_test() {
+		let mut suppress = SuppressPair::new();
+		suppress.idx_event = PowerEvent::kPowerEvent_Hit;
+		suppress.ul_seconds = 5;
+		suppress.b_always = false;
+		assert_eq!(suppress.describe(), "suppressed for 5s after Hit (unless already applied)");
+		suppress.b_always = true;
+		assert_eq!(suppress.describe(), "suppressed for 5s after Hit (always)");
+	}
+
+	#[test]
+	fn vec3_test() {
+		let v = Vec3 { x: 3.0, y: 4.0, z: 12.0 };
+		assert_eq!(v.length(), 13.0);
+		let sum = v.add(&Vec3 { x: 1.0, y: -4.0, z: 0.5 });
+		assert_eq!((sum.x, sum.y, sum.z), (4.0, 0.0, 12.5));
+		let scaled = v.scale(-2.0);
+		assert_eq!((scaled.x, scaled.y, scaled.z), (-6.0, -8.0, -24.0));
+		assert_eq!(scaled.length(), 26.0);
+		assert!(!v.is_zero());
+		assert!(Vec3::default().is_zero());
+		assert_eq!(Vec3::default().length(), 0.0);
+	}
+
+	#[test]
+	fn box_volume_test() {
+		let mut power = BasePower::new();
+		power.vec_box_size = Vec3 { x: -10.0, y: 8.0, z: 20.0 };
+		assert_eq!(power.box_volume(), None);
+		power.e_effect_area = EffectArea::kEffectArea_Box;
+		assert_eq!(power.box_volume(), Some(1600.0));
+		power.vec_box_size.y = 0.0;
+		assert_eq!(power.box_volume(), Some(0.0));
+	}
+
+	#[test]
+	fn frames_as_seconds_test() {
+		let mut fx = PowerFX::new();
+		assert_eq!(fx.frame_rate, 30);
+		assert_eq!(fx.frames_as_seconds(15), 0.5);
+		assert_eq!(fx.frames_as_seconds(35), 35.0 / 30.0);
+		fx.frame_rate = 60;
+		assert_eq!(fx.frames_as_seconds(15), 0.25);
+		assert_eq!(fx.frames_as_seconds(120), 2.0);
+		assert_eq!(frames_as_seconds_at(30, 30), 1.0);
+		assert_eq!(frames_as_seconds_at(30, 60), 0.5);
+		assert_eq!(frames_as_seconds_at(30, 0), 1.0);
+	}
+
+	#[test]
+	fn effect_group_walk_test() {
+		let make_group = |chance: f32, children: Vec<EffectGroup>| {
+			let mut effect_group = EffectGroup::new();
+			effect_group.f_chance = chance;
+			effect_group.pp_effects = children;
+			effect_group
+		};
+		let root = make_group(
+			1.0,
+			vec![make_group(2.0, vec![make_group(3.0, Vec::new())]), make_group(4.0, Vec::new())],
+		);
+		let mut visited = Vec::new();
+		root.walk(None, &mut |effect_group, depth| visited.push((effect_group.f_chance, depth)))
+			.unwrap();
+		assert_eq!(visited, vec![(1.0, 0), (2.0, 1), (3.0, 2), (4.0, 1)]);
+
+		// groups past the limit are skipped, but their siblings are still visited
+		let mut visited = Vec::new();
+		let err = root
+			.walk(Some(2), &mut |effect_group, depth| visited.push((effect_group.f_chance, depth)))
+			.unwrap_err();
+		assert_eq!(err.max_depth, 2);
+		assert_eq!(visited, vec![(1.0, 0), (2.0, 1), (4.0, 1)]);
+		assert!(root.walk(Some(3), &mut |_, _| ()).is_ok());
+
+		let mut deep = EffectGroup::new();
+		for _ in 0..1000 {
+			deep = make_group(1.0, vec![deep]);
+		}
+		let mut count = 0;
+		deep.walk(None, &mut |_, _| count += 1).unwrap();
+		assert_eq!(count, 1001);
+	}
+
+	#[test]
+	fn endurance_per_second_test() {
+		let mut toggle = BasePower::new();
+		toggle.e_type = PowerType::kPowerType_Toggle;
+		toggle.f_endurance_cost = 0.26;
+		toggle.f_activate_period = 0.5;
+		toggle.f_recharge_time = 4.0;
+		assert_eq!(toggle.endurance_per_second(), Some(0.52));
+
+		let mut click = BasePower::new();
+		click.e_type = PowerType::kPowerType_Click;
+		click.f_endurance_cost = 10.0;
+		click.f_recharge_time = 8.0;
+		click.f_time_to_activate = 2.0;
+		assert_eq!(click.endurance_per_second(), Some(1.0));
+
+		click.f_recharge_time = 0.0;
+		click.f_time_to_activate = 0.0;
+		assert_eq!(click.endurance_per_second(), None);
+		toggle.f_activate_period = 0.0;
+		assert_eq!(toggle.endurance_per_second(), None);
+		let mut inspiration = BasePower::new();
+		inspiration.e_type = PowerType::kPowerType_Inspiration;
+		inspiration.f_endurance_cost = 1.0;
+		inspiration.f_activate_period = 1.0;
+		assert_eq!(inspiration.endurance_per_second(), None);
+	}
+
+	#[test]
+	fn iter_powers_test() {
+		let make_power = |name: &str, include_in_output| {
+			let mut power = BasePower::new();
+			power.pch_full_name = Some(NameKey::new(name));
+			power.include_in_output = include_in_output;
+			Rc::new(RefCell::new(power))
+		};
+		let make_set = |name: &str, include_in_output, powers| {
+			let mut pset = BasePowerSet::new();
+			pset.pch_full_name = Some(NameKey::new(name));
+			pset.include_in_output = include_in_output;
+			pset.pp_powers = powers;
+			Rc::new(RefCell::new(pset))
+		};
+		let mut pool = PowerCategory::new();
+		pool.pch_name = Some(NameKey::new("Pool"));
+		pool.include_in_output = true;
+		pool.pp_power_sets = vec![
+			make_set(
+				"Pool.Flight",
+				true,
+				vec![make_power("Pool.Flight.Hover", true), make_power("Pool.Flight.Fly", false)],
+			),
+			make_set("Pool.Hidden", false, vec![make_power("Pool.Hidden.Secret", true)]),
+		];
+		let mut temporary = PowerCategory::new();
+		temporary.pch_name = Some(NameKey::new("Temporary_Powers"));
+		temporary.pp_power_sets = vec![make_set(
+			"Temporary_Powers.Accolades",
+			true,
+			vec![make_power("Temporary_Powers.Accolades.Task_Force_Commander", true)],
+		)];
+		let powers_dict = PowersDictionary::from_power_categories(vec![Rc::new(RefCell::new(pool)), Rc::new(RefCell::new(temporary))]);
+
+		let names = |powers: Vec<ObjRef<BasePower>>| {
+			powers
+				.iter()
+				.map(|p| p.borrow().pch_full_name.as_ref().unwrap().get().to_owned())
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(
+			names(powers_dict.iter_powers(false).collect()),
+			vec![
+				"Pool.Flight.Hover",
+				"Pool.Flight.Fly",
+				"Pool.Hidden.Secret",
+				"Temporary_Powers.Accolades.Task_Force_Commander"
+			]
+		);
+		assert_eq!(names(powers_dict.iter_powers(true).collect()), vec!["Pool.Flight.Hover"]);
+		assert_eq!(powers_dict.iter_power_sets(false).count(), 3);
+		assert_eq!(powers_dict.iter_power_sets(true).count(), 1);
+		assert_eq!(powers_dict.iter_categories(false).count(), 2);
+		assert_eq!(powers_dict.iter_categories(true).count(), 1);
+	}
+
+	#[test]
+	fn powers_available_at_test() {
+		let make_set = |name: &str, powers: &[(&str, i32)]| {
+			let mut pset = BasePowerSet::new();
+			pset.pch_full_name = Some(NameKey::new(name));
+			for (power, level) in powers {
+				pset.pp_power_names.push(NameKey::new(format!("{}.{}", name, power)));
+				pset.pi_available.push(*level - 1);
+			}
+			Rc::new(RefCell::new(pset))
+		};
+		let blaster = make_set("Blaster_Ranged.Fire_Blast", &[("Flares", 1), ("Fire_Blast", 4), ("Fire_Ball", 10)]);
+		{
+			let pset = blaster.borrow();
+			let names = |level| pset.powers_available_at(level).iter().map(|n| n.get().to_owned()).collect::<Vec<_>>();
+			assert!(names(0).is_empty());
+			assert_eq!(names(1), vec!["Blaster_Ranged.Fire_Blast.Flares"]);
+			assert_eq!(names(9), vec!["Blaster_Ranged.Fire_Blast.Flares", "Blaster_Ranged.Fire_Blast.Fire_Blast"]);
+			assert_eq!(names(10).len(), 3);
+			assert_eq!(pset.available_levels().map(|(_, level)| level).collect::<Vec<_>>(), vec![1, 4, 10]);
+		}
+
+		let make_category = |name: &str, sets| {
+			let mut pcat = PowerCategory::new();
+			pcat.pch_name = Some(NameKey::new(name));
+			pcat.pp_power_sets = sets;
+			Rc::new(RefCell::new(pcat))
+		};
+		let powers_dict = PowersDictionary::from_power_categories(vec![
+			make_category("Blaster_Ranged", vec![blaster]),
+			make_category("Blaster_Support", vec![make_set("Blaster_Support.Devices", &[("Web_Grenade", 1), ("Caltrops", 2)])]),
+			make_category("Pool", vec![make_set("Pool.Flight", &[("Hover", 4)])]),
+		]);
+		let mut archetype = Archetype::new();
+		archetype.pch_primary_category = Some(NameKey::new("Blaster_Ranged"));
+		archetype.pch_secondary_category = Some(NameKey::new("Blaster_Support"));
+		let names = |level| {
+			available_powers_at_level(&powers_dict, &archetype, level)
+				.iter()
+				.map(|n| n.get().to_owned())
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(names(1), vec!["Blaster_Ranged.Fire_Blast.Flares", "Blaster_Support.Devices.Web_Grenade"]);
+		assert_eq!(
+			names(4),
+			vec![
+				"Blaster_Ranged.Fire_Blast.Flares",
+				"Blaster_Ranged.Fire_Blast.Fire_Blast",
+				"Blaster_Support.Devices.Web_Grenade",
+				"Blaster_Support.Devices.Caltrops",
+			]
+		);
+	}
+
+	#[test]
+	fn effective_chance_test() {
+		let mut power = BasePower::new();
+		power.f_recharge_time = 10.0;
+		power.f_time_to_activate = 1.0;
+		let mut effect_group = EffectGroup::new();
+		effect_group.f_procs_per_minute = 3.5;
+		let close = |a: f32, b: f32| (a - b).abs() < 0.0001;
+
+		// single target click: 3.5 * 11 / 60
+		assert!(close(effect_group.effective_chance(&power), 0.641667));
+		// 15 ft. sphere: area factor of 1 + 0.75 * 15 * 4500 / 40000
+		power.e_effect_area = EffectArea::kEffectArea_Sphere;
+		power.f_radius = 15.0;
+		assert!(close(effect_group.effective_chance(&power), 0.641667 / 2.265625));
+		// 90 degree cone
+		power.e_effect_area = EffectArea::kEffectArea_Cone;
+		power.f_arc = std::f32::consts::FRAC_PI_2;
+		assert!(close(effect_group.effective_chance(&power), 0.641667 / 1.4303125));
+		// toggles check every 10 seconds, regardless of recharge
+		power.e_effect_area = EffectArea::kEffectArea_Character;
+		power.e_type = PowerType::kPowerType_Toggle;
+		assert!(close(effect_group.effective_chance(&power), 3.5 * 10.0 / 60.0));
+
+		// limited to 5% + 1.5% per PPM and 90%
+		power.e_type = PowerType::kPowerType_Click;
+		power.f_recharge_time = 0.5;
+		assert!(close(effect_group.effective_chance(&power), 0.05 + 0.015 * 3.5));
+		power.f_recharge_time = 10.0;
+		effect_group.f_procs_per_minute = 10.0;
+		assert!(close(effect_group.effective_chance(&power), 0.9));
+
+		// no PPM falls back to the flat chance
+		effect_group.f_procs_per_minute = 0.0;
+		effect_group.f_chance = 0.2;
+		assert!(close(effect_group.effective_chance(&power), 0.2));
+		effect_group.f_chance = 1.5;
+		assert_eq!(effect_group.effective_chance(&power), 1.0);
+	}
 }
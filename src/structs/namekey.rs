@@ -51,6 +51,57 @@ impl NameKey {
         // altered to remove blank entries as I found some that have a trailing dot
         self.0.split(SEPARATOR).filter(|s| *s != "").collect()
     }
+
+    /// Tests if this `NameKey` matches `pattern`, ignoring case. The pattern must have the same
+    /// number of segments, and `*` matches any run of characters within a segment
+    /// (e.g. `Tanker_Melee.*.*` or `Incarnate.Lore_Pet_*`).
+    pub fn matches_glob(&self, pattern: &str) -> bool {
+        let segments = self.split();
+        let pattern_segments: Vec<_> = pattern.split(SEPARATOR).filter(|s| *s != "").collect();
+        segments.len() == pattern_segments.len()
+            && segments
+                .iter()
+                .zip(&pattern_segments)
+                .all(|(s, p)| glob_match_segment(s.as_bytes(), p.as_bytes()))
+    }
+
+    /// Tests if the leading segments of this `NameKey` are `prefix`, ignoring case.
+    /// For example, `Pool.Flight.Fly` starts with `Pool.Flight` but not `Pool.Fl`.
+    #[allow(dead_code)]
+    pub fn starts_with_segment(&self, prefix: &str) -> bool {
+        let segments = self.split();
+        let prefix_segments: Vec<_> = prefix.split(SEPARATOR).filter(|s| *s != "").collect();
+        !prefix_segments.is_empty()
+            && prefix_segments.len() <= segments.len()
+            && segments
+                .iter()
+                .zip(&prefix_segments)
+                .all(|(s, p)| s.eq_ignore_ascii_case(p))
+    }
+}
+
+/// Matches a single name segment against a pattern segment containing `*` wildcards, ignoring case.
+fn glob_match_segment(segment: &[u8], pattern: &[u8]) -> bool {
+    let (mut s, mut p) = (0, 0);
+    // position of the last `*` seen in the pattern, and where in the segment it started matching
+    let mut backtrack = None;
+    while s < segment.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            backtrack = Some((p, s));
+            p += 1;
+        } else if p < pattern.len() && pattern[p].eq_ignore_ascii_case(&segment[s]) {
+            p += 1;
+            s += 1;
+        } else if let Some((star_p, star_s)) = backtrack {
+            // let the last `*` swallow one more character and try again
+            backtrack = Some((star_p, star_s + 1));
+            p = star_p + 1;
+            s = star_s + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
 }
 
 impl From<String> for NameKey {
@@ -110,7 +161,8 @@ impl<'de> Deserialize<'de> for NameKey {
     where
         D: Deserializer<'de>,
     {
-        let s: &str = Deserialize::deserialize(deserializer)?;
+        // not borrowed, so readers that can't lend out their buffers (e.g. MessagePack files) work too
+        let s: String = Deserialize::deserialize(deserializer)?;
         Ok(NameKey::new(s))
     }
 }
@@ -123,3 +175,45 @@ impl Serialize for NameKey {
         serializer.serialize_str(self.get())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_glob_test() {
+        let power = NameKey::new("Tanker_Melee.Super_Strength.Knockout_Blow");
+        assert!(power.matches_glob("Tanker_Melee.*.*"));
+        assert!(power.matches_glob("tanker_melee.super_strength.*"));
+        assert!(power.matches_glob("*.*_Strength.Knockout_*"));
+        assert!(power.matches_glob("Tanker_Melee.Super_Strength.Knockout_Blow"));
+        assert!(!power.matches_glob("Tanker_Melee.*"));
+        assert!(!power.matches_glob("Tanker_Melee.*.*.*"));
+        assert!(!power.matches_glob("Brute_Melee.*.*"));
+        assert!(!power.matches_glob("Tanker_Melee.*.Knockout"));
+        assert!(NameKey::new("Incarnate.Lore_Pet_Cimeroran").matches_glob("Incarnate.Lore_Pet_*"));
+        assert!(NameKey::new("Pool.Flight.").matches_glob("Pool.Flight"));
+    }
+
+    #[test]
+    fn entcreate_wildcard_test() {
+        // villain defs use a bare "*" as the power name to mean every power in the set
+        let wildcard = NameKey::new("*");
+        assert!(wildcard.is_wildcard());
+        assert!(!NameKey::new("Pool.*").is_wildcard());
+        let set_glob = format!("{}.{}.{}", "Pets", "Lore_Pet_Banished_Pantheon", wildcard);
+        assert!(NameKey::new("Pets.Lore_Pet_Banished_Pantheon.Summon").matches_glob(&set_glob));
+        assert!(!NameKey::new("Pets.Lore_Pet_Cimeroran.Summon").matches_glob(&set_glob));
+    }
+
+    #[test]
+    fn starts_with_segment_test() {
+        let power = NameKey::new("Pool.Flight.Fly");
+        assert!(power.starts_with_segment("Pool"));
+        assert!(power.starts_with_segment("pool.flight"));
+        assert!(power.starts_with_segment("Pool.Flight.Fly"));
+        assert!(!power.starts_with_segment("Pool.Fl"));
+        assert!(!power.starts_with_segment("Pool.Flight.Fly.Extra"));
+        assert!(!power.starts_with_segment(""));
+    }
+}
@@ -0,0 +1,493 @@
+//! Evaluates the postfix requires expressions found in the bins (`ppch_buy_requires`,
+//! `ppch_requires`, etc.) against a hypothetical character.
+//!
+//! Nothing in the extractor itself needs this, it's provided for downstream consumers.
+
+use super::{BasePower, EffectGroup, NameKey, ObjRef, PowerRedirect, PowersDictionary};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use thiserror::Error;
+
+/// The result of evaluating a requires expression, or any value on the stack along the way.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RequiresValue {
+    Float(f32),
+    Bool(bool),
+    String(String),
+}
+
+impl RequiresValue {
+    /// Gets the value as a number. Like the game, `true` is 1 and `false` is 0.
+    fn as_float(&self, op: &str) -> Result<f32, EvalError> {
+        match self {
+            RequiresValue::Float(f) => Ok(*f),
+            RequiresValue::Bool(b) => Ok(if *b { 1.0 } else { 0.0 }),
+            RequiresValue::String(s) => match s.parse::<f32>() {
+                Ok(f) => Ok(f),
+                Err(_) => Err(EvalError::TypeMismatch {
+                    op: op.to_owned(),
+                    value: self.clone(),
+                }),
+            },
+        }
+    }
+
+    /// Gets the value as a boolean. Any non-zero number is `true`.
+    fn as_bool(&self, op: &str) -> Result<bool, EvalError> {
+        match self {
+            RequiresValue::Bool(b) => Ok(*b),
+            _ => self.as_float(op).map(|f| f != 0.0),
+        }
+    }
+
+    /// Gets the value as a string for comparison with `eq`.
+    fn to_compare_string(&self) -> String {
+        match self {
+            RequiresValue::String(s) => s.clone(),
+            RequiresValue::Float(f) => f.to_string(),
+            RequiresValue::Bool(b) => (if *b { "1" } else { "0" }).to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for RequiresValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RequiresValue::Float(v) => write!(f, "{}", v),
+            RequiresValue::Bool(v) => write!(f, "{}", v),
+            RequiresValue::String(v) => write!(f, "\"{}\"", v),
+        }
+    }
+}
+
+/// Errors that can occur while evaluating a requires expression.
+#[derive(Debug, Error, PartialEq)]
+pub enum EvalError {
+    /// The expression was empty.
+    #[error("The expression is empty.")]
+    Empty,
+    /// An operator didn't have enough arguments on the stack.
+    #[error("Not enough arguments for {0}.")]
+    StackUnderflow(String),
+    /// The expression left more than one value on the stack.
+    #[error("The expression left {0} values on the stack.")]
+    TooManyValues(usize),
+    /// A variable or function wasn't bound in the `RequiresContext`.
+    #[error("No value for {0}.")]
+    Unbound(String),
+    /// An operator was given a value it can't use.
+    #[error("Can't use {value} with {op}.")]
+    TypeMismatch { op: String, value: RequiresValue },
+    /// The operator isn't supported by the evaluator (e.g. `rand`).
+    #[error("Unsupported operator {0}.")]
+    Unsupported(String),
+}
+
+/// Variable and function bindings used to evaluate requires expressions.
+///
+/// Names are written the same way `requires_to_string` prints them, and are matched
+/// without regard to case:
+///
+/// * Variables: `$archetype`, `source.Level`, `target>kHeldMag`
+/// * Functions: `source.ownPower?(Pool.Flight.Hover)`, `isPVPMap?()`
+#[derive(Debug, Default)]
+pub struct RequiresContext {
+    bindings: HashMap<String, RequiresValue>,
+}
+
+impl RequiresContext {
+    /// Creates an empty `RequiresContext`.
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Binds `name` to `value`, replacing any previous value.
+    #[allow(dead_code)]
+    pub fn set<T: Into<RequiresValue>>(&mut self, name: &str, value: T) -> &mut Self {
+        self.bindings
+            .insert(name.to_ascii_lowercase(), value.into());
+        self
+    }
+
+    /// Gets the value bound to `name`.
+    #[allow(dead_code)]
+    pub fn get(&self, name: &str) -> Option<&RequiresValue> {
+        self.bindings.get(&name.to_ascii_lowercase())
+    }
+
+    /// Gets the value bound to `name`, or an error if it's unbound.
+    fn lookup(&self, name: &str) -> Result<RequiresValue, EvalError> {
+        self.get(name)
+            .cloned()
+            .ok_or_else(|| EvalError::Unbound(name.to_owned()))
+    }
+}
+
+impl From<f32> for RequiresValue {
+    fn from(value: f32) -> Self {
+        RequiresValue::Float(value)
+    }
+}
+
+impl From<bool> for RequiresValue {
+    fn from(value: bool) -> Self {
+        RequiresValue::Bool(value)
+    }
+}
+
+impl From<&str> for RequiresValue {
+    fn from(value: &str) -> Self {
+        RequiresValue::String(value.to_owned())
+    }
+}
+
+impl From<String> for RequiresValue {
+    fn from(value: String) -> Self {
+        RequiresValue::String(value)
+    }
+}
+
+/// Evaluates a postfix requires expression.
+///
+/// # Arguments:
+/// * `tokens` - The expression, as stored in the bins.
+/// * `ctx` - Bindings for any variables or functions used by the expression.
+///
+/// # Returns:
+/// The value of the expression, or an `EvalError` if it couldn't be evaluated.
+///
+/// # Notes:
+/// Supports the same tokens as `requires_to_string`, except for `rand`. Numeric comparisons
+/// and logical operators produce `RequiresValue::Bool`. `eq` compares strings without
+/// regard to case, while `==` compares numbers.
+#[allow(dead_code)]
+pub fn eval_requires(tokens: &[String], ctx: &RequiresContext) -> Result<RequiresValue, EvalError> {
+    let mut stack: Vec<RequiresValue> = Vec::new();
+    let mut tokens = tokens.iter().peekable();
+    while let Some(token) = tokens.next() {
+        let mut pop = || {
+            stack
+                .pop()
+                .ok_or_else(|| EvalError::StackUnderflow(token.clone()))
+        };
+        let value = match token.as_ref() {
+            "!" => RequiresValue::Bool(!pop()?.as_bool(token)?),
+            "&&" | "||" => {
+                let b = pop()?.as_bool(token)?;
+                let a = pop()?.as_bool(token)?;
+                RequiresValue::Bool(if token == "&&" { a && b } else { a || b })
+            }
+            "eq" => {
+                let b = pop()?.to_compare_string();
+                let a = pop()?.to_compare_string();
+                RequiresValue::Bool(a.eq_ignore_ascii_case(&b))
+            }
+            "==" | "<" | "<=" | ">" | ">=" => {
+                let b = pop()?.as_float(token)?;
+                let a = pop()?.as_float(token)?;
+                RequiresValue::Bool(match token.as_ref() {
+                    "==" => a == b,
+                    "<" => a < b,
+                    "<=" => a <= b,
+                    ">" => a > b,
+                    _ => a >= b,
+                })
+            }
+            "+" | "-" | "*" | "/" => {
+                let b = pop()?.as_float(token)?;
+                let a = pop()?.as_float(token)?;
+                RequiresValue::Float(match token.as_ref() {
+                    "+" => a + b,
+                    "-" => a - b,
+                    "*" => a * b,
+                    _ => a / b,
+                })
+            }
+            "negate" => RequiresValue::Float(-pop()?.as_float(token)?),
+            "minmax" => {
+                let max = pop()?.as_float(token)?;
+                let min = pop()?.as_float(token)?;
+                let val = pop()?.as_float(token)?;
+                RequiresValue::Float(val.max(min).min(max))
+            }
+            "dup" => {
+                let a = pop()?;
+                stack.push(a.clone());
+                a
+            }
+            "drop" => {
+                pop()?;
+                continue;
+            }
+            "rand" => return Err(EvalError::Unsupported(token.clone())),
+            "source.MapTeamArea>" | "source.VillainName>" => {
+                ctx.lookup(&token[0..token.len() - 1])?
+            }
+            _ if token.ends_with('>') => {
+                // struct pointer, the member name is the previous token
+                let member = pop()?.to_compare_string();
+                let path = format!("{}{}", token, member);
+                if tokens
+                    .peek()
+                    .map_or(false, |next| next.len() > 1 && next.ends_with('>'))
+                {
+                    // chained pointer (e.g. source>owner>kHeldMag), keep building the path
+                    // (a bare ">" is the comparison operator, not another pointer)
+                    RequiresValue::String(path)
+                } else {
+                    ctx.lookup(&path)?
+                }
+            }
+            _ if token.ends_with('?') => {
+                // function, the argument is the previous token unless it's an "is" check
+                if token.find(".is").is_some()
+                    || token.find(".Is").is_some()
+                    || token.starts_with("is")
+                    || token.starts_with("Is")
+                {
+                    ctx.lookup(&format!("{}()", token))?
+                } else {
+                    let arg = pop()?.to_compare_string();
+                    ctx.lookup(&format!("{}({})", token, arg))?
+                }
+            }
+            _ if token.starts_with('$') => ctx.lookup(token)?,
+            _ => match token.parse::<f32>() {
+                Ok(f) => RequiresValue::Float(f),
+                Err(_) => match ctx.get(token) {
+                    Some(value) => value.clone(),
+                    // anything else is a literal (power names, class keys, etc.)
+                    None => RequiresValue::String(token.clone()),
+                },
+            },
+        };
+        stack.push(value);
+    }
+    match stack.len() {
+        0 => Err(EvalError::Empty),
+        1 => Ok(stack.pop().unwrap()),
+        n => Err(EvalError::TooManyValues(n)),
+    }
+}
+
+impl BasePower {
+    /// Gets the effects this power applies for the character described by `ctx`, after
+    /// following any redirects.
+    ///
+    /// # Arguments:
+    /// * `ctx` - Bindings for the variables used by the redirect requires expressions (level,
+    ///   archetype, owned powers, etc.).
+    /// * `dict` - Used to look up the redirect targets.
+    ///
+    /// # Returns:
+    /// The effect groups of the power that's actually used. This is the target of the first
+    /// redirect whose requires expression is true, or of the redirect with an empty expression
+    /// if none of them are. Targets are followed through their own redirects in turn. If there's
+    /// no usable redirect, or following one would loop back to a power already visited, the
+    /// effects of the last power reached are returned.
+    ///
+    /// # Notes:
+    /// An expression that can't be evaluated (e.g. because of a missing binding in `ctx`)
+    /// counts as false.
+    #[allow(dead_code)]
+    pub fn resolved_effects(
+        &self,
+        ctx: &RequiresContext,
+        dict: &PowersDictionary,
+    ) -> Vec<ObjRef<EffectGroup>> {
+        let mut visited = HashSet::new();
+        if let Some(name) = &self.pch_full_name {
+            visited.insert(name.clone());
+        }
+        match self.resolve_redirect(ctx, dict, &mut visited) {
+            Some(target) => target.borrow().pp_effects.clone(),
+            None => self.pp_effects.clone(),
+        }
+    }
+
+    /// Follows this power's redirects for `resolved_effects`.
+    ///
+    /// # Returns:
+    /// The power that ends up being used, or `None` if that's this power.
+    fn resolve_redirect(
+        &self,
+        ctx: &RequiresContext,
+        dict: &PowersDictionary,
+        visited: &mut HashSet<NameKey>,
+    ) -> Option<ObjRef<BasePower>> {
+        let passes = |redirect: &&PowerRedirect| {
+            !redirect.ppch_requires.is_empty()
+                && matches!(
+                    eval_requires(&redirect.ppch_requires, ctx),
+                    Ok(value) if value.as_bool("redirect").unwrap_or(false)
+                )
+        };
+        let redirect = self.pp_redirect.iter().find(passes).or_else(|| {
+            self.pp_redirect
+                .iter()
+                .find(|redirect| redirect.ppch_requires.is_empty())
+        })?;
+        let target_name = redirect.pch_name.as_ref()?;
+        if !visited.insert(target_name.clone()) {
+            return None;
+        }
+        let target = dict.find_power(target_name)?;
+        let next = target.borrow().resolve_redirect(ctx, dict, visited);
+        Some(next.unwrap_or(target))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{AttribModTemplate, BasePowerSet, PowerCategory};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn tokens(expression: &str) -> Vec<String> {
+        expression.split(' ').map(String::from).collect()
+    }
+
+    #[test]
+    fn resolved_effects_test() {
+        let make_power = |name: &str, scale: f32| {
+            let mut template = AttribModTemplate::new();
+            template.f_scale = scale;
+            let mut effect_group = EffectGroup::new();
+            effect_group.pp_templates.push(template);
+            let mut power = BasePower::new();
+            power.pch_full_name = Some(NameKey::new(name));
+            power.include_in_output = true;
+            power.pp_effects.push(Rc::new(RefCell::new(effect_group)));
+            power
+        };
+        let make_redirect = |name: &str, requires: &str| {
+            let mut redirect = PowerRedirect::new();
+            redirect.pch_name = Some(NameKey::new(name));
+            if !requires.is_empty() {
+                redirect.ppch_requires = tokens(requires);
+            }
+            redirect
+        };
+        let mut fire_blast = make_power("Blaster_Ranged.Fire_Blast.Fire_Blast", 1.0);
+        fire_blast.pp_redirect.push(make_redirect(
+            "Blaster_Ranged.Fire_Blast.Fire_Blast_Fiery",
+            "source.Level 20 >=",
+        ));
+        fire_blast.pp_redirect.push(make_redirect(
+            "Blaster_Ranged.Fire_Blast.Fire_Blast_Normal",
+            "",
+        ));
+        let fiery = make_power("Blaster_Ranged.Fire_Blast.Fire_Blast_Fiery", 2.0);
+        let mut normal = make_power("Blaster_Ranged.Fire_Blast.Fire_Blast_Normal", 3.0);
+        // loops back to the original power, which should stop at the fallback
+        normal
+            .pp_redirect
+            .push(make_redirect("Blaster_Ranged.Fire_Blast.Fire_Blast", ""));
+        let mut pset = BasePowerSet::new();
+        pset.pch_full_name = Some(NameKey::new("Blaster_Ranged.Fire_Blast"));
+        pset.include_in_output = true;
+        for power in vec![fiery, normal] {
+            pset.pp_powers.push(Rc::new(RefCell::new(power)));
+        }
+        let mut pcat = PowerCategory::new();
+        pcat.pch_name = Some(NameKey::new("Blaster_Ranged"));
+        pcat.include_in_output = true;
+        pcat.pp_power_sets.push(Rc::new(RefCell::new(pset)));
+        let dict = PowersDictionary::from_power_categories(vec![Rc::new(RefCell::new(pcat))]);
+        let scale_of = |effects: Vec<ObjRef<EffectGroup>>| {
+            assert_eq!(effects.len(), 1);
+            let scale = effects[0].borrow().pp_templates[0].f_scale;
+            scale
+        };
+
+        let mut ctx = RequiresContext::new();
+        ctx.set("source.Level", 25.0);
+        assert_eq!(scale_of(fire_blast.resolved_effects(&ctx, &dict)), 2.0);
+        ctx.set("source.Level", 10.0);
+        assert_eq!(scale_of(fire_blast.resolved_effects(&ctx, &dict)), 3.0);
+        // unbound variables count as false
+        let ctx = RequiresContext::new();
+        assert_eq!(scale_of(fire_blast.resolved_effects(&ctx, &dict)), 3.0);
+
+        fire_blast.pp_redirect.clear();
+        assert_eq!(scale_of(fire_blast.resolved_effects(&ctx, &dict)), 1.0);
+    }
+
+    #[test]
+    fn eval_buy_requires_test() {
+        let requires = tokens(
+            "Pool.Flight.Air_Superiority source.ownPower? Pool.Flight.Hover source.ownPower? ||",
+        );
+        let mut ctx = RequiresContext::new();
+        ctx.set("source.ownPower?(Pool.Flight.Air_Superiority)", false)
+            .set("source.ownPower?(pool.flight.hover)", true);
+        assert_eq!(
+            eval_requires(&requires, &ctx),
+            Ok(RequiresValue::Bool(true))
+        );
+        ctx.set("source.ownPower?(Pool.Flight.Hover)", false);
+        assert_eq!(
+            eval_requires(&requires, &ctx),
+            Ok(RequiresValue::Bool(false))
+        );
+
+        let requires = tokens("$archetype Class_Blaster eq");
+        ctx.set("$archetype", "class_blaster");
+        assert_eq!(
+            eval_requires(&requires, &ctx),
+            Ok(RequiresValue::Bool(true))
+        );
+    }
+
+    #[test]
+    fn eval_arithmetic_test() {
+        let mut ctx = RequiresContext::new();
+        ctx.set("target>kHeldMag", 3.0).set("source.Level", 20.0);
+        let requires = tokens("kHeldMag target> 2 * negate -10 0 minmax");
+        assert_eq!(
+            eval_requires(&requires, &ctx),
+            Ok(RequiresValue::Float(-6.0))
+        );
+        ctx.set("source>owner>kHeldMag", 1.0);
+        let requires = tokens("kHeldMag owner> source> kHeldMag target> +");
+        assert_eq!(
+            eval_requires(&requires, &ctx),
+            Ok(RequiresValue::Float(4.0))
+        );
+        let requires = tokens("0 kHeldMag target> >");
+        assert_eq!(
+            eval_requires(&requires, &ctx),
+            Ok(RequiresValue::Bool(false))
+        );
+        let requires = tokens("source.Level 22 >= isPVPMap? ! &&");
+        ctx.set("isPVPMap?()", false);
+        assert_eq!(
+            eval_requires(&requires, &ctx),
+            Ok(RequiresValue::Bool(false))
+        );
+    }
+
+    #[test]
+    fn eval_errors_test() {
+        let ctx = RequiresContext::new();
+        assert_eq!(
+            eval_requires(&tokens("kHeldMag target> 1 >"), &ctx),
+            Err(EvalError::Unbound(String::from("target>kHeldMag")))
+        );
+        assert_eq!(
+            eval_requires(&tokens("1 &&"), &ctx),
+            Err(EvalError::StackUnderflow(String::from("&&")))
+        );
+        assert_eq!(
+            eval_requires(&tokens("1 2"), &ctx),
+            Err(EvalError::TooManyValues(2))
+        );
+        assert!(matches!(
+            eval_requires(&tokens("Class_Blaster 1 +"), &ctx),
+            Err(EvalError::TypeMismatch { .. })
+        ));
+        assert_eq!(eval_requires(&[], &ctx), Err(EvalError::Empty));
+    }
+}
@@ -0,0 +1,61 @@
+//! `JsonSchema` impls for the types that have handwritten serializers. Only built with the
+//! "schema" feature.
+
+use super::{NameKey, Vec3, RGBA};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Metadata, Schema, SchemaObject, StringValidation};
+use schemars::JsonSchema;
+
+/// Creates a schema for a string matching `pattern` (if any).
+fn string_schema(description: &str, pattern: Option<&str>) -> Schema {
+    SchemaObject {
+        metadata: Some(Box::new(Metadata {
+            description: Some(description.to_owned()),
+            ..Default::default()
+        })),
+        instance_type: Some(InstanceType::String.into()),
+        string: Some(Box::new(StringValidation {
+            pattern: pattern.map(str::to_owned),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+impl JsonSchema for NameKey {
+    fn schema_name() -> String {
+        String::from("NameKey")
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        string_schema(
+            "A case-insensitive name, with parts separated by periods (e.g. Pool.Flight.Fly).",
+            None,
+        )
+    }
+}
+
+impl JsonSchema for Vec3 {
+    fn schema_name() -> String {
+        String::from("Vec3")
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        string_schema(
+            "A vector written as (x, y, z).",
+            Some(r"^\([^,]+, [^,]+, [^,]+\)$"),
+        )
+    }
+}
+
+impl JsonSchema for RGBA {
+    fn schema_name() -> String {
+        String::from("RGBA")
+    }
+
+    /// Describes the default `rgba_format` ("hex_string").
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        string_schema("A color written as #RRGGBBAA.", Some("^#[0-9A-F]{8}$"))
+    }
+}
@@ -0,0 +1,185 @@
+//! Simple name search over the powers in a dictionary, for search boxes and the like.
+
+use super::*;
+use serde::Serialize;
+
+/// Score for a query matching the whole display name.
+const SCORE_EXACT_DISPLAY: u32 = 100;
+/// Score for a query matching the whole internal name.
+const SCORE_EXACT_NAME: u32 = 90;
+/// Score for a display name starting with the query.
+const SCORE_PREFIX_DISPLAY: u32 = 75;
+/// Score for an internal name starting with the query.
+const SCORE_PREFIX_NAME: u32 = 65;
+/// Score for a display name containing the query.
+const SCORE_SUBSTRING_DISPLAY: u32 = 50;
+/// Score for an internal name containing the query.
+const SCORE_SUBSTRING_NAME: u32 = 40;
+
+/// A power found by `PowersDictionary::search`.
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    /// Full name of the power (e.g. "Pool.Flight.Fly").
+    pub full_name: NameKey,
+    /// The power's display name, if it has one.
+    pub display_name: Option<String>,
+    /// How well the power matched. Higher is better.
+    pub score: u32,
+}
+
+impl PowersDictionary {
+    /// Finds the powers whose display name or internal name contain `query`, ignoring case.
+    ///
+    /// # Arguments:
+    ///
+    /// * `query` - The text to search for.
+    /// * `limit` - The maximum number of hits to return.
+    ///
+    /// # Returns:
+    ///
+    /// A `Vec<SearchHit>` sorted from best to worst match. An exact match ranks above a prefix
+    /// match, which ranks above a substring match, and display names rank above internal names.
+    /// Only powers included in the output are searched.
+    #[allow(dead_code)]
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        // powers shared between sets come up once per set
+        let mut seen = HashSet::new();
+        let mut hits: Vec<SearchHit> = self
+            .iter_powers(true)
+            .filter_map(|power| {
+                let power = power.borrow();
+                let full_name = power.pch_full_name.clone()?;
+                if !seen.insert(full_name.clone()) {
+                    return None;
+                }
+                let score = score_match(
+                    power.pch_display_name.as_deref(),
+                    &query,
+                    SCORE_EXACT_DISPLAY,
+                    SCORE_PREFIX_DISPLAY,
+                    SCORE_SUBSTRING_DISPLAY,
+                )
+                .max(score_match(
+                    power.pch_name.as_deref(),
+                    &query,
+                    SCORE_EXACT_NAME,
+                    SCORE_PREFIX_NAME,
+                    SCORE_SUBSTRING_NAME,
+                ));
+                if score == 0 {
+                    return None;
+                }
+                Some(SearchHit {
+                    full_name,
+                    display_name: power.pch_display_name.clone(),
+                    score,
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.display_name.cmp(&b.display_name))
+                .then_with(|| a.full_name.get().cmp(b.full_name.get()))
+        });
+        hits.truncate(limit);
+        hits
+    }
+}
+
+/// Scores how well `text` matches `query`, which must already be lowercase.
+///
+/// # Returns:
+///
+/// `exact`, `prefix`, or `substring` depending on how it matched, or 0 if it didn't.
+fn score_match(text: Option<&str>, query: &str, exact: u32, prefix: u32, substring: u32) -> u32 {
+    let text = match text {
+        Some(text) => text.to_lowercase(),
+        None => return 0,
+    };
+    if text == query {
+        exact
+    } else if text.starts_with(query) {
+        prefix
+    } else if text.contains(query) {
+        substring
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_test() {
+        let mut pset = BasePowerSet::new();
+        pset.include_in_output = true;
+        for (name, display_name, included) in &[
+            ("Pool.Flight.Air_Superiority", "Air Superiority", true),
+            ("Pool.Flight.Fly", "Fly", true),
+            ("Pool.Flight.Hover", "Hover", true),
+            ("Pool.Flight.Afterburner", "Fly Faster", true),
+            ("Pool.Flight.Combat_Hover", "Combat Hover", true),
+            ("Pool.Flight.Fly_Excluded", "Fly", false),
+        ] {
+            let mut power = BasePower::new();
+            power.pch_full_name = Some(NameKey::new(*name));
+            power.pch_name = name.rsplit('.').next().map(String::from);
+            power.pch_display_name = Some(String::from(*display_name));
+            power.include_in_output = *included;
+            pset.pp_powers.push(Rc::new(RefCell::new(power)));
+        }
+        // the same power can show up in more than one set
+        let mut shared_pset = BasePowerSet::new();
+        shared_pset.include_in_output = true;
+        shared_pset.pp_powers.push(Rc::clone(&pset.pp_powers[1]));
+        let mut pcat = PowerCategory::new();
+        pcat.include_in_output = true;
+        pcat.pp_power_sets.push(Rc::new(RefCell::new(pset)));
+        pcat.pp_power_sets.push(Rc::new(RefCell::new(shared_pset)));
+        let powers_dict = PowersDictionary::from_power_categories(vec![Rc::new(RefCell::new(pcat))]);
+
+        let hits: Vec<(String, u32)> = powers_dict
+            .search("FLY", 10)
+            .into_iter()
+            .map(|hit| (hit.full_name.to_string(), hit.score))
+            .collect();
+        assert_eq!(
+            hits,
+            vec![
+                (String::from("Pool.Flight.Fly"), SCORE_EXACT_DISPLAY),
+                (
+                    String::from("Pool.Flight.Afterburner"),
+                    SCORE_PREFIX_DISPLAY
+                ),
+            ]
+        );
+
+        // an exact display name match ranks above a substring match
+        let hits: Vec<(String, u32)> = powers_dict
+            .search("hover", 10)
+            .into_iter()
+            .map(|hit| (hit.full_name.to_string(), hit.score))
+            .collect();
+        assert_eq!(
+            hits,
+            vec![
+                (String::from("Pool.Flight.Hover"), SCORE_EXACT_DISPLAY),
+                (
+                    String::from("Pool.Flight.Combat_Hover"),
+                    SCORE_SUBSTRING_DISPLAY
+                ),
+            ]
+        );
+        let hits = powers_dict.search("er", 10);
+        assert_eq!(hits.len(), 4);
+        assert_eq!(powers_dict.search("er", 1).len(), 1);
+        assert!(powers_dict.search("  ", 10).is_empty());
+    }
+}
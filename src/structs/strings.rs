@@ -1,3 +1,5 @@
+use super::config::StringEncoding;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::str;
 
@@ -106,10 +108,35 @@ impl IntoMessage for Option<&str> {
     }
 }
 
+/// Converts a string read from a .bin into UTF-8.
+///
+/// # Arguments
+///
+/// * `bytes` - The bytes of the string, without a NUL terminator.
+/// * `encoding` - How bytes outside of 7-bit ASCII are handled.
+///
+/// # Returns
+///
+/// The converted string, borrowed from `bytes` where possible. Only fails for
+/// `StringEncoding::Utf8Strict` when `bytes` isn't valid UTF-8.
+pub fn decode_string(bytes: &[u8], encoding: StringEncoding) -> Result<Cow<'_, str>, str::Utf8Error> {
+	match encoding {
+		// valid UTF-8 is kept as is, otherwise anything outside of ASCII is dropped
+		StringEncoding::Lossy => match str::from_utf8(bytes) {
+			Ok(s) => Ok(Cow::Borrowed(s)),
+			Err(_) => Ok(Cow::Owned(bytes.iter().filter(|b| b.is_ascii()).map(|&b| b as char).collect())),
+		},
+		// ISO 8859-1 occupies the first 256 Unicode code points, so each byte maps directly
+		StringEncoding::Latin1 if !bytes.is_ascii() => Ok(Cow::Owned(bytes.iter().map(|&b| b as char).collect())),
+		// plain ASCII is already valid UTF-8 for every policy
+		_ => str::from_utf8(bytes).map(Cow::Borrowed),
+	}
+}
+
 /// Represents a pool of strings from a .bin file. A string pool is a series of NUL-terminated
 /// strings that are referenced by offset from struct fields in the .bin.
 #[derive(Debug)]
-pub struct StringPool(Vec<u8>);
+pub struct StringPool(Vec<u8>, StringEncoding);
 
 impl StringPool {
 	/// Create a new string pool, initialized with the bytes in `pool`.
@@ -117,14 +144,15 @@ impl StringPool {
 	/// # Arguments
 	///
 	/// * `pool` - A vector of bytes populated with the string pool data.
+	/// * `encoding` - How strings in the pool are converted to UTF-8.
 	///
 	/// # Returns
 	///
 	/// A `StringPool`.
-	pub fn new(pool: Vec<u8>) -> Self {
+	pub fn new(pool: Vec<u8>, encoding: StringEncoding) -> Self {
 		debug_assert!(pool.len() == 0 || *pool.first().unwrap() == 0u8, "StringPool should start with NUL");
 		debug_assert!(pool.len() == 0 || *pool.last().unwrap() == 0u8, "StringPool should end with NUL");
-		StringPool(pool)
+		StringPool(pool, encoding)
 	}
 
 	/// Attempts to get the string at offset `offset` in the pool, by reading until it encounters a NUL
@@ -136,16 +164,50 @@ impl StringPool {
 	///
 	/// # Returns
 	/// 
-	/// If successful, a slice representing the string at `offset`. Will return `None` if the string
-	/// is empty or could not be parsed into a valid UTF-8 string.
-	pub fn get_string(&self, offset: usize) -> Option<&str> {
+	/// If successful, the string at `offset`, or `None` if there's no string there. A `Utf8Error`
+	/// if the string couldn't be converted to UTF-8 with the pool's `StringEncoding`.
+	pub fn get_string(&self, offset: usize) -> Result<Option<Cow<'_, str>>, str::Utf8Error> {
 		if offset > 0 && offset < self.0.len() {
 			if let Some(end) = self.0[offset..].iter().enumerate().find(|(_, b)| **b == 0) {
-				if let Ok(s) = str::from_utf8(&self.0[offset..offset + end.0]) {
-					return Some(s);
-				}
+				return decode_string(&self.0[offset..offset + end.0], self.1).map(Some);
 			}
 		}
-		None
+		Ok(None)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// "Café" as the game stores it, in ISO 8859-1.
+	const CAFE: &[u8] = b"Caf\xe9";
+
+	#[test]
+	fn decode_string_test() {
+		assert_eq!(decode_string(CAFE, StringEncoding::Lossy).unwrap(), "Caf");
+		// already valid UTF-8, so nothing is dropped
+		assert!(matches!(
+			decode_string("Caf\u{e9}".as_bytes(), StringEncoding::Lossy).unwrap(),
+			Cow::Borrowed("Caf\u{e9}")
+		));
+		assert!(decode_string(CAFE, StringEncoding::Utf8Strict).is_err());
+		assert_eq!(decode_string(CAFE, StringEncoding::Latin1).unwrap(), "Caf\u{e9}");
+		assert_eq!(decode_string("Caf\u{e9}".as_bytes(), StringEncoding::Utf8Strict).unwrap(), "Caf\u{e9}");
+		for &encoding in &[StringEncoding::Lossy, StringEncoding::Utf8Strict, StringEncoding::Latin1] {
+			assert!(matches!(decode_string(b"Cafe", encoding).unwrap(), Cow::Borrowed("Cafe")));
+		}
+	}
+
+	#[test]
+	fn string_pool_encoding_test() {
+		let mut pool = vec![0u8];
+		pool.extend_from_slice(CAFE);
+		pool.push(0);
+		let get = |encoding| StringPool::new(pool.clone(), encoding).get_string(1).map(|s| s.map(Cow::into_owned));
+		assert_eq!(get(StringEncoding::Lossy).unwrap().as_deref(), Some("Caf"));
+		assert!(get(StringEncoding::Utf8Strict).is_err());
+		assert_eq!(get(StringEncoding::Latin1).unwrap().as_deref(), Some("Caf\u{e9}"));
+		assert_eq!(StringPool::new(pool.clone(), StringEncoding::Utf8Strict).get_string(0), Ok(None));
 	}
 }
@@ -0,0 +1,234 @@
+//! Checks for name references that don't resolve after loading and filtering.
+
+use super::*;
+use log::warn;
+use serde::Serialize;
+
+/// The kinds of name references checked by `PowersDictionary::validate`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceKind {
+    /// A power set listed in `PowerCategory::ppch_power_set_names`.
+    PowerSet,
+    /// A power listed in `BasePowerSet::pp_power_names`.
+    Power,
+    /// The target of a `PowerRedirect`.
+    Redirect,
+    /// A power used by an entity created by an `EntCreate` attrib mod.
+    EntCreatePower,
+    /// A power granted by a `Power` attrib mod.
+    GrantedPower,
+}
+
+/// A name reference that doesn't resolve to anything in the output.
+#[derive(Debug, Serialize)]
+pub struct ValidationIssue {
+    /// Full name of the category, power set, or power holding the reference.
+    pub source: NameKey,
+    /// What kind of reference it is.
+    pub kind: ReferenceKind,
+    /// The name that couldn't be found.
+    pub missing: NameKey,
+}
+
+impl PowersDictionary {
+    /// Checks that every name referenced by the categories, power sets, and powers included in
+    /// the output points at something that is also included.
+    ///
+    /// # Arguments:
+    ///
+    /// * `max_effect_depth` - How many levels of effect groups to search for references, from
+    ///   the config.
+    ///
+    /// # Returns:
+    ///
+    /// A `Vec<ValidationIssue>` with one entry for each reference that doesn't resolve. Empty if
+    /// everything was found.
+    ///
+    /// # Notes:
+    ///
+    /// A reference to an object that was loaded but excluded by the filters in the config
+    /// counts as missing, since it won't be in the output either. Powers with effect groups nested
+    /// deeper than `max_effect_depth` are logged, and only the references within the limit are
+    /// checked.
+    #[allow(dead_code)]
+    pub fn validate(&self, max_effect_depth: Option<usize>) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        for pcat in self.iter_categories(true) {
+            let pcat = pcat.borrow();
+            if let Some(source) = &pcat.pch_name {
+                for pset_name in &pcat.ppch_power_set_names {
+                    if !self.power_set_included(pset_name) {
+                        issues.push(ValidationIssue {
+                            source: source.clone(),
+                            kind: ReferenceKind::PowerSet,
+                            missing: pset_name.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        for pset in self.iter_power_sets(true) {
+            let pset = pset.borrow();
+            if let Some(source) = &pset.pch_full_name {
+                for power_name in &pset.pp_power_names {
+                    if !self.power_included(power_name) {
+                        issues.push(ValidationIssue {
+                            source: source.clone(),
+                            kind: ReferenceKind::Power,
+                            missing: power_name.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        for power in self.iter_powers(true) {
+            let power = power.borrow();
+            if let Some(source) = &power.pch_full_name {
+                let mut references = Vec::new();
+                for redirect in &power.pp_redirect {
+                    if let Some(target) = &redirect.pch_name {
+                        references.push((ReferenceKind::Redirect, target.clone()));
+                    }
+                }
+                for effect_group in &power.pp_effects {
+                    if let Err(e) = collect_param_references(
+                        &*effect_group.borrow(),
+                        max_effect_depth,
+                        &mut references,
+                    ) {
+                        warn!("Not all references in {} were checked. {}", source, e);
+                    }
+                }
+                for (kind, missing) in references {
+                    if !self.power_included(&missing) {
+                        issues.push(ValidationIssue {
+                            source: source.clone(),
+                            kind,
+                            missing,
+                        });
+                    }
+                }
+            }
+        }
+        issues
+    }
+
+    /// Tests if the power set named `key` exists and is included in the output.
+    fn power_set_included(&self, key: &NameKey) -> bool {
+        matches!(self.find_power_set(key), Some(pset) if pset.borrow().include_in_output)
+    }
+
+    /// Tests if the power named `key` exists and is included in the output.
+    fn power_included(&self, key: &NameKey) -> bool {
+        matches!(self.find_power(key), Some(power) if power.borrow().include_in_output)
+    }
+}
+
+/// Appends the power names referenced by the `EntCreate` and `Power` params of every template in
+/// `effect_group` (and its children) to `references`.
+///
+/// # Returns:
+///
+/// An `EffectDepthError` if the groups are nested deeper than `max_depth`. References in the
+/// groups within the limit are still appended.
+fn collect_param_references(
+    effect_group: &EffectGroup,
+    max_depth: Option<usize>,
+    references: &mut Vec<(ReferenceKind, NameKey)>,
+) -> Result<(), EffectDepthError> {
+    effect_group.walk(max_depth, &mut |effect_group, _| {
+        for template in &effect_group.pp_templates {
+            match &template.p_params {
+                Some(AttribModParam::EntCreate(e)) => references.extend(
+                    e.power_refs
+                        .iter()
+                        .map(|name| (ReferenceKind::EntCreatePower, name.clone())),
+                ),
+                Some(AttribModParam::Power(p)) => references.extend(
+                    p.ppch_power_names
+                        .iter()
+                        .map(|name| (ReferenceKind::GrantedPower, name.clone())),
+                ),
+                _ => (),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_test() {
+        let make_power = |name: &str, included: bool| {
+            let mut power = BasePower::new();
+            power.pch_full_name = Some(NameKey::new(name));
+            power.include_in_output = included;
+            Rc::new(RefCell::new(power))
+        };
+        let mut redirect = PowerRedirect::new();
+        redirect.pch_name = Some(NameKey::new("Pool.Flight.Fly_Excluded"));
+        let fly = make_power("Pool.Flight.Fly", true);
+        fly.borrow_mut().pp_redirect.push(redirect);
+        let mut grant = AttribModParam_Power::default();
+        grant.ppch_power_names.push(NameKey::new("Pool.Flight.Fly"));
+        grant
+            .ppch_power_names
+            .push(NameKey::new("Pool.Flight.Granted_Missing"));
+        let mut template = AttribModTemplate::new();
+        template.p_params = Some(AttribModParam::Power(grant));
+        let mut child = EffectGroup::new();
+        child.pp_templates.push(template);
+        let mut effect_group = EffectGroup::new();
+        effect_group.pp_effects.push(child);
+        fly.borrow_mut()
+            .pp_effects
+            .push(Rc::new(RefCell::new(effect_group)));
+
+        let mut pset = BasePowerSet::new();
+        pset.pch_full_name = Some(NameKey::new("Pool.Flight"));
+        pset.include_in_output = true;
+        pset.pp_power_names.push(NameKey::new("Pool.Flight.Fly"));
+        pset.pp_power_names.push(NameKey::new("Pool.Flight.Hover"));
+        pset.pp_powers.push(fly);
+        pset.pp_powers
+            .push(make_power("Pool.Flight.Fly_Excluded", false));
+        let mut pcat = PowerCategory::new();
+        pcat.pch_name = Some(NameKey::new("Pool"));
+        pcat.include_in_output = true;
+        pcat.ppch_power_set_names.push(NameKey::new("Pool.Flight"));
+        pcat.ppch_power_set_names.push(NameKey::new("Pool.Leaping"));
+        pcat.pp_power_sets.push(Rc::new(RefCell::new(pset)));
+        let powers_dict = PowersDictionary::from_power_categories(vec![Rc::new(RefCell::new(pcat))]);
+
+        let issues: Vec<(String, ReferenceKind, String)> = powers_dict
+            .validate(None)
+            .into_iter()
+            .map(|i| (i.source.to_string(), i.kind, i.missing.to_string()))
+            .collect();
+        let expected = vec![
+            ("Pool", ReferenceKind::PowerSet, "Pool.Leaping"),
+            ("Pool.Flight", ReferenceKind::Power, "Pool.Flight.Hover"),
+            (
+                "Pool.Flight.Fly",
+                ReferenceKind::Redirect,
+                "Pool.Flight.Fly_Excluded",
+            ),
+            (
+                "Pool.Flight.Fly",
+                ReferenceKind::GrantedPower,
+                "Pool.Flight.Granted_Missing",
+            ),
+        ];
+        let expected: Vec<(String, ReferenceKind, String)> = expected
+            .into_iter()
+            .map(|(s, k, m)| (s.to_string(), k, m.to_string()))
+            .collect();
+        assert_eq!(issues, expected);
+        assert!(serde_json::to_string(&powers_dict.validate(None)[0])
+            .unwrap()
+            .contains("\"kind\":\"power_set\""));
+    }
+}
@@ -0,0 +1,42 @@
+/// Fields that were added to the .bin formats after the original source release, along with
+/// the issue they first appeared in. This is maintained by hand from the "Added iXX" notes on
+/// the struct fields, so keep it in sync when adding new fields.
+const FIELDS_INTRODUCED: &'static [(&'static str, &'static str)] = &[
+    // BasePower
+    ("ppch_chain_target_expr", "i26p5"),
+    ("ppch_max_targets_expr", "i26p5"),
+    ("b_target_untargetable", "i27"),
+    ("p_global_strengths_disallowed", "i27"),
+    // PowerFX
+    ("b_fx_important", "i26p5"),
+    ("b_hide_original", "i26p5"),
+];
+
+/// Looks up the issue that a struct field was introduced in.
+///
+/// # Arguments:
+///
+/// * `field` - The name of a struct field, such as `b_fx_important`.
+///
+/// # Returns:
+///
+/// The issue the field first appeared in (e.g. "i26p5"), or `None` if the field
+/// was part of the original data or isn't known.
+pub fn field_introduced(field: &str) -> Option<&'static str> {
+    FIELDS_INTRODUCED
+        .iter()
+        .find(|(name, _)| *name == field)
+        .map(|(_, issue)| *issue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_introduced_test() {
+        assert_eq!(field_introduced("b_fx_important"), Some("i26p5"));
+        assert_eq!(field_introduced("b_target_untargetable"), Some("i27"));
+        assert_eq!(field_introduced("f_accuracy"), None);
+    }
+}